@@ -1,10 +1,11 @@
 //! ArcDB - CLI Client
 
 use std::io::{self, Write};
+use std::path::Path;
 use std::sync::Arc;
 
-use arcdb::catalog::Catalog;
-use arcdb::executor::{ExecutionEngine, Planner};
+use arcdb::catalog::{Catalog, CATALOG_FILE};
+use arcdb::executor::{Analyzer, ExecutionEngine, Planner};
 use arcdb::sql::Parser;
 
 /// Print welcome banner
@@ -134,6 +135,15 @@ fn execute_sql(sql: &str, catalog: &Catalog, engine: &mut ExecutionEngine) {
         }
     };
 
+    // Analyze
+    let stmt = match Analyzer::new(catalog).analyze(stmt) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
     // Plan
     let planner = Planner::new(catalog);
     let plan = planner.plan(stmt);
@@ -156,13 +166,15 @@ fn execute_sql(sql: &str, catalog: &Catalog, engine: &mut ExecutionEngine) {
 }
 
 /// Handle special dot commands
-fn handle_special_command(cmd: &str, catalog: &Catalog) {
+fn handle_special_command(cmd: &str, catalog: &Catalog, engine: &ExecutionEngine) {
     let parts: Vec<&str> = cmd.split_whitespace().collect();
 
-    match parts.first().map(|s| *s) {
+    match parts.first().copied() {
         Some(".help") => print_help(),
         Some(".quit") | Some(".exit") => {
-            catalog.save_to_disk("arcdb.meta").ok();
+            if let Err(e) = catalog.save_to_disk(engine.catalog_path()) {
+                eprintln!("Error: {}", e);
+            }
             println!("Goodbye!");
             std::process::exit(0);
         }
@@ -207,10 +219,21 @@ fn handle_special_command(cmd: &str, catalog: &Catalog) {
 }
 
 /// Main REPL loop
-fn run_repl() {
+fn run_repl(page_size: Option<usize>) {
+    let catalog_path = Path::new("data").join(CATALOG_FILE);
     let catalog =
-        Arc::new(Catalog::load_from_disk("arcdb.meta").unwrap_or_else(|_| Catalog::new()));
-    let mut engine = ExecutionEngine::new(catalog.clone()).unwrap();
+        Arc::new(Catalog::load_from_disk(catalog_path).unwrap_or_else(|_| Catalog::new()));
+    let engine = match page_size {
+        Some(page_size) => ExecutionEngine::with_page_size(catalog.clone(), page_size),
+        None => ExecutionEngine::new(catalog.clone()),
+    };
+    let mut engine = match engine {
+        Ok(engine) => engine,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     print_banner();
 
@@ -227,7 +250,9 @@ fn run_repl() {
         let mut line = String::new();
         match io::stdin().read_line(&mut line) {
             Ok(0) => {
-                catalog.save_to_disk("arcdb.meta").ok();
+                if let Err(e) = catalog.save_to_disk(engine.catalog_path()) {
+                    eprintln!("Error: {}", e);
+                }
                 break;
             } // EOF
             Ok(_) => {}
@@ -241,7 +266,7 @@ fn run_repl() {
 
         // Handle special commands
         if !in_multiline && trimmed.starts_with('.') {
-            handle_special_command(trimmed, &catalog);
+            handle_special_command(trimmed, &catalog, &engine);
             continue;
         }
 
@@ -275,5 +300,18 @@ fn run_repl() {
 }
 
 fn main() {
-    run_repl();
+    // Optional page size for a new database: --page-size <bytes>
+    let args: Vec<String> = std::env::args().collect();
+    let page_size = args
+        .iter()
+        .position(|a| a == "--page-size")
+        .and_then(|i| args.get(i + 1))
+        .map(|s| {
+            s.parse().unwrap_or_else(|_| {
+                eprintln!("Error: invalid page size '{}'", s);
+                std::process::exit(1);
+            })
+        });
+
+    run_repl(page_size);
 }
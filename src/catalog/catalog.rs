@@ -2,12 +2,20 @@
 //!
 //! This module manages metadata about tables, indexes, and other database objects.
 
-use super::schema::{Column, IndexDef, Schema, TableDef, TableStatistics};
+use super::schema::{
+    Column, Constraint, ConstraintKind, ForeignKey, IndexDef, Schema, Sequence, TableDef,
+    TableStatistics,
+};
 use super::types::DataType;
 use crate::error::{Error, Result};
+use crate::storage::format::{self, FORMAT_VERSION, JSON_MAGIC};
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::{Arc, RwLock};
 
+/// Name of the catalog file within a data directory
+pub const CATALOG_FILE: &str = "arcdb.meta";
+
 /// System Catalog - manages all database metadata
 #[derive(Debug)]
 pub struct Catalog {
@@ -15,6 +23,8 @@ pub struct Catalog {
     tables: RwLock<HashMap<String, Arc<TableDef>>>,
     /// Index definitions by name
     indexes: RwLock<HashMap<String, Arc<IndexDef>>>,
+    /// Sequences by name
+    sequences: RwLock<HashMap<String, Sequence>>,
     /// Next table ID
     next_table_id: RwLock<u32>,
     /// Next index ID
@@ -27,6 +37,7 @@ impl Catalog {
         Self {
             tables: RwLock::new(HashMap::new()),
             indexes: RwLock::new(HashMap::new()),
+            sequences: RwLock::new(HashMap::new()),
             next_table_id: RwLock::new(1),
             next_index_id: RwLock::new(1),
         }
@@ -34,6 +45,19 @@ impl Catalog {
 
     /// Create a new table
     pub fn create_table(&self, name: &str, schema: Schema) -> Result<Arc<TableDef>> {
+        self.create_table_with_constraints(name, schema, Vec::new())
+    }
+
+    /// Create a new table with constraints on its rows
+    ///
+    /// The indexes backing key constraints are created separately with
+    /// `create_constraint_index`.
+    pub fn create_table_with_constraints(
+        &self,
+        name: &str,
+        schema: Schema,
+        constraints: Vec<Constraint>,
+    ) -> Result<Arc<TableDef>> {
         let mut tables = self.tables.write().unwrap();
 
         if tables.contains_key(name) {
@@ -41,7 +65,9 @@ impl Catalog {
         }
 
         let mut next_id = self.next_table_id.write().unwrap();
-        let table_def = Arc::new(TableDef::new(name, schema, *next_id));
+        let mut table_def = TableDef::new(name, schema, *next_id);
+        table_def.constraints = constraints;
+        let table_def = Arc::new(table_def);
         *next_id += 1;
 
         tables.insert(name.to_string(), table_def.clone());
@@ -98,6 +124,48 @@ impl Catalog {
         Ok(())
     }
 
+    /// Replace the definition of a table changed by ALTER TABLE
+    ///
+    /// The new definition may rename the table, in which case its indexes
+    /// follow it.
+    pub fn alter_table(&self, name: &str, def: TableDef) -> Result<Arc<TableDef>> {
+        let mut tables = self.tables.write().unwrap();
+        if !tables.contains_key(name) {
+            return Err(Error::TableNotFound(name.to_string()));
+        }
+        if def.name != name && tables.contains_key(&def.name) {
+            return Err(Error::TableAlreadyExists(def.name.clone()));
+        }
+
+        let def = Arc::new(def);
+        tables.remove(name);
+        tables.insert(def.name.clone(), def.clone());
+
+        if def.name != name {
+            let mut indexes = self.indexes.write().unwrap();
+            for index in indexes.values_mut() {
+                if index.table_name == name {
+                    Arc::make_mut(index).table_name = def.name.clone();
+                }
+            }
+        }
+        Ok(def)
+    }
+
+    /// Rename a column in the indexes of a table
+    pub fn rename_index_column(&self, table_name: &str, name: &str, new_name: &str) {
+        let mut indexes = self.indexes.write().unwrap();
+        for index in indexes.values_mut() {
+            if index.table_name == table_name && index.columns.iter().any(|c| c == name) {
+                for column in &mut Arc::make_mut(index).columns {
+                    if column == name {
+                        *column = new_name.to_string();
+                    }
+                }
+            }
+        }
+    }
+
     /// Create an index
     pub fn create_index(
         &self,
@@ -105,6 +173,28 @@ impl Catalog {
         table_name: &str,
         columns: Vec<String>,
         unique: bool,
+    ) -> Result<Arc<IndexDef>> {
+        self.add_index(name, table_name, columns, unique, false)
+    }
+
+    /// Create the unique index backing a PRIMARY KEY or UNIQUE constraint
+    pub fn create_constraint_index(
+        &self,
+        constraint: &Constraint,
+        table_name: &str,
+    ) -> Result<Arc<IndexDef>> {
+        let primary = matches!(constraint.kind, ConstraintKind::PrimaryKey(_));
+        let columns = constraint.key_columns().to_vec();
+        self.add_index(&constraint.name, table_name, columns, true, primary)
+    }
+
+    fn add_index(
+        &self,
+        name: &str,
+        table_name: &str,
+        columns: Vec<String>,
+        unique: bool,
+        primary: bool,
     ) -> Result<Arc<IndexDef>> {
         // Verify table exists
         let table = self.get_table(table_name)?;
@@ -126,7 +216,11 @@ impl Catalog {
         }
 
         let mut next_id = self.next_index_id.write().unwrap();
-        let index_def = Arc::new(IndexDef::new(name, table_name, columns, *next_id).unique(unique));
+        let index_def = Arc::new(
+            IndexDef::new(name, table_name, columns, *next_id)
+                .unique(unique)
+                .primary(primary),
+        );
         *next_id += 1;
 
         indexes.insert(name.to_string(), index_def.clone());
@@ -163,12 +257,109 @@ impl Catalog {
             .collect()
     }
 
+    /// Foreign keys referencing a table, with their tables and constraint names
+    pub fn get_referencing_foreign_keys(
+        &self,
+        table_name: &str,
+    ) -> Vec<(Arc<TableDef>, String, ForeignKey)> {
+        let tables = self.tables.read().unwrap();
+        let mut foreign_keys = Vec::new();
+        for table in tables.values() {
+            for (name, fk) in table.foreign_keys() {
+                if fk.ref_table == table_name {
+                    foreign_keys.push((table.clone(), name.to_string(), fk.clone()));
+                }
+            }
+        }
+        foreign_keys.sort_by(|a, b| (&a.0.name, &a.1).cmp(&(&b.0.name, &b.1)));
+        foreign_keys
+    }
+
     /// List all index names
     pub fn list_indexes(&self) -> Vec<String> {
         let indexes = self.indexes.read().unwrap();
         indexes.keys().cloned().collect()
     }
 
+    // ========== Sequences ==========
+
+    /// Create a sequence
+    pub fn create_sequence(&self, sequence: Sequence) -> Result<()> {
+        let mut sequences = self.sequences.write().unwrap();
+        if sequences.contains_key(&sequence.name) {
+            return Err(Error::SequenceAlreadyExists(sequence.name));
+        }
+        sequences.insert(sequence.name.clone(), sequence);
+        Ok(())
+    }
+
+    /// Drop a sequence
+    pub fn drop_sequence(&self, name: &str) -> Result<()> {
+        self.sequences
+            .write()
+            .unwrap()
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| Error::SequenceNotFound(name.to_string()))
+    }
+
+    /// Get a sequence by name
+    pub fn get_sequence(&self, name: &str) -> Result<Sequence> {
+        self.sequences
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Error::SequenceNotFound(name.to_string()))
+    }
+
+    /// Check if a sequence exists
+    pub fn sequence_exists(&self, name: &str) -> bool {
+        self.sequences.read().unwrap().contains_key(name)
+    }
+
+    /// The table and column whose identity values a sequence generates
+    pub fn get_sequence_owner(&self, name: &str) -> Option<(String, String)> {
+        let tables = self.tables.read().unwrap();
+        tables.values().find_map(|table| {
+            table
+                .schema()
+                .columns()
+                .iter()
+                .find(|c| c.identity.as_ref().is_some_and(|i| i.sequence == name))
+                .map(|c| (table.name.clone(), c.name.clone()))
+        })
+    }
+
+    /// Advance a sequence, returning its new value and, when more values were
+    /// reserved, the last value reserved, which must be made durable
+    pub fn next_sequence_value(&self, name: &str) -> Result<(i64, Option<i64>)> {
+        self.sequences
+            .write()
+            .unwrap()
+            .get_mut(name)
+            .ok_or_else(|| Error::SequenceNotFound(name.to_string()))?
+            .next_value()
+    }
+
+    /// Set the last value returned by a sequence
+    pub fn set_sequence_value(&self, name: &str, value: i64) -> Result<()> {
+        self.sequences
+            .write()
+            .unwrap()
+            .get_mut(name)
+            .ok_or_else(|| Error::SequenceNotFound(name.to_string()))?
+            .set_value(value)
+    }
+
+    /// Continue a sequence after a value reserved in the log
+    pub fn restore_sequence(&self, name: &str, reserved: i64) {
+        if let Some(sequence) = self.sequences.write().unwrap().get_mut(name) {
+            sequence.reserved = Some(reserved);
+            sequence.resume();
+        }
+    }
+
     /// Get table schema info as a formatted string (for .schema command)
     pub fn get_table_info(&self, name: &str) -> Result<String> {
         let table = self.get_table(name)?;
@@ -186,6 +377,11 @@ impl Catalog {
             if col.unique {
                 flags.push("UNIQUE");
             }
+            match &col.identity {
+                Some(identity) if identity.always => flags.push("GENERATED ALWAYS AS IDENTITY"),
+                Some(_) => flags.push("GENERATED BY DEFAULT AS IDENTITY"),
+                None => {}
+            }
 
             let flags_str = if flags.is_empty() {
                 String::new()
@@ -196,6 +392,13 @@ impl Catalog {
             info.push_str(&format!("  {} {}{}\n", col.name, col.data_type, flags_str));
         }
 
+        if !table.constraints.is_empty() {
+            info.push_str("Constraints:\n");
+            for constraint in &table.constraints {
+                info.push_str(&format!("  {} {}\n", constraint.name, constraint.kind));
+            }
+        }
+
         // Add index info
         let indexes = self.get_table_indexes(name);
         if !indexes.is_empty() {
@@ -214,8 +417,10 @@ impl Catalog {
     }
 
     /// Save catalog to disk
-    pub fn save_to_disk(&self, path: &str) -> Result<()> {
+    pub fn save_to_disk(&self, path: impl AsRef<Path>) -> Result<()> {
         let data = CatalogData {
+            magic: JSON_MAGIC.to_string(),
+            format_version: FORMAT_VERSION,
             tables: self
                 .tables
                 .read()
@@ -230,22 +435,32 @@ impl Catalog {
                 .values()
                 .map(|i| (**i).clone())
                 .collect(),
+            sequences: self.sequences.read().unwrap().values().cloned().collect(),
             next_table_id: *self.next_table_id.read().unwrap(),
             next_index_id: *self.next_index_id.read().unwrap(),
         };
 
         let json =
             serde_json::to_string_pretty(&data).map_err(|e| Error::Internal(e.to_string()))?;
-        std::fs::write(path, json).map_err(|e| Error::IoError(e))?;
+        std::fs::write(path, json).map_err(Error::IoError)?;
         Ok(())
     }
 
     /// Load catalog from disk
-    pub fn load_from_disk(path: &str) -> Result<Self> {
-        let json = std::fs::read_to_string(path).map_err(|e| Error::IoError(e))?;
+    ///
+    /// Catalogs written before format headers existed are rewritten with one.
+    pub fn load_from_disk(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(Error::IoError)?;
         let data: CatalogData =
             serde_json::from_str(&json).map_err(|e| Error::Internal(e.to_string()))?;
 
+        let legacy = data.magic.is_empty();
+        if !legacy && data.magic != JSON_MAGIC {
+            return Err(Error::InvalidFileHeader(path.display().to_string()));
+        }
+        format::check_version(data.format_version)?;
+
         let mut tables = HashMap::new();
         for table in data.tables {
             tables.insert(table.name.clone(), Arc::new(table));
@@ -256,20 +471,40 @@ impl Catalog {
             indexes.insert(index.name.clone(), Arc::new(index));
         }
 
-        Ok(Self {
+        // Values reserved before the catalog was written may have been used
+        let mut sequences = HashMap::new();
+        for mut sequence in data.sequences {
+            sequence.resume();
+            sequences.insert(sequence.name.clone(), sequence);
+        }
+
+        let catalog = Self {
             tables: RwLock::new(tables),
             indexes: RwLock::new(indexes),
+            sequences: RwLock::new(sequences),
             next_table_id: RwLock::new(data.next_table_id),
             next_index_id: RwLock::new(data.next_index_id),
-        })
+        };
+
+        if legacy || data.format_version < FORMAT_VERSION {
+            catalog.save_to_disk(path)?;
+        }
+        Ok(catalog)
     }
 }
 
 /// Serializable proxy for Catalog
 #[derive(serde::Serialize, serde::Deserialize)]
 struct CatalogData {
+    /// Missing in catalogs written before format headers existed
+    #[serde(default)]
+    magic: String,
+    #[serde(default)]
+    format_version: u32,
     tables: Vec<TableDef>,
     indexes: Vec<IndexDef>,
+    #[serde(default)]
+    sequences: Vec<Sequence>,
     next_table_id: u32,
     next_index_id: u32,
 }
@@ -405,4 +640,53 @@ mod tests {
         let indexes = catalog.get_table_indexes("users");
         assert_eq!(indexes.len(), 1);
     }
+
+    #[test]
+    fn test_alter_table_rename() {
+        let catalog = Catalog::new();
+        let mut schema = Schema::new();
+        schema.add_column(Column::new("id", DataType::Integer, 0));
+        schema.add_column(Column::new("email", DataType::Text, 1));
+        catalog.create_table("users", schema).unwrap();
+        catalog
+            .create_index("idx_email", "users", vec!["email".to_string()], false)
+            .unwrap();
+
+        let mut def = (*catalog.get_table("users").unwrap()).clone();
+        def.name = "people".to_string();
+        def.schema.rename_column("email", "mail");
+        let altered = catalog.alter_table("users", def).unwrap();
+        catalog.rename_index_column("people", "email", "mail");
+
+        assert_eq!(altered.name, "people");
+        assert!(!catalog.table_exists("users"));
+        assert!(catalog
+            .get_table("people")
+            .unwrap()
+            .get_column("mail")
+            .is_some());
+        let index = catalog.get_index("idx_email").unwrap();
+        assert_eq!(index.table_name, "people");
+        assert_eq!(index.columns, vec!["mail"]);
+    }
+
+    #[test]
+    fn test_load_upgrades_legacy_catalog() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("arcdb.meta");
+        let path = path.to_str().unwrap();
+        std::fs::write(
+            path,
+            r#"{"tables": [], "indexes": [], "next_table_id": 4, "next_index_id": 2}"#,
+        )
+        .unwrap();
+
+        let catalog = Catalog::load_from_disk(path).unwrap();
+        assert_eq!(*catalog.next_table_id.read().unwrap(), 4);
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(json["magic"], JSON_MAGIC);
+        assert_eq!(json["format_version"], FORMAT_VERSION);
+    }
 }
@@ -2,10 +2,14 @@
 //!
 //! This module contains the system catalog, schema definitions, and data types.
 
+#[allow(clippy::module_inception)]
 pub mod catalog;
 pub mod schema;
 pub mod types;
 
-pub use catalog::Catalog;
-pub use schema::{Column, IndexDef, Schema, TableDef, TableStatistics};
+pub use catalog::{Catalog, CATALOG_FILE};
+pub use schema::{
+    Column, Constraint, ConstraintKind, ForeignKey, Identity, IndexDef, ReferentialAction, Schema,
+    Sequence, StorageLayout, StoredColumn, TableDef, TableStatistics, SEQUENCE_RESERVE,
+};
 pub use types::DataType;
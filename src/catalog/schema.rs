@@ -3,8 +3,11 @@
 //! This module defines table schemas and column metadata.
 
 use super::types::DataType;
+use crate::error::{Error, Result};
+use crate::storage::tuple::{Tuple, Value};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
 
 /// Column definition in a table
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +26,18 @@ pub struct Column {
     pub primary_key: bool,
     /// Is this column unique?
     pub unique: bool,
+    /// Sequence generating the values of a SERIAL or identity column
+    #[serde(default)]
+    pub identity: Option<Identity>,
+}
+
+/// How a SERIAL or identity column is generated
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Identity {
+    /// Sequence owned by the column
+    pub sequence: String,
+    /// GENERATED ALWAYS: only DEFAULT may be written to the column
+    pub always: bool,
 }
 
 impl Column {
@@ -36,6 +51,7 @@ impl Column {
             default: None,
             primary_key: false,
             unique: false,
+            identity: None,
         }
     }
 
@@ -65,6 +81,13 @@ impl Column {
         self.unique = unique;
         self
     }
+
+    /// Generate the column's values from a sequence, by default
+    pub fn identity(mut self, identity: Identity) -> Self {
+        self.default = Some(format!("nextval('{}')", identity.sequence));
+        self.identity = Some(identity);
+        self
+    }
 }
 
 /// Table schema - defines the structure of a table
@@ -141,6 +164,33 @@ impl Schema {
     pub fn column_names(&self) -> Vec<&str> {
         self.columns.iter().map(|c| c.name.as_str()).collect()
     }
+
+    /// Get a mutable column by name
+    pub fn get_column_mut(&mut self, name: &str) -> Option<&mut Column> {
+        let idx = *self.name_to_index.get(name)?;
+        Some(&mut self.columns[idx])
+    }
+
+    /// Remove a column, renumbering the ones after it
+    pub fn remove_column(&mut self, name: &str) -> Option<Column> {
+        let idx = self.name_to_index.remove(name)?;
+        let column = self.columns.remove(idx);
+        for col in &mut self.columns[idx..] {
+            col.position -= 1;
+            self.name_to_index.insert(col.name.clone(), col.position);
+        }
+        Some(column)
+    }
+
+    /// Rename a column, returning false if it does not exist
+    pub fn rename_column(&mut self, name: &str, new_name: &str) -> bool {
+        let Some(idx) = self.name_to_index.remove(name) else {
+            return false;
+        };
+        self.columns[idx].name = new_name.to_string();
+        self.name_to_index.insert(new_name.to_string(), idx);
+        true
+    }
 }
 
 impl Default for Schema {
@@ -166,6 +216,157 @@ pub struct TableDef {
     pub id: u32,
     /// Table statistics
     pub stats: Option<TableStatistics>,
+    /// Where columns are stored in tuples once the schema has been altered;
+    /// `None` while tuples hold the columns in schema order
+    #[serde(default)]
+    pub layout: Option<StorageLayout>,
+    /// Table constraints, including column PRIMARY KEY and UNIQUE
+    #[serde(default)]
+    pub constraints: Vec<Constraint>,
+}
+
+/// Constraint on the rows of a table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Constraint {
+    /// Constraint name, reported when a row violates it
+    pub name: String,
+    /// What the constraint requires
+    pub kind: ConstraintKind,
+}
+
+/// Kind of table constraint
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConstraintKind {
+    /// PRIMARY KEY, backed by a unique index named after the constraint
+    PrimaryKey(Vec<String>),
+    /// UNIQUE, backed by a unique index named after the constraint
+    Unique(Vec<String>),
+    /// CHECK, with the expression stored as SQL
+    Check(String),
+    /// FOREIGN KEY, referencing a key of another table
+    ForeignKey(ForeignKey),
+}
+
+/// Columns whose values must match a row of the referenced table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForeignKey {
+    /// Referencing columns
+    pub columns: Vec<String>,
+    /// Referenced table
+    pub ref_table: String,
+    /// Columns of a PRIMARY KEY or UNIQUE constraint of the referenced table
+    pub ref_columns: Vec<String>,
+    /// Action when a referenced row is deleted
+    pub on_delete: ReferentialAction,
+    /// Action when the key of a referenced row is updated
+    pub on_update: ReferentialAction,
+}
+
+/// What happens to referencing rows when the row they reference goes away
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReferentialAction {
+    /// Fail the statement
+    #[default]
+    NoAction,
+    /// Fail the statement
+    Restrict,
+    /// Delete the referencing rows, or update their keys
+    Cascade,
+    /// Set the referencing columns to NULL
+    SetNull,
+    /// Set the referencing columns to their defaults
+    SetDefault,
+}
+
+impl Constraint {
+    /// Columns of a key constraint, empty for CHECK and FOREIGN KEY
+    pub fn key_columns(&self) -> &[String] {
+        match &self.kind {
+            ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns) => columns,
+            ConstraintKind::Check(_) | ConstraintKind::ForeignKey(_) => &[],
+        }
+    }
+}
+
+impl fmt::Display for ConstraintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConstraintKind::PrimaryKey(columns) => {
+                write!(f, "PRIMARY KEY ({})", columns.join(", "))
+            }
+            ConstraintKind::Unique(columns) => write!(f, "UNIQUE ({})", columns.join(", ")),
+            ConstraintKind::Check(expr) => write!(f, "CHECK ({})", expr),
+            ConstraintKind::ForeignKey(fk) => write!(f, "{}", fk),
+        }
+    }
+}
+
+impl fmt::Display for ForeignKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FOREIGN KEY ({}) REFERENCES {} ({})",
+            self.columns.join(", "),
+            self.ref_table,
+            self.ref_columns.join(", ")
+        )?;
+        if self.on_delete != ReferentialAction::NoAction {
+            write!(f, " ON DELETE {}", self.on_delete)?;
+        }
+        if self.on_update != ReferentialAction::NoAction {
+            write!(f, " ON UPDATE {}", self.on_update)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReferentialAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReferentialAction::NoAction => write!(f, "NO ACTION"),
+            ReferentialAction::Restrict => write!(f, "RESTRICT"),
+            ReferentialAction::Cascade => write!(f, "CASCADE"),
+            ReferentialAction::SetNull => write!(f, "SET NULL"),
+            ReferentialAction::SetDefault => write!(f, "SET DEFAULT"),
+        }
+    }
+}
+
+/// Mapping between the columns of a table and the values of its stored tuples
+///
+/// Stored slots are never reused: a dropped column keeps its slot, written as
+/// NULL, and an added column takes a new slot at the end. Tuples written
+/// before a column was added are shorter and read its `missing` value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageLayout {
+    /// Number of values in a tuple written under the current schema
+    pub width: usize,
+    /// Stored slot of each column, in schema order
+    pub slots: Vec<StoredColumn>,
+}
+
+/// Where a column is stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredColumn {
+    /// Index of the column's value in stored tuples
+    pub slot: usize,
+    /// Value read from tuples too short to hold the slot
+    pub missing: Value,
+}
+
+impl StorageLayout {
+    /// Layout storing `count` columns in order
+    fn identity(count: usize) -> Self {
+        Self {
+            width: count,
+            slots: (0..count)
+                .map(|slot| StoredColumn {
+                    slot,
+                    missing: Value::Null,
+                })
+                .collect(),
+        }
+    }
 }
 
 impl TableDef {
@@ -176,6 +377,8 @@ impl TableDef {
             schema,
             id,
             stats: None,
+            layout: None,
+            constraints: Vec::new(),
         }
     }
 
@@ -193,6 +396,227 @@ impl TableDef {
     pub fn get_column(&self, name: &str) -> Option<&Column> {
         self.schema.get_column(name)
     }
+
+    /// Read a stored tuple as a row of the current schema
+    pub fn decode(&self, stored: Tuple) -> Tuple {
+        let Some(layout) = &self.layout else {
+            return stored;
+        };
+        let values = layout
+            .slots
+            .iter()
+            .map(|col| stored.get(col.slot).unwrap_or(&col.missing).clone())
+            .collect();
+        Tuple::new(values)
+    }
+
+    /// Convert a row of the current schema to the tuple stored for it
+    pub fn encode(&self, row: Tuple) -> Tuple {
+        let Some(layout) = &self.layout else {
+            return row;
+        };
+        let mut values = vec![Value::Null; layout.width];
+        for (col, value) in layout.slots.iter().zip(row.into_values()) {
+            values[col.slot] = value;
+        }
+        Tuple::new(values)
+    }
+
+    /// Add a column, read as `missing` from the rows already stored
+    pub fn add_column(&mut self, column: Column, missing: Value) {
+        let mut layout = self.take_layout();
+        layout.slots.push(StoredColumn {
+            slot: layout.width,
+            missing,
+        });
+        layout.width += 1;
+        self.layout = Some(layout);
+        self.schema.add_column(column);
+    }
+
+    /// Drop a column, keeping its slot in stored tuples
+    pub fn drop_column(&mut self, name: &str) -> Option<Column> {
+        let idx = self.schema.get_column_index(name)?;
+        let mut layout = self.take_layout();
+        layout.slots.remove(idx);
+        self.layout = Some(layout);
+        self.schema.remove_column(name)
+    }
+
+    /// The PRIMARY KEY or UNIQUE constraint on exactly the given columns
+    pub fn key_constraint(&self, columns: &[String]) -> Option<&Constraint> {
+        self.constraints.iter().find(|c| {
+            let key = c.key_columns();
+            !key.is_empty()
+                && key.len() == columns.len()
+                && key.iter().all(|column| columns.contains(column))
+        })
+    }
+
+    /// Columns of the primary key constraint
+    pub fn primary_key(&self) -> Option<&[String]> {
+        self.constraints.iter().find_map(|c| match &c.kind {
+            ConstraintKind::PrimaryKey(columns) => Some(columns.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// Foreign keys of this table, with their constraint names
+    pub fn foreign_keys(&self) -> impl Iterator<Item = (&str, &ForeignKey)> {
+        self.constraints.iter().filter_map(|c| match &c.kind {
+            ConstraintKind::ForeignKey(fk) => Some((c.name.as_str(), fk)),
+            _ => None,
+        })
+    }
+
+    fn take_layout(&mut self) -> StorageLayout {
+        self.layout
+            .take()
+            .unwrap_or_else(|| StorageLayout::identity(self.schema.column_count()))
+    }
+}
+
+/// Number of sequence values reserved by each write to the catalog and log
+pub const SEQUENCE_RESERVE: u32 = 32;
+
+/// A generator of integers, advanced outside of transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sequence {
+    /// Sequence name
+    pub name: String,
+    /// Step between values, negative for a descending sequence
+    pub increment: i64,
+    /// Smallest value
+    pub min_value: i64,
+    /// Largest value
+    pub max_value: i64,
+    /// First value
+    pub start: i64,
+    /// Whether to wrap around past the last value instead of failing
+    pub cycle: bool,
+    /// Last value returned, None before the first
+    pub last_value: Option<i64>,
+    /// Last value reserved by a durable write; values up to it may have been
+    /// returned before a crash, so a reopened sequence continues after it
+    #[serde(default)]
+    pub reserved: Option<i64>,
+    /// Values left to return before reserving more
+    #[serde(skip)]
+    pub cached: u32,
+}
+
+impl Sequence {
+    /// Create a sequence, failing unless its bounds hold the start value
+    pub fn new(
+        name: impl Into<String>,
+        increment: i64,
+        min_value: i64,
+        max_value: i64,
+        start: i64,
+        cycle: bool,
+    ) -> Result<Self> {
+        let name = name.into();
+        if increment == 0 {
+            return Err(Error::SemanticError(format!(
+                "INCREMENT of sequence '{}' must not be zero",
+                name
+            )));
+        }
+        if min_value >= max_value {
+            return Err(Error::SemanticError(format!(
+                "MINVALUE ({}) of sequence '{}' must be less than MAXVALUE ({})",
+                min_value, name, max_value
+            )));
+        }
+        if !(min_value..=max_value).contains(&start) {
+            return Err(Error::SemanticError(format!(
+                "START value ({}) of sequence '{}' must be between {} and {}",
+                start, name, min_value, max_value
+            )));
+        }
+        Ok(Self {
+            name,
+            increment,
+            min_value,
+            max_value,
+            start,
+            cycle,
+            last_value: None,
+            reserved: None,
+            cached: 0,
+        })
+    }
+
+    /// The value following `value`, or the start before the first value
+    fn step(&self, value: Option<i64>) -> Result<i64> {
+        let Some(value) = value else {
+            return Ok(self.start);
+        };
+        match value.checked_add(self.increment) {
+            Some(next) if (self.min_value..=self.max_value).contains(&next) => Ok(next),
+            _ if self.cycle && self.increment > 0 => Ok(self.min_value),
+            _ if self.cycle => Ok(self.max_value),
+            _ => Err(Error::ExecutionError(format!(
+                "nextval: reached {} value of sequence '{}' ({})",
+                if self.increment > 0 {
+                    "maximum"
+                } else {
+                    "minimum"
+                },
+                self.name,
+                if self.increment > 0 {
+                    self.max_value
+                } else {
+                    self.min_value
+                }
+            ))),
+        }
+    }
+
+    /// Advance the sequence, returning the new value and, when more values
+    /// had to be reserved, the last value reserved
+    pub fn next_value(&mut self) -> Result<(i64, Option<i64>)> {
+        let value = self.step(self.last_value)?;
+        self.last_value = Some(value);
+
+        let mut reserved = None;
+        if self.cached == 0 {
+            let mut last = value;
+            for _ in 1..SEQUENCE_RESERVE {
+                match self.step(Some(last)) {
+                    Ok(next) => last = next,
+                    Err(_) => break,
+                }
+            }
+            self.reserved = Some(last);
+            self.cached = SEQUENCE_RESERVE;
+            reserved = Some(last);
+        }
+        self.cached -= 1;
+        Ok((value, reserved))
+    }
+
+    /// Set the last value returned; the next value follows it
+    pub fn set_value(&mut self, value: i64) -> Result<()> {
+        if !(self.min_value..=self.max_value).contains(&value) {
+            return Err(Error::ExecutionError(format!(
+                "setval: value {} is out of bounds for sequence '{}' ({}..{})",
+                value, self.name, self.min_value, self.max_value
+            )));
+        }
+        self.last_value = Some(value);
+        self.reserved = Some(value);
+        self.cached = 0;
+        Ok(())
+    }
+
+    /// Continue after the values reserved before the sequence was last written
+    pub fn resume(&mut self) {
+        if self.reserved.is_some() {
+            self.last_value = self.reserved;
+        }
+        self.cached = 0;
+    }
 }
 
 /// Index definition
@@ -275,4 +699,35 @@ mod tests {
         assert_eq!(table.schema().column_count(), 2);
         assert!(table.get_column("id").is_some());
     }
+
+    #[test]
+    fn test_altered_layout() {
+        let mut schema = Schema::new();
+        schema.add_column(Column::new("a", DataType::Integer, 0));
+        schema.add_column(Column::new("b", DataType::Integer, 1));
+        let mut table = TableDef::new("t", schema, 1);
+        let old = table.encode(Tuple::new(vec![Value::Integer(1), Value::Integer(2)]));
+
+        table.add_column(Column::new("c", DataType::Integer, 0), Value::Integer(9));
+        table.drop_column("a").unwrap();
+        assert_eq!(table.schema().column_names(), vec!["b", "c"]);
+        assert_eq!(table.get_column("c").unwrap().position, 1);
+
+        // Rows written before the change read the default of the new column
+        assert_eq!(
+            table.decode(old).into_values(),
+            vec![Value::Integer(2), Value::Integer(9)]
+        );
+
+        // New rows keep the dropped column's slot
+        let new = table.encode(Tuple::new(vec![Value::Integer(3), Value::Integer(4)]));
+        assert_eq!(
+            new.values(),
+            &[Value::Null, Value::Integer(3), Value::Integer(4)]
+        );
+        assert_eq!(
+            table.decode(new).into_values(),
+            vec![Value::Integer(3), Value::Integer(4)]
+        );
+    }
 }
@@ -34,6 +34,8 @@ pub enum DataType {
     Time,
     /// Timestamp (date + time)
     Timestamp,
+    /// Interval (months, days and time)
+    Interval,
     /// Binary data
     Blob,
 }
@@ -50,9 +52,10 @@ impl DataType {
             DataType::Double => Some(8),
             DataType::Decimal(_, _) => Some(16), // Fixed size for decimal
             DataType::Char(n) => Some(*n),
-            DataType::Date => Some(4),       // Days since epoch
-            DataType::Time => Some(8),       // Microseconds since midnight
-            DataType::Timestamp => Some(12), // Date + Time
+            DataType::Date => Some(4),      // Days since epoch
+            DataType::Time => Some(8),      // Milliseconds since midnight
+            DataType::Timestamp => Some(8), // Milliseconds since epoch
+            DataType::Interval => Some(16), // Months, days and milliseconds
             // Variable-length types
             DataType::Varchar(_) => None,
             DataType::Text => None,
@@ -96,10 +99,44 @@ impl DataType {
             (DataType::Timestamp, DataType::Timestamp) => true,
             (DataType::Date, DataType::Timestamp) => true,
             (DataType::Timestamp, DataType::Date) => true,
+            (DataType::Interval, DataType::Interval) => true,
             _ => false,
         }
     }
 
+    /// Check if a value of this type may be stored in a column of `target`
+    pub fn is_assignable_to(&self, target: &DataType) -> bool {
+        match (self, target) {
+            (a, b) if a == b => true,
+            (a, b) if a.is_numeric() && b.is_numeric() => true,
+            (a, b) if a.is_string() && b.is_string() => true,
+            (DataType::Date, DataType::Timestamp) => true,
+            _ => false,
+        }
+    }
+
+    /// Check if an explicit CAST from this type to `target` is supported
+    pub fn can_cast_to(&self, target: &DataType) -> bool {
+        let is_integer =
+            |t: &DataType| matches!(t, DataType::SmallInt | DataType::Integer | DataType::BigInt);
+        match target {
+            _ if self.is_assignable_to(target) => true,
+            _ if target.is_string() => *self != DataType::Blob,
+            t if is_integer(t) => self.is_string() || *self == DataType::Boolean,
+            t if t.is_numeric() => self.is_string(),
+            DataType::Boolean => is_integer(self) || self.is_string(),
+            DataType::Blob => self.is_string(),
+            DataType::Date | DataType::Time => self.is_string() || *self == DataType::Timestamp,
+            DataType::Timestamp | DataType::Interval => self.is_string(),
+            _ => false,
+        }
+    }
+
+    /// Check if this type is a date, time or timestamp
+    pub fn is_temporal(&self) -> bool {
+        matches!(self, DataType::Date | DataType::Time | DataType::Timestamp)
+    }
+
     /// Get the default value for this type
     pub fn default_value(&self) -> &'static str {
         match self {
@@ -110,6 +147,7 @@ impl DataType {
             DataType::Date => "CURRENT_DATE",
             DataType::Time => "CURRENT_TIME",
             DataType::Timestamp => "CURRENT_TIMESTAMP",
+            DataType::Interval => "INTERVAL '0'",
             DataType::Blob => "NULL",
         }
     }
@@ -131,6 +169,7 @@ impl fmt::Display for DataType {
             DataType::Date => write!(f, "DATE"),
             DataType::Time => write!(f, "TIME"),
             DataType::Timestamp => write!(f, "TIMESTAMP"),
+            DataType::Interval => write!(f, "INTERVAL"),
             DataType::Blob => write!(f, "BLOB"),
         }
     }
@@ -153,4 +192,16 @@ mod tests {
         assert!(DataType::Varchar(50).is_comparable_with(&DataType::Text));
         assert!(!DataType::Integer.is_comparable_with(&DataType::Text));
     }
+
+    #[test]
+    fn test_assignment_and_cast() {
+        assert!(DataType::Integer.is_assignable_to(&DataType::BigInt));
+        assert!(DataType::Text.is_assignable_to(&DataType::Varchar(10)));
+        assert!(!DataType::Text.is_assignable_to(&DataType::Integer));
+        assert!(DataType::Text.can_cast_to(&DataType::Integer));
+        assert!(DataType::Integer.can_cast_to(&DataType::Text));
+        assert!(!DataType::Date.can_cast_to(&DataType::Integer));
+        assert!(DataType::Text.can_cast_to(&DataType::Interval));
+        assert!(DataType::Timestamp.can_cast_to(&DataType::Date));
+    }
 }
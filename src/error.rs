@@ -27,6 +27,13 @@ pub enum Error {
     #[error("Parse error: {0}")]
     ParseError(String),
 
+    // ========== Semantic Errors ==========
+    #[error("Semantic error: column reference '{0}' is ambiguous")]
+    AmbiguousColumn(String),
+
+    #[error("Semantic error: {0}")]
+    SemanticError(String),
+
     // ========== Catalog Errors ==========
     #[error("Catalog error: table '{0}' not found")]
     TableNotFound(String),
@@ -46,6 +53,12 @@ pub enum Error {
     #[error("Catalog error: index '{0}' already exists")]
     IndexAlreadyExists(String),
 
+    #[error("Catalog error: sequence '{0}' not found")]
+    SequenceNotFound(String),
+
+    #[error("Catalog error: sequence '{0}' already exists")]
+    SequenceAlreadyExists(String),
+
     // ========== Type Errors ==========
     #[error("Type error: cannot convert {from} to {to}")]
     TypeMismatch { from: String, to: String },
@@ -56,6 +69,13 @@ pub enum Error {
     #[error("Type error: value too large for column '{0}'")]
     ValueTooLarge(String),
 
+    #[error("Type error: operator {op} cannot be applied to {left} and {right}")]
+    OperatorTypeMismatch {
+        op: String,
+        left: String,
+        right: String,
+    },
+
     // ========== Execution Errors ==========
     #[error("Storage error: {0}")]
     StorageError(String),
@@ -90,6 +110,12 @@ pub enum Error {
     #[error("Storage error: file '{0}' not found")]
     FileNotFound(String),
 
+    #[error("Storage error: invalid header in file '{0}'")]
+    InvalidFileHeader(String),
+
+    #[error("Storage error: unsupported format version {found} (supported up to {supported})")]
+    UnsupportedFormatVersion { found: u32, supported: u32 },
+
     // ========== I/O Errors ==========
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
@@ -0,0 +1,2332 @@
+//! Semantic analysis for ArcDB
+//!
+//! The analyzer runs between the parser and the planner. It resolves table
+//! and column names against the catalog, infers the type of every
+//! expression, inserts implicit casts where a value is converted on write
+//! or where join keys of different numeric types are compared, and rejects
+//! ill-typed statements before any row is read.
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use super::binder::{self, for_each_child, for_each_child_mut, visit, BoundColumn};
+use super::eval::expr_to_string;
+use super::planner::{
+    is_aggregate_function, is_output_position, is_sequence_function, is_window_function,
+};
+use crate::catalog::{Catalog, Column, Constraint, ConstraintKind, DataType, Schema, TableDef};
+use crate::error::{Error, Result};
+use crate::sql::ast::*;
+use crate::sql::Parser;
+use crate::storage::datetime::{self, DateTimeField};
+use crate::storage::decimal::{MAX_PRECISION, MIN_DIVISION_SCALE};
+
+/// The type of an expression; None for an untyped NULL
+type ExprType = Option<DataType>;
+
+/// Columns visible to an expression, with their types
+#[derive(Debug, Clone, Default)]
+struct Scope {
+    columns: Vec<BoundColumn>,
+    types: Vec<ExprType>,
+    /// Scope of the enclosing query, seen by correlated subqueries
+    outer: Option<Box<Scope>>,
+}
+
+impl Scope {
+    /// All columns of a table schema, qualified by `qualifier`
+    fn from_schema(qualifier: &str, schema: &Schema) -> Self {
+        let mut scope = Scope::default();
+        for column in schema.columns() {
+            scope.push(
+                BoundColumn::qualified(qualifier, column.name.clone()),
+                Some(column.data_type.clone()),
+            );
+        }
+        scope
+    }
+
+    /// The output of a derived table, with every column qualified by its
+    /// alias and the leading ones renamed to `names`
+    fn requalify(self, qualifier: &str, names: &[String]) -> Result<Self> {
+        if names.len() > self.columns.len() {
+            return Err(Error::SemanticError(format!(
+                "table '{}' has {} columns available but {} columns specified",
+                qualifier,
+                self.columns.len(),
+                names.len()
+            )));
+        }
+        let columns = self
+            .columns
+            .into_iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let name = names.get(idx).cloned().unwrap_or(column.name);
+                BoundColumn::qualified(qualifier, name)
+            })
+            .collect();
+        Ok(Scope {
+            columns,
+            types: self.types,
+            outer: None,
+        })
+    }
+
+    /// Bare column names, as the output names of a derived table
+    fn names(&self) -> Vec<String> {
+        binder::column_names(&self.columns)
+    }
+
+    fn push(&mut self, column: BoundColumn, data_type: ExprType) {
+        self.columns.push(column);
+        self.types.push(data_type);
+    }
+
+    fn extend(&mut self, other: Scope) {
+        self.columns.extend(other.columns);
+        self.types.extend(other.types);
+    }
+
+    /// Resolve a column, falling back to the enclosing queries
+    fn resolve(&self, col_ref: &ColumnRef) -> Result<ExprType> {
+        match (binder::resolve_column(&self.columns, col_ref), &self.outer) {
+            (Ok(idx), _) => Ok(self.types[idx].clone()),
+            (Err(Error::ColumnNotFound(..)), Some(outer)) => outer.resolve(col_ref),
+            (Err(e), _) => Err(e),
+        }
+    }
+
+    /// Whether a column is bound by an enclosing query rather than this one
+    fn is_outer(&self, col_ref: &ColumnRef) -> bool {
+        matches!(
+            binder::resolve_column(&self.columns, col_ref),
+            Err(Error::ColumnNotFound(..))
+        ) && self
+            .outer
+            .as_ref()
+            .is_some_and(|o| o.resolve(col_ref).is_ok())
+    }
+}
+
+/// The clause an expression appears in
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Clause {
+    Select,
+    Where,
+    On,
+    GroupBy,
+    Having,
+    OrderBy,
+    Limit,
+    Values,
+    Set,
+    Returning,
+    Default,
+    Check,
+    /// Inside the argument list of an aggregate call
+    AggregateArg,
+    /// Inside the arguments or OVER clause of a window function call
+    WindowArg,
+}
+
+impl Clause {
+    fn allows_aggregates(self) -> bool {
+        matches!(
+            self,
+            Clause::Select | Clause::Having | Clause::OrderBy | Clause::WindowArg
+        )
+    }
+
+    fn allows_subqueries(self) -> bool {
+        matches!(
+            self,
+            Clause::Select | Clause::Where | Clause::Having | Clause::Set | Clause::Returning
+        )
+    }
+
+    fn allows_sequence_calls(self) -> bool {
+        matches!(
+            self,
+            Clause::Select
+                | Clause::Where
+                | Clause::Values
+                | Clause::Set
+                | Clause::Returning
+                | Clause::Default
+        )
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Clause::Select => "SELECT",
+            Clause::Where => "WHERE",
+            Clause::On => "JOIN conditions",
+            Clause::GroupBy => "GROUP BY",
+            Clause::Having => "HAVING",
+            Clause::OrderBy => "ORDER BY unless they appear in the select list",
+            Clause::Limit => "LIMIT",
+            Clause::Values => "VALUES",
+            Clause::Set => "UPDATE SET",
+            Clause::Returning => "RETURNING",
+            Clause::Default => "DEFAULT expressions",
+            Clause::Check => "CHECK constraints",
+            Clause::AggregateArg => "aggregate arguments",
+            Clause::WindowArg => "window function arguments",
+        }
+    }
+}
+
+/// Name resolution and type checking against the catalog
+pub struct Analyzer<'a> {
+    catalog: &'a Catalog,
+    /// Output of the previous iteration of each recursive CTE being analyzed
+    work_tables: RefCell<Vec<(String, Scope)>>,
+}
+
+impl<'a> Analyzer<'a> {
+    /// Create an analyzer over a catalog
+    pub fn new(catalog: &'a Catalog) -> Self {
+        Self {
+            catalog,
+            work_tables: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Check a statement, returning it with implicit casts inserted
+    pub fn analyze(&self, stmt: Statement) -> Result<Statement> {
+        match stmt {
+            Statement::Select(select) => {
+                Ok(Statement::Select(self.analyze_select(select, None)?.0))
+            }
+            Statement::Insert(insert) => Ok(Statement::Insert(self.analyze_insert(insert)?)),
+            Statement::Update(update) => Ok(Statement::Update(self.analyze_update(update)?)),
+            Statement::Delete(delete) => Ok(Statement::Delete(self.analyze_delete(delete)?)),
+            Statement::CreateTable(mut create) => {
+                self.check_create_table(&mut create)?;
+                Ok(Statement::CreateTable(create))
+            }
+            Statement::CreateIndex(create) => {
+                self.check_create_index(&create)?;
+                Ok(Statement::CreateIndex(create))
+            }
+            Statement::AlterTable(mut alter) => {
+                self.check_alter_table(&mut alter)?;
+                Ok(Statement::AlterTable(alter))
+            }
+            other => Ok(other),
+        }
+    }
+
+    // ========== Statements ==========
+
+    /// Check a query, returning it with its output columns
+    ///
+    /// A subquery is checked with the scope of its enclosing query as `outer`.
+    fn analyze_select(
+        &self,
+        mut select: SelectStatement,
+        outer: Option<&Scope>,
+    ) -> Result<(SelectStatement, Scope)> {
+        // References to WITH queries become derived tables
+        inline_ctes(&mut select, &mut Vec::new(), 0)?;
+        if let Some(set_op) = select.set_operation.take() {
+            return self.analyze_compound(select, *set_op, outer);
+        }
+
+        // FROM and JOIN ... ON
+        let mut scope = Scope::default();
+        if let Some(from) = &mut select.from {
+            scope = self.table_scope(&mut from.table)?;
+            for join in &mut from.joins {
+                let right = self.table_scope(&mut join.table)?;
+                if let Some(table) = right.columns.first().and_then(|c| c.table.as_ref()) {
+                    if scope
+                        .columns
+                        .iter()
+                        .any(|c| c.table.as_ref() == Some(table))
+                    {
+                        return Err(Error::SemanticError(format!(
+                            "table name '{}' specified more than once",
+                            table
+                        )));
+                    }
+                }
+                scope.extend(right);
+
+                if let Some(cond) = join.condition.take() {
+                    join.condition = Some(self.check_predicate(cond, &scope, Clause::On)?);
+                }
+            }
+        }
+        scope.outer = outer.map(|outer| Box::new(outer.clone()));
+
+        if let Some(pred) = select.where_clause.take() {
+            select.where_clause = Some(self.check_predicate(pred, &scope, Clause::Where)?);
+        }
+
+        select.group_by = select
+            .group_by
+            .into_iter()
+            .map(|expr| Ok(self.check_expr(expr, &scope, Clause::GroupBy)?.0))
+            .collect::<Result<_>>()?;
+
+        // Select list, collecting the output columns seen by ORDER BY
+        let mut output = Scope::default();
+        let mut items = Vec::with_capacity(select.columns.len());
+        // Each expression as written, with its output position
+        let mut projected = Vec::new();
+        for item in select.columns {
+            match item {
+                SelectItem::Wildcard => {
+                    output.extend(scope.clone());
+                    items.push(SelectItem::Wildcard);
+                }
+                SelectItem::QualifiedWildcard(table) => {
+                    for idx in binder::resolve_qualified_wildcard(&scope.columns, &table)? {
+                        output.push(scope.columns[idx].clone(), scope.types[idx].clone());
+                    }
+                    items.push(SelectItem::QualifiedWildcard(table));
+                }
+                SelectItem::Expr { expr, alias } => {
+                    projected.push((expr.clone(), output.columns.len()));
+                    let (expr, data_type) = self.check_expr(expr, &scope, Clause::Select)?;
+                    let column = match (&alias, &expr) {
+                        (Some(alias), _) => BoundColumn::unqualified(alias.clone()),
+                        (None, Expr::Column(col_ref)) if !scope.is_outer(col_ref) => {
+                            scope.columns[binder::resolve_column(&scope.columns, col_ref)?].clone()
+                        }
+                        (None, expr) => BoundColumn::unqualified(expr_to_string(expr)),
+                    };
+                    output.push(column, data_type);
+                    items.push(SelectItem::Expr { expr, alias });
+                }
+            }
+        }
+        select.columns = items;
+
+        if let Some(having) = select.having.take() {
+            select.having = Some(self.check_predicate(having, &scope, Clause::Having)?);
+        }
+
+        select.order_by = std::mem::take(&mut select.order_by)
+            .into_iter()
+            .map(|item| {
+                let expr = self.check_order_key(item.expr, &scope, &output, &projected)?;
+                if select.distinct && !is_output_position(&expr) {
+                    return Err(Error::SemanticError(
+                        "for SELECT DISTINCT, ORDER BY expressions must appear in select list"
+                            .to_string(),
+                    ));
+                }
+                Ok(OrderByItem {
+                    expr,
+                    ascending: item.ascending,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        // Every column outside an aggregate must be grouped
+        let order_keys = || {
+            select
+                .order_by
+                .iter()
+                .map(|item| &item.expr)
+                .filter(|expr| !is_output_position(expr))
+        };
+        let grouped = !select.group_by.is_empty()
+            || select.columns.iter().any(|item| match item {
+                SelectItem::Expr { expr, .. } => contains_aggregate(expr),
+                _ => false,
+            })
+            || select.having.is_some()
+            || order_keys().any(contains_aggregate);
+        if grouped {
+            for item in &select.columns {
+                match item {
+                    SelectItem::Expr { expr, .. } => {
+                        check_grouped(expr, &select.group_by, &scope)?;
+                    }
+                    _ => {
+                        return Err(Error::SemanticError(
+                            "SELECT * cannot be used with GROUP BY or aggregates".to_string(),
+                        ))
+                    }
+                }
+            }
+            if let Some(having) = &select.having {
+                check_grouped(having, &select.group_by, &scope)?;
+            }
+            for expr in order_keys() {
+                check_grouped(expr, &select.group_by, &scope)?;
+            }
+        }
+
+        self.check_limit(&mut select)?;
+        Ok((select, output))
+    }
+
+    /// Check both queries of a set operation, whose columns must agree in
+    /// number and type; the output columns are named after the left query
+    fn analyze_compound(
+        &self,
+        mut select: SelectStatement,
+        set_op: SetOperation,
+        outer: Option<&Scope>,
+    ) -> Result<(SelectStatement, Scope)> {
+        let (left, left_output) = self.analyze_select(set_op.left, outer)?;
+        let (right, right_output) = self.analyze_select(set_op.right, outer)?;
+        if left_output.columns.len() != right_output.columns.len() {
+            return Err(Error::SemanticError(format!(
+                "each {} query must have the same number of columns",
+                set_op.op
+            )));
+        }
+
+        let mut output = Scope::default();
+        let types = left_output.types.iter().zip(&right_output.types);
+        for (column, (left_type, right_type)) in left_output.columns.iter().zip(types) {
+            let data_type = common_type(left_type, right_type)
+                .map_err(|_| operator_mismatch(&set_op.op.to_string(), left_type, right_type))?;
+            output.push(BoundColumn::unqualified(column.name.clone()), data_type);
+        }
+
+        let set_op_name = set_op.op.to_string();
+        select.set_operation = Some(Box::new(SetOperation {
+            left,
+            right,
+            ..set_op
+        }));
+        // ORDER BY sees only the output columns, by name or position
+        select.order_by = std::mem::take(&mut select.order_by)
+            .into_iter()
+            .map(|item| {
+                let expr = match output_position(&item.expr, &output)? {
+                    Some(position) => position,
+                    None => {
+                        let expr = reference_output_aggregates(item.expr, &output);
+                        let (expr, _) = self.check_expr(expr, &output, Clause::OrderBy)?;
+                        if contains_aggregate(&expr) {
+                            return Err(Error::SemanticError(format!(
+                                "aggregate functions are not allowed in ORDER BY of a {} query",
+                                set_op_name
+                            )));
+                        }
+                        expr
+                    }
+                };
+                Ok(OrderByItem {
+                    expr,
+                    ascending: item.ascending,
+                })
+            })
+            .collect::<Result<_>>()?;
+        self.check_limit(&mut select)?;
+        Ok((select, output))
+    }
+
+    /// Check an ORDER BY key of a simple query. A position, an output
+    /// column name or a repeated select list expression becomes the
+    /// position of the output column; any other expression is checked
+    /// against the query's input columns.
+    fn check_order_key(
+        &self,
+        expr: Expr,
+        scope: &Scope,
+        output: &Scope,
+        projected: &[(Expr, usize)],
+    ) -> Result<Expr> {
+        if let Some(position) = output_position(&expr, output)? {
+            return Ok(position);
+        }
+        if let Some((_, index)) = projected.iter().find(|(item, _)| *item == expr) {
+            return Ok(position_literal(*index));
+        }
+        Ok(self.check_expr(expr, scope, Clause::OrderBy)?.0)
+    }
+
+    /// Check LIMIT and OFFSET
+    fn check_limit(&self, select: &mut SelectStatement) -> Result<()> {
+        select.limit = select
+            .limit
+            .take()
+            .map(|expr| self.check_count(expr))
+            .transpose()?;
+        select.offset = select
+            .offset
+            .take()
+            .map(|expr| self.check_count(expr))
+            .transpose()?;
+        Ok(())
+    }
+
+    fn analyze_insert(&self, mut insert: InsertStatement) -> Result<InsertStatement> {
+        let table = self.catalog.get_table(&insert.table_name)?;
+        let schema = table.schema();
+        let mut defaulted = Vec::new();
+
+        // Target columns, in VALUES order
+        let targets = match &insert.columns {
+            Some(names) => {
+                let mut targets = Vec::with_capacity(names.len());
+                for name in names {
+                    let column = schema.get_column(name).ok_or_else(|| {
+                        Error::ColumnNotFound(name.clone(), insert.table_name.clone())
+                    })?;
+                    if targets.iter().any(|c: &&Column| c.name == *name) {
+                        return Err(Error::SemanticError(format!(
+                            "column '{}' specified more than once",
+                            name
+                        )));
+                    }
+                    targets.push(column);
+                }
+
+                // Omitted columns take their default, or NULL without one
+                for column in schema.columns() {
+                    if names.contains(&column.name) {
+                        continue;
+                    }
+                    if column.default.is_some() {
+                        defaulted.push(column);
+                    } else if !column.nullable {
+                        return Err(Error::NullNotAllowed(column.name.clone()));
+                    }
+                }
+                targets
+            }
+            None => schema.columns().iter().collect(),
+        };
+
+        if let Some(query) = insert.query.take() {
+            insert.query = Some(Box::new(
+                self.check_insert_query(*query, &targets, &defaulted)?,
+            ));
+        }
+
+        let empty = Scope::default();
+        for row in &mut insert.values {
+            if row.len() != targets.len() {
+                return Err(Error::SemanticError(format!(
+                    "INSERT has {} values but {} target columns",
+                    row.len(),
+                    targets.len()
+                )));
+            }
+
+            for (value, column) in row.iter_mut().zip(&targets) {
+                let mut expr = std::mem::replace(value, Expr::Literal(Literal::Null));
+                if matches!(expr, Expr::Default) {
+                    expr = column_default(column)?;
+                } else {
+                    check_not_generated_always(column)?;
+                }
+                *value = self.check_assignment(expr, column, &empty, Clause::Values)?;
+            }
+
+            // Each row gets its own copy, so defaults are evaluated per row
+            for column in &defaulted {
+                let expr = column_default(column)?;
+                row.push(self.check_assignment(expr, column, &empty, Clause::Default)?);
+            }
+        }
+        if let Some(names) = &mut insert.columns {
+            names.extend(defaulted.iter().map(|column| column.name.clone()));
+        }
+
+        insert.returning = self.check_returning(
+            std::mem::take(&mut insert.returning),
+            &Scope::from_schema(&insert.table_name, schema),
+        )?;
+        Ok(insert)
+    }
+
+    /// Check the query of INSERT ... SELECT against the target columns,
+    /// returning it with the default of each omitted column appended to its
+    /// rows
+    fn check_insert_query(
+        &self,
+        query: SelectStatement,
+        targets: &[&Column],
+        defaulted: &[&Column],
+    ) -> Result<SelectStatement> {
+        for column in targets {
+            check_not_generated_always(column)?;
+        }
+
+        // Each row gets its own default, so the query becomes a derived
+        // table selected from with the defaults as extra columns
+        let query = if defaulted.is_empty() {
+            query
+        } else {
+            let mut columns = vec![SelectItem::Wildcard];
+            for column in defaulted {
+                columns.push(SelectItem::Expr {
+                    expr: column_default(column)?,
+                    alias: Some(column.name.clone()),
+                });
+            }
+            let source = TableRef {
+                name: "insert_source".to_string(),
+                alias: None,
+                subquery: Some(Box::new(query)),
+                columns: Vec::new(),
+                recursive: None,
+            };
+            SelectStatement {
+                columns,
+                from: Some(FromClause {
+                    table: source,
+                    joins: Vec::new(),
+                }),
+                ..Default::default()
+            }
+        };
+
+        let (query, output) = self.analyze_select(query, None)?;
+        let provided = output.types.len() - defaulted.len();
+        if provided != targets.len() {
+            return Err(Error::SemanticError(format!(
+                "INSERT has {} query columns but {} target columns",
+                provided,
+                targets.len()
+            )));
+        }
+        for (data_type, column) in output.types.iter().zip(targets.iter().chain(defaulted)) {
+            check_assignable(data_type, &column.data_type)?;
+        }
+        Ok(query)
+    }
+
+    fn analyze_update(&self, mut update: UpdateStatement) -> Result<UpdateStatement> {
+        let table = self.catalog.get_table(&update.table_name)?;
+        let schema = table.schema();
+        let scope = Scope::from_schema(&update.table_name, schema);
+
+        for assignment in &mut update.assignments {
+            let column = schema.get_column(&assignment.column).ok_or_else(|| {
+                Error::ColumnNotFound(assignment.column.clone(), update.table_name.clone())
+            })?;
+            let mut expr = std::mem::replace(&mut assignment.value, Expr::Literal(Literal::Null));
+            if matches!(expr, Expr::Default) {
+                expr = column_default(column)?;
+            } else {
+                check_not_generated_always(column)?;
+            }
+            assignment.value = self.check_assignment(expr, column, &scope, Clause::Set)?;
+        }
+
+        if let Some(pred) = update.where_clause.take() {
+            update.where_clause = Some(self.check_predicate(pred, &scope, Clause::Where)?);
+        }
+        update.returning = self.check_returning(std::mem::take(&mut update.returning), &scope)?;
+        Ok(update)
+    }
+
+    fn analyze_delete(&self, mut delete: DeleteStatement) -> Result<DeleteStatement> {
+        let table = self.catalog.get_table(&delete.table_name)?;
+        let scope = Scope::from_schema(&delete.table_name, table.schema());
+
+        if let Some(pred) = delete.where_clause.take() {
+            delete.where_clause = Some(self.check_predicate(pred, &scope, Clause::Where)?);
+        }
+        delete.returning = self.check_returning(std::mem::take(&mut delete.returning), &scope)?;
+        Ok(delete)
+    }
+
+    /// Check the RETURNING list of INSERT, UPDATE or DELETE, which sees the
+    /// columns of the changed table
+    fn check_returning(&self, items: Vec<SelectItem>, scope: &Scope) -> Result<Vec<SelectItem>> {
+        items
+            .into_iter()
+            .map(|item| match item {
+                SelectItem::Wildcard => Ok(SelectItem::Wildcard),
+                SelectItem::QualifiedWildcard(table) => {
+                    binder::resolve_qualified_wildcard(&scope.columns, &table)?;
+                    Ok(SelectItem::QualifiedWildcard(table))
+                }
+                SelectItem::Expr { expr, alias } => {
+                    let (expr, _) = self.check_expr(expr, scope, Clause::Returning)?;
+                    Ok(SelectItem::Expr { expr, alias })
+                }
+            })
+            .collect()
+    }
+
+    fn check_create_table(&self, create: &mut CreateTableStatement) -> Result<()> {
+        let mut scope = Scope::default();
+        for column in &create.columns {
+            if scope.columns.iter().any(|c| c.name == column.name) {
+                return Err(Error::ColumnAlreadyExists(
+                    column.name.clone(),
+                    create.table_name.clone(),
+                ));
+            }
+            scope.push(
+                BoundColumn::qualified(create.table_name.clone(), column.name.clone()),
+                Some(column.data_type.clone()),
+            );
+        }
+
+        for column in &create.columns {
+            check_identity(column)?;
+            if let Some(default) = &column.default {
+                let (_, data_type) =
+                    self.check_expr(default.clone(), &Scope::default(), Clause::Default)?;
+                check_assignable(&data_type, &column.data_type)?;
+            }
+        }
+
+        let check_columns = |columns: &[String]| {
+            columns.iter().try_for_each(|name| {
+                if scope.columns.iter().any(|c| c.name == *name) {
+                    Ok(())
+                } else {
+                    Err(Error::ColumnNotFound(
+                        name.clone(),
+                        create.table_name.clone(),
+                    ))
+                }
+            })
+        };
+        // A foreign key may reference the table being created
+        let pending = pending_definition(create);
+        let referenced = |table: &str| -> Result<Arc<TableDef>> {
+            if table == create.table_name {
+                Ok(Arc::new(pending.clone()))
+            } else {
+                self.catalog.get_table(table)
+            }
+        };
+        for column in &mut create.columns {
+            if let Some(references) = &mut column.references {
+                let parent = referenced(&references.table)?;
+                check_references(references, &[&column.data_type], &parent)?;
+            }
+        }
+
+        let mut names = HashSet::new();
+        for constraint in &mut create.constraints {
+            let name = match constraint {
+                TableConstraint::PrimaryKey { name, columns }
+                | TableConstraint::Unique { name, columns } => {
+                    check_columns(columns)?;
+                    name
+                }
+                TableConstraint::ForeignKey {
+                    name,
+                    columns,
+                    references,
+                } => {
+                    check_columns(columns)?;
+                    let types: Vec<&DataType> = columns
+                        .iter()
+                        .filter_map(|name| create.columns.iter().find(|c| c.name == *name))
+                        .map(|c| &c.data_type)
+                        .collect();
+                    let parent = referenced(&references.table)?;
+                    check_references(references, &types, &parent)?;
+                    name
+                }
+                TableConstraint::Check { name, expr } => {
+                    let checked = std::mem::replace(expr, Expr::Literal(Literal::Null));
+                    *expr = self.check_predicate(checked, &scope, Clause::Check)?;
+                    name
+                }
+            };
+            if let Some(name) = name {
+                if !names.insert(name.clone()) {
+                    return Err(Error::SemanticError(format!(
+                        "constraint '{}' specified more than once",
+                        name
+                    )));
+                }
+            }
+        }
+
+        let primary_keys = create.columns.iter().filter(|c| c.primary_key).count()
+            + create
+                .constraints
+                .iter()
+                .filter(|c| matches!(c, TableConstraint::PrimaryKey { .. }))
+                .count();
+        if primary_keys > 1 {
+            return Err(Error::SemanticError(format!(
+                "multiple primary keys for table '{}' are not allowed",
+                create.table_name
+            )));
+        }
+        Ok(())
+    }
+
+    fn check_create_index(&self, create: &CreateIndexStatement) -> Result<()> {
+        let table = self.catalog.get_table(&create.table_name)?;
+        for name in &create.columns {
+            if !table.schema().has_column(name) {
+                return Err(Error::ColumnNotFound(
+                    name.clone(),
+                    create.table_name.clone(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_alter_table(&self, alter: &mut AlterTableStatement) -> Result<()> {
+        let table = self.catalog.get_table(&alter.table_name)?;
+        let schema = table.schema();
+        let existing = |name: &str| {
+            schema
+                .get_column(name)
+                .ok_or_else(|| Error::ColumnNotFound(name.to_string(), alter.table_name.clone()))
+        };
+        let unused = |name: &str| {
+            if schema.has_column(name) {
+                Err(Error::ColumnAlreadyExists(
+                    name.to_string(),
+                    alter.table_name.clone(),
+                ))
+            } else {
+                Ok(())
+            }
+        };
+
+        match &mut alter.operation {
+            AlterTableOperation::AddColumn(column) => {
+                unused(&column.name)?;
+                check_identity(column)?;
+                if column.primary_key {
+                    return Err(Error::SemanticError(format!(
+                        "cannot add PRIMARY KEY column '{}' to an existing table",
+                        column.name
+                    )));
+                }
+                if let Some(default) = &column.default {
+                    let (_, data_type) =
+                        self.check_expr(default.clone(), &Scope::default(), Clause::Default)?;
+                    check_assignable(&data_type, &column.data_type)?;
+                }
+                if let Some(references) = &mut column.references {
+                    let parent = self.catalog.get_table(&references.table)?;
+                    check_references(references, &[&column.data_type], &parent)?;
+                }
+            }
+            AlterTableOperation::DropColumn(name) => {
+                existing(name)?;
+                if schema.column_count() == 1 {
+                    return Err(Error::SemanticError(format!(
+                        "cannot drop '{}', the only column of table '{}'",
+                        name, alter.table_name
+                    )));
+                }
+                let referencing = self.catalog.get_referencing_foreign_keys(&alter.table_name);
+                if let Some((child, constraint, _)) = referencing
+                    .iter()
+                    .find(|(_, _, fk)| fk.ref_columns.contains(name))
+                {
+                    return Err(Error::SemanticError(format!(
+                        "cannot drop '{}' because constraint '{}' on table '{}' references it",
+                        name, constraint, child.name
+                    )));
+                }
+            }
+            AlterTableOperation::RenameColumn { old_name, new_name } => {
+                existing(old_name)?;
+                unused(new_name)?;
+            }
+            AlterTableOperation::RenameTable(new_name) => {
+                if self.catalog.table_exists(new_name) {
+                    return Err(Error::TableAlreadyExists(new_name.clone()));
+                }
+            }
+            AlterTableOperation::SetNotNull { column, not_null } => {
+                if existing(column)?.primary_key && !*not_null {
+                    return Err(Error::SemanticError(format!(
+                        "column '{}' is in the primary key",
+                        column
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // ========== Helpers ==========
+
+    fn table_scope(&self, table: &mut TableRef) -> Result<Scope> {
+        let qualifier = table.alias.as_ref().unwrap_or(&table.name).clone();
+        if table.is_work_table() {
+            let work_tables = self.work_tables.borrow();
+            let (_, scope) = work_tables
+                .iter()
+                .rev()
+                .find(|(name, _)| *name == table.name)
+                .ok_or_else(|| Error::TableNotFound(table.name.clone()))?;
+            table.columns = scope.names();
+            return scope.clone().requalify(&qualifier, &[]);
+        }
+        if let Some(subquery) = table.subquery.take() {
+            // A derived table cannot see the columns of the query it appears in
+            let (subquery, output) = self.analyze_select(*subquery, None)?;
+            table.subquery = Some(Box::new(subquery));
+            let output = output.requalify(&qualifier, &table.columns)?;
+            if let Some(term) = table.recursive.take() {
+                table.recursive = Some(self.check_recursive_term(term, &table.name, &output)?);
+            }
+            table.columns = output.names();
+            return Ok(output);
+        }
+
+        let def = self.catalog.get_table(&table.name)?;
+        Ok(Scope::from_schema(&qualifier, def.schema()))
+    }
+
+    /// Check the recursive term of a CTE, which reads the rows of the
+    /// previous iteration with the columns of the non-recursive part
+    fn check_recursive_term(
+        &self,
+        term: RecursiveTerm,
+        name: &str,
+        output: &Scope,
+    ) -> Result<RecursiveTerm> {
+        let work_table = TableRef::work_table_name(name);
+        self.work_tables
+            .borrow_mut()
+            .push((work_table, output.clone()));
+        let result = self.analyze_select(*term.query, None);
+        self.work_tables.borrow_mut().pop();
+        let (query, term_output) = result?;
+
+        if term_output.columns.len() != output.columns.len() {
+            return Err(Error::SemanticError(format!(
+                "the recursive term of '{}' returns {} columns, not {}",
+                name,
+                term_output.columns.len(),
+                output.columns.len()
+            )));
+        }
+        for (left, right) in output.types.iter().zip(&term_output.types) {
+            common_type(left, right).map_err(|_| operator_mismatch("UNION", left, right))?;
+        }
+
+        Ok(RecursiveTerm {
+            query: Box::new(query),
+            all: term.all,
+        })
+    }
+
+    /// Check an expression that must be a boolean condition
+    fn check_predicate(&self, expr: Expr, scope: &Scope, clause: Clause) -> Result<Expr> {
+        let (expr, data_type) = self.check_expr(expr, scope, clause)?;
+        expect_type(&data_type, |t| *t == DataType::Boolean, "BOOLEAN")?;
+        Ok(expr)
+    }
+
+    /// Check a LIMIT or OFFSET count
+    fn check_count(&self, expr: Expr) -> Result<Expr> {
+        let (expr, data_type) = self.check_expr(expr, &Scope::default(), Clause::Limit)?;
+        expect_type(&data_type, is_integer, "INTEGER")?;
+        Ok(expr)
+    }
+
+    /// Check a value written to a column
+    ///
+    /// The value is converted to the column type by the table on write,
+    /// where range and length violations can name the column.
+    fn check_assignment(
+        &self,
+        expr: Expr,
+        column: &Column,
+        scope: &Scope,
+        clause: Clause,
+    ) -> Result<Expr> {
+        let (expr, data_type) = self.check_expr(expr, scope, clause)?;
+        let (expr, data_type) = typed_literal(expr, data_type, &Some(column.data_type.clone()))?;
+        match data_type {
+            None if !column.nullable => Err(Error::NullNotAllowed(column.name.clone())),
+            None => Ok(expr),
+            Some(t) => {
+                check_assignable(&Some(t), &column.data_type)?;
+                Ok(expr)
+            }
+        }
+    }
+
+    /// Infer the type of an expression
+    fn check_expr(&self, expr: Expr, scope: &Scope, clause: Clause) -> Result<(Expr, ExprType)> {
+        match expr {
+            Expr::Column(col_ref) => {
+                let data_type = scope.resolve(&col_ref)?;
+                Ok((Expr::Column(col_ref), data_type))
+            }
+            Expr::Literal(lit) => {
+                let data_type = literal_type(&lit);
+                Ok((Expr::Literal(lit), data_type))
+            }
+            Expr::Default => Err(Error::SemanticError(
+                "DEFAULT can only be used as a whole value in VALUES or UPDATE SET".to_string(),
+            )),
+            Expr::BinaryOp { left, op, right } => {
+                let (left, lt) = self.check_expr(*left, scope, clause)?;
+                let (right, rt) = self.check_expr(*right, scope, clause)?;
+                let is_comparison = matches!(
+                    op,
+                    BinaryOperator::Eq
+                        | BinaryOperator::Neq
+                        | BinaryOperator::Lt
+                        | BinaryOperator::Gt
+                        | BinaryOperator::Lte
+                        | BinaryOperator::Gte
+                );
+                let (left, lt, right, rt) = if is_comparison {
+                    let (left, new_lt) = typed_literal(left, lt, &rt)?;
+                    let (right, rt) = typed_literal(right, rt, &new_lt)?;
+                    (left, new_lt, right, rt)
+                } else {
+                    (left, lt, right, rt)
+                };
+                let data_type = binary_op_type(op, &lt, &rt)?;
+                // Stored CHAR values are padded, so pad the other side to match
+                let (left, right) = match (&lt, &rt) {
+                    _ if !is_comparison => (left, right),
+                    (Some(DataType::Char(n)), Some(t)) if is_unpadded_string(t) => {
+                        (left, pad_to_char(right, *n))
+                    }
+                    (Some(t), Some(DataType::Char(n))) if is_unpadded_string(t) => {
+                        (pad_to_char(left, *n), right)
+                    }
+                    // Join keys of different numeric types are cast to a
+                    // common type, so equal values hash alike
+                    (Some(l), Some(r))
+                        if clause == Clause::On
+                            && op == BinaryOperator::Eq
+                            && l != r
+                            && l.is_numeric()
+                            && r.is_numeric() =>
+                    {
+                        let common = numeric_result(None, l, r);
+                        (cast_to(left, l, &common), cast_to(right, r, &common))
+                    }
+                    _ => (left, right),
+                };
+                Ok((
+                    Expr::BinaryOp {
+                        left: Box::new(left),
+                        op,
+                        right: Box::new(right),
+                    },
+                    data_type,
+                ))
+            }
+            Expr::UnaryOp { op, expr } => {
+                let (expr, data_type) = self.check_expr(*expr, scope, clause)?;
+                match op {
+                    UnaryOperator::Not => {
+                        expect_type(&data_type, |t| *t == DataType::Boolean, "BOOLEAN")?
+                    }
+                    UnaryOperator::Minus | UnaryOperator::Plus => {
+                        let is_signed = |t: &DataType| t.is_numeric() || *t == DataType::Interval;
+                        expect_type(&data_type, is_signed, "numeric")?
+                    }
+                }
+                let data_type = match op {
+                    UnaryOperator::Not => Some(DataType::Boolean),
+                    _ => data_type,
+                };
+                Ok((
+                    Expr::UnaryOp {
+                        op,
+                        expr: Box::new(expr),
+                    },
+                    data_type,
+                ))
+            }
+            Expr::IsNull(inner) => {
+                let (inner, _) = self.check_expr(*inner, scope, clause)?;
+                Ok((Expr::IsNull(Box::new(inner)), Some(DataType::Boolean)))
+            }
+            Expr::IsNotNull(inner) => {
+                let (inner, _) = self.check_expr(*inner, scope, clause)?;
+                Ok((Expr::IsNotNull(Box::new(inner)), Some(DataType::Boolean)))
+            }
+            Expr::Nested(inner) => {
+                let (inner, data_type) = self.check_expr(*inner, scope, clause)?;
+                Ok((Expr::Nested(Box::new(inner)), data_type))
+            }
+            Expr::Function {
+                name,
+                args,
+                distinct,
+                over: Some(over),
+            } => self.check_window_function(name, args, distinct, *over, scope, clause),
+            Expr::Function {
+                name,
+                args,
+                distinct,
+                over: None,
+            } => self.check_function(name, args, distinct, scope, clause),
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let (expr, t) = self.check_expr(*expr, scope, clause)?;
+                let (low, lt) = self.check_expr(*low, scope, clause)?;
+                let (high, ht) = self.check_expr(*high, scope, clause)?;
+                check_comparable("BETWEEN", &t, &lt)?;
+                check_comparable("BETWEEN", &t, &ht)?;
+                Ok((
+                    Expr::Between {
+                        expr: Box::new(expr),
+                        low: Box::new(low),
+                        high: Box::new(high),
+                        negated,
+                    },
+                    Some(DataType::Boolean),
+                ))
+            }
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let (expr, t) = self.check_expr(*expr, scope, clause)?;
+                let mut checked = Vec::with_capacity(list.len());
+                for item in list {
+                    let (item, it) = self.check_expr(item, scope, clause)?;
+                    check_comparable("IN", &t, &it)?;
+                    checked.push(item);
+                }
+                Ok((
+                    Expr::InList {
+                        expr: Box::new(expr),
+                        list: checked,
+                        negated,
+                    },
+                    Some(DataType::Boolean),
+                ))
+            }
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+                case_insensitive,
+            } => {
+                let op_name = if case_insensitive { "ILIKE" } else { "LIKE" };
+                let (expr, t) = self.check_expr(*expr, scope, clause)?;
+                let (pattern, pt) = self.check_expr(*pattern, scope, clause)?;
+                if !is_string_or_null(&t) || !is_string_or_null(&pt) {
+                    return Err(operator_mismatch(op_name, &t, &pt));
+                }
+                let escape = match escape {
+                    Some(escape) => {
+                        let (escape, et) = self.check_expr(*escape, scope, clause)?;
+                        if !is_string_or_null(&et) {
+                            return Err(operator_mismatch("ESCAPE", &et, &pt));
+                        }
+                        Some(Box::new(escape))
+                    }
+                    None => None,
+                };
+                Ok((
+                    Expr::Like {
+                        expr: Box::new(expr),
+                        pattern: Box::new(pattern),
+                        escape,
+                        negated,
+                        case_insensitive,
+                    },
+                    Some(DataType::Boolean),
+                ))
+            }
+            Expr::Case {
+                operand,
+                when_clauses,
+                else_clause,
+            } => {
+                let operand = match operand {
+                    Some(operand) => Some(self.check_expr(*operand, scope, clause)?),
+                    None => None,
+                };
+
+                let mut result_type: ExprType = None;
+                let mut unify = |t: ExprType| -> Result<()> {
+                    result_type = common_type(&result_type, &t)?;
+                    Ok(())
+                };
+
+                let mut whens = Vec::with_capacity(when_clauses.len());
+                for (condition, result) in when_clauses {
+                    let (condition, ct) = self.check_expr(condition, scope, clause)?;
+                    match &operand {
+                        Some((_, ot)) => check_comparable("CASE", ot, &ct)?,
+                        None => expect_type(&ct, |t| *t == DataType::Boolean, "BOOLEAN")?,
+                    }
+                    let (result, rt) = self.check_expr(result, scope, clause)?;
+                    unify(rt)?;
+                    whens.push((condition, result));
+                }
+                let else_clause = match else_clause {
+                    Some(e) => {
+                        let (e, et) = self.check_expr(*e, scope, clause)?;
+                        unify(et)?;
+                        Some(Box::new(e))
+                    }
+                    None => None,
+                };
+
+                Ok((
+                    Expr::Case {
+                        operand: operand.map(|(e, _)| Box::new(e)),
+                        when_clauses: whens,
+                        else_clause,
+                    },
+                    result_type,
+                ))
+            }
+            Expr::Cast { expr, data_type } => {
+                let (expr, from) = self.check_expr(*expr, scope, clause)?;
+                if let Some(from) = &from {
+                    if !from.can_cast_to(&data_type) {
+                        return Err(Error::TypeMismatch {
+                            from: from.to_string(),
+                            to: data_type.to_string(),
+                        });
+                    }
+                }
+                Ok((
+                    Expr::Cast {
+                        expr: Box::new(expr),
+                        data_type: data_type.clone(),
+                    },
+                    Some(data_type),
+                ))
+            }
+            Expr::Subquery(_) | Expr::Exists(_) | Expr::InSubquery { .. }
+                if !clause.allows_subqueries() =>
+            {
+                Err(Error::SemanticError(format!(
+                    "subqueries are not allowed in {}",
+                    clause.name()
+                )))
+            }
+            Expr::Subquery(query) => {
+                let (query, output) = self.analyze_select(*query, Some(scope))?;
+                let data_type = single_column_type(&output)?;
+                Ok((Expr::Subquery(Box::new(query)), data_type))
+            }
+            Expr::Exists(query) => {
+                let (query, _) = self.analyze_select(*query, Some(scope))?;
+                Ok((Expr::Exists(Box::new(query)), Some(DataType::Boolean)))
+            }
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let (expr, t) = self.check_expr(*expr, scope, clause)?;
+                let (subquery, output) = self.analyze_select(*subquery, Some(scope))?;
+                check_comparable("IN", &t, &single_column_type(&output)?)?;
+                Ok((
+                    Expr::InSubquery {
+                        expr: Box::new(expr),
+                        subquery: Box::new(subquery),
+                        negated,
+                    },
+                    Some(DataType::Boolean),
+                ))
+            }
+        }
+    }
+
+    fn check_function(
+        &self,
+        name: String,
+        args: Vec<Expr>,
+        distinct: bool,
+        scope: &Scope,
+        clause: Clause,
+    ) -> Result<(Expr, ExprType)> {
+        let upper = name.to_uppercase();
+        let is_aggregate = is_aggregate_function(&upper);
+        if is_window_function(&upper) {
+            return Err(Error::SemanticError(format!(
+                "window function {} requires an OVER clause",
+                upper
+            )));
+        }
+
+        if is_aggregate && clause == Clause::AggregateArg {
+            return Err(Error::SemanticError(
+                "aggregate function calls cannot be nested".to_string(),
+            ));
+        }
+        if is_aggregate && !clause.allows_aggregates() {
+            return Err(Error::SemanticError(format!(
+                "aggregate functions are not allowed in {}",
+                clause.name()
+            )));
+        }
+        if is_sequence_function(&upper) && !clause.allows_sequence_calls() {
+            return Err(Error::SemanticError(format!(
+                "{} is not allowed in {}",
+                upper,
+                clause.name()
+            )));
+        }
+
+        // COUNT(*) has no argument to check
+        let is_star = |arg: &Expr| matches!(arg, Expr::Column(c) if c.column == "*");
+        if upper == "COUNT" && args.len() == 1 && is_star(&args[0]) {
+            let expr = Expr::Function {
+                name,
+                args,
+                distinct,
+                over: None,
+            };
+            return Ok((expr, Some(DataType::BigInt)));
+        }
+
+        let arg_clause = if is_aggregate {
+            Clause::AggregateArg
+        } else {
+            clause
+        };
+        let mut checked = Vec::with_capacity(args.len());
+        let mut types = Vec::with_capacity(args.len());
+        for arg in args {
+            let (arg, t) = self.check_expr(arg, scope, arg_clause)?;
+            checked.push(arg);
+            types.push(t);
+        }
+
+        let expect_args = |n: usize| {
+            if types.len() == n {
+                Ok(())
+            } else {
+                Err(Error::SemanticError(format!(
+                    "function {} expects {} argument(s), got {}",
+                    upper,
+                    n,
+                    types.len()
+                )))
+            }
+        };
+
+        let data_type = match upper.as_str() {
+            "COUNT" => {
+                expect_args(1)?;
+                Some(DataType::BigInt)
+            }
+            "SUM" => {
+                expect_args(1)?;
+                expect_type(&types[0], DataType::is_numeric, "numeric")?;
+                match &types[0] {
+                    Some(t) if is_integer(t) => Some(DataType::BigInt),
+                    Some(DataType::Decimal(_, s)) => {
+                        Some(DataType::Decimal(MAX_PRECISION as u8, *s))
+                    }
+                    _ => Some(DataType::Double),
+                }
+            }
+            "AVG" => {
+                expect_args(1)?;
+                expect_type(&types[0], DataType::is_numeric, "numeric")?;
+                match &types[0] {
+                    Some(DataType::Decimal(_, s)) => Some(DataType::Decimal(
+                        MAX_PRECISION as u8,
+                        (*s).max(MIN_DIVISION_SCALE as u8),
+                    )),
+                    _ => Some(DataType::Double),
+                }
+            }
+            "MIN" | "MAX" => {
+                expect_args(1)?;
+                types[0].clone()
+            }
+            "UPPER" | "LOWER" => {
+                expect_args(1)?;
+                expect_type(&types[0], DataType::is_string, "TEXT")?;
+                Some(DataType::Text)
+            }
+            "LENGTH" => {
+                expect_args(1)?;
+                expect_type(&types[0], DataType::is_string, "TEXT")?;
+                Some(DataType::Integer)
+            }
+            "NEXTVAL" | "CURRVAL" | "SETVAL" => {
+                expect_args(if upper == "SETVAL" { 2 } else { 1 })?;
+                expect_type(&types[0], DataType::is_string, "TEXT")?;
+                if let Expr::Literal(Literal::String(sequence)) = &checked[0] {
+                    self.catalog.get_sequence(sequence)?;
+                }
+                if let Some(value) = types.get(1) {
+                    expect_type(value, is_integer, "INTEGER")?;
+                }
+                Some(DataType::BigInt)
+            }
+            "CURRENT_DATE" => {
+                expect_args(0)?;
+                Some(DataType::Date)
+            }
+            "CURRENT_TIME" => {
+                expect_args(0)?;
+                Some(DataType::Time)
+            }
+            "CURRENT_TIMESTAMP" | "NOW" => {
+                expect_args(0)?;
+                Some(DataType::Timestamp)
+            }
+            "EXTRACT" => {
+                expect_args(2)?;
+                let field = datetime_field(&checked[0])?;
+                let is_source = |t: &DataType| t.is_temporal() || *t == DataType::Interval;
+                expect_type(&types[1], is_source, "DATE, TIME, TIMESTAMP or INTERVAL")?;
+                if field.is_fractional() {
+                    Some(DataType::Decimal(MAX_PRECISION as u8, 3))
+                } else {
+                    Some(DataType::BigInt)
+                }
+            }
+            "DATE_TRUNC" => {
+                expect_args(2)?;
+                datetime_field(&checked[0])?;
+                let is_source = |t: &DataType| matches!(t, DataType::Date | DataType::Timestamp);
+                expect_type(&types[1], is_source, "DATE or TIMESTAMP")?;
+                types[1].clone()
+            }
+            _ => return Err(Error::SemanticError(format!("unknown function '{}'", name))),
+        };
+
+        let expr = Expr::Function {
+            name,
+            args: checked,
+            distinct,
+            over: None,
+        };
+        Ok((expr, data_type))
+    }
+
+    /// Check a function call with an OVER clause: a ranking or offset
+    /// function, or an aggregate computed over a window frame
+    fn check_window_function(
+        &self,
+        name: String,
+        args: Vec<Expr>,
+        distinct: bool,
+        over: WindowSpec,
+        scope: &Scope,
+        clause: Clause,
+    ) -> Result<(Expr, ExprType)> {
+        let upper = name.to_uppercase();
+        if clause != Clause::Select {
+            return Err(Error::SemanticError(format!(
+                "window functions are not allowed in {}",
+                clause.name()
+            )));
+        }
+        if distinct {
+            return Err(Error::SemanticError(
+                "DISTINCT is not supported in window functions".to_string(),
+            ));
+        }
+
+        let partition_by = over
+            .partition_by
+            .into_iter()
+            .map(|expr| Ok(self.check_expr(expr, scope, Clause::WindowArg)?.0))
+            .collect::<Result<Vec<_>>>()?;
+        let mut order_by = Vec::with_capacity(over.order_by.len());
+        let mut order_types = Vec::with_capacity(over.order_by.len());
+        for item in over.order_by {
+            let (expr, t) = self.check_expr(item.expr, scope, Clause::WindowArg)?;
+            order_by.push(OrderByItem {
+                expr,
+                ascending: item.ascending,
+            });
+            order_types.push(t);
+        }
+        if let Some(frame) = &over.frame {
+            check_window_frame(frame, &order_types)?;
+        }
+        let over = Some(Box::new(WindowSpec {
+            partition_by,
+            order_by,
+            frame: over.frame,
+        }));
+
+        // Aggregates over a window are typed like their grouped form
+        if is_aggregate_function(&upper) {
+            let (expr, data_type) =
+                self.check_function(name, args, false, scope, Clause::Select)?;
+            let Expr::Function { name, args, .. } = expr else {
+                unreachable!("check_function returns a function call");
+            };
+            let expr = Expr::Function {
+                name,
+                args,
+                distinct,
+                over,
+            };
+            return Ok((expr, data_type));
+        }
+
+        let mut checked = Vec::with_capacity(args.len());
+        let mut types = Vec::with_capacity(args.len());
+        for arg in args {
+            let (arg, t) = self.check_expr(arg, scope, Clause::WindowArg)?;
+            checked.push(arg);
+            types.push(t);
+        }
+
+        let data_type = match upper.as_str() {
+            "ROW_NUMBER" | "RANK" | "DENSE_RANK" => {
+                if !types.is_empty() {
+                    return Err(Error::SemanticError(format!(
+                        "function {} expects 0 argument(s), got {}",
+                        upper,
+                        types.len()
+                    )));
+                }
+                Some(DataType::BigInt)
+            }
+            "LAG" | "LEAD" => {
+                if types.is_empty() || types.len() > 3 {
+                    return Err(Error::SemanticError(format!(
+                        "function {} expects 1 to 3 argument(s), got {}",
+                        upper,
+                        types.len()
+                    )));
+                }
+                if let Some(offset) = types.get(1) {
+                    expect_type(offset, is_integer, "INTEGER")?;
+                }
+                match types.get(2) {
+                    Some(default) => common_type(&types[0], default)
+                        .map_err(|_| operator_mismatch(&upper, &types[0], default))?,
+                    None => types[0].clone(),
+                }
+            }
+            _ => {
+                return Err(Error::SemanticError(format!(
+                    "function {} is not a window function",
+                    upper
+                )))
+            }
+        };
+
+        let expr = Expr::Function {
+            name,
+            args: checked,
+            distinct,
+            over,
+        };
+        Ok((expr, data_type))
+    }
+}
+
+/// Check that a frame's bounds are in order, and that an offset in RANGE
+/// units can be applied to the single ORDER BY key
+fn check_window_frame(frame: &WindowFrame, order_types: &[ExprType]) -> Result<()> {
+    let rank = |bound: &FrameBound| match bound {
+        FrameBound::UnboundedPreceding => 0,
+        FrameBound::Preceding(_) => 1,
+        FrameBound::CurrentRow => 2,
+        FrameBound::Following(_) => 3,
+        FrameBound::UnboundedFollowing => 4,
+    };
+    if frame.start == FrameBound::UnboundedFollowing
+        || frame.end == FrameBound::UnboundedPreceding
+        || rank(&frame.start) > rank(&frame.end)
+    {
+        return Err(Error::SemanticError(format!(
+            "invalid window frame '{}'",
+            frame
+        )));
+    }
+
+    let has_offset =
+        |bound: &FrameBound| matches!(bound, FrameBound::Preceding(_) | FrameBound::Following(_));
+    if frame.units == FrameUnits::Range && (has_offset(&frame.start) || has_offset(&frame.end)) {
+        match order_types {
+            [t] => expect_type(t, DataType::is_numeric, "numeric")?,
+            _ => {
+                return Err(Error::SemanticError(
+                    "RANGE with an offset requires exactly one ORDER BY column".to_string(),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+// ========== Common Table Expressions ==========
+
+/// A name bound by a WITH clause
+enum CteBinding {
+    /// A CTE, inlined as a derived table wherever it is referenced
+    Query(Cte),
+    /// A recursive CTE seen from its own recursive term
+    WorkTable {
+        name: String,
+        /// Nesting depth of the recursive term, where the reference must appear
+        depth: usize,
+        references: usize,
+    },
+}
+
+impl CteBinding {
+    fn name(&self) -> &str {
+        match self {
+            CteBinding::Query(cte) => &cte.name,
+            CteBinding::WorkTable { name, .. } => name,
+        }
+    }
+}
+
+/// Replace every reference to a common table expression in a query, and in
+/// the subqueries and derived tables nested `depth` levels inside it, with a
+/// derived table holding the CTE's query
+fn inline_ctes(
+    select: &mut SelectStatement,
+    env: &mut Vec<CteBinding>,
+    depth: usize,
+) -> Result<()> {
+    let bound = env.len();
+    if let Some(with) = select.with.take() {
+        for mut cte in with.ctes {
+            inline_ctes(&mut cte.query, env, depth + 1)?;
+            if let Some(mut term) = cte.recursive.take() {
+                env.push(CteBinding::WorkTable {
+                    name: cte.name.clone(),
+                    depth: depth + 1,
+                    references: 0,
+                });
+                let result = inline_ctes(&mut term.query, env, depth + 1);
+                let references = match env.pop() {
+                    Some(CteBinding::WorkTable { references, .. }) => references,
+                    _ => 0,
+                };
+                result?;
+
+                // A UNION whose second query never reads the CTE is not recursive
+                if references == 0 {
+                    let anchor = std::mem::take(&mut *cte.query);
+                    *cte.query = SelectStatement::compound(
+                        SetOperator::Union,
+                        term.all,
+                        anchor,
+                        *term.query,
+                    );
+                } else {
+                    cte.recursive = Some(term);
+                }
+            }
+            env.push(CteBinding::Query(cte));
+        }
+    }
+
+    let result = inline_select_body(select, env, depth);
+    env.truncate(bound);
+    result
+}
+
+fn inline_select_body(
+    select: &mut SelectStatement,
+    env: &mut Vec<CteBinding>,
+    depth: usize,
+) -> Result<()> {
+    if let Some(set_op) = &mut select.set_operation {
+        inline_ctes(&mut set_op.left, env, depth)?;
+        inline_ctes(&mut set_op.right, env, depth)?;
+    }
+    if let Some(from) = &mut select.from {
+        inline_table_ref(&mut from.table, env, depth)?;
+        for join in &mut from.joins {
+            inline_table_ref(&mut join.table, env, depth)?;
+        }
+    }
+
+    let mut result = Ok(());
+    let mut inline = |expr: &mut Expr| {
+        if result.is_ok() {
+            result = inline_expr(expr, env, depth);
+        }
+    };
+    for item in &mut select.columns {
+        if let SelectItem::Expr { expr, .. } = item {
+            inline(expr);
+        }
+    }
+    if let Some(from) = &mut select.from {
+        from.joins
+            .iter_mut()
+            .filter_map(|join| join.condition.as_mut())
+            .for_each(&mut inline);
+    }
+    select.where_clause.iter_mut().for_each(&mut inline);
+    select.group_by.iter_mut().for_each(&mut inline);
+    select.having.iter_mut().for_each(&mut inline);
+    select
+        .order_by
+        .iter_mut()
+        .for_each(|item| inline(&mut item.expr));
+    result
+}
+
+fn inline_table_ref(table: &mut TableRef, env: &mut Vec<CteBinding>, depth: usize) -> Result<()> {
+    if let Some(subquery) = &mut table.subquery {
+        return inline_ctes(subquery, env, depth + 1);
+    }
+
+    let Some(binding) = env.iter_mut().rev().find(|b| b.name() == table.name) else {
+        return Ok(());
+    };
+    match binding {
+        CteBinding::Query(cte) => {
+            table.alias = table.alias.take().or_else(|| Some(cte.name.clone()));
+            table.subquery = Some(cte.query.clone());
+            table.columns = cte.columns.clone();
+            table.recursive = cte.recursive.clone();
+        }
+        CteBinding::WorkTable {
+            name,
+            depth: term_depth,
+            references,
+        } => {
+            if depth != *term_depth {
+                return Err(Error::SemanticError(format!(
+                    "recursive reference to '{}' must not appear within a subquery",
+                    name
+                )));
+            }
+            *references += 1;
+            if *references > 1 {
+                return Err(Error::SemanticError(format!(
+                    "recursive reference to '{}' must not appear more than once",
+                    name
+                )));
+            }
+            table.alias = table.alias.take().or_else(|| Some(name.clone()));
+            table.name = TableRef::work_table_name(name);
+        }
+    }
+    Ok(())
+}
+
+fn inline_expr(expr: &mut Expr, env: &mut Vec<CteBinding>, depth: usize) -> Result<()> {
+    match expr {
+        Expr::Subquery(query) | Expr::Exists(query) => inline_ctes(query, env, depth + 1)?,
+        Expr::InSubquery { subquery, .. } => inline_ctes(subquery, env, depth + 1)?,
+        _ => {}
+    }
+    let mut result = Ok(());
+    for_each_child_mut(expr, &mut |child| {
+        if result.is_ok() {
+            result = inline_expr(child, env, depth);
+        }
+    });
+    result
+}
+
+// ========== Type Rules ==========
+
+/// The date/time field named by the first argument of EXTRACT or DATE_TRUNC
+fn datetime_field(arg: &Expr) -> Result<DateTimeField> {
+    match arg {
+        Expr::Literal(Literal::String(field)) => field.parse().map_err(Error::SemanticError),
+        _ => Err(Error::SemanticError(
+            "date/time field must be a string literal".to_string(),
+        )),
+    }
+}
+
+/// Read a string literal used with a date/time value as a literal of that type
+///
+/// `'2024-01-01'` compared with a DATE column becomes `DATE '2024-01-01'`.
+fn typed_literal(expr: Expr, data_type: ExprType, other: &ExprType) -> Result<(Expr, ExprType)> {
+    let Expr::Literal(Literal::String(text)) = &expr else {
+        return Ok((expr, data_type));
+    };
+    let literal = match other {
+        Some(DataType::Date) => datetime::parse_date(text).map(Literal::Date),
+        Some(DataType::Time) => datetime::parse_time(text).map(Literal::Time),
+        Some(DataType::Timestamp) => datetime::parse_timestamp(text).map(Literal::Timestamp),
+        Some(DataType::Interval) => text.parse().map(Literal::Interval),
+        _ => return Ok((expr, data_type)),
+    }
+    .map_err(Error::SemanticError)?;
+    Ok((Expr::Literal(literal), other.clone()))
+}
+
+/// Result type of adding or subtracting dates, times and intervals
+fn temporal_result(op: BinaryOperator, left: &DataType, right: &DataType) -> Option<DataType> {
+    use DataType::*;
+    let is_add = op == BinaryOperator::Add;
+    let is_sub = op == BinaryOperator::Sub;
+    match (left, right) {
+        (Date, t) if is_integer(t) && (is_add || is_sub) => Some(Date),
+        (t, Date) if is_integer(t) && is_add => Some(Date),
+        (Date, Date) if is_sub => Some(Integer),
+        (Date | Timestamp, Interval) if is_add || is_sub => Some(Timestamp),
+        (Interval, Date | Timestamp) if is_add => Some(Timestamp),
+        (Timestamp, Timestamp) if is_sub => Some(Interval),
+        (Time, Interval) if is_add || is_sub => Some(Time),
+        (Interval, Time) if is_add => Some(Time),
+        (Time, Time) if is_sub => Some(Interval),
+        (Interval, Interval) if is_add || is_sub => Some(Interval),
+        (Date, Time) | (Time, Date) if is_add => Some(Timestamp),
+        _ => None,
+    }
+}
+
+fn literal_type(lit: &Literal) -> ExprType {
+    match lit {
+        Literal::Null => None,
+        Literal::Boolean(_) => Some(DataType::Boolean),
+        Literal::Integer(i) if i32::try_from(*i).is_ok() => Some(DataType::Integer),
+        Literal::Integer(_) => Some(DataType::BigInt),
+        Literal::Float(_) => Some(DataType::Double),
+        Literal::Decimal(d) => Some(DataType::Decimal(
+            d.precision().max(d.scale()) as u8,
+            d.scale() as u8,
+        )),
+        Literal::String(_) => Some(DataType::Text),
+        Literal::Date(_) => Some(DataType::Date),
+        Literal::Time(_) => Some(DataType::Time),
+        Literal::Timestamp(_) => Some(DataType::Timestamp),
+        Literal::Interval(_) => Some(DataType::Interval),
+    }
+}
+
+fn is_integer(t: &DataType) -> bool {
+    matches!(t, DataType::SmallInt | DataType::Integer | DataType::BigInt)
+}
+
+fn is_string_or_null(t: &ExprType) -> bool {
+    t.as_ref().is_none_or(DataType::is_string)
+}
+
+fn type_name(t: &ExprType) -> String {
+    t.as_ref()
+        .map_or_else(|| "NULL".to_string(), |t| t.to_string())
+}
+
+fn operator_mismatch(op: &str, left: &ExprType, right: &ExprType) -> Error {
+    Error::OperatorTypeMismatch {
+        op: op.to_string(),
+        left: type_name(left),
+        right: type_name(right),
+    }
+}
+
+/// Require a type to satisfy `pred` (an untyped NULL always does)
+fn expect_type(t: &ExprType, pred: impl Fn(&DataType) -> bool, expected: &str) -> Result<()> {
+    match t {
+        Some(t) if !pred(t) => Err(Error::TypeMismatch {
+            from: t.to_string(),
+            to: expected.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Type of the only output column of a subquery used as a value
+fn single_column_type(output: &Scope) -> Result<ExprType> {
+    match output.types.as_slice() {
+        [data_type] => Ok(data_type.clone()),
+        types => Err(Error::SemanticError(format!(
+            "subquery must return only one column, not {}",
+            types.len()
+        ))),
+    }
+}
+
+fn check_comparable(op: &str, left: &ExprType, right: &ExprType) -> Result<()> {
+    match (left, right) {
+        (Some(l), Some(r)) if !l.is_comparable_with(r) => Err(operator_mismatch(op, left, right)),
+        _ => Ok(()),
+    }
+}
+
+/// Check that an identity column has an integer type and no DEFAULT
+fn check_identity(column: &ColumnDef) -> Result<()> {
+    if column.identity.is_none() {
+        return Ok(());
+    }
+    if !is_integer(&column.data_type) {
+        return Err(Error::SemanticError(format!(
+            "identity column '{}' must be SMALLINT, INTEGER or BIGINT",
+            column.name
+        )));
+    }
+    if column.default.is_some() {
+        return Err(Error::SemanticError(format!(
+            "both DEFAULT and identity specified for column '{}'",
+            column.name
+        )));
+    }
+    Ok(())
+}
+
+/// Fail unless a column accepts values other than DEFAULT
+fn check_not_generated_always(column: &Column) -> Result<()> {
+    match &column.identity {
+        Some(identity) if identity.always => Err(Error::SemanticError(format!(
+            "column '{}' is GENERATED ALWAYS and only accepts DEFAULT",
+            column.name
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// The DEFAULT expression of a column, NULL if it has none
+fn column_default(column: &Column) -> Result<Expr> {
+    match &column.default {
+        Some(default) => Parser::new(default)?.parse_expression(),
+        None => Ok(Expr::Literal(Literal::Null)),
+    }
+}
+
+/// The definition of a table being created, with its key constraints
+fn pending_definition(create: &CreateTableStatement) -> TableDef {
+    let mut schema = Schema::new();
+    for (i, column) in create.columns.iter().enumerate() {
+        schema.add_column(Column::new(
+            column.name.clone(),
+            column.data_type.clone(),
+            i,
+        ));
+    }
+    let mut def = TableDef::new(create.table_name.clone(), schema, 0);
+    let key = |kind| Constraint {
+        name: String::new(),
+        kind,
+    };
+    for column in &create.columns {
+        let columns = vec![column.name.clone()];
+        if column.primary_key {
+            def.constraints
+                .push(key(ConstraintKind::PrimaryKey(columns)));
+        } else if column.unique {
+            def.constraints.push(key(ConstraintKind::Unique(columns)));
+        }
+    }
+    for constraint in &create.constraints {
+        match constraint {
+            TableConstraint::PrimaryKey { columns, .. } => def
+                .constraints
+                .push(key(ConstraintKind::PrimaryKey(columns.clone()))),
+            TableConstraint::Unique { columns, .. } => def
+                .constraints
+                .push(key(ConstraintKind::Unique(columns.clone()))),
+            _ => {}
+        }
+    }
+    def
+}
+
+/// Check the REFERENCES clause of a foreign key whose columns have `types`,
+/// filling in the primary key of the referenced table when no columns are given
+fn check_references(
+    references: &mut References,
+    types: &[&DataType],
+    parent: &TableDef,
+) -> Result<()> {
+    if references.columns.is_empty() {
+        references.columns = parent
+            .primary_key()
+            .ok_or_else(|| {
+                Error::SemanticError(format!(
+                    "there is no primary key for referenced table '{}'",
+                    parent.name
+                ))
+            })?
+            .to_vec();
+    }
+    if references.columns.len() != types.len() {
+        return Err(Error::SemanticError(
+            "number of referencing and referenced columns for foreign key disagree".to_string(),
+        ));
+    }
+    for (name, data_type) in references.columns.iter().zip(types) {
+        let column = parent
+            .get_column(name)
+            .ok_or_else(|| Error::ColumnNotFound(name.clone(), parent.name.clone()))?;
+        let is_integer =
+            |t: &DataType| matches!(t, DataType::SmallInt | DataType::Integer | DataType::BigInt);
+        let comparable = **data_type == column.data_type
+            || (is_integer(data_type) && is_integer(&column.data_type))
+            || (data_type.is_string() && column.data_type.is_string());
+        if !comparable {
+            return Err(Error::TypeMismatch {
+                from: data_type.to_string(),
+                to: column.data_type.to_string(),
+            });
+        }
+    }
+    if parent.key_constraint(&references.columns).is_none() {
+        return Err(Error::SemanticError(format!(
+            "there is no unique constraint matching given keys for referenced table '{}'",
+            parent.name
+        )));
+    }
+    Ok(())
+}
+
+fn check_assignable(from: &ExprType, to: &DataType) -> Result<()> {
+    match from {
+        Some(from) if !from.is_assignable_to(to) => Err(Error::TypeMismatch {
+            from: from.to_string(),
+            to: to.to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// Result type of an arithmetic expression over two numeric types
+/// Precision and scale of an exact numeric type; integers have scale 0
+fn exact_parts(t: &DataType) -> Option<(u32, u32)> {
+    match t {
+        DataType::SmallInt => Some((5, 0)),
+        DataType::Integer => Some((10, 0)),
+        DataType::BigInt => Some((19, 0)),
+        DataType::Decimal(p, s) => Some((*p as u32, *s as u32)),
+        _ => None,
+    }
+}
+
+/// DECIMAL type of combining two exact numerics, following the SQL scale rules
+///
+/// `op` is `None` when the values are only unified, as for CASE branches.
+fn decimal_result(op: Option<BinaryOperator>, left: (u32, u32), right: (u32, u32)) -> DataType {
+    let ((p1, s1), (p2, s2)) = (left, right);
+    let (precision, scale) = match op {
+        Some(BinaryOperator::Mul) => (p1 + p2 + 1, s1 + s2),
+        Some(BinaryOperator::Div) => {
+            let scale = MIN_DIVISION_SCALE.max(s1 + p2 + 1);
+            (p1.saturating_sub(s1) + s2 + scale, scale)
+        }
+        Some(_) => {
+            let scale = s1.max(s2);
+            (
+                p1.saturating_sub(s1).max(p2.saturating_sub(s2)) + scale + 1,
+                scale,
+            )
+        }
+        None => {
+            let scale = s1.max(s2);
+            (
+                p1.saturating_sub(s1).max(p2.saturating_sub(s2)) + scale,
+                scale,
+            )
+        }
+    };
+    let precision = precision.min(MAX_PRECISION);
+    DataType::Decimal(precision as u8, scale.min(precision) as u8)
+}
+
+fn numeric_result(op: Option<BinaryOperator>, left: &DataType, right: &DataType) -> DataType {
+    let is_decimal = |t: &DataType| matches!(t, DataType::Decimal(_, _));
+    if let (Some(l), Some(r)) = (exact_parts(left), exact_parts(right)) {
+        if is_decimal(left) || is_decimal(right) {
+            return decimal_result(op, l, r);
+        }
+    }
+
+    let is_float = |t: &DataType| t.is_numeric() && !is_integer(t);
+    if is_float(left) || is_float(right) {
+        DataType::Double
+    } else if *left == DataType::BigInt || *right == DataType::BigInt {
+        DataType::BigInt
+    } else {
+        DataType::Integer
+    }
+}
+
+/// Type both branches of a CASE can be converted to
+fn common_type(a: &ExprType, b: &ExprType) -> Result<ExprType> {
+    match (a, b) {
+        (None, t) | (t, None) => Ok(t.clone()),
+        (Some(x), Some(y)) if x == y => Ok(Some(x.clone())),
+        (Some(x), Some(y)) if x.is_numeric() && y.is_numeric() => {
+            Ok(Some(numeric_result(None, x, y)))
+        }
+        (Some(x), Some(y)) if x.is_string() && y.is_string() => Ok(Some(DataType::Text)),
+        _ => Err(operator_mismatch("CASE", a, b)),
+    }
+}
+
+/// Whether a string type compares against CHAR(n) only after padding
+fn is_unpadded_string(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Varchar(_) | DataType::Text)
+}
+
+/// Cast an expression of type `from` to `to`, unless it already has that type
+fn cast_to(expr: Expr, from: &DataType, to: &DataType) -> Expr {
+    if from == to {
+        return expr;
+    }
+    Expr::Cast {
+        expr: Box::new(expr),
+        data_type: to.clone(),
+    }
+}
+
+/// Cast an expression to CHAR(n), padding it with spaces
+fn pad_to_char(expr: Expr, n: usize) -> Expr {
+    Expr::Cast {
+        expr: Box::new(expr),
+        data_type: DataType::Char(n),
+    }
+}
+
+fn binary_op_type(op: BinaryOperator, left: &ExprType, right: &ExprType) -> Result<ExprType> {
+    let op_name = op.to_string();
+    match op {
+        BinaryOperator::Eq
+        | BinaryOperator::Neq
+        | BinaryOperator::Lt
+        | BinaryOperator::Gt
+        | BinaryOperator::Lte
+        | BinaryOperator::Gte => {
+            check_comparable(&op_name, left, right)?;
+            Ok(Some(DataType::Boolean))
+        }
+        BinaryOperator::And | BinaryOperator::Or => {
+            let is_bool = |t: &ExprType| t.as_ref().is_none_or(|t| *t == DataType::Boolean);
+            if !is_bool(left) || !is_bool(right) {
+                return Err(operator_mismatch(&op_name, left, right));
+            }
+            Ok(Some(DataType::Boolean))
+        }
+        BinaryOperator::Add
+        | BinaryOperator::Sub
+        | BinaryOperator::Mul
+        | BinaryOperator::Div
+        | BinaryOperator::Mod => match (left, right) {
+            (Some(l), Some(r)) if temporal_result(op, l, r).is_some() => {
+                Ok(temporal_result(op, l, r))
+            }
+            (Some(l), Some(r)) if l.is_numeric() && r.is_numeric() => {
+                Ok(Some(numeric_result(Some(op), l, r)))
+            }
+            (Some(t), None) | (None, Some(t)) if t.is_numeric() => Ok(Some(t.clone())),
+            // NULL with a date/time operand: the result type is still unknown
+            (Some(t), None) | (None, Some(t)) if t.is_temporal() || *t == DataType::Interval => {
+                Ok(None)
+            }
+            (None, None) => Ok(None),
+            _ => Err(operator_mismatch(&op_name, left, right)),
+        },
+        BinaryOperator::Concat => Ok(Some(DataType::Text)),
+    }
+}
+
+// ========== Grouping ==========
+
+fn contains_aggregate(expr: &Expr) -> bool {
+    let mut found = false;
+    visit(expr, &mut |e| {
+        if let Expr::Function { name, over, .. } = e {
+            found |= over.is_none() && is_aggregate_function(&name.to_uppercase());
+        }
+    });
+    found
+}
+
+/// Check that every column outside an aggregate is a GROUP BY expression
+fn check_grouped(expr: &Expr, group_by: &[Expr], scope: &Scope) -> Result<()> {
+    if group_by.contains(expr) {
+        return Ok(());
+    }
+    match expr {
+        // A column of an enclosing query is constant within each group
+        Expr::Column(col_ref) if scope.is_outer(col_ref) => Ok(()),
+        Expr::Column(col_ref) => {
+            let idx = binder::resolve_column(&scope.columns, col_ref)?;
+            let grouped = group_by.iter().any(|g| match g {
+                Expr::Column(g) => binder::resolve_column(&scope.columns, g).ok() == Some(idx),
+                _ => false,
+            });
+            if grouped {
+                Ok(())
+            } else {
+                Err(Error::SemanticError(format!(
+                    "column '{}' must appear in the GROUP BY clause or be used in an aggregate function",
+                    col_ref
+                )))
+            }
+        }
+        Expr::Function {
+            name, over: None, ..
+        } if is_aggregate_function(&name.to_uppercase()) => Ok(()),
+        _ => {
+            let mut result = Ok(());
+            for_each_child(expr, &mut |child| {
+                if result.is_ok() {
+                    result = check_grouped(child, group_by, scope);
+                }
+            });
+            result
+        }
+    }
+}
+
+/// An ORDER BY key naming an output column: its 1-based position, or a
+/// name that resolves among the output columns
+fn output_position(expr: &Expr, output: &Scope) -> Result<Option<Expr>> {
+    match expr {
+        Expr::Literal(Literal::Integer(n)) => {
+            if *n >= 1 && (*n as usize) <= output.columns.len() {
+                Ok(Some(expr.clone()))
+            } else {
+                Err(Error::SemanticError(format!(
+                    "ORDER BY position {} is not in select list",
+                    n
+                )))
+            }
+        }
+        Expr::Column(col_ref) => match binder::resolve_column(&output.columns, col_ref) {
+            Ok(index) => Ok(Some(position_literal(index))),
+            Err(Error::ColumnNotFound(..)) => Ok(None),
+            Err(e) => Err(e),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// The ORDER BY key for the output column at `index`
+fn position_literal(index: usize) -> Expr {
+    Expr::Literal(Literal::Integer(index as i64 + 1))
+}
+
+/// Turn aggregate and window calls in ORDER BY into references to the
+/// matching output column
+fn reference_output_aggregates(expr: Expr, output: &Scope) -> Expr {
+    match expr {
+        Expr::Function {
+            ref name, ref over, ..
+        } if over.is_some() || is_aggregate_function(&name.to_uppercase()) => {
+            let text = expr.to_string();
+            if output
+                .columns
+                .iter()
+                .any(|c| c.table.is_none() && c.name == text)
+            {
+                Expr::Column(ColumnRef::from(text))
+            } else {
+                expr
+            }
+        }
+        Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+            left: Box::new(reference_output_aggregates(*left, output)),
+            op,
+            right: Box::new(reference_output_aggregates(*right, output)),
+        },
+        Expr::UnaryOp { op, expr } => Expr::UnaryOp {
+            op,
+            expr: Box::new(reference_output_aggregates(*expr, output)),
+        },
+        Expr::Nested(expr) => Expr::Nested(Box::new(reference_output_aggregates(*expr, output))),
+        other => other,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::catalog::catalog::TableBuilder;
+    use crate::sql::Parser;
+
+    fn catalog() -> Catalog {
+        let catalog = Catalog::new();
+        TableBuilder::new("t")
+            .column("id", DataType::Integer)
+            .column("big", DataType::BigInt)
+            .column("name", DataType::Varchar(20))
+            .build(&catalog)
+            .unwrap();
+        catalog
+    }
+
+    fn analyze(catalog: &Catalog, sql: &str) -> Result<Statement> {
+        let stmt = Parser::new(sql)?.parse()?;
+        Analyzer::new(catalog).analyze(stmt)
+    }
+
+    #[test]
+    fn test_rejects_ill_typed_statements() {
+        let catalog = catalog();
+
+        assert!(matches!(
+            analyze(&catalog, "SELECT * FROM t WHERE id = 'x'"),
+            Err(Error::OperatorTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            analyze(&catalog, "SELECT name + 1 FROM t"),
+            Err(Error::OperatorTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            analyze(&catalog, "INSERT INTO t VALUES ('a', 1, 'b')"),
+            Err(Error::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            analyze(&catalog, "SELECT missing FROM t"),
+            Err(Error::ColumnNotFound(_, _))
+        ));
+        assert!(matches!(
+            analyze(&catalog, "SELECT * FROM nope"),
+            Err(Error::TableNotFound(_))
+        ));
+        assert!(matches!(
+            analyze(&catalog, "SELECT id FROM t WHERE COUNT(*) > 1"),
+            Err(Error::SemanticError(_))
+        ));
+        assert!(matches!(
+            analyze(&catalog, "SELECT name, COUNT(*) FROM t"),
+            Err(Error::SemanticError(_))
+        ));
+    }
+
+    #[test]
+    fn test_assignments_left_to_storage() {
+        let catalog = catalog();
+        let stmt = analyze(&catalog, "INSERT INTO t VALUES (1, 2, 'x')").unwrap();
+
+        let Statement::Insert(insert) = stmt else {
+            panic!("expected INSERT");
+        };
+        // Assignable values are converted by the table, not rewritten here
+        assert_eq!(insert.values[0][1], Expr::Literal(Literal::Integer(2)));
+        assert_eq!(
+            insert.values[0][2],
+            Expr::Literal(Literal::String("x".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_join_keys_cast_to_common_type() {
+        let catalog = catalog();
+        let stmt = analyze(&catalog, "SELECT * FROM t a JOIN t b ON a.id = b.big").unwrap();
+
+        let Statement::Select(select) = stmt else {
+            panic!("expected SELECT");
+        };
+        let condition = select.from.unwrap().joins[0].condition.clone().unwrap();
+        let Expr::BinaryOp { left, right, .. } = condition else {
+            panic!("expected a comparison");
+        };
+        assert!(matches!(
+            *left,
+            Expr::Cast {
+                data_type: DataType::BigInt,
+                ..
+            }
+        ));
+        assert!(matches!(*right, Expr::Column(_)));
+    }
+}
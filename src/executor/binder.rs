@@ -0,0 +1,322 @@
+//! Name binding for ArcDB
+//!
+//! Every operator describes its output with a list of [`BoundColumn`]s that
+//! record where each column came from. The binder resolves column references
+//! (`c` or `t.c`) against such a schema and rejects references that are
+//! unknown or ambiguous.
+
+use std::fmt;
+
+use crate::error::{Error, Result};
+use crate::sql::ast::*;
+
+/// An output column together with the table (or alias) it was read from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundColumn {
+    /// Qualifier: the table alias, or the table name when there is no alias
+    pub table: Option<String>,
+    /// Column name
+    pub name: String,
+}
+
+impl BoundColumn {
+    /// Create a column read from a table
+    pub fn qualified(table: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            table: Some(table.into()),
+            name: name.into(),
+        }
+    }
+
+    /// Create a computed column with no table provenance
+    pub fn unqualified(name: impl Into<String>) -> Self {
+        Self {
+            table: None,
+            name: name.into(),
+        }
+    }
+
+    /// Check whether a column reference names this column
+    pub fn matches(&self, col_ref: &ColumnRef) -> bool {
+        if self.name != col_ref.column {
+            return false;
+        }
+        match &col_ref.table {
+            Some(table) => self.table.as_ref() == Some(table),
+            None => true,
+        }
+    }
+}
+
+impl fmt::Display for BoundColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "{}.{}", table, self.name),
+            None => write!(f, "{}", self.name),
+        }
+    }
+}
+
+/// Qualify every column of a table schema with a table name or alias
+pub fn qualify(table: &str, names: &[&str]) -> Vec<BoundColumn> {
+    names
+        .iter()
+        .map(|name| BoundColumn::qualified(table, *name))
+        .collect()
+}
+
+/// Bare column names of a schema, as shown in query results
+pub fn column_names(columns: &[BoundColumn]) -> Vec<String> {
+    columns.iter().map(|c| c.name.clone()).collect()
+}
+
+/// Resolve a column reference to its position in `columns`
+pub fn resolve_column(columns: &[BoundColumn], col_ref: &ColumnRef) -> Result<usize> {
+    let mut found = None;
+    for (idx, column) in columns.iter().enumerate() {
+        if column.matches(col_ref) {
+            if found.is_some() {
+                return Err(Error::AmbiguousColumn(col_ref.to_string()));
+            }
+            found = Some(idx);
+        }
+    }
+
+    found.ok_or_else(|| {
+        Error::ColumnNotFound(
+            col_ref.column.clone(),
+            col_ref.table.clone().unwrap_or_default(),
+        )
+    })
+}
+
+/// Positions of the columns selected by `table.*`
+pub fn resolve_qualified_wildcard(columns: &[BoundColumn], table: &str) -> Result<Vec<usize>> {
+    let positions: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| column.table.as_deref() == Some(table))
+        .map(|(idx, _)| idx)
+        .collect();
+
+    if positions.is_empty() {
+        return Err(Error::TableNotFound(table.to_string()));
+    }
+    Ok(positions)
+}
+
+/// Check that every column referenced by an expression resolves in `columns`
+pub fn bind_expr(expr: &Expr, columns: &[BoundColumn]) -> Result<()> {
+    match expr {
+        Expr::Column(col_ref) => resolve_column(columns, col_ref).map(|_| ()),
+        Expr::BinaryOp { left, right, .. } => {
+            bind_expr(left, columns)?;
+            bind_expr(right, columns)
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::Nested(expr)
+        | Expr::Cast { expr, .. } => bind_expr(expr, columns),
+        Expr::Function {
+            name, args, over, ..
+        } => {
+            if let Some(over) = over {
+                over.partition_by
+                    .iter()
+                    .chain(over.order_by.iter().map(|item| &item.expr))
+                    .try_for_each(|expr| bind_expr(expr, columns))?;
+            }
+            // COUNT(*) has no column to bind
+            let is_star = |arg: &Expr| matches!(arg, Expr::Column(c) if c.column == "*");
+            if name.eq_ignore_ascii_case("COUNT") && args.iter().all(is_star) {
+                return Ok(());
+            }
+            args.iter().try_for_each(|arg| bind_expr(arg, columns))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Call `f` on each direct child expression
+pub fn for_each_child(expr: &Expr, f: &mut dyn FnMut(&Expr)) {
+    match expr {
+        Expr::BinaryOp { left, right, .. } => {
+            f(left);
+            f(right);
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::Nested(expr)
+        | Expr::Cast { expr, .. } => f(expr),
+        Expr::Function { args, over, .. } => {
+            args.iter().for_each(&mut *f);
+            if let Some(over) = over {
+                over.partition_by.iter().for_each(&mut *f);
+                over.order_by.iter().for_each(|item| f(&item.expr));
+            }
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            f(expr);
+            f(low);
+            f(high);
+        }
+        Expr::InList { expr, list, .. } => {
+            f(expr);
+            list.iter().for_each(f);
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            f(expr);
+            f(pattern);
+            if let Some(escape) = escape {
+                f(escape);
+            }
+        }
+        Expr::Case {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            if let Some(operand) = operand {
+                f(operand);
+            }
+            for (condition, result) in when_clauses {
+                f(condition);
+                f(result);
+            }
+            if let Some(else_clause) = else_clause {
+                f(else_clause);
+            }
+        }
+        // Subqueries are bound in their own scope; only the IN operand is a child
+        Expr::InSubquery { expr, .. } => f(expr),
+        Expr::Column(_)
+        | Expr::Literal(_)
+        | Expr::Default
+        | Expr::Subquery(_)
+        | Expr::Exists(_) => {}
+    }
+}
+
+/// Call `f` on an expression and all of its descendants
+pub fn visit(expr: &Expr, f: &mut dyn FnMut(&Expr)) {
+    f(expr);
+    for_each_child(expr, &mut |child| visit(child, f));
+}
+
+/// Call `f` on each direct child expression, allowing it to be rewritten
+pub fn for_each_child_mut(expr: &mut Expr, f: &mut dyn FnMut(&mut Expr)) {
+    match expr {
+        Expr::BinaryOp { left, right, .. } => {
+            f(left);
+            f(right);
+        }
+        Expr::UnaryOp { expr, .. }
+        | Expr::IsNull(expr)
+        | Expr::IsNotNull(expr)
+        | Expr::Nested(expr)
+        | Expr::Cast { expr, .. } => f(expr),
+        Expr::Function { args, over, .. } => {
+            args.iter_mut().for_each(&mut *f);
+            if let Some(over) = over {
+                over.partition_by.iter_mut().for_each(&mut *f);
+                over.order_by.iter_mut().for_each(|item| f(&mut item.expr));
+            }
+        }
+        Expr::Between {
+            expr, low, high, ..
+        } => {
+            f(expr);
+            f(low);
+            f(high);
+        }
+        Expr::InList { expr, list, .. } => {
+            f(expr);
+            list.iter_mut().for_each(f);
+        }
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            ..
+        } => {
+            f(expr);
+            f(pattern);
+            if let Some(escape) = escape {
+                f(escape);
+            }
+        }
+        Expr::Case {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            if let Some(operand) = operand {
+                f(operand);
+            }
+            for (condition, result) in when_clauses {
+                f(condition);
+                f(result);
+            }
+            if let Some(else_clause) = else_clause {
+                f(else_clause);
+            }
+        }
+        // Subqueries are bound in their own scope; only the IN operand is a child
+        Expr::InSubquery { expr, .. } => f(expr),
+        Expr::Column(_)
+        | Expr::Literal(_)
+        | Expr::Default
+        | Expr::Subquery(_)
+        | Expr::Exists(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn col(table: Option<&str>, column: &str) -> ColumnRef {
+        ColumnRef {
+            table: table.map(str::to_string),
+            column: column.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_qualified_and_ambiguous() {
+        let mut columns = qualify("e", &["id", "name"]);
+        columns.extend(qualify("d", &["id", "dname"]));
+
+        assert_eq!(resolve_column(&columns, &col(Some("d"), "id")).unwrap(), 2);
+        assert_eq!(resolve_column(&columns, &col(None, "dname")).unwrap(), 3);
+        assert!(matches!(
+            resolve_column(&columns, &col(None, "id")),
+            Err(Error::AmbiguousColumn(_))
+        ));
+        assert!(matches!(
+            resolve_column(&columns, &col(Some("x"), "id")),
+            Err(Error::ColumnNotFound(_, _))
+        ));
+    }
+
+    #[test]
+    fn test_qualified_wildcard() {
+        let mut columns = qualify("e", &["id", "name"]);
+        columns.extend(qualify("d", &["id"]));
+
+        assert_eq!(
+            resolve_qualified_wildcard(&columns, "e").unwrap(),
+            vec![0, 1]
+        );
+        assert!(resolve_qualified_wildcard(&columns, "x").is_err());
+    }
+}
@@ -0,0 +1,884 @@
+//! Expression evaluation for ArcDB
+//!
+//! This module evaluates AST expressions against rows. It is shared by the
+//! execution engine and the physical operators.
+
+use std::cmp::Ordering;
+
+use super::binder::{resolve_column, BoundColumn};
+use crate::catalog::DataType;
+use crate::error::{Error, Result};
+use crate::sql::ast::*;
+use crate::storage::datetime::{self, DateTimeField, Interval, MS_PER_DAY};
+use crate::storage::table::pad_char;
+use crate::storage::{Decimal, Value};
+
+/// Evaluate an expression against a row whose columns are described by `columns`
+pub fn evaluate_expr(expr: &Expr, row: &[Value], columns: &[BoundColumn]) -> Result<Value> {
+    match expr {
+        Expr::Literal(lit) => Ok(literal_to_value(lit)),
+
+        Expr::Column(col_ref) => {
+            let idx = resolve_column(columns, col_ref)?;
+
+            row.get(idx)
+                .cloned()
+                .ok_or_else(|| Error::ExecutionError(format!("Column index {} out of bounds", idx)))
+        }
+
+        Expr::BinaryOp {
+            left,
+            op: op @ (BinaryOperator::And | BinaryOperator::Or),
+            right,
+        } => {
+            // FALSE AND x and TRUE OR x are decided by the left side alone
+            let left_val = truth_value(&evaluate_expr(left, row, columns)?)?;
+            let decisive = *op == BinaryOperator::Or;
+            if left_val == Some(decisive) {
+                return Ok(Value::Boolean(decisive));
+            }
+            let right_val = truth_value(&evaluate_expr(right, row, columns)?)?;
+            let result = if decisive {
+                or3(left_val, right_val)
+            } else {
+                and3(left_val, right_val)
+            };
+            Ok(from_truth(result))
+        }
+
+        Expr::BinaryOp { left, op, right } => {
+            let left_val = evaluate_expr(left, row, columns)?;
+            let right_val = evaluate_expr(right, row, columns)?;
+
+            evaluate_binary_op(&left_val, op, &right_val)
+        }
+
+        Expr::UnaryOp { op, expr } => {
+            let val = evaluate_expr(expr, row, columns)?;
+            evaluate_unary_op(op, &val)
+        }
+
+        Expr::IsNull(inner) => {
+            let val = evaluate_expr(inner, row, columns)?;
+            Ok(Value::Boolean(val.is_null()))
+        }
+
+        Expr::IsNotNull(inner) => {
+            let val = evaluate_expr(inner, row, columns)?;
+            Ok(Value::Boolean(!val.is_null()))
+        }
+
+        Expr::Nested(inner) => evaluate_expr(inner, row, columns),
+
+        Expr::Function { name, args, .. } => evaluate_function(name, args, row, columns),
+
+        Expr::Cast { expr, data_type } => {
+            let val = evaluate_expr(expr, row, columns)?;
+            cast_value(val, data_type)
+        }
+
+        Expr::Between {
+            expr,
+            low,
+            high,
+            negated,
+        } => {
+            let val = evaluate_expr(expr, row, columns)?;
+            let low = evaluate_expr(low, row, columns)?;
+            let high = evaluate_expr(high, row, columns)?;
+            let within = and3(
+                compare3(&val, BinaryOperator::Gte, &low),
+                compare3(&val, BinaryOperator::Lte, &high),
+            );
+            Ok(from_truth(if *negated { not3(within) } else { within }))
+        }
+
+        Expr::InList {
+            expr,
+            list,
+            negated,
+        } => {
+            let val = evaluate_expr(expr, row, columns)?;
+            let candidates = list
+                .iter()
+                .map(|item| evaluate_expr(item, row, columns))
+                .collect::<Result<Vec<_>>>()?;
+            Ok(evaluate_in(&val, &candidates, *negated))
+        }
+
+        Expr::Like {
+            expr,
+            pattern,
+            escape,
+            negated,
+            case_insensitive,
+        } => {
+            let val = evaluate_expr(expr, row, columns)?;
+            let pattern = evaluate_expr(pattern, row, columns)?;
+            let escape = match escape {
+                Some(escape) => evaluate_expr(escape, row, columns)?,
+                None => Value::String(DEFAULT_LIKE_ESCAPE.to_string()),
+            };
+            let (Value::String(text), Value::String(pattern), Value::String(escape)) =
+                (&val, &pattern, &escape)
+            else {
+                return Ok(Value::Null);
+            };
+            let matched = like_match(text, pattern, escape, *case_insensitive)?;
+            Ok(Value::Boolean(matched != *negated))
+        }
+
+        Expr::Case {
+            operand,
+            when_clauses,
+            else_clause,
+        } => {
+            let operand = match operand {
+                Some(operand) => Some(evaluate_expr(operand, row, columns)?),
+                None => None,
+            };
+            for (condition, result) in when_clauses {
+                let condition = evaluate_expr(condition, row, columns)?;
+                // A simple CASE compares with `=`, so a NULL operand matches nothing
+                let matched = match &operand {
+                    Some(operand) => compare3(operand, BinaryOperator::Eq, &condition),
+                    None => truth_value(&condition)?,
+                };
+                if matched == Some(true) {
+                    return evaluate_expr(result, row, columns);
+                }
+            }
+            match else_clause {
+                Some(else_clause) => evaluate_expr(else_clause, row, columns),
+                None => Ok(Value::Null),
+            }
+        }
+
+        _ => Err(Error::ExecutionError(format!(
+            "Unsupported expression: {:?}",
+            expr
+        ))),
+    }
+}
+
+// ========== Three-Valued Logic ==========
+
+/// Truth value of a boolean operand: `None` is UNKNOWN (a NULL operand)
+pub fn truth_value(value: &Value) -> Result<Option<bool>> {
+    match value {
+        Value::Null => Ok(None),
+        other => other
+            .as_bool()
+            .map(Some)
+            .ok_or_else(|| Error::TypeMismatch {
+                from: other.type_name().to_string(),
+                to: "BOOLEAN".to_string(),
+            }),
+    }
+}
+
+/// Convert a truth value back to a value, UNKNOWN being NULL
+fn from_truth(truth: Option<bool>) -> Value {
+    truth.map_or(Value::Null, Value::Boolean)
+}
+
+/// Three-valued AND: FALSE wins over UNKNOWN
+fn and3(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(false), _) | (_, Some(false)) => Some(false),
+        (Some(true), Some(true)) => Some(true),
+        _ => None,
+    }
+}
+
+/// Three-valued OR: TRUE wins over UNKNOWN
+fn or3(left: Option<bool>, right: Option<bool>) -> Option<bool> {
+    match (left, right) {
+        (Some(true), _) | (_, Some(true)) => Some(true),
+        (Some(false), Some(false)) => Some(false),
+        _ => None,
+    }
+}
+
+/// Three-valued NOT: NOT UNKNOWN is UNKNOWN
+fn not3(truth: Option<bool>) -> Option<bool> {
+    truth.map(|b| !b)
+}
+
+/// `value [NOT] IN (candidates)`: TRUE on any match, otherwise UNKNOWN if
+/// any comparison was
+pub fn evaluate_in(value: &Value, candidates: &[Value], negated: bool) -> Value {
+    let mut found = Some(false);
+    for candidate in candidates {
+        found = or3(found, compare3(value, BinaryOperator::Eq, candidate));
+        if found == Some(true) {
+            break;
+        }
+    }
+    from_truth(if negated { not3(found) } else { found })
+}
+
+/// Compare two values; any comparison with NULL is UNKNOWN
+fn compare3(left: &Value, op: BinaryOperator, right: &Value) -> Option<bool> {
+    if left.is_null() || right.is_null() {
+        return None;
+    }
+    let cmp = left.compare(right);
+    let result = match op {
+        BinaryOperator::Eq => cmp == Some(Ordering::Equal),
+        BinaryOperator::Neq => cmp != Some(Ordering::Equal),
+        BinaryOperator::Lt => cmp == Some(Ordering::Less),
+        BinaryOperator::Gt => cmp == Some(Ordering::Greater),
+        BinaryOperator::Lte => matches!(cmp, Some(Ordering::Less | Ordering::Equal)),
+        BinaryOperator::Gte => matches!(cmp, Some(Ordering::Greater | Ordering::Equal)),
+        _ => return None,
+    };
+    Some(result)
+}
+
+// ========== Pattern Matching ==========
+
+/// Escape character of a LIKE pattern without an ESCAPE clause
+const DEFAULT_LIKE_ESCAPE: char = '\\';
+
+/// One element of a compiled LIKE pattern
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum LikeToken {
+    /// A character that must match exactly
+    Char(char),
+    /// `_`: exactly one character
+    AnyChar,
+    /// `%`: any run of characters, including none
+    AnyRun,
+}
+
+/// Split a LIKE pattern into tokens, resolving escapes
+///
+/// An empty escape string disables escaping.
+fn compile_like(pattern: &str, escape: &str) -> Result<Vec<LikeToken>> {
+    let mut escape_chars = escape.chars();
+    let escape = match (escape_chars.next(), escape_chars.next()) {
+        (escape, None) => escape,
+        _ => {
+            return Err(Error::ExecutionError(format!(
+                "invalid escape string '{}': must be a single character",
+                escape
+            )))
+        }
+    };
+
+    let mut tokens = Vec::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if Some(c) == escape => match chars.next() {
+                Some(escaped) => LikeToken::Char(escaped),
+                None => {
+                    return Err(Error::ExecutionError(format!(
+                        "LIKE pattern '{}' must not end with the escape character",
+                        pattern
+                    )))
+                }
+            },
+            '%' => LikeToken::AnyRun,
+            '_' => LikeToken::AnyChar,
+            c => LikeToken::Char(c),
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Match `text` against a LIKE pattern
+pub fn like_match(text: &str, pattern: &str, escape: &str, case_insensitive: bool) -> Result<bool> {
+    let fold = |c: char| {
+        if case_insensitive {
+            c.to_lowercase().next().unwrap_or(c)
+        } else {
+            c
+        }
+    };
+    let text: Vec<char> = text.chars().map(fold).collect();
+    let tokens: Vec<LikeToken> = compile_like(pattern, escape)?
+        .into_iter()
+        .map(|token| match token {
+            LikeToken::Char(c) => LikeToken::Char(fold(c)),
+            other => other,
+        })
+        .collect();
+
+    // Greedy scan that backtracks to the most recent `%`
+    let (mut t, mut p) = (0, 0);
+    let mut last_run: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match tokens.get(p) {
+            Some(LikeToken::AnyRun) => {
+                p += 1;
+                last_run = Some((p, t));
+            }
+            Some(LikeToken::AnyChar) => {
+                t += 1;
+                p += 1;
+            }
+            Some(LikeToken::Char(c)) if *c == text[t] => {
+                t += 1;
+                p += 1;
+            }
+            _ => match last_run {
+                // Let the last `%` absorb one more character and retry
+                Some((run_p, run_t)) => {
+                    p = run_p;
+                    t = run_t + 1;
+                    last_run = Some((run_p, t));
+                }
+                None => return Ok(false),
+            },
+        }
+    }
+    Ok(tokens[p..].iter().all(|token| *token == LikeToken::AnyRun))
+}
+
+/// Evaluate an expression that does not reference any columns
+pub fn evaluate_const_expr(expr: &Expr) -> Result<Value> {
+    evaluate_expr(expr, &[], &[])
+}
+
+/// Convert a literal to a value
+pub fn literal_to_value(lit: &Literal) -> Value {
+    match lit {
+        Literal::Null => Value::Null,
+        Literal::Boolean(b) => Value::Boolean(*b),
+        Literal::Integer(i) => match i32::try_from(*i) {
+            Ok(i) => Value::Integer(i),
+            Err(_) => Value::BigInt(*i),
+        },
+        Literal::Float(f) => Value::Float(*f),
+        Literal::Decimal(d) => Value::Decimal(*d),
+        Literal::String(s) => Value::String(s.clone()),
+        Literal::Date(d) => Value::Date(*d),
+        Literal::Time(t) => Value::Time(*t),
+        Literal::Timestamp(t) => Value::Timestamp(*t),
+        Literal::Interval(i) => Value::Interval(*i),
+    }
+}
+
+/// Convert a value to the representation of a column type
+pub fn cast_value(value: Value, target: &DataType) -> Result<Value> {
+    let mismatch = |value: &Value| Error::TypeMismatch {
+        from: value.type_name().to_string(),
+        to: target.to_string(),
+    };
+    let out_of_range =
+        |value: &Value| Error::ExecutionError(format!("{} is out of range for {}", value, target));
+
+    if value.is_null() {
+        return Ok(Value::Null);
+    }
+
+    match target {
+        DataType::SmallInt | DataType::Integer => {
+            let wide = match &value {
+                Value::Integer(i) => *i as i64,
+                Value::BigInt(i) => *i,
+                Value::Float(f) if f.is_finite() => f.trunc() as i64,
+                Value::Decimal(d) => d.to_i64().ok_or_else(|| out_of_range(&value))?,
+                Value::Boolean(b) => *b as i64,
+                Value::String(s) => s.trim().parse().map_err(|_| mismatch(&value))?,
+                _ => return Err(mismatch(&value)),
+            };
+            let (min, max) = if *target == DataType::SmallInt {
+                (i16::MIN as i64, i16::MAX as i64)
+            } else {
+                (i32::MIN as i64, i32::MAX as i64)
+            };
+            if wide < min || wide > max {
+                return Err(out_of_range(&value));
+            }
+            Ok(Value::Integer(wide as i32))
+        }
+        DataType::BigInt => match &value {
+            Value::Integer(i) => Ok(Value::BigInt(*i as i64)),
+            Value::BigInt(i) => Ok(Value::BigInt(*i)),
+            Value::Float(f) if f.is_finite() => {
+                if f.trunc() < i64::MIN as f64 || f.trunc() >= i64::MAX as f64 {
+                    return Err(out_of_range(&value));
+                }
+                Ok(Value::BigInt(f.trunc() as i64))
+            }
+            Value::Decimal(d) => d
+                .to_i64()
+                .map(Value::BigInt)
+                .ok_or_else(|| out_of_range(&value)),
+            Value::Boolean(b) => Ok(Value::BigInt(*b as i64)),
+            Value::String(s) => s
+                .trim()
+                .parse()
+                .map(Value::BigInt)
+                .map_err(|_| mismatch(&value)),
+            _ => Err(mismatch(&value)),
+        },
+        DataType::Decimal(precision, scale) => {
+            let decimal = match &value {
+                Value::Float(f) => Decimal::from_f64(*f).ok_or_else(|| out_of_range(&value))?,
+                Value::String(s) => s.parse().map_err(|_| mismatch(&value))?,
+                other => other.as_decimal().ok_or_else(|| mismatch(&value))?,
+            };
+            decimal
+                .fit(*precision as u32, *scale as u32)
+                .map(Value::Decimal)
+                .ok_or_else(|| out_of_range(&value))
+        }
+        DataType::Float | DataType::Double => match &value {
+            Value::String(s) => s
+                .trim()
+                .parse()
+                .map(Value::Float)
+                .map_err(|_| mismatch(&value)),
+            other => other
+                .as_f64()
+                .map(Value::Float)
+                .ok_or_else(|| mismatch(&value)),
+        },
+        DataType::Char(_) | DataType::Varchar(_) | DataType::Text => {
+            let s = match value {
+                Value::String(s) => s,
+                Value::Bytes(_) => return Err(mismatch(&value)),
+                other => other.to_string(),
+            };
+            match target {
+                DataType::Char(n) => Ok(Value::String(pad_char(s, *n))),
+                _ => Ok(Value::String(s)),
+            }
+        }
+        DataType::Boolean => match &value {
+            Value::Boolean(b) => Ok(Value::Boolean(*b)),
+            Value::Integer(_) | Value::BigInt(_) => {
+                Ok(Value::Boolean(value.as_bool() == Some(true)))
+            }
+            Value::String(s) => match s.trim().to_lowercase().as_str() {
+                "true" | "t" | "1" => Ok(Value::Boolean(true)),
+                "false" | "f" | "0" => Ok(Value::Boolean(false)),
+                _ => Err(mismatch(&value)),
+            },
+            _ => Err(mismatch(&value)),
+        },
+        DataType::Date => match value {
+            Value::Date(d) => Ok(Value::Date(d)),
+            Value::Timestamp(t) => Ok(Value::Date(t.div_euclid(MS_PER_DAY) as i32)),
+            // A timestamp string casts to its date
+            Value::String(s) => datetime::parse_date(&s)
+                .or_else(|_| datetime::parse_timestamp(&s).map(|t| t.div_euclid(MS_PER_DAY) as i32))
+                .map(Value::Date)
+                .map_err(Error::ExecutionError),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Time => match value {
+            Value::Time(t) => Ok(Value::Time(t)),
+            Value::Timestamp(t) => Ok(Value::Time(t.rem_euclid(MS_PER_DAY))),
+            Value::String(s) => datetime::parse_time(&s)
+                .map(Value::Time)
+                .map_err(Error::ExecutionError),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Timestamp => match value {
+            Value::Timestamp(t) => Ok(Value::Timestamp(t)),
+            Value::Date(d) => Ok(Value::Timestamp(d as i64 * MS_PER_DAY)),
+            Value::String(s) => datetime::parse_timestamp(&s)
+                .map(Value::Timestamp)
+                .map_err(Error::ExecutionError),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Interval => match value {
+            Value::Interval(i) => Ok(Value::Interval(i)),
+            Value::String(s) => s
+                .parse()
+                .map(Value::Interval)
+                .map_err(Error::ExecutionError),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Blob => match value {
+            Value::Bytes(b) => Ok(Value::Bytes(b)),
+            Value::String(s) => Ok(Value::Bytes(s.into_bytes())),
+            other => Err(mismatch(&other)),
+        },
+    }
+}
+
+/// Apply a binary operator to two values
+pub fn evaluate_binary_op(left: &Value, op: &BinaryOperator, right: &Value) -> Result<Value> {
+    let is_arithmetic = matches!(
+        op,
+        BinaryOperator::Add
+            | BinaryOperator::Sub
+            | BinaryOperator::Mul
+            | BinaryOperator::Div
+            | BinaryOperator::Mod
+    );
+    let is_temporal = |v: &Value| {
+        matches!(
+            v,
+            Value::Date(_) | Value::Time(_) | Value::Timestamp(_) | Value::Interval(_)
+        )
+    };
+    if is_arithmetic && (is_temporal(left) || is_temporal(right)) {
+        return evaluate_temporal_op(left, op, right);
+    }
+    if is_arithmetic && (matches!(left, Value::Decimal(_)) || matches!(right, Value::Decimal(_))) {
+        return evaluate_decimal_op(left, op, right);
+    }
+
+    match op {
+        BinaryOperator::Eq
+        | BinaryOperator::Neq
+        | BinaryOperator::Lt
+        | BinaryOperator::Gt
+        | BinaryOperator::Lte
+        | BinaryOperator::Gte => Ok(from_truth(compare3(left, *op, right))),
+        BinaryOperator::And => Ok(from_truth(and3(truth_value(left)?, truth_value(right)?))),
+        BinaryOperator::Or => Ok(from_truth(or3(truth_value(left)?, truth_value(right)?))),
+        // Arithmetic and concatenation with NULL yield NULL
+        _ if left.is_null() || right.is_null() => Ok(Value::Null),
+        BinaryOperator::Add => left.add(right).ok_or_else(|| Error::TypeMismatch {
+            from: left.type_name().to_string(),
+            to: right.type_name().to_string(),
+        }),
+        BinaryOperator::Sub => left.sub(right).ok_or_else(|| Error::TypeMismatch {
+            from: left.type_name().to_string(),
+            to: right.type_name().to_string(),
+        }),
+        BinaryOperator::Mul => left.mul(right).ok_or_else(|| Error::TypeMismatch {
+            from: left.type_name().to_string(),
+            to: right.type_name().to_string(),
+        }),
+        BinaryOperator::Div => {
+            if let Some(r) = right.as_f64() {
+                if r == 0.0 {
+                    return Err(Error::DivisionByZero);
+                }
+            }
+            left.div(right).ok_or_else(|| Error::TypeMismatch {
+                from: left.type_name().to_string(),
+                to: right.type_name().to_string(),
+            })
+        }
+        BinaryOperator::Mod => {
+            if right.as_f64() == Some(0.0) {
+                return Err(Error::DivisionByZero);
+            }
+            left.rem(right).ok_or_else(|| Error::TypeMismatch {
+                from: left.type_name().to_string(),
+                to: right.type_name().to_string(),
+            })
+        }
+        BinaryOperator::Concat => match (left, right) {
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            _ => Ok(Value::String(format!("{}{}", left, right))),
+        },
+    }
+}
+
+/// Arithmetic with a DECIMAL operand: exact unless the other side is a float
+fn evaluate_decimal_op(left: &Value, op: &BinaryOperator, right: &Value) -> Result<Value> {
+    let mismatch = || Error::TypeMismatch {
+        from: left.type_name().to_string(),
+        to: right.type_name().to_string(),
+    };
+    if left.is_null() || right.is_null() {
+        return Ok(Value::Null);
+    }
+    if matches!(left, Value::Float(_)) || matches!(right, Value::Float(_)) {
+        let (l, r) = (
+            left.as_f64().ok_or_else(mismatch)?,
+            right.as_f64().ok_or_else(mismatch)?,
+        );
+        return evaluate_binary_op(&Value::Float(l), op, &Value::Float(r));
+    }
+
+    let (l, r) = (
+        left.as_decimal().ok_or_else(mismatch)?,
+        right.as_decimal().ok_or_else(mismatch)?,
+    );
+    let result = match op {
+        BinaryOperator::Add => l.checked_add(&r),
+        BinaryOperator::Sub => l.checked_sub(&r),
+        BinaryOperator::Mul => l.checked_mul(&r),
+        BinaryOperator::Div if r.is_zero() => return Err(Error::DivisionByZero),
+        BinaryOperator::Div => l.checked_div(&r),
+        BinaryOperator::Mod if r.is_zero() => return Err(Error::DivisionByZero),
+        BinaryOperator::Mod => l.checked_rem(&r),
+        _ => return Err(mismatch()),
+    };
+    result
+        .map(Value::Decimal)
+        .ok_or_else(|| Error::ExecutionError(format!("numeric overflow in {} {} {}", l, op, r)))
+}
+
+/// Arithmetic on dates, times, timestamps and intervals
+fn evaluate_temporal_op(left: &Value, op: &BinaryOperator, right: &Value) -> Result<Value> {
+    let out_of_range = || {
+        Error::ExecutionError(format!(
+            "date/time value out of range in {} {} {}",
+            left, op, right
+        ))
+    };
+    let is_add = *op == BinaryOperator::Add;
+    let is_sub = *op == BinaryOperator::Sub;
+    let signed = |i: &Interval| if is_sub { i.neg() } else { *i };
+    let shift = |timestamp: i64, interval: &Interval| {
+        interval
+            .add_to_timestamp(timestamp)
+            .map(Value::Timestamp)
+            .ok_or_else(out_of_range)
+    };
+    let day_count = |v: &Value| match v {
+        Value::Integer(n) => Some(*n as i64),
+        Value::BigInt(n) => Some(*n),
+        _ => None,
+    };
+
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Date(d), n) if (is_add || is_sub) && day_count(n).is_some() => {
+            let n = day_count(n).unwrap();
+            let days = if is_sub { *d as i64 - n } else { *d as i64 + n };
+            i32::try_from(days)
+                .map(Value::Date)
+                .map_err(|_| out_of_range())
+        }
+        (n, Value::Date(d)) if is_add && day_count(n).is_some() => {
+            let days = *d as i64 + day_count(n).unwrap();
+            i32::try_from(days)
+                .map(Value::Date)
+                .map_err(|_| out_of_range())
+        }
+        (Value::Date(a), Value::Date(b)) if is_sub => Ok(Value::Integer(a - b)),
+        (Value::Date(d), Value::Interval(i)) if is_add || is_sub => {
+            shift(*d as i64 * MS_PER_DAY, &signed(i))
+        }
+        (Value::Timestamp(t), Value::Interval(i)) if is_add || is_sub => shift(*t, &signed(i)),
+        (Value::Interval(i), Value::Date(d)) if is_add => shift(*d as i64 * MS_PER_DAY, i),
+        (Value::Interval(i), Value::Timestamp(t)) if is_add => shift(*t, i),
+        (Value::Timestamp(a), Value::Timestamp(b)) if is_sub => {
+            let diff = a.checked_sub(*b).ok_or_else(out_of_range)?;
+            let days = i32::try_from(diff / MS_PER_DAY).map_err(|_| out_of_range())?;
+            Ok(Value::Interval(Interval::new(0, days, diff % MS_PER_DAY)))
+        }
+        (Value::Time(t), Value::Interval(i)) if is_add || is_sub => {
+            Ok(Value::Time((t + signed(i).millis).rem_euclid(MS_PER_DAY)))
+        }
+        (Value::Interval(i), Value::Time(t)) if is_add => {
+            Ok(Value::Time((t + i.millis).rem_euclid(MS_PER_DAY)))
+        }
+        (Value::Time(a), Value::Time(b)) if is_sub => {
+            Ok(Value::Interval(Interval::new(0, 0, a - b)))
+        }
+        (Value::Interval(a), Value::Interval(b)) if is_add || is_sub => a
+            .checked_add(&signed(b))
+            .map(Value::Interval)
+            .ok_or_else(out_of_range),
+        (Value::Date(d), Value::Time(t)) | (Value::Time(t), Value::Date(d)) if is_add => {
+            Ok(Value::Timestamp(*d as i64 * MS_PER_DAY + t))
+        }
+        _ => Err(Error::OperatorTypeMismatch {
+            op: op.to_string(),
+            left: left.type_name().to_string(),
+            right: right.type_name().to_string(),
+        }),
+    }
+}
+
+fn evaluate_unary_op(op: &UnaryOperator, val: &Value) -> Result<Value> {
+    match op {
+        UnaryOperator::Not => Ok(from_truth(not3(truth_value(val)?))),
+        UnaryOperator::Minus => match val {
+            Value::Null => Ok(Value::Null),
+            Value::Integer(i) => Ok(Value::Integer(-i)),
+            Value::BigInt(i) => Ok(Value::BigInt(-i)),
+            Value::Float(f) => Ok(Value::Float(-f)),
+            Value::Decimal(d) => Ok(Value::Decimal(d.neg())),
+            Value::Interval(i) => Ok(Value::Interval(i.neg())),
+            _ => Err(Error::TypeMismatch {
+                from: val.type_name().to_string(),
+                to: "numeric".to_string(),
+            }),
+        },
+        UnaryOperator::Plus => Ok(val.clone()),
+    }
+}
+
+fn evaluate_function(
+    name: &str,
+    args: &[Expr],
+    row: &[Value],
+    columns: &[BoundColumn],
+) -> Result<Value> {
+    let name_upper = name.to_uppercase();
+
+    // For now, just handle simple cases
+    match name_upper.as_str() {
+        "COUNT" => {
+            // This is a simplified version; real aggregates need proper handling
+            Ok(Value::Integer(1))
+        }
+        "UPPER" => {
+            if let Some(arg) = args.first() {
+                let val = evaluate_expr(arg, row, columns)?;
+                if let Value::String(s) = val {
+                    Ok(Value::String(s.to_uppercase()))
+                } else {
+                    Ok(val)
+                }
+            } else {
+                Ok(Value::Null)
+            }
+        }
+        "LOWER" => {
+            if let Some(arg) = args.first() {
+                let val = evaluate_expr(arg, row, columns)?;
+                if let Value::String(s) = val {
+                    Ok(Value::String(s.to_lowercase()))
+                } else {
+                    Ok(val)
+                }
+            } else {
+                Ok(Value::Null)
+            }
+        }
+        "LENGTH" => {
+            if let Some(arg) = args.first() {
+                let val = evaluate_expr(arg, row, columns)?;
+                if let Value::String(s) = val {
+                    Ok(Value::Integer(s.len() as i32))
+                } else {
+                    Ok(Value::Null)
+                }
+            } else {
+                Ok(Value::Null)
+            }
+        }
+        "CURRENT_DATE" => Ok(Value::Date(datetime::current_date())),
+        "CURRENT_TIME" => Ok(Value::Time(datetime::current_time())),
+        "CURRENT_TIMESTAMP" | "NOW" => Ok(Value::Timestamp(datetime::current_timestamp())),
+        "EXTRACT" | "DATE_TRUNC" => {
+            let [field, source] = args else {
+                return Err(Error::ExecutionError(format!(
+                    "{} expects 2 arguments",
+                    name_upper
+                )));
+            };
+            let field = match evaluate_expr(field, row, columns)? {
+                Value::String(s) => s.parse().map_err(Error::ExecutionError)?,
+                other => {
+                    return Err(Error::TypeMismatch {
+                        from: other.type_name().to_string(),
+                        to: "TEXT".to_string(),
+                    })
+                }
+            };
+            let source = evaluate_expr(source, row, columns)?;
+            if name_upper == "EXTRACT" {
+                extract(field, &source)
+            } else {
+                date_trunc(field, &source)
+            }
+        }
+        // Sequence calls are computed by a SequenceOperator below the evaluation
+        "NEXTVAL" | "CURRVAL" | "SETVAL" => Err(Error::ExecutionError(format!(
+            "{} cannot be evaluated here",
+            name_upper
+        ))),
+        _ => Err(Error::ExecutionError(format!("Unknown function: {}", name))),
+    }
+}
+
+/// EXTRACT(field FROM source)
+fn extract(field: DateTimeField, source: &Value) -> Result<Value> {
+    let unsupported = || {
+        Error::ExecutionError(format!(
+            "EXTRACT({} FROM ...) is not supported for {}",
+            field,
+            source.type_name()
+        ))
+    };
+    let n = match source {
+        Value::Null => return Ok(Value::Null),
+        Value::Date(d) => datetime::extract_from_timestamp(field, *d as i64 * MS_PER_DAY),
+        Value::Timestamp(t) => datetime::extract_from_timestamp(field, *t),
+        Value::Time(t) => datetime::extract_from_time(field, *t).ok_or_else(unsupported)?,
+        Value::Interval(i) => datetime::extract_from_interval(field, i).ok_or_else(unsupported)?,
+        _ => return Err(unsupported()),
+    };
+    // SECOND and EPOCH come back in milliseconds
+    if field.is_fractional() {
+        Ok(Value::Decimal(
+            Decimal::new(n as i128, 3).ok_or_else(unsupported)?,
+        ))
+    } else {
+        Ok(Value::BigInt(n))
+    }
+}
+
+/// DATE_TRUNC(field, source)
+fn date_trunc(field: DateTimeField, source: &Value) -> Result<Value> {
+    let unsupported = || {
+        Error::ExecutionError(format!(
+            "DATE_TRUNC('{}', ...) is not supported for {}",
+            field,
+            source.type_name()
+        ))
+    };
+    match source {
+        Value::Null => Ok(Value::Null),
+        Value::Timestamp(t) => datetime::truncate_timestamp(field, *t)
+            .map(Value::Timestamp)
+            .ok_or_else(unsupported),
+        Value::Date(d) => datetime::truncate_timestamp(field, *d as i64 * MS_PER_DAY)
+            .map(|t| Value::Date(t.div_euclid(MS_PER_DAY) as i32))
+            .ok_or_else(unsupported),
+        _ => Err(unsupported()),
+    }
+}
+
+/// Default output column name for an expression
+pub fn expr_to_string(expr: &Expr) -> String {
+    match expr {
+        Expr::Column(col_ref) => col_ref.column.clone(),
+        Expr::Literal(
+            lit @ (Literal::Decimal(_)
+            | Literal::Date(_)
+            | Literal::Time(_)
+            | Literal::Timestamp(_)
+            | Literal::Interval(_)),
+        ) => lit.to_string(),
+        Expr::Literal(lit) => format!("{:?}", lit),
+        Expr::Function { .. } => expr.to_string(),
+        Expr::Cast { expr, .. } => expr_to_string(expr),
+        _ => "expr".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_like_match() {
+        let like = |text, pattern| like_match(text, pattern, "\\", false).unwrap();
+        assert!(like("hello", "h%o"));
+        assert!(like("hello", "_ello"));
+        assert!(like("hello", "%l%l%"));
+        assert!(like("", "%"));
+        assert!(!like("hello", "h_o"));
+        assert!(!like("hello", "hello_"));
+        assert!(like("50%", "50\\%"));
+        assert!(!like("500", "50\\%"));
+
+        assert!(like_match("a_b", "a!_b", "!", false).unwrap());
+        assert!(!like_match("axb", "a!_b", "!", false).unwrap());
+        assert!(like_match("HeLLo", "hel%", "\\", true).unwrap());
+        assert!(!like_match("HeLLo", "hel%", "\\", false).unwrap());
+
+        assert!(like_match("abc", "abc\\", "\\", false).is_err());
+        assert!(like_match("abc", "abc", "!!", false).is_err());
+    }
+}
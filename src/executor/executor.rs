@@ -3,18 +3,31 @@
 //! This module executes logical plans and returns results.
 
 use serde::Serialize;
-use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
-use super::{HeuristicOptimizer, LogicalPlan};
-#[cfg(test)]
-use crate::catalog::DataType;
-use crate::catalog::{Catalog, Column, Schema, TableStatistics};
+use super::binder::{self, BoundColumn};
+use super::eval;
+use super::operator::{
+    self, AggregateOperator, AliasOperator, ApplyOperator, BoxedOperator, DistinctOperator,
+    FilterOperator, HashJoinOperator, HashSemiJoinOperator, IndexScanOperator, LimitOperator,
+    NestedLoopJoinOperator, OuterRow, ParameterOperator, ProjectOperator, SeqScanOperator,
+    SequenceOperator, SetOperationOperator, SortOperator, SubqueryKind, ValuesOperator,
+    WindowOperator,
+};
+use super::planner::{contains_sequence_call, contains_subquery, is_sequence_function};
+use super::{HeuristicOptimizer, LogicalPlan, Planner};
+use crate::catalog::{
+    Catalog, Column, Constraint, ConstraintKind, DataType, ForeignKey, Identity, ReferentialAction,
+    Schema, Sequence, TableDef, TableStatistics, CATALOG_FILE,
+};
 use crate::error::{Error, Result};
 use crate::sql::ast::*;
+use crate::sql::Parser;
 use crate::storage::btree::IndexKey;
-use crate::storage::wal::LogManager;
+use crate::storage::table::coerce_value;
+use crate::storage::wal::{LogManager, LogRecord, LogRecordType};
 use crate::storage::{BufferPoolManager, DiskManager, SlotId, Table, Tuple, Value};
 use crate::transaction::{LockMode, TransactionManager};
 
@@ -63,6 +76,121 @@ impl QueryResult {
     }
 }
 
+/// The sequences of the catalog, as seen by one session
+///
+/// Sequences are advanced outside of transactions. Whenever a sequence
+/// reserves more values, the last value reserved is logged and the catalog
+/// saved, so values handed out before a crash are never handed out again.
+#[derive(Clone)]
+pub struct Sequences {
+    catalog: Arc<Catalog>,
+    log_manager: Arc<LogManager>,
+    /// File the catalog is saved to
+    catalog_path: PathBuf,
+    /// Value nextval last returned for each sequence in this session
+    current: Arc<Mutex<HashMap<String, i64>>>,
+}
+
+impl Sequences {
+    fn new(catalog: Arc<Catalog>, log_manager: Arc<LogManager>, catalog_path: PathBuf) -> Self {
+        Self {
+            catalog,
+            log_manager,
+            catalog_path,
+            current: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Call NEXTVAL, CURRVAL or SETVAL with evaluated arguments
+    pub fn call(&self, function: &str, args: &[Value]) -> Result<Value> {
+        let upper = function.to_uppercase();
+        let name = match args.first() {
+            Some(Value::String(name)) => name,
+            Some(Value::Null) => return Ok(Value::Null),
+            _ => {
+                return Err(Error::ExecutionError(format!(
+                    "{} expects a sequence name",
+                    upper
+                )))
+            }
+        };
+        let value = match upper.as_str() {
+            "NEXTVAL" => self.next_value(name)?,
+            "CURRVAL" => self.current_value(name)?,
+            "SETVAL" => match args.get(1).map(Value::as_i64) {
+                Some(Some(value)) => self.set_value(name, value)?,
+                Some(None) if args[1].is_null() => return Ok(Value::Null),
+                _ => {
+                    return Err(Error::ExecutionError(
+                        "SETVAL expects an integer value".to_string(),
+                    ))
+                }
+            },
+            _ => {
+                return Err(Error::ExecutionError(format!(
+                    "Unknown function: {}",
+                    function
+                )))
+            }
+        };
+        Ok(Value::BigInt(value))
+    }
+
+    /// Advance a sequence and return its new value
+    pub fn next_value(&self, name: &str) -> Result<i64> {
+        let (value, reserved) = self.catalog.next_sequence_value(name)?;
+        if let Some(reserved) = reserved {
+            self.persist(name, reserved)?;
+        }
+        self.current.lock().unwrap().insert(name.to_string(), value);
+        Ok(value)
+    }
+
+    /// The value nextval last returned for a sequence in this session
+    pub fn current_value(&self, name: &str) -> Result<i64> {
+        self.catalog.get_sequence(name)?;
+        self.current
+            .lock()
+            .unwrap()
+            .get(name)
+            .copied()
+            .ok_or_else(|| {
+                Error::ExecutionError(format!(
+                    "currval of sequence '{}' is not yet defined in this session",
+                    name
+                ))
+            })
+    }
+
+    /// Set the last value of a sequence, so nextval continues after it
+    pub fn set_value(&self, name: &str, value: i64) -> Result<i64> {
+        self.catalog.set_sequence_value(name, value)?;
+        self.persist(name, value)?;
+        Ok(value)
+    }
+
+    /// Log the last value a sequence reserved and save it with the catalog
+    fn persist(&self, name: &str, reserved: i64) -> Result<()> {
+        self.log_manager.append(
+            0,
+            LogRecordType::Sequence,
+            Some(name.to_string()),
+            None,
+            None,
+            Some(Tuple::new(vec![Value::BigInt(reserved)])),
+        )?;
+        self.log_manager.flush()?;
+        self.catalog.save_to_disk(&self.catalog_path)?;
+        Ok(())
+    }
+}
+
+/// Iterations a recursive CTE may run before the query is aborted
+pub const MAX_RECURSION_DEPTH: usize = 1000;
+
+/// Rows logged by one bulk insert record
+const BULK_INSERT_BATCH: usize = 1024;
+
 /// Execution Engine
 pub struct ExecutionEngine {
     /// System catalog
@@ -75,19 +203,55 @@ pub struct ExecutionEngine {
     current_trans_id: Option<u64>,
     /// Buffer Pool Manager
     buffer_pool: Arc<Mutex<BufferPoolManager>>,
+    /// Directory the database files live in
+    data_dir: PathBuf,
+    /// Outer rows of the correlated subqueries being built, innermost last
+    outer_rows: Vec<OuterRow>,
+    /// Rows added by the last iteration of each recursive CTE being evaluated
+    work_tables: HashMap<String, Vec<Tuple>>,
+    /// Sequences, with the values returned in this session
+    sequences: Sequences,
 }
 
 impl ExecutionEngine {
-    /// Create a new execution engine
+    /// Create a new execution engine over the default data directory
     pub fn new(catalog: Arc<Catalog>) -> Result<Self> {
+        Self::open(catalog, "data")
+    }
+
+    /// Create a new execution engine, creating the database with the given page size
+    pub fn with_page_size(catalog: Arc<Catalog>, page_size: usize) -> Result<Self> {
+        Self::open_with_page_size(catalog, "data", page_size)
+    }
+
+    /// Create a new execution engine over a data directory
+    pub fn open(catalog: Arc<Catalog>, data_dir: impl Into<PathBuf>) -> Result<Self> {
+        let disk_manager = DiskManager::open(data_dir.into())?;
+        Self::with_disk_manager(catalog, disk_manager)
+    }
+
+    /// Create a new execution engine over a data directory, creating the
+    /// database with the given page size
+    pub fn open_with_page_size(
+        catalog: Arc<Catalog>,
+        data_dir: impl Into<PathBuf>,
+        page_size: usize,
+    ) -> Result<Self> {
+        let disk_manager = DiskManager::open_with_page_size(data_dir.into(), page_size)?;
+        Self::with_disk_manager(catalog, disk_manager)
+    }
+
+    fn with_disk_manager(catalog: Arc<Catalog>, disk_manager: DiskManager) -> Result<Self> {
+        let data_dir = disk_manager.data_dir().to_path_buf();
         let log_manager = Arc::new(LogManager::new());
+        let sequences = Sequences::new(
+            catalog.clone(),
+            log_manager.clone(),
+            data_dir.join(CATALOG_FILE),
+        );
         let transaction_manager = Arc::new(TransactionManager::new(log_manager));
 
-        let data_dir = std::path::PathBuf::from("data");
-        if !data_dir.exists() {
-            std::fs::create_dir_all(&data_dir).map_err(Error::IoError)?;
-        }
-        let disk_manager = Arc::new(DiskManager::new(data_dir));
+        let disk_manager = Arc::new(disk_manager);
         let buffer_pool = Arc::new(Mutex::new(BufferPoolManager::new(1024, disk_manager)));
 
         let mut engine = Self {
@@ -96,6 +260,10 @@ impl ExecutionEngine {
             transaction_manager,
             current_trans_id: None,
             buffer_pool,
+            data_dir,
+            outer_rows: Vec::new(),
+            work_tables: HashMap::new(),
+            sequences,
         };
 
         // Automatic recovery on startup
@@ -114,8 +282,9 @@ impl ExecutionEngine {
             LogicalPlan::CreateTable {
                 table_name,
                 columns,
+                constraints,
                 if_not_exists,
-            } => self.execute_create_table(&table_name, columns, if_not_exists),
+            } => self.execute_create_table(&table_name, columns, constraints, if_not_exists),
             LogicalPlan::DropTable {
                 table_name,
                 if_exists,
@@ -129,124 +298,583 @@ impl ExecutionEngine {
             } => {
                 self.execute_create_index(&index_name, &table_name, columns, unique, if_not_exists)
             }
+            LogicalPlan::DropIndex {
+                index_name,
+                if_exists,
+            } => self.execute_drop_index(&index_name, if_exists),
+            LogicalPlan::AlterTable {
+                table_name,
+                operation,
+            } => {
+                self.open_for_write(&table_name)?;
+                self.execute_alter_table(&table_name, operation)
+            }
             LogicalPlan::Insert {
                 table_name,
                 columns,
                 values,
+                query,
+                returning,
             } => {
-                self.ensure_table_loaded(&table_name)?;
-                if let Some(trans_id) = self.current_trans_id {
-                    if !self.transaction_manager.acquire_lock(
-                        &table_name,
-                        trans_id,
-                        LockMode::Exclusive,
-                    )? {
-                        return Err(Error::Internal(format!(
-                            "Could not acquire lock on table {}",
-                            table_name
-                        )));
-                    }
-                }
-                self.execute_insert(&table_name, columns, values)
+                self.open_for_write(&table_name)?;
+                self.execute_insert(&table_name, columns, values, query, returning)
             }
             LogicalPlan::Update {
                 table_name,
                 assignments,
                 predicate,
+                returning,
             } => {
-                self.ensure_table_loaded(&table_name)?;
-                if let Some(trans_id) = self.current_trans_id {
-                    if !self.transaction_manager.acquire_lock(
-                        &table_name,
-                        trans_id,
-                        LockMode::Exclusive,
-                    )? {
-                        return Err(Error::Internal(format!(
-                            "Could not acquire lock on table {}",
-                            table_name
+                self.open_for_write(&table_name)?;
+                self.execute_update(&table_name, assignments, predicate, returning)
+            }
+            LogicalPlan::Delete {
+                table_name,
+                predicate,
+                returning,
+            } => {
+                self.open_for_write(&table_name)?;
+                self.execute_delete(&table_name, predicate, returning)
+            }
+            LogicalPlan::CreateSequence {
+                name,
+                options,
+                if_not_exists,
+            } => self.execute_create_sequence(&name, options, if_not_exists),
+            LogicalPlan::DropSequence { name, if_exists } => {
+                self.execute_drop_sequence(&name, if_exists)
+            }
+            LogicalPlan::BeginTransaction => self.execute_begin(),
+            LogicalPlan::Commit => self.execute_commit(),
+            LogicalPlan::Rollback => self.execute_rollback(),
+            LogicalPlan::Analyze { table_name } => self.execute_analyze(table_name),
+            LogicalPlan::Project { .. }
+            | LogicalPlan::Scan { .. }
+            | LogicalPlan::Derived { .. }
+            | LogicalPlan::RecursiveCte { .. }
+            | LogicalPlan::WorkTable { .. }
+            | LogicalPlan::IndexScan { .. }
+            | LogicalPlan::Filter { .. }
+            | LogicalPlan::Join { .. }
+            | LogicalPlan::HashJoin { .. }
+            | LogicalPlan::SemiJoin { .. }
+            | LogicalPlan::SetOperation { .. }
+            | LogicalPlan::Parameters { .. }
+            | LogicalPlan::Aggregate { .. }
+            | LogicalPlan::Window { .. }
+            | LogicalPlan::Distinct { .. }
+            | LogicalPlan::DropColumns { .. }
+            | LogicalPlan::Sort { .. }
+            | LogicalPlan::Limit { .. } => self.execute_query(plan),
+        }
+    }
+
+    /// Execute a query plan by pulling rows through an operator tree
+    fn execute_query(&mut self, plan: LogicalPlan) -> Result<QueryResult> {
+        let mut root = self.build_operator(plan)?;
+        let rows = operator::collect(root.as_mut())?;
+
+        Ok(QueryResult {
+            columns: binder::column_names(root.columns()),
+            rows,
+            affected_rows: 0,
+            message: None,
+        })
+    }
+
+    /// Build the operator tree for a query plan
+    fn build_operator(&mut self, plan: LogicalPlan) -> Result<BoxedOperator> {
+        match plan {
+            LogicalPlan::Scan {
+                table_name, alias, ..
+            } => {
+                if table_name.is_empty() {
+                    // SELECT without FROM
+                    return Ok(Box::new(ValuesOperator::single_row()));
+                }
+
+                let table = self.open_for_read(&table_name)?;
+                let qualifier = alias.as_ref().unwrap_or(&table_name);
+                Ok(Box::new(SeqScanOperator::new(table, qualifier)))
+            }
+            LogicalPlan::Derived {
+                input,
+                alias,
+                columns,
+            } => {
+                let input = self.build_operator(*input)?;
+                Ok(Box::new(AliasOperator::new(input, &alias, &columns)))
+            }
+            LogicalPlan::RecursiveCte {
+                anchor,
+                recursive,
+                work_table,
+                all,
+                alias,
+                columns,
+            } => {
+                let rows = self.evaluate_recursive(*anchor, *recursive, &work_table, all)?;
+                let names: Vec<&str> = columns.iter().map(String::as_str).collect();
+                Ok(Box::new(ValuesOperator::new(
+                    binder::qualify(&alias, &names),
+                    rows,
+                )))
+            }
+            LogicalPlan::WorkTable {
+                name,
+                alias,
+                columns,
+            } => {
+                let rows = self.work_tables.get(&name).cloned().ok_or_else(|| {
+                    Error::Internal(format!("{} read outside its recursive CTE", name))
+                })?;
+                let names: Vec<&str> = columns.iter().map(String::as_str).collect();
+                Ok(Box::new(ValuesOperator::new(
+                    binder::qualify(&alias, &names),
+                    rows,
+                )))
+            }
+            LogicalPlan::IndexScan {
+                table_name,
+                alias,
+                index_name,
+                op,
+                value,
+                ..
+            } => {
+                let key = IndexKey::new(eval::evaluate_const_expr(&value)?);
+                let table = self.open_for_read(&table_name)?;
+
+                let index = table
+                    .get_index(&index_name)
+                    .ok_or_else(|| Error::IndexNotFound(index_name.clone()))?;
+                let (slots, residual) = match op {
+                    BinaryOperator::Eq => (index.search(&key).into_iter().collect(), None),
+                    BinaryOperator::Gt
+                    | BinaryOperator::Gte
+                    | BinaryOperator::Lt
+                    | BinaryOperator::Lte => {
+                        let (min, max) = match op {
+                            BinaryOperator::Gt | BinaryOperator::Gte => (Some(&key), None),
+                            _ => (None, Some(&key)),
+                        };
+                        let slots = index
+                            .range_scan(min, max)
+                            .into_iter()
+                            .map(|(_, slot_id)| slot_id)
+                            .collect();
+
+                        // The range scan is inclusive; exclusive bounds are re-checked
+                        let residual = match (op, table.get_index_columns(&index_name)) {
+                            (BinaryOperator::Gt | BinaryOperator::Lt, Some(cols)) => {
+                                Some((cols[0], op, key))
+                            }
+                            _ => None,
+                        };
+                        (slots, residual)
+                    }
+                    _ => {
+                        return Err(Error::ExecutionError(format!(
+                            "Operator {:?} not supported for index scan",
+                            op
                         )));
                     }
-                }
-                self.execute_update(&table_name, assignments, predicate)
+                };
+
+                let qualifier = alias.as_ref().unwrap_or(&table_name);
+                Ok(Box::new(IndexScanOperator::new(
+                    table, qualifier, slots, residual,
+                )))
             }
             LogicalPlan::Join {
                 left,
                 right,
                 join_type,
                 condition,
-            } => self.execute_join(*left, *right, join_type, condition),
+            } => {
+                let left = self.build_operator(*left)?;
+                let right = self.build_operator(*right)?;
+                Ok(Box::new(NestedLoopJoinOperator::new(
+                    left, right, join_type, condition,
+                )?))
+            }
             LogicalPlan::HashJoin {
                 left,
                 right,
                 left_key,
                 right_key,
-            } => self.execute_hash_join(*left, *right, left_key, right_key),
-            LogicalPlan::Delete {
-                table_name,
-                predicate,
             } => {
-                self.ensure_table_loaded(&table_name)?;
-                if let Some(trans_id) = self.current_trans_id {
-                    if !self.transaction_manager.acquire_lock(
-                        &table_name,
-                        trans_id,
-                        LockMode::Exclusive,
-                    )? {
-                        return Err(Error::Internal(format!(
-                            "Could not acquire lock on table {}",
-                            table_name
-                        )));
-                    }
-                }
-                self.execute_delete(&table_name, predicate)
+                let left = self.build_operator(*left)?;
+                let right = self.build_operator(*right)?;
+                Ok(Box::new(HashJoinOperator::new(
+                    left, right, left_key, right_key,
+                )?))
             }
-            LogicalPlan::IndexScan {
-                table_name,
-                index_name,
-                columns: _columns, // Ignored
+            LogicalPlan::SemiJoin {
+                left,
+                right,
+                left_keys,
+                anti,
+                null_aware,
+            } => {
+                let left = self.build_operator(*left)?;
+                let right = self.build_operator(*right)?;
+                Ok(Box::new(HashSemiJoinOperator::new(
+                    left, right, left_keys, anti, null_aware,
+                )?))
+            }
+            LogicalPlan::SetOperation {
+                left,
+                right,
                 op,
-                value,
+                all,
             } => {
-                self.ensure_table_loaded(&table_name)?;
-                self.execute_index_scan(&table_name, &index_name, &op, &value)
+                let left = self.build_operator(*left)?;
+                let right = self.build_operator(*right)?;
+                Ok(Box::new(SetOperationOperator::new(left, right, op, all)?))
             }
-            LogicalPlan::BeginTransaction => self.execute_begin(),
-            LogicalPlan::Commit => self.execute_commit(),
-            LogicalPlan::Rollback => self.execute_rollback(),
-            LogicalPlan::Analyze { table_name } => self.execute_analyze(table_name),
-            LogicalPlan::Project { input, expressions } => self.execute_select(*input, expressions),
-            LogicalPlan::Scan { table_name, .. } => {
-                if !table_name.is_empty() {
-                    self.ensure_table_loaded(&table_name)?;
-                    // Acquire Shared Lock for Scan
-                    if let Some(trans_id) = self.current_trans_id {
-                        if !self.transaction_manager.acquire_lock(
-                            &table_name,
-                            trans_id,
-                            LockMode::Shared,
-                        )? {
-                            return Err(Error::Internal(format!(
-                                "Could not acquire lock on table {}",
-                                table_name
-                            )));
-                        }
-                    }
+            LogicalPlan::Parameters { columns } => {
+                let outer_row = self.outer_rows.last().cloned().ok_or_else(|| {
+                    Error::Internal("outer row read outside a subquery".to_string())
+                })?;
+                Ok(Box::new(ParameterOperator::new(&columns, outer_row)))
+            }
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+            } => {
+                let input = self.build_operator(*input)?;
+                Ok(Box::new(AggregateOperator::new(
+                    input, group_by, aggregates,
+                )?))
+            }
+            LogicalPlan::Window { input, functions } => {
+                let input = self.build_operator(*input)?;
+                Ok(Box::new(WindowOperator::new(input, functions)?))
+            }
+            LogicalPlan::Filter {
+                input,
+                mut predicate,
+            } => {
+                let input = self.build_operator(*input)?;
+                let width = input.columns().len();
+                let input = self.apply_subqueries(&mut predicate, input)?;
+                let input = self.apply_sequence_calls(&mut predicate, input)?;
+                let filter: BoxedOperator = Box::new(FilterOperator::new(input, predicate)?);
+                if filter.columns().len() == width {
+                    Ok(filter)
+                } else {
+                    Ok(Box::new(ProjectOperator::truncate(filter, width)?))
                 }
-                self.execute_scan(&table_name)
             }
-            _ => Err(Error::ExecutionError("Unsupported plan type".to_string())),
+            LogicalPlan::Project { input, expressions } => {
+                let input = self.build_operator(*input)?;
+                self.project(input, expressions)
+            }
+            LogicalPlan::Distinct { input } => {
+                let input = self.build_operator(*input)?;
+                Ok(Box::new(DistinctOperator::new(input)))
+            }
+            LogicalPlan::DropColumns { input, count } => {
+                let input = self.build_operator(*input)?;
+                let width = input.columns().len().saturating_sub(count);
+                Ok(Box::new(ProjectOperator::truncate(input, width)?))
+            }
+            LogicalPlan::Sort { input, order_by } => {
+                let input = self.build_operator(*input)?;
+                Ok(Box::new(SortOperator::new(input, order_by)?))
+            }
+            LogicalPlan::Limit {
+                input,
+                limit,
+                offset,
+            } => {
+                let input = self.build_operator(*input)?;
+
+                let offset = match offset {
+                    Some(e) => eval::evaluate_const_expr(&e)?.as_i64().unwrap_or(0).max(0),
+                    None => 0,
+                } as usize;
+                let limit = match limit {
+                    Some(e) => eval::evaluate_const_expr(&e)?
+                        .as_i64()
+                        .map(|l| l.max(0) as usize),
+                    None => None,
+                };
+
+                Ok(Box::new(LimitOperator::new(input, limit, offset)))
+            }
+            _ => Err(Error::ExecutionError(
+                "Unsupported plan in query".to_string(),
+            )),
         }
     }
 
+    // ========== Recursive CTEs ==========
+
+    /// Evaluate a recursive CTE: the rows of `anchor`, then those of
+    /// `recursive` over the rows the previous iteration added, until an
+    /// iteration adds none. Without `all`, rows already produced are dropped.
+    fn evaluate_recursive(
+        &mut self,
+        anchor: LogicalPlan,
+        recursive: LogicalPlan,
+        work_table: &str,
+        all: bool,
+    ) -> Result<Vec<Tuple>> {
+        let mut seen = HashSet::new();
+        let mut keep_new = |rows: Vec<Tuple>| -> Vec<Tuple> {
+            if all {
+                return rows;
+            }
+            rows.into_iter()
+                .filter(|row| seen.insert(row.values().to_vec()))
+                .collect()
+        };
+
+        let mut root = self.build_operator(anchor)?;
+        let mut added = keep_new(operator::collect(root.as_mut())?);
+        let mut rows = added.clone();
+        let mut depth = 0;
+
+        let previous = self.work_tables.remove(work_table);
+        let result = loop {
+            if added.is_empty() {
+                break Ok(rows);
+            }
+            if depth == MAX_RECURSION_DEPTH {
+                break Err(Error::ExecutionError(format!(
+                    "recursive query '{}' exceeded the maximum recursion depth of {}",
+                    work_table.trim_start_matches(TableRef::WORK_TABLE_PREFIX),
+                    MAX_RECURSION_DEPTH
+                )));
+            }
+            depth += 1;
+
+            self.work_tables.insert(work_table.to_string(), added);
+            let iteration = self
+                .build_operator(recursive.clone())
+                .and_then(|mut root| operator::collect(root.as_mut()));
+            match iteration {
+                Ok(new_rows) => {
+                    added = keep_new(new_rows);
+                    rows.extend(added.iter().cloned());
+                }
+                Err(e) => break Err(e),
+            }
+        };
+
+        self.work_tables.remove(work_table);
+        if let Some(previous) = previous {
+            self.work_tables.insert(work_table.to_string(), previous);
+        }
+        result
+    }
+
+    /// Project the rows of `input` onto a select list, computing its
+    /// subqueries and sequence calls first
+    fn project(
+        &mut self,
+        mut input: BoxedOperator,
+        expressions: Vec<SelectItem>,
+    ) -> Result<BoxedOperator> {
+        let width = input.columns().len();
+        let mut items = Vec::with_capacity(expressions.len());
+        for item in expressions {
+            match item {
+                SelectItem::Expr { mut expr, alias } if needs_apply(&expr) => {
+                    // Name the column after the expression, not the column holding its result
+                    let alias = alias.or_else(|| Some(eval::expr_to_string(&expr)));
+                    input = self.apply_subqueries(&mut expr, input)?;
+                    input = self.apply_sequence_calls(&mut expr, input)?;
+                    items.push(SelectItem::Expr { expr, alias });
+                }
+                other => items.push(other),
+            }
+        }
+        Ok(Box::new(ProjectOperator::over_prefix(input, items, width)?))
+    }
+
+    // ========== Subqueries ==========
+
+    /// Compute the subqueries of an expression with Apply operators over
+    /// `input`, replacing each with a reference to the column holding its result
+    fn apply_subqueries(&mut self, expr: &mut Expr, input: BoxedOperator) -> Result<BoxedOperator> {
+        let mut subqueries = Vec::new();
+        take_subqueries(expr, input.columns().len(), &mut subqueries);
+
+        let mut input = input;
+        for (name, kind, query) in subqueries {
+            input = self.build_apply(input, kind, query, name)?;
+        }
+        Ok(input)
+    }
+
+    /// Compute the NEXTVAL, CURRVAL and SETVAL calls of an expression for
+    /// each row of `input`, replacing each with a reference to the column
+    /// holding its result
+    fn apply_sequence_calls(
+        &mut self,
+        expr: &mut Expr,
+        input: BoxedOperator,
+    ) -> Result<BoxedOperator> {
+        let mut calls = Vec::new();
+        take_sequence_calls(expr, input.columns().len(), &mut calls);
+        if calls.is_empty() {
+            return Ok(input);
+        }
+        Ok(Box::new(SequenceOperator::new(
+            input,
+            calls,
+            self.sequences.clone(),
+        )?))
+    }
+
+    /// Build an Apply operator computing one subquery for each input row
+    fn build_apply(
+        &mut self,
+        input: BoxedOperator,
+        kind: SubqueryKind,
+        mut query: SelectStatement,
+        name: String,
+    ) -> Result<BoxedOperator> {
+        let catalog = self.catalog.clone();
+        let planner = Planner::new(&catalog);
+
+        // Outer references become parameters read from the current input row
+        let mut outer_refs: Vec<ColumnRef> = Vec::new();
+        planner.for_each_outer_reference(&mut query, &mut |col_ref| {
+            let idx = match outer_refs.iter().position(|r| r == col_ref) {
+                Some(idx) => idx,
+                None => {
+                    outer_refs.push(col_ref.clone());
+                    outer_refs.len() - 1
+                }
+            };
+            *col_ref = ColumnRef::from(format!("$outer{}", idx));
+        });
+        let outer_positions = outer_refs
+            .iter()
+            .map(|col_ref| binder::resolve_column(input.columns(), col_ref))
+            .collect::<Result<Vec<_>>>()?;
+        let parameters = (0..outer_refs.len())
+            .map(|idx| format!("$outer{}", idx))
+            .collect();
+
+        let plan = planner.plan_subquery(query, parameters);
+        let plan = HeuristicOptimizer::new(&self.tables).optimize(plan);
+        let outer_row = OuterRow::default();
+        self.outer_rows.push(outer_row.clone());
+        let subquery = self.build_operator(plan);
+        self.outer_rows.pop();
+
+        Ok(Box::new(ApplyOperator::new(
+            input,
+            subquery?,
+            kind,
+            outer_positions,
+            outer_row,
+            name,
+        )?))
+    }
+
+    /// Evaluate expressions that may contain subqueries against each row
+    fn evaluate_per_row(
+        &mut self,
+        exprs: &[Expr],
+        columns: &[BoundColumn],
+        rows: Vec<Tuple>,
+    ) -> Result<Vec<Vec<Value>>> {
+        if !exprs.iter().any(needs_apply) {
+            return rows
+                .iter()
+                .map(|row| {
+                    exprs
+                        .iter()
+                        .map(|expr| eval::evaluate_expr(expr, row.values(), columns))
+                        .collect()
+                })
+                .collect();
+        }
+
+        let mut input: BoxedOperator = Box::new(ValuesOperator::new(columns.to_vec(), rows));
+        let mut exprs = exprs.to_vec();
+        for expr in &mut exprs {
+            input = self.apply_subqueries(expr, input)?;
+            input = self.apply_sequence_calls(expr, input)?;
+        }
+        let rows = operator::collect(input.as_mut())?;
+        rows.iter()
+            .map(|row| {
+                exprs
+                    .iter()
+                    .map(|expr| eval::evaluate_expr(expr, row.values(), input.columns()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Evaluate expressions that read no columns, such as a row of VALUES
+    fn evaluate_row(&mut self, exprs: &[Expr]) -> Result<Vec<Value>> {
+        let mut rows = self.evaluate_per_row(exprs, &[], vec![Tuple::empty()])?;
+        Ok(rows.pop().unwrap_or_default())
+    }
+
+    /// Load a table and take a shared lock on it for reading
+    fn open_for_read(&mut self, table_name: &str) -> Result<&Table> {
+        self.ensure_table_loaded(table_name)?;
+        if let Some(trans_id) = self.current_trans_id {
+            if !self
+                .transaction_manager
+                .acquire_lock(table_name, trans_id, LockMode::Shared)?
+            {
+                return Err(Error::Internal(format!(
+                    "Could not acquire lock on table {}",
+                    table_name
+                )));
+            }
+        }
+
+        self.tables
+            .get(table_name)
+            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))
+    }
+
+    /// Load a table and take an exclusive lock on it for writing
+    fn open_for_write(&mut self, table_name: &str) -> Result<&mut Table> {
+        self.ensure_table_loaded(table_name)?;
+        if let Some(trans_id) = self.current_trans_id {
+            if !self
+                .transaction_manager
+                .acquire_lock(table_name, trans_id, LockMode::Exclusive)?
+            {
+                return Err(Error::Internal(format!(
+                    "Could not acquire lock on table {}",
+                    table_name
+                )));
+            }
+        }
+
+        self.tables
+            .get_mut(table_name)
+            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))
+    }
+
     pub fn recover(&mut self) -> Result<()> {
-        let wal_path = "arcdb.wal";
-        if !std::path::Path::new(wal_path).exists() {
+        let wal_path = self.data_path("arcdb.wal");
+        if !wal_path.exists() {
             return Ok(());
         }
 
         println!("Recovery: Starting from WAL...");
         let log_manager = self.transaction_manager.log_manager();
-        let records = log_manager.read_from_log(wal_path)?;
+        // A bulk insert is recovered like the inserts of its rows
+        let records: Vec<LogRecord> = log_manager
+            .read_from_log(&wal_path)?
+            .into_iter()
+            .flat_map(LogRecord::split)
+            .collect();
 
         // Pass 1: Analysis - Find committed and active transactions
         let mut committed = std::collections::HashSet::new();
@@ -269,6 +897,20 @@ impl ExecutionEngine {
             }
         }
 
+        // Sequences are not transactional: every reservation stands
+        for record in &records {
+            if record.record_type == LogRecordType::Sequence {
+                let reserved = record
+                    .after_image
+                    .as_ref()
+                    .and_then(|image| image.get(0))
+                    .and_then(Value::as_i64);
+                if let (Some(name), Some(reserved)) = (&record.table_name, reserved) {
+                    self.catalog.restore_sequence(name, reserved);
+                }
+            }
+        }
+
         // Pass 2: Redo committed transactions
         for record in &records {
             if committed.contains(&record.trans_id) {
@@ -360,6 +1002,21 @@ impl ExecutionEngine {
         )))
     }
 
+    /// Path of a database file within the data directory
+    fn data_path(&self, file_name: &str) -> PathBuf {
+        self.data_dir.join(file_name)
+    }
+
+    /// File the catalog is saved to
+    pub fn catalog_path(&self) -> PathBuf {
+        self.data_path(CATALOG_FILE)
+    }
+
+    /// Save the catalog to the data directory
+    fn save_catalog(&self) -> Result<()> {
+        self.catalog.save_to_disk(self.catalog_path())
+    }
+
     fn ensure_table_loaded(&mut self, table_name: &str) -> Result<()> {
         if self.tables.contains_key(table_name) {
             return Ok(());
@@ -369,8 +1026,8 @@ impl ExecutionEngine {
         let table_def = self.catalog.get_table(table_name)?;
 
         // Try to open it from disk
-        let path = format!("table_{}.data", table_def.id);
-        let mut table = if std::path::Path::new(&path).exists() {
+        let path = self.data_path(&format!("table_{}.data", table_def.id));
+        let mut table = if path.exists() {
             Table::open(table_def.clone(), path, self.buffer_pool.clone())
                 .map_err(|e| Error::Internal(e.to_string()))?
         } else {
@@ -435,6 +1092,7 @@ impl ExecutionEngine {
         &mut self,
         table_name: &str,
         columns: Vec<ColumnDef>,
+        table_constraints: Vec<TableConstraint>,
         if_not_exists: bool,
     ) -> Result<QueryResult> {
         // Check if table already exists
@@ -448,21 +1106,103 @@ impl ExecutionEngine {
             return Err(Error::TableAlreadyExists(table_name.to_string()));
         }
 
-        // Build schema
+        // Column constraints become constraints like the table-level ones
+        let mut constraints = Vec::new();
+        for col_def in &columns {
+            let column = vec![col_def.name.clone()];
+            if col_def.primary_key {
+                let kind = ConstraintKind::PrimaryKey(column.clone());
+                self.add_constraint(&mut constraints, table_name, None, kind)?;
+            }
+            if col_def.unique {
+                let kind = ConstraintKind::Unique(column.clone());
+                self.add_constraint(&mut constraints, table_name, None, kind)?;
+            }
+            if let Some(references) = col_def.references.clone() {
+                let kind = ConstraintKind::ForeignKey(foreign_key(column, references));
+                self.add_constraint(&mut constraints, table_name, None, kind)?;
+            }
+        }
+        for constraint in table_constraints {
+            let (name, kind) = match constraint {
+                TableConstraint::PrimaryKey { name, columns } => {
+                    (name, ConstraintKind::PrimaryKey(columns))
+                }
+                TableConstraint::Unique { name, columns } => {
+                    (name, ConstraintKind::Unique(columns))
+                }
+                TableConstraint::Check { name, expr } => {
+                    (name, ConstraintKind::Check(expr.to_string()))
+                }
+                TableConstraint::ForeignKey {
+                    name,
+                    columns,
+                    references,
+                } => (
+                    name,
+                    ConstraintKind::ForeignKey(foreign_key(columns, references)),
+                ),
+            };
+            self.add_constraint(&mut constraints, table_name, name, kind)?;
+        }
+        let primary_key = constraints
+            .iter()
+            .find_map(|c| match &c.kind {
+                ConstraintKind::PrimaryKey(columns) => Some(columns.clone()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        // Build schema, creating the sequence of each identity column
         let mut schema = Schema::new();
+        let mut sequences = Vec::new();
         for (i, col_def) in columns.into_iter().enumerate() {
-            let mut column = Column::new(col_def.name.clone(), col_def.data_type, i);
+            let mut column = Column::new(col_def.name.clone(), col_def.data_type.clone(), i);
             column = column.nullable(!col_def.not_null);
-            column = column.primary_key(col_def.primary_key);
+            column = column.primary_key(primary_key.contains(&col_def.name));
             column = column.unique(col_def.unique);
+            if let Some(default) = &col_def.default {
+                column = column.default(default.to_string());
+            }
+            if let Some(identity) = &col_def.identity {
+                let identity = create_identity_sequence(
+                    &self.catalog,
+                    table_name,
+                    &col_def.name,
+                    &col_def.data_type,
+                    identity,
+                )?;
+                sequences.push(identity.sequence.clone());
+                column = column.identity(identity);
+            }
             schema.add_column(column);
         }
 
-        // Create table in catalog
-        let table_def = self.catalog.create_table(table_name, schema)?;
+        // Create table in catalog, dropping its sequences if that fails
+        let table_def =
+            match self
+                .catalog
+                .create_table_with_constraints(table_name, schema, constraints)
+            {
+                Ok(table_def) => table_def,
+                Err(e) => {
+                    for sequence in &sequences {
+                        self.catalog.drop_sequence(sequence).ok();
+                    }
+                    return Err(e);
+                }
+            };
 
-        // Create storage
-        let table = Table::new(table_def, self.buffer_pool.clone());
+        // Create storage, with a unique index for each key constraint
+        let mut table = Table::new(table_def.clone(), self.buffer_pool.clone());
+        for constraint in &table_def.constraints {
+            let columns = constraint.key_columns();
+            if !columns.is_empty() {
+                table.create_index(constraint.name.clone(), columns.to_vec(), true)?;
+                self.catalog
+                    .create_constraint_index(constraint, table_name)?;
+            }
+        }
         self.tables.insert(table_name.to_string(), table);
 
         Ok(QueryResult::with_message(format!(
@@ -471,6 +1211,53 @@ impl ExecutionEngine {
         )))
     }
 
+    /// Add a constraint to those of a table, generating its name if not given
+    ///
+    /// Generated names follow PostgreSQL: `t_pkey`, `t_a_b_key`, `t_check` and `t_a_fkey`,
+    /// with a number appended when taken. Names of key constraints are also
+    /// the names of their indexes, so they must not be used by another index.
+    fn add_constraint(
+        &self,
+        constraints: &mut Vec<Constraint>,
+        table_name: &str,
+        name: Option<String>,
+        kind: ConstraintKind,
+    ) -> Result<()> {
+        let taken = |name: &str| {
+            constraints.iter().any(|c| c.name == name) || self.catalog.get_index(name).is_ok()
+        };
+        let name = match name {
+            Some(name) if taken(&name) => {
+                return Err(Error::SemanticError(format!(
+                    "constraint or index '{}' already exists",
+                    name
+                )));
+            }
+            Some(name) => name,
+            None => {
+                let base = match &kind {
+                    ConstraintKind::PrimaryKey(_) => format!("{}_pkey", table_name),
+                    ConstraintKind::Unique(columns) => {
+                        format!("{}_{}_key", table_name, columns.join("_"))
+                    }
+                    ConstraintKind::Check(_) => format!("{}_check", table_name),
+                    ConstraintKind::ForeignKey(fk) => {
+                        format!("{}_{}_fkey", table_name, fk.columns.join("_"))
+                    }
+                };
+                let mut name = base.clone();
+                let mut suffix = 0;
+                while taken(&name) {
+                    suffix += 1;
+                    name = format!("{}{}", base, suffix);
+                }
+                name
+            }
+        };
+        constraints.push(Constraint { name, kind });
+        Ok(())
+    }
+
     fn execute_drop_table(&mut self, table_name: &str, if_exists: bool) -> Result<QueryResult> {
         if !self.catalog.table_exists(table_name) {
             if if_exists {
@@ -482,16 +1269,27 @@ impl ExecutionEngine {
             return Err(Error::TableNotFound(table_name.to_string()));
         }
 
+        let referencing = self.catalog.get_referencing_foreign_keys(table_name);
+        if let Some((child, constraint, _)) = referencing
+            .iter()
+            .find(|(child, _, _)| child.name != table_name)
+        {
+            return Err(Error::SemanticError(format!(
+                "cannot drop table '{}' because constraint '{}' on table '{}' references it",
+                table_name, constraint, child.name
+            )));
+        }
+
         // Drop storage
         self.tables.remove(table_name);
 
         // Delete files from disk
         let table_id = self.catalog.get_table(table_name).map(|t| t.id).ok();
         if let Some(id) = table_id {
-            std::fs::remove_file(format!("table_{}.data", id)).ok();
+            std::fs::remove_file(self.data_path(&format!("table_{}.data", id))).ok();
 
             // Delete all index files for this table
-            if let Ok(entries) = std::fs::read_dir(".") {
+            if let Ok(entries) = std::fs::read_dir(&self.data_dir) {
                 for entry in entries.flatten() {
                     if let Some(filename) = entry.file_name().to_str() {
                         if filename.starts_with(&format!("table_{}_", id))
@@ -504,10 +1302,16 @@ impl ExecutionEngine {
             }
         }
 
-        // Drop from catalog
+        // Drop from catalog, with the sequences of its identity columns
+        let table_def = self.catalog.get_table(table_name)?;
         self.catalog.drop_table(table_name)?;
+        for column in table_def.schema().columns() {
+            if let Some(identity) = &column.identity {
+                self.catalog.drop_sequence(&identity.sequence).ok();
+            }
+        }
 
-        self.catalog.save_to_disk("arcdb.meta").ok();
+        self.save_catalog()?;
 
         Ok(QueryResult::with_message(format!(
             "Table '{}' dropped",
@@ -521,8 +1325,18 @@ impl ExecutionEngine {
         table_name: &str,
         columns: Vec<String>,
         unique: bool,
-        _if_not_exists: bool,
+        if_not_exists: bool,
     ) -> Result<QueryResult> {
+        if self.catalog.get_index(index_name).is_ok() {
+            if if_not_exists {
+                return Ok(QueryResult::with_message(format!(
+                    "Index '{}' already exists",
+                    index_name
+                )));
+            }
+            return Err(Error::IndexAlreadyExists(index_name.to_string()));
+        }
+
         self.ensure_table_loaded(table_name)?;
 
         let table = self
@@ -531,14 +1345,19 @@ impl ExecutionEngine {
             .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
 
         // Create index in storage
-        table.create_index(index_name.to_string(), columns.clone())?;
+        table.create_index(index_name.to_string(), columns.clone(), unique)?;
 
-        // Register in catalog
-        self.catalog
-            .create_index(index_name, table_name, columns, unique)?;
+        // Register in catalog, dropping the storage index if that fails
+        if let Err(e) = self
+            .catalog
+            .create_index(index_name, table_name, columns, unique)
+        {
+            table.drop_index(index_name).ok();
+            return Err(e);
+        }
 
         // Auto-save catalog
-        self.catalog.save_to_disk("arcdb.meta").ok();
+        self.save_catalog()?;
 
         Ok(QueryResult::with_message(format!(
             "Index '{}' created on '{}'",
@@ -546,11 +1365,372 @@ impl ExecutionEngine {
         )))
     }
 
+    fn execute_alter_table(
+        &mut self,
+        table_name: &str,
+        operation: AlterTableOperation,
+    ) -> Result<QueryResult> {
+        let mut def = (*self.catalog.get_table(table_name)?).clone();
+        let table = self
+            .tables
+            .get_mut(table_name)
+            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
+
+        let mut dropped_indexes = Vec::new();
+        let mut renamed_column = None;
+        let mut unique_column = None;
+        let mut foreign_key_column = None;
+        let mut identity_column = None;
+        let mut dropped_sequence = None;
+        match operation {
+            AlterTableOperation::AddColumn(col_def) => {
+                // Generated values would differ from row to row
+                let generated = col_def.identity.is_some()
+                    || col_def.default.as_ref().is_some_and(contains_sequence_call);
+                if generated && table.tuple_count() > 0 {
+                    return Err(Error::SemanticError(format!(
+                        "cannot add column '{}' with generated values to table '{}', which has rows",
+                        col_def.name, table_name
+                    )));
+                }
+                if let Some(identity) = col_def.identity.clone() {
+                    identity_column =
+                        Some((col_def.name.clone(), col_def.data_type.clone(), identity));
+                }
+                let mut column = Column::new(col_def.name, col_def.data_type, 0)
+                    .nullable(!col_def.not_null)
+                    .unique(col_def.unique);
+                // Rows already stored read the default, evaluated once
+                let missing = match col_def.default {
+                    Some(default) if generated => {
+                        column.default = Some(default.to_string());
+                        Value::Null
+                    }
+                    Some(default) => {
+                        let value = eval::evaluate_expr(&default, &[], &[])?;
+                        column.default = Some(default.to_string());
+                        coerce_value(value, &column)?
+                    }
+                    None => Value::Null,
+                };
+                if missing.is_null() && !column.nullable && table.tuple_count() > 0 {
+                    return Err(Error::NullNotAllowed(column.name));
+                }
+                if col_def.unique {
+                    // Every stored row reads the same value
+                    if !missing.is_null() && table.tuple_count() > 1 {
+                        return Err(Error::ConstraintViolation(format!(
+                            "{}_{}_key",
+                            table_name, column.name
+                        )));
+                    }
+                    unique_column = Some(column.name.clone());
+                }
+                if let Some(references) = col_def.references {
+                    foreign_key_column = Some((column.name.clone(), references, missing.clone()));
+                }
+                def.add_column(column, missing);
+            }
+            AlterTableOperation::DropColumn(name) => {
+                dropped_sequence = def
+                    .schema
+                    .get_column(&name)
+                    .and_then(|column| column.identity.as_ref())
+                    .map(|identity| identity.sequence.clone());
+                def.drop_column(&name);
+                // So are the constraints that involve it
+                let mut kept = Vec::new();
+                for constraint in std::mem::take(&mut def.constraints) {
+                    let involved = match &constraint.kind {
+                        ConstraintKind::Check(sql) => check_references(sql, &name)?,
+                        ConstraintKind::ForeignKey(fk) => fk.columns.contains(&name),
+                        _ => constraint.key_columns().contains(&name),
+                    };
+                    if !involved {
+                        kept.push(constraint);
+                    }
+                }
+                def.constraints = kept;
+                // Indexes on the column are dropped with it
+                dropped_indexes = self
+                    .catalog
+                    .get_table_indexes(table_name)
+                    .into_iter()
+                    .filter(|index| index.columns.contains(&name))
+                    .map(|index| index.name.clone())
+                    .collect();
+            }
+            AlterTableOperation::RenameColumn { old_name, new_name } => {
+                def.schema.rename_column(&old_name, &new_name);
+                for constraint in &mut def.constraints {
+                    match &mut constraint.kind {
+                        ConstraintKind::PrimaryKey(columns) | ConstraintKind::Unique(columns) => {
+                            rename(columns, &old_name, &new_name);
+                        }
+                        ConstraintKind::Check(sql) => {
+                            *sql = rewrite_check(sql, &mut |col_ref| {
+                                if col_ref.column == old_name {
+                                    col_ref.column = new_name.clone();
+                                }
+                            })?;
+                        }
+                        ConstraintKind::ForeignKey(fk) => {
+                            rename(&mut fk.columns, &old_name, &new_name);
+                            if fk.ref_table == table_name {
+                                rename(&mut fk.ref_columns, &old_name, &new_name);
+                            }
+                        }
+                    }
+                }
+                renamed_column = Some((old_name, new_name));
+            }
+            AlterTableOperation::RenameTable(new_name) => {
+                for constraint in &mut def.constraints {
+                    match &mut constraint.kind {
+                        ConstraintKind::Check(sql) => {
+                            *sql = rewrite_check(sql, &mut |col_ref| {
+                                if col_ref.table.as_deref() == Some(table_name) {
+                                    col_ref.table = Some(new_name.clone());
+                                }
+                            })?;
+                        }
+                        ConstraintKind::ForeignKey(fk) if fk.ref_table == table_name => {
+                            fk.ref_table = new_name.clone();
+                        }
+                        _ => {}
+                    }
+                }
+                def.name = new_name;
+            }
+            AlterTableOperation::SetNotNull { column, not_null } => {
+                if not_null {
+                    let idx = table.get_column_index(&column).ok_or_else(|| {
+                        Error::ColumnNotFound(column.clone(), table_name.to_string())
+                    })?;
+                    if table
+                        .scan()
+                        .iter()
+                        .any(|(_, tuple)| tuple.get(idx).is_none_or(Value::is_null))
+                    {
+                        return Err(Error::NullNotAllowed(column));
+                    }
+                }
+                if let Some(col) = def.schema.get_column_mut(&column) {
+                    col.nullable = !not_null;
+                }
+            }
+        }
+
+        if let Some(column) = &unique_column {
+            let kind = ConstraintKind::Unique(vec![column.clone()]);
+            let mut constraints = std::mem::take(&mut def.constraints);
+            self.add_constraint(&mut constraints, table_name, None, kind)?;
+            def.constraints = constraints;
+        }
+        if let Some((column, references, missing)) = foreign_key_column {
+            // Every stored row reads the same value, which must be referenced
+            let fk = foreign_key(vec![column], references);
+            if !missing.is_null()
+                && self.loaded_table(table_name)?.tuple_count() > 0
+                && !self.referenced_key_exists(&fk, &[missing])?
+            {
+                return Err(Error::ForeignKeyViolation(format!(
+                    "{}_{}_fkey",
+                    table_name, fk.columns[0]
+                )));
+            }
+            let mut constraints = std::mem::take(&mut def.constraints);
+            self.add_constraint(
+                &mut constraints,
+                table_name,
+                None,
+                ConstraintKind::ForeignKey(fk),
+            )?;
+            def.constraints = constraints;
+        }
+
+        if let Some((column, data_type, identity)) = identity_column {
+            let identity =
+                create_identity_sequence(&self.catalog, &def.name, &column, &data_type, &identity)?;
+            if let Some(col) = def.schema.get_column_mut(&column) {
+                *col = col.clone().identity(identity);
+            }
+        }
+
+        let def = self.catalog.alter_table(table_name, def)?;
+        if let Some(sequence) = &dropped_sequence {
+            self.catalog.drop_sequence(sequence)?;
+        }
+        for index in &dropped_indexes {
+            self.catalog.drop_index(index)?;
+        }
+        if let Some((old_name, new_name)) = &renamed_column {
+            self.catalog
+                .rename_index_column(&def.name, old_name, new_name);
+        }
+
+        // Foreign keys of other tables follow the renamed key
+        let mut children: Vec<String> = self
+            .catalog
+            .get_referencing_foreign_keys(table_name)
+            .into_iter()
+            .map(|(child, _, _)| child.name.clone())
+            .filter(|child| *child != def.name)
+            .collect();
+        children.dedup();
+        if renamed_column.is_none() && def.name == table_name {
+            children.clear();
+        }
+        for child in children {
+            let mut child_def = (*self.catalog.get_table(&child)?).clone();
+            for constraint in &mut child_def.constraints {
+                if let ConstraintKind::ForeignKey(fk) = &mut constraint.kind {
+                    if fk.ref_table == table_name {
+                        fk.ref_table = def.name.clone();
+                        if let Some((old_name, new_name)) = &renamed_column {
+                            rename(&mut fk.ref_columns, old_name, new_name);
+                        }
+                    }
+                }
+            }
+            let child_def = self.catalog.alter_table(&child, child_def)?;
+            if let Some(table) = self.tables.get_mut(&child) {
+                let indexes = self.catalog.get_table_indexes(&child);
+                table.alter(child_def, &indexes)?;
+            }
+        }
+
+        // Rename the storage along with the table
+        let mut table = self
+            .tables
+            .remove(table_name)
+            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
+        for index in &dropped_indexes {
+            table.drop_index(index)?;
+        }
+        let indexes = self.catalog.get_table_indexes(&def.name);
+        table.alter(def.clone(), &indexes)?;
+        if unique_column.is_some() {
+            let constraint = def.constraints.last().expect("added unique constraint");
+            table.create_index(
+                constraint.name.clone(),
+                constraint.key_columns().to_vec(),
+                true,
+            )?;
+            self.catalog
+                .create_constraint_index(constraint, &def.name)?;
+        }
+        self.tables.insert(def.name.clone(), table);
+
+        self.save_catalog()?;
+
+        Ok(QueryResult::with_message(format!(
+            "Table '{}' altered",
+            def.name
+        )))
+    }
+
+    fn execute_create_sequence(
+        &mut self,
+        name: &str,
+        options: SequenceOptions,
+        if_not_exists: bool,
+    ) -> Result<QueryResult> {
+        if self.catalog.sequence_exists(name) {
+            if if_not_exists {
+                return Ok(QueryResult::with_message(format!(
+                    "Sequence '{}' already exists",
+                    name
+                )));
+            }
+            return Err(Error::SequenceAlreadyExists(name.to_string()));
+        }
+
+        let sequence = sequence_from_options(name, &options, &DataType::BigInt)?;
+        self.catalog.create_sequence(sequence)?;
+
+        self.save_catalog()?;
+
+        Ok(QueryResult::with_message(format!(
+            "Sequence '{}' created",
+            name
+        )))
+    }
+
+    fn execute_drop_sequence(&mut self, name: &str, if_exists: bool) -> Result<QueryResult> {
+        if !self.catalog.sequence_exists(name) {
+            if if_exists {
+                return Ok(QueryResult::with_message(format!(
+                    "Sequence '{}' does not exist",
+                    name
+                )));
+            }
+            return Err(Error::SequenceNotFound(name.to_string()));
+        }
+
+        if let Some((table, column)) = self.catalog.get_sequence_owner(name) {
+            return Err(Error::SemanticError(format!(
+                "cannot drop sequence '{}' because column '{}' of table '{}' requires it",
+                name, column, table
+            )));
+        }
+
+        self.catalog.drop_sequence(name)?;
+
+        self.save_catalog()?;
+
+        Ok(QueryResult::with_message(format!(
+            "Sequence '{}' dropped",
+            name
+        )))
+    }
+
+    fn execute_drop_index(&mut self, index_name: &str, if_exists: bool) -> Result<QueryResult> {
+        let index = match self.catalog.get_index(index_name) {
+            Ok(index) => index,
+            Err(_) if if_exists => {
+                return Ok(QueryResult::with_message(format!(
+                    "Index '{}' does not exist",
+                    index_name
+                )));
+            }
+            Err(e) => return Err(e),
+        };
+
+        let table_def = self.catalog.get_table(&index.table_name)?;
+        if table_def.constraints.iter().any(|c| c.name == index.name) {
+            return Err(Error::SemanticError(format!(
+                "cannot drop index '{}' because constraint '{}' on table '{}' requires it",
+                index_name, index_name, index.table_name
+            )));
+        }
+
+        self.ensure_table_loaded(&index.table_name)?;
+        let table = self
+            .tables
+            .get_mut(&index.table_name)
+            .ok_or_else(|| Error::TableNotFound(index.table_name.clone()))?;
+
+        // The table drops its index file first, so the catalog only forgets
+        // an index once it is gone from disk
+        table.drop_index(index_name)?;
+        self.catalog.drop_index(index_name)?;
+
+        self.save_catalog()?;
+
+        Ok(QueryResult::with_message(format!(
+            "Index '{}' dropped",
+            index_name
+        )))
+    }
+
     fn execute_insert(
         &mut self,
         table_name: &str,
         columns: Option<Vec<String>>,
         values: Vec<Vec<Expr>>,
+        query: Option<Box<LogicalPlan>>,
+        returning: Vec<SelectItem>,
     ) -> Result<QueryResult> {
         // First, get schema info without mutable borrow
         let schema = {
@@ -561,9 +1741,24 @@ impl ExecutionEngine {
             table.schema().clone()
         };
 
-        // Evaluate all expressions first (before mutable borrow)
+        // Evaluate all expressions first (before mutable borrow); the rows
+        // of a query are all read before any is inserted, so it never sees
+        // its own inserts
+        let rows = match query {
+            Some(query) => {
+                let mut root = self.build_operator(*query)?;
+                operator::collect(root.as_mut())?
+                    .into_iter()
+                    .map(Tuple::into_values)
+                    .collect()
+            }
+            None => values
+                .iter()
+                .map(|row| self.evaluate_row(row))
+                .collect::<Result<Vec<_>>>()?,
+        };
         let mut all_tuples = Vec::new();
-        for row_values in values {
+        for row_values in rows {
             let mut tuple_values = Vec::new();
 
             if let Some(ref cols) = columns {
@@ -577,48 +1772,20 @@ impl ExecutionEngine {
                     let col_idx = schema.get_column_index(col_name).ok_or_else(|| {
                         Error::ColumnNotFound(col_name.clone(), table_name.to_string())
                     })?;
-                    if i < row_values.len() {
-                        tuple_values[col_idx] = self.evaluate_expr(&row_values[i], &[], &[])?;
+                    if let Some(value) = row_values.get(i) {
+                        tuple_values[col_idx] = value.clone();
                     }
                 }
             } else {
-                for expr in &row_values {
-                    tuple_values.push(self.evaluate_expr(expr, &[], &[])?);
-                }
+                tuple_values = row_values;
             }
 
             all_tuples.push(Tuple::new(tuple_values));
         }
 
-        // Now borrow table mutably and insert all tuples
-        let table = self
-            .tables
-            .get_mut(table_name)
-            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
-
-        let mut inserted = 0;
-        for tuple in all_tuples {
-            let slot_id = table.insert(tuple.clone())?;
-            inserted += 1;
-
-            // Log insert if transaction active
-            if let Some(trans_id) = self.current_trans_id {
-                let lsn = self.transaction_manager.log_manager().append(
-                    trans_id,
-                    crate::storage::wal::LogRecordType::Insert,
-                    Some(table_name.to_string()),
-                    Some(slot_id),
-                    None,
-                    Some(tuple),
-                )?;
-                table.set_page_lsn(slot_id.page_id, lsn);
-            }
-        }
-
-        Ok(QueryResult::with_affected_rows(
-            inserted,
-            format!("{} row(s) inserted", inserted),
-        ))
+        // Now insert all tuples
+        let inserted = self.insert_rows(table_name, all_tuples)?;
+        self.change_result(table_name, "inserted", inserted, returning)
     }
 
     fn execute_update(
@@ -626,6 +1793,7 @@ impl ExecutionEngine {
         table_name: &str,
         assignments: Vec<Assignment>,
         predicate: Option<Expr>,
+        returning: Vec<SelectItem>,
     ) -> Result<QueryResult> {
         // Get schema and data without mutable borrow
         let (schema, column_names, tuples_to_check) = {
@@ -634,738 +1802,774 @@ impl ExecutionEngine {
                 .get_mut(table_name)
                 .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
             let schema = table.schema().clone();
-            let column_names: Vec<String> = schema
-                .column_names()
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
+            let column_names = binder::qualify(table_name, &schema.column_names());
             let tuples: Vec<(SlotId, Tuple)> = table.scan();
             (schema, column_names, tuples)
         };
 
-        // Filter and prepare updates
-        let mut updates = Vec::new();
-        for (slot_id, tuple) in tuples_to_check {
-            let matches = if let Some(ref pred) = predicate {
-                let result = self.evaluate_expr(pred, tuple.values(), &column_names)?;
-                result.as_bool().unwrap_or(false)
-            } else {
-                true
-            };
-
-            if matches {
-                // Evaluate new values
-                let mut new_tuple = tuple.clone();
-                for assignment in &assignments {
-                    let col_idx = schema.get_column_index(&assignment.column).ok_or_else(|| {
-                        Error::ColumnNotFound(assignment.column.clone(), table_name.to_string())
-                    })?;
-                    let new_value =
-                        self.evaluate_expr(&assignment.value, tuple.values(), &column_names)?;
-                    new_tuple.set(col_idx, new_value);
-                }
-                updates.push((slot_id, new_tuple));
-            }
-        }
-
-        let updated_count = updates.len();
-
-        // Apply updates with mutable borrow
-        let table = self
-            .tables
-            .get_mut(table_name)
-            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
-
-        for (slot_id, new_tuple) in updates {
-            let before_image = table.get_tuple(slot_id);
-            table.update(slot_id, new_tuple.clone())?;
-
-            // Log update if transaction active
-            if let Some(trans_id) = self.current_trans_id {
-                let lsn = self.transaction_manager.log_manager().append(
-                    trans_id,
-                    crate::storage::wal::LogRecordType::Update,
-                    Some(table_name.to_string()),
-                    Some(slot_id),
-                    before_image,
-                    Some(new_tuple),
-                )?;
-                table.set_page_lsn(slot_id.page_id, lsn);
-            }
-        }
-
-        Ok(QueryResult::with_affected_rows(
-            updated_count,
-            format!("{} row(s) updated", updated_count),
-        ))
-    }
-
-    fn execute_delete(&mut self, table_name: &str, predicate: Option<Expr>) -> Result<QueryResult> {
-        // Get data without mutable borrow
-        let (column_names, tuples_to_check) = {
-            let table = self
-                .tables
-                .get_mut(table_name)
-                .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
-            let column_names: Vec<String> = table
-                .schema()
-                .column_names()
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
-            let tuples = table.scan();
-            (column_names, tuples)
-        };
-
-        // Determine which rows to delete
-        let mut to_delete = Vec::new();
-        for (slot_id, tuple) in tuples_to_check {
-            let matches = if let Some(ref pred) = predicate {
-                let result = self.evaluate_expr(pred, tuple.values(), &column_names)?;
-                result.as_bool().unwrap_or(false)
-            } else {
-                true
-            };
-
-            if matches {
-                to_delete.push(slot_id);
-            }
-        }
-
-        let deleted_count = to_delete.len();
-
-        // Delete with mutable borrow
-        let table = self
-            .tables
-            .get_mut(table_name)
-            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
-
-        for slot_id in to_delete {
-            let before_image = table.get_tuple(slot_id);
-            table.delete(slot_id)?;
-
-            // Log delete if transaction active
-            if let Some(trans_id) = self.current_trans_id {
-                let lsn = self.transaction_manager.log_manager().append(
-                    trans_id,
-                    crate::storage::wal::LogRecordType::Delete,
-                    Some(table_name.to_string()),
-                    Some(slot_id),
-                    before_image,
-                    None,
-                )?;
-                table.set_page_lsn(slot_id.page_id, lsn);
-            }
-        }
-
-        Ok(QueryResult::with_affected_rows(
-            deleted_count,
-            format!("{} row(s) deleted", deleted_count),
-        ))
-    }
-
-    fn execute_select(
-        &mut self,
-        input: LogicalPlan,
-        expressions: Vec<SelectItem>,
-    ) -> Result<QueryResult> {
-        // First, execute the input plan to get rows
-        let (input_rows, input_columns) = self.execute_scan_plan(input)?;
-
-        // Build result columns
-        let mut result_columns = Vec::new();
-        for item in &expressions {
-            match item {
-                SelectItem::Wildcard => {
-                    result_columns.extend(input_columns.clone());
-                }
-                SelectItem::QualifiedWildcard(_table) => {
-                    // TODO: Handle qualified wildcards properly
-                    result_columns.extend(input_columns.clone());
-                }
-                SelectItem::Expr { alias, expr } => {
-                    let col_name = alias.clone().unwrap_or_else(|| self.expr_to_string(expr));
-                    result_columns.push(col_name);
-                }
-            }
-        }
-
-        // Project rows
-        let mut result_rows = Vec::new();
-        for row in &input_rows {
-            let mut projected_values = Vec::new();
+        // Filter and prepare updates
+        let to_update = self.matching_rows(predicate, &column_names, tuples_to_check)?;
+        let positions = assignments
+            .iter()
+            .map(|assignment| {
+                schema.get_column_index(&assignment.column).ok_or_else(|| {
+                    Error::ColumnNotFound(assignment.column.clone(), table_name.to_string())
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let values: Vec<Expr> = assignments.into_iter().map(|a| a.value).collect();
+        let (slot_ids, tuples): (Vec<SlotId>, Vec<Tuple>) = to_update.into_iter().unzip();
+        let new_values = self.evaluate_per_row(&values, &column_names, tuples.clone())?;
 
-            for item in &expressions {
-                match item {
-                    SelectItem::Wildcard => {
-                        projected_values.extend(row.values().iter().cloned());
-                    }
-                    SelectItem::QualifiedWildcard(_) => {
-                        projected_values.extend(row.values().iter().cloned());
-                    }
-                    SelectItem::Expr { expr, .. } => {
-                        let value = self.evaluate_expr(expr, row.values(), &input_columns)?;
-                        projected_values.push(value);
-                    }
-                }
+        let mut updates = Vec::new();
+        for ((slot_id, tuple), row_values) in slot_ids.into_iter().zip(tuples).zip(new_values) {
+            let mut new_tuple = tuple;
+            for (&col_idx, new_value) in positions.iter().zip(row_values) {
+                new_tuple.set(col_idx, new_value);
             }
-
-            result_rows.push(Tuple::new(projected_values));
+            updates.push((slot_id, new_tuple));
         }
 
-        Ok(QueryResult {
-            columns: result_columns,
-            rows: result_rows,
-            affected_rows: 0,
-            message: None,
-        })
-    }
-
-    fn execute_scan(&mut self, table_name: &str) -> Result<QueryResult> {
-        if table_name.is_empty() {
-            // SELECT without FROM
-            return Ok(QueryResult::empty());
+        // Check every new row before applying any update
+        let checks = TableChecks::new(self.loaded_table(table_name)?.definition())?;
+        let mut checked = Vec::with_capacity(updates.len());
+        for (slot_id, new_tuple) in updates {
+            let (old, new_tuple) = self.check_update(table_name, slot_id, new_tuple, &checks)?;
+            self.dependent_rows(table_name, slot_id, &old, Some(&new_tuple))?;
+            checked.push((slot_id, old, new_tuple));
+        }
+        let mut updated = Vec::with_capacity(checked.len());
+        for (slot_id, old, new_tuple) in checked {
+            updated.push(self.store_update(table_name, slot_id, old, new_tuple)?);
         }
 
-        let table = self
-            .tables
-            .get_mut(table_name)
-            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
-
-        let columns: Vec<String> = table
-            .schema()
-            .column_names()
-            .iter()
-            .map(|s| s.to_string())
-            .collect();
-        let rows: Vec<Tuple> = table.scan().into_iter().map(|(_, t)| t).collect();
+        self.change_result(table_name, "updated", updated, returning)
+    }
 
-        Ok(QueryResult {
-            columns,
-            rows,
-            affected_rows: 0,
-            message: None,
-        })
+    /// The rows of a table for which a WHERE clause is true
+    fn matching_rows(
+        &mut self,
+        predicate: Option<Expr>,
+        columns: &[BoundColumn],
+        rows: Vec<(SlotId, Tuple)>,
+    ) -> Result<Vec<(SlotId, Tuple)>> {
+        let Some(predicate) = predicate else {
+            return Ok(rows);
+        };
+        let tuples = rows.iter().map(|(_, tuple)| tuple.clone()).collect();
+        let results = self.evaluate_per_row(&[predicate], columns, tuples)?;
+        Ok(rows
+            .into_iter()
+            .zip(results)
+            .filter(|(_, result)| result[0].as_bool().unwrap_or(false))
+            .map(|(row, _)| row)
+            .collect())
     }
 
-    fn execute_index_scan(
+    fn execute_delete(
         &mut self,
         table_name: &str,
-        index_name: &str,
-        op: &BinaryOperator,
-        value: &Expr,
+        predicate: Option<Expr>,
+        returning: Vec<SelectItem>,
     ) -> Result<QueryResult> {
-        // Collect results first to avoid borrow conflicts
-        let mut rows = Vec::new();
-        let columns;
-
-        {
-            let val = self.evaluate_expr(value, &[], &[])?;
-            let key = IndexKey::new(val);
-
+        // Get data without mutable borrow
+        let (column_names, tuples_to_check) = {
             let table = self
                 .tables
                 .get_mut(table_name)
                 .ok_or_else(|| Error::TableNotFound(table_name.to_string()))?;
+            let column_names = binder::qualify(table_name, &table.schema().column_names());
+            let tuples = table.scan();
+            (column_names, tuples)
+        };
 
-            columns = table
-                .schema()
-                .column_names()
-                .iter()
-                .map(|s| s.to_string())
-                .collect();
-
-            match op {
-                BinaryOperator::Eq => {
-                    let maybe_slot = table.get_index(index_name).and_then(|idx| idx.search(&key));
-                    if let Some(slot_id) = maybe_slot {
-                        if let Some(tuple) = table.get(slot_id) {
-                            rows.push(tuple);
-                        }
-                    }
-                }
-                BinaryOperator::Gt
-                | BinaryOperator::Gte
-                | BinaryOperator::Lt
-                | BinaryOperator::Lte => {
-                    let (min, max) = match op {
-                        BinaryOperator::Gt | BinaryOperator::Gte => (Some(&key), None),
-                        BinaryOperator::Lt | BinaryOperator::Lte => (None, Some(&key)),
-                        _ => unreachable!(),
-                    };
+        // Determine which rows to delete, checking the actions on the rows
+        // referencing them before deleting any
+        let to_delete = self.matching_rows(predicate, &column_names, tuples_to_check)?;
+        for (slot_id, old) in &to_delete {
+            self.dependent_rows(table_name, *slot_id, old, None)?;
+        }
 
-                    if let Some(col_indices) = table.get_index_columns(index_name) {
-                        let col_idx = col_indices[0];
-                        if let Some(index) = table.get_index(index_name) {
-                            for (_, slot_id) in index.range_scan(min, max) {
-                                if let Some(tuple) = table.get(slot_id) {
-                                    // Filter for exclusive if needed
-                                    let matches = match op {
-                                        BinaryOperator::Gt => tuple
-                                            .get(col_idx)
-                                            .map_or(false, |v| IndexKey::new(v.clone()) > key),
-                                        BinaryOperator::Lt => tuple
-                                            .get(col_idx)
-                                            .map_or(false, |v| IndexKey::new(v.clone()) < key),
-                                        _ => true, // Gte and Lte are handled by inclusive range_scan
-                                    };
-
-                                    if matches {
-                                        rows.push(tuple);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                _ => {
-                    return Err(Error::ExecutionError(format!(
-                        "Operator {:?} not supported for index scan",
-                        op
-                    )));
-                }
+        let mut deleted = Vec::new();
+        for (slot_id, _) in to_delete {
+            if let Some(old) = self.delete_row(table_name, slot_id)? {
+                deleted.push(old);
             }
         }
 
-        Ok(QueryResult {
-            columns,
-            rows,
-            affected_rows: 0,
-            message: None,
-        })
+        self.change_result(table_name, "deleted", deleted, returning)
     }
 
-    fn execute_join(
+    /// The result of INSERT, UPDATE or DELETE: a count of the changed rows,
+    /// and the RETURNING list computed over them when there is one
+    fn change_result(
         &mut self,
-        left: LogicalPlan,
-        right: LogicalPlan,
-        join_type: JoinType,
-        condition: Option<Expr>,
+        table_name: &str,
+        action: &str,
+        rows: Vec<Tuple>,
+        returning: Vec<SelectItem>,
     ) -> Result<QueryResult> {
-        // Execute left child
-        let left_result = self.execute(left)?;
-
-        // Execute right child
-        let right_result = self.execute(right)?;
-
-        // Only support Inner Join for now
-        if !matches!(join_type, JoinType::Inner) {
-            return Err(Error::ExecutionError(
-                "Only INNER JOIN is currently supported".to_string(),
+        let count = rows.len();
+        if returning.is_empty() {
+            return Ok(QueryResult::with_affected_rows(
+                count,
+                format!("{} row(s) {}", count, action),
             ));
         }
 
-        let mut result_rows = Vec::new();
-        let mut columns = left_result.columns.clone();
-        columns.extend(right_result.columns.clone());
-
-        // Nested Loop Join
-        for l_row in &left_result.rows {
-            for r_row in &right_result.rows {
-                // Combine tuples
-                let mut values = l_row.values().to_vec();
-                values.extend(r_row.values().to_vec());
-                let joined_tuple = Tuple::new(values);
-
-                // Check condition
-                let matches = if let Some(ref cond) = condition {
-                    let result = self.evaluate_expr(cond, joined_tuple.values(), &columns)?;
-                    result.as_bool().unwrap_or(false)
-                } else {
-                    true
-                };
-
-                if matches {
-                    result_rows.push(joined_tuple);
-                }
-            }
-        }
-
+        let schema = self.loaded_table(table_name)?.schema();
+        let columns = binder::qualify(table_name, &schema.column_names());
+        let input = Box::new(ValuesOperator::new(columns, rows));
+        let mut root = self.project(input, returning)?;
+        let rows = operator::collect(root.as_mut())?;
         Ok(QueryResult {
-            columns,
-            rows: result_rows,
-            affected_rows: 0,
+            columns: binder::column_names(root.columns()),
+            rows,
+            affected_rows: count,
             message: None,
         })
     }
 
-    fn execute_hash_join(
-        &mut self,
-        left: LogicalPlan,
-        right: LogicalPlan,
-        left_key: Expr,
-        right_key: Expr,
-    ) -> Result<QueryResult> {
-        // 1. Execute build side (left)
-        let left_result = self.execute(left)?;
+    // ========== Row Changes ==========
 
-        // Build hash table
-        let mut hash_table: HashMap<Value, Vec<Tuple>> = HashMap::new();
-
-        for row in &left_result.rows {
-            let key = self.evaluate_expr(&left_key, row.values(), &left_result.columns)?;
-            hash_table.entry(key).or_default().push(row.clone());
-        }
+    /// A loaded table
+    fn loaded_table(&mut self, table_name: &str) -> Result<&mut Table> {
+        self.tables
+            .get_mut(table_name)
+            .ok_or_else(|| Error::TableNotFound(table_name.to_string()))
+    }
 
-        // 2. Execute probe side (right)
-        let right_result = self.execute(right)?;
+    /// Insert a row after checking it against the constraints of its table,
+    /// returning the row as stored
+    fn insert_row(
+        &mut self,
+        table_name: &str,
+        tuple: Tuple,
+        checks: &TableChecks,
+    ) -> Result<Tuple> {
+        let table = self.loaded_table(table_name)?;
+        let def = table.shared_definition();
+        let tuple = table.conform(tuple)?;
+        checks.enforce(&tuple)?;
+        self.check_foreign_keys(&def, None, &tuple)?;
+
+        let slot_id = self.loaded_table(table_name)?.insert(tuple.clone())?;
+        self.log_change(
+            table_name,
+            LogRecordType::Insert,
+            slot_id,
+            None,
+            Some(tuple.clone()),
+        )?;
+        Ok(tuple)
+    }
 
-        let mut result_rows = Vec::new();
+    /// Insert rows after checking them against the constraints of their
+    /// table, storing them all under one buffer pool lock and logging them
+    /// in records of up to `BULK_INSERT_BATCH` rows; returns the rows as
+    /// stored
+    ///
+    /// Every row is checked before any is stored, so either all rows are
+    /// inserted or none. A table with a foreign key to itself takes the rows
+    /// one at a time, so a row may reference one inserted before it.
+    fn insert_rows(&mut self, table_name: &str, tuples: Vec<Tuple>) -> Result<Vec<Tuple>> {
+        let def = self.loaded_table(table_name)?.shared_definition();
+        let checks = TableChecks::new(&def)?;
+        let self_referencing = def.foreign_keys().any(|(_, fk)| fk.ref_table == def.name);
+        if tuples.len() < 2 || self_referencing {
+            return tuples
+                .into_iter()
+                .map(|tuple| self.insert_row(table_name, tuple, &checks))
+                .collect();
+        }
 
-        // Probe hash table
-        for r_row in &right_result.rows {
-            let key = self.evaluate_expr(&right_key, r_row.values(), &right_result.columns)?;
+        let mut rows = Vec::with_capacity(tuples.len());
+        for tuple in tuples {
+            let tuple = self.loaded_table(table_name)?.conform(tuple)?;
+            checks.enforce(&tuple)?;
+            self.check_foreign_keys(&def, None, &tuple)?;
+            rows.push(tuple);
+        }
 
-            if let Some(matches) = hash_table.get(&key) {
-                for l_row in matches {
-                    // Combine tuples: left + right
-                    let mut values = l_row.values().to_vec();
-                    values.extend(r_row.values().to_vec());
-                    result_rows.push(Tuple::new(values));
+        let slot_ids = self.loaded_table(table_name)?.insert_many(rows.clone())?;
+        if let Some(trans_id) = self.current_trans_id {
+            let logged: Vec<(SlotId, Tuple)> = slot_ids.into_iter().zip(rows.clone()).collect();
+            for batch in logged.chunks(BULK_INSERT_BATCH) {
+                let lsn = self.transaction_manager.log_manager().append_bulk_insert(
+                    trans_id,
+                    table_name.to_string(),
+                    batch.to_vec(),
+                )?;
+                let table = self.loaded_table(table_name)?;
+                for (slot_id, _) in batch {
+                    table.set_page_lsn(slot_id.page_id, lsn);
                 }
             }
         }
+        Ok(rows)
+    }
+
+    /// Replace a row after checking it against the constraints of its table,
+    /// then apply the ON UPDATE actions of the foreign keys referencing it;
+    /// returns the row as stored
+    fn update_row(
+        &mut self,
+        table_name: &str,
+        slot_id: SlotId,
+        tuple: Tuple,
+        checks: &TableChecks,
+    ) -> Result<Tuple> {
+        let (old, tuple) = self.check_update(table_name, slot_id, tuple, checks)?;
+        self.store_update(table_name, slot_id, old, tuple)
+    }
 
-        // Combine schemas
-        let mut columns = left_result.columns;
-        columns.extend(right_result.columns);
+    /// Check a row replacing the one at `slot_id` against the constraints of
+    /// its table; returns the old row and the new row as it would be stored
+    fn check_update(
+        &mut self,
+        table_name: &str,
+        slot_id: SlotId,
+        tuple: Tuple,
+        checks: &TableChecks,
+    ) -> Result<(Tuple, Tuple)> {
+        let table = self.loaded_table(table_name)?;
+        let def = table.shared_definition();
+        let old = table
+            .get_tuple(slot_id)
+            .ok_or_else(|| Error::ExecutionError("Tuple not found".to_string()))?;
+        let tuple = table.conform(tuple)?;
+        checks.enforce(&tuple)?;
+        self.check_foreign_keys(&def, Some(&old), &tuple)?;
+        Ok((old, tuple))
+    }
 
-        Ok(QueryResult {
-            columns,
-            rows: result_rows,
-            affected_rows: 0,
-            message: None,
-        })
+    /// Replace a checked row, then apply the ON UPDATE actions of the foreign
+    /// keys referencing it; returns the row as stored
+    fn store_update(
+        &mut self,
+        table_name: &str,
+        slot_id: SlotId,
+        old: Tuple,
+        tuple: Tuple,
+    ) -> Result<Tuple> {
+        let dependents = self.dependent_rows(table_name, slot_id, &old, Some(&tuple))?;
+
+        self.loaded_table(table_name)?
+            .update(slot_id, tuple.clone())?;
+        self.log_change(
+            table_name,
+            LogRecordType::Update,
+            slot_id,
+            Some(old),
+            Some(tuple.clone()),
+        )?;
+        self.apply_referential_actions(dependents)?;
+        Ok(tuple)
     }
 
-    fn execute_scan_plan(&mut self, plan: LogicalPlan) -> Result<(Vec<Tuple>, Vec<String>)> {
-        match plan {
-            LogicalPlan::Scan { table_name, .. } => {
-                if table_name.is_empty() {
-                    return Ok((vec![Tuple::empty()], vec![]));
-                }
+    /// Delete a row, then apply the ON DELETE actions of the foreign keys
+    /// referencing it; returns the deleted row, or None if an earlier action
+    /// already deleted it
+    fn delete_row(&mut self, table_name: &str, slot_id: SlotId) -> Result<Option<Tuple>> {
+        let Some(old) = self.loaded_table(table_name)?.get_tuple(slot_id) else {
+            return Ok(None);
+        };
+        let dependents = self.dependent_rows(table_name, slot_id, &old, None)?;
+
+        self.loaded_table(table_name)?.delete(slot_id)?;
+        self.log_change(
+            table_name,
+            LogRecordType::Delete,
+            slot_id,
+            Some(old.clone()),
+            None,
+        )?;
+        self.apply_referential_actions(dependents)?;
+        Ok(Some(old))
+    }
 
-                let table = self
-                    .tables
-                    .get_mut(&table_name)
-                    .ok_or_else(|| Error::TableNotFound(table_name.clone()))?;
+    /// Log a change to a row if a transaction is active
+    fn log_change(
+        &mut self,
+        table_name: &str,
+        record_type: LogRecordType,
+        slot_id: SlotId,
+        before: Option<Tuple>,
+        after: Option<Tuple>,
+    ) -> Result<()> {
+        if let Some(trans_id) = self.current_trans_id {
+            let lsn = self.transaction_manager.log_manager().append(
+                trans_id,
+                record_type,
+                Some(table_name.to_string()),
+                Some(slot_id),
+                before,
+                after,
+            )?;
+            self.loaded_table(table_name)?
+                .set_page_lsn(slot_id.page_id, lsn);
+        }
+        Ok(())
+    }
 
-                let columns: Vec<String> = table
-                    .schema()
-                    .column_names()
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect();
-                let rows: Vec<Tuple> = table.scan().into_iter().map(|(_, t)| t).collect();
+    // ========== Foreign Keys ==========
 
-                Ok((rows, columns))
+    /// Fail with `ForeignKeyViolation` if a row of `def` references a key
+    /// missing from the referenced table; `old` is the row it replaces
+    fn check_foreign_keys(
+        &mut self,
+        def: &TableDef,
+        old: Option<&Tuple>,
+        row: &Tuple,
+    ) -> Result<()> {
+        for (name, fk) in def.foreign_keys() {
+            let positions = column_positions(def, &fk.columns)?;
+            let key = row_key(row, &positions);
+            // A key with a NULL references nothing
+            if key.iter().any(Value::is_null)
+                || old.is_some_and(|old| row_key(old, &positions) == key)
+            {
+                continue;
+            }
+            // A row may reference itself
+            if fk.ref_table == def.name
+                && row_key(row, &column_positions(def, &fk.ref_columns)?) == key
+            {
+                continue;
             }
-            LogicalPlan::Filter { input, predicate } => {
-                let (rows, columns) = self.execute_scan_plan(*input)?;
+            if !self.referenced_key_exists(fk, &key)? {
+                return Err(Error::ForeignKeyViolation(name.to_string()));
+            }
+        }
+        Ok(())
+    }
 
-                let filtered: Vec<Tuple> = rows
-                    .into_iter()
-                    .filter(|row| {
-                        self.evaluate_expr(&predicate, row.values(), &columns)
-                            .ok()
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false)
-                    })
-                    .collect();
+    /// Whether the table referenced by a foreign key has `key` in the
+    /// referenced columns
+    fn referenced_key_exists(&mut self, fk: &ForeignKey, key: &[Value]) -> Result<bool> {
+        let parent = self.open_for_read(&fk.ref_table)?.shared_definition();
+        let positions = column_positions(&parent, &fk.ref_columns)?;
+        let Some(key) = convert_key(key, &parent, &positions) else {
+            return Ok(false);
+        };
+        Ok(self
+            .loaded_table(&fk.ref_table)?
+            .contains_key(&positions, &key))
+    }
+
+    /// The rows referencing a row of `table_name` that is deleted, or updated
+    /// to `new`
+    ///
+    /// Fails if a NO ACTION or RESTRICT foreign key forbids the change, or if
+    /// a referential action would fail on any row it reaches, directly or
+    /// through the rows referencing those, so nothing is changed before the
+    /// whole change is known to succeed.
+    fn dependent_rows(
+        &mut self,
+        table_name: &str,
+        slot_id: SlotId,
+        old: &Tuple,
+        new: Option<&Tuple>,
+    ) -> Result<Vec<Dependents>> {
+        let dependents = self.find_dependents(table_name, slot_id, old, new)?;
+        let mut visited = HashSet::from([(table_name.to_string(), slot_id)]);
+        self.check_dependents(&dependents, &mut visited)?;
+        Ok(dependents)
+    }
 
-                Ok((filtered, columns))
+    /// The rows directly referencing a row that is deleted, or updated to
+    /// `new`, failing if a NO ACTION or RESTRICT foreign key forbids it
+    fn find_dependents(
+        &mut self,
+        table_name: &str,
+        slot_id: SlotId,
+        old: &Tuple,
+        new: Option<&Tuple>,
+    ) -> Result<Vec<Dependents>> {
+        let def = self.catalog.get_table(table_name)?;
+        let mut dependents = Vec::new();
+        for (child, name, fk) in self.catalog.get_referencing_foreign_keys(table_name) {
+            let ref_positions = column_positions(&def, &fk.ref_columns)?;
+            let old_key = row_key(old, &ref_positions);
+            let new_key = new.map(|row| row_key(row, &ref_positions));
+            if old_key.iter().any(Value::is_null) || new_key.as_ref() == Some(&old_key) {
+                continue;
             }
-            LogicalPlan::Sort { input, order_by } => {
-                let (mut rows, columns) = self.execute_scan_plan(*input)?;
 
-                rows.sort_by(|a, b| {
-                    for item in &order_by {
-                        let val_a = self.evaluate_expr(&item.expr, a.values(), &columns).ok();
-                        let val_b = self.evaluate_expr(&item.expr, b.values(), &columns).ok();
+            let positions = column_positions(&child, &fk.columns)?;
+            let Some(key) = convert_key(&old_key, &child, &positions) else {
+                continue;
+            };
+            let mut rows = self
+                .open_for_write(&child.name)?
+                .find_rows(&positions, &key);
+            // A deleted row referencing itself goes with it
+            if new.is_none() && child.name == table_name {
+                rows.retain(|(slot, _)| *slot != slot_id);
+            }
+            if rows.is_empty() {
+                continue;
+            }
 
-                        let cmp = match (val_a, val_b) {
-                            (Some(va), Some(vb)) => va.compare(&vb).unwrap_or(Ordering::Equal),
-                            _ => Ordering::Equal,
-                        };
+            let action = if new.is_some() {
+                fk.on_update
+            } else {
+                fk.on_delete
+            };
+            let values = match action {
+                ReferentialAction::NoAction | ReferentialAction::Restrict => {
+                    return Err(Error::ForeignKeyViolation(name));
+                }
+                ReferentialAction::Cascade => new_key,
+                ReferentialAction::SetNull => Some(vec![Value::Null; positions.len()]),
+                ReferentialAction::SetDefault => {
+                    let defaults = positions
+                        .iter()
+                        .map(|&i| column_default(&child.schema().columns()[i]))
+                        .collect::<Result<Vec<_>>>()?;
+                    let defaults = self.evaluate_row(&defaults)?;
+                    // Rows keeping their key would reference the row going away
+                    if convert_key(&defaults, &child, &positions).as_ref() == Some(&key) {
+                        return Err(Error::ForeignKeyViolation(name));
+                    }
+                    // and so would rows taking a key the referenced table lacks
+                    if !defaults.iter().any(Value::is_null)
+                        && !self.referenced_key_exists(&fk, &defaults)?
+                    {
+                        return Err(Error::ForeignKeyViolation(name));
+                    }
+                    Some(defaults)
+                }
+            };
+            dependents.push(Dependents {
+                table_name: child.name.clone(),
+                positions,
+                slots: rows.into_iter().map(|(slot, _)| slot).collect(),
+                values,
+            });
+        }
+        Ok(dependents)
+    }
 
-                        if cmp != Ordering::Equal {
-                            return if item.ascending { cmp } else { cmp.reverse() };
+    /// Fail if a referential action would fail on a dependent row, checking
+    /// the rows that reference it in turn; `visited` holds the rows already
+    /// checked, so cycles of foreign keys end
+    fn check_dependents(
+        &mut self,
+        dependents: &[Dependents],
+        visited: &mut HashSet<(String, SlotId)>,
+    ) -> Result<()> {
+        for dependents in dependents {
+            let table_name = &dependents.table_name;
+            let checks = TableChecks::new(self.loaded_table(table_name)?.definition())?;
+            for &slot_id in &dependents.slots {
+                if !visited.insert((table_name.clone(), slot_id)) {
+                    continue;
+                }
+                let Some(row) = self.loaded_table(table_name)?.get_tuple(slot_id) else {
+                    continue;
+                };
+                let new = match &dependents.values {
+                    None => None,
+                    Some(values) => {
+                        let mut new = row.clone();
+                        for (&col_idx, value) in dependents.positions.iter().zip(values) {
+                            new.set(col_idx, value.clone());
                         }
+                        let new = self.loaded_table(table_name)?.conform(new)?;
+                        checks.enforce(&new)?;
+                        Some(new)
                     }
-                    Ordering::Equal
-                });
-
-                Ok((rows, columns))
+                };
+                let nested = self.find_dependents(table_name, slot_id, &row, new.as_ref())?;
+                self.check_dependents(&nested, visited)?;
             }
-            LogicalPlan::Limit {
-                input,
-                limit,
-                offset,
-            } => {
-                let (rows, columns) = self.execute_scan_plan(*input)?;
-
-                let offset_val = offset
-                    .and_then(|e| self.evaluate_const_expr(&e).ok())
-                    .and_then(|v| v.as_i64())
-                    .unwrap_or(0) as usize;
-
-                let limit_val = limit
-                    .and_then(|e| self.evaluate_const_expr(&e).ok())
-                    .and_then(|v| v.as_i64())
-                    .map(|l| l as usize)
-                    .unwrap_or(rows.len());
-
-                let limited: Vec<Tuple> =
-                    rows.into_iter().skip(offset_val).take(limit_val).collect();
+        }
+        Ok(())
+    }
 
-                Ok((limited, columns))
+    /// Delete or update the rows referencing a deleted or updated key
+    fn apply_referential_actions(&mut self, dependents: Vec<Dependents>) -> Result<()> {
+        for dependents in dependents {
+            let table_name = &dependents.table_name;
+            let Some(values) = &dependents.values else {
+                for slot_id in dependents.slots {
+                    self.delete_row(table_name, slot_id)?;
+                }
+                continue;
+            };
+            let checks = TableChecks::new(self.loaded_table(table_name)?.definition())?;
+            for slot_id in dependents.slots {
+                // An earlier action may have changed or deleted the row
+                let Some(mut row) = self.loaded_table(table_name)?.get_tuple(slot_id) else {
+                    continue;
+                };
+                for (&col_idx, value) in dependents.positions.iter().zip(values) {
+                    row.set(col_idx, value.clone());
+                }
+                self.update_row(table_name, slot_id, row, &checks)?;
             }
-            _ => Err(Error::ExecutionError(
-                "Unsupported plan in scan".to_string(),
-            )),
         }
+        Ok(())
     }
+}
 
-    fn evaluate_expr(&self, expr: &Expr, row: &[Value], columns: &[String]) -> Result<Value> {
-        match expr {
-            Expr::Literal(lit) => Ok(self.literal_to_value(lit)),
-
-            Expr::Column(col_ref) => {
-                let col_name = &col_ref.column;
-                let idx = columns
-                    .iter()
-                    .position(|c| c == col_name)
-                    .ok_or_else(|| Error::ColumnNotFound(col_name.clone(), String::new()))?;
-
-                row.get(idx).cloned().ok_or_else(|| {
-                    Error::ExecutionError(format!("Column index {} out of bounds", idx))
-                })
-            }
+/// Rows referencing a deleted or updated key through a foreign key
+struct Dependents {
+    table_name: String,
+    /// Positions of the referencing columns
+    positions: Vec<usize>,
+    slots: Vec<SlotId>,
+    /// New values of the referencing columns, or None to delete the rows
+    values: Option<Vec<Value>>,
+}
 
-            Expr::BinaryOp { left, op, right } => {
-                let left_val = self.evaluate_expr(left, row, columns)?;
-                let right_val = self.evaluate_expr(right, row, columns)?;
+/// CHECK constraints of a table, parsed for evaluation against its rows
+struct TableChecks {
+    columns: Vec<BoundColumn>,
+    checks: Vec<(String, Expr)>,
+}
 
-                self.evaluate_binary_op(&left_val, op, &right_val)
-            }
+impl TableChecks {
+    fn new(def: &TableDef) -> Result<Self> {
+        let checks = def
+            .constraints
+            .iter()
+            .filter_map(|constraint| match &constraint.kind {
+                ConstraintKind::Check(sql) => Some(
+                    Parser::new(sql)
+                        .and_then(|mut parser| parser.parse_expression())
+                        .map(|expr| (constraint.name.clone(), expr)),
+                ),
+                _ => None,
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            columns: binder::qualify(&def.name, &def.schema().column_names()),
+            checks,
+        })
+    }
 
-            Expr::UnaryOp { op, expr } => {
-                let val = self.evaluate_expr(expr, row, columns)?;
-                self.evaluate_unary_op(op, &val)
+    /// Fail with `ConstraintViolation` if a row makes a CHECK constraint
+    /// false; a NULL result satisfies the constraint
+    fn enforce(&self, row: &Tuple) -> Result<()> {
+        for (name, expr) in &self.checks {
+            if eval::evaluate_expr(expr, row.values(), &self.columns)? == Value::Boolean(false) {
+                return Err(Error::ConstraintViolation(name.clone()));
             }
+        }
+        Ok(())
+    }
+}
 
-            Expr::IsNull(inner) => {
-                let val = self.evaluate_expr(inner, row, columns)?;
-                Ok(Value::Boolean(val.is_null()))
-            }
+/// Positions of the named columns of a table
+fn column_positions(def: &TableDef, columns: &[String]) -> Result<Vec<usize>> {
+    columns
+        .iter()
+        .map(|name| {
+            def.schema()
+                .get_column_index(name)
+                .ok_or_else(|| Error::ColumnNotFound(name.clone(), def.name.clone()))
+        })
+        .collect()
+}
 
-            Expr::IsNotNull(inner) => {
-                let val = self.evaluate_expr(inner, row, columns)?;
-                Ok(Value::Boolean(!val.is_null()))
-            }
+/// The values of a row at the given positions
+fn row_key(row: &Tuple, positions: &[usize]) -> Vec<Value> {
+    positions
+        .iter()
+        .map(|&i| row.get(i).cloned().unwrap_or(Value::Null))
+        .collect()
+}
 
-            Expr::Nested(inner) => self.evaluate_expr(inner, row, columns),
+/// A key converted to the types of the columns at `positions` of a table,
+/// or None if some value cannot be stored in its column
+fn convert_key(key: &[Value], def: &TableDef, positions: &[usize]) -> Option<Vec<Value>> {
+    let columns = def.schema().columns();
+    key.iter()
+        .zip(positions)
+        .map(|(value, &i)| coerce_value(value.clone(), &columns[i]).ok())
+        .collect()
+}
 
-            Expr::Function { name, args, .. } => self.evaluate_function(name, args, row, columns),
+/// The default expression of a column, NULL if it has none
+fn column_default(column: &Column) -> Result<Expr> {
+    match &column.default {
+        Some(sql) => Parser::new(sql)?.parse_expression(),
+        None => Ok(Expr::Literal(Literal::Null)),
+    }
+}
 
-            _ => Err(Error::ExecutionError(format!(
-                "Unsupported expression: {:?}",
-                expr
-            ))),
+/// A sequence for values of `data_type`, with PostgreSQL's defaults for the
+/// options not given: from 1 up to the largest value of the type, or from -1
+/// down to the smallest for a negative increment
+fn sequence_from_options(
+    name: &str,
+    options: &SequenceOptions,
+    data_type: &DataType,
+) -> Result<Sequence> {
+    let (type_min, type_max) = match data_type {
+        DataType::SmallInt => (i16::MIN as i64, i16::MAX as i64),
+        DataType::Integer => (i32::MIN as i64, i32::MAX as i64),
+        _ => (i64::MIN, i64::MAX),
+    };
+    let increment = options.increment.unwrap_or(1);
+    let (min_value, max_value) = if increment > 0 {
+        (
+            options.min_value.unwrap_or(1),
+            options.max_value.unwrap_or(type_max),
+        )
+    } else {
+        (
+            options.min_value.unwrap_or(type_min),
+            options.max_value.unwrap_or(-1),
+        )
+    };
+    for (option, value) in [("MINVALUE", min_value), ("MAXVALUE", max_value)] {
+        if !(type_min..=type_max).contains(&value) {
+            return Err(Error::SemanticError(format!(
+                "{} ({}) of sequence '{}' is out of range for {}",
+                option, value, name, data_type
+            )));
         }
     }
+    let start = options
+        .start
+        .unwrap_or(if increment > 0 { min_value } else { max_value });
+    Sequence::new(name, increment, min_value, max_value, start, options.cycle)
+}
 
-    fn evaluate_const_expr(&self, expr: &Expr) -> Result<Value> {
-        self.evaluate_expr(expr, &[], &[])
+/// Create the sequence generating an identity column, named like
+/// PostgreSQL's `t_col_seq` with a number appended when taken
+fn create_identity_sequence(
+    catalog: &Catalog,
+    table_name: &str,
+    column: &str,
+    data_type: &DataType,
+    identity: &IdentityDef,
+) -> Result<Identity> {
+    let base = format!("{}_{}_seq", table_name, column);
+    let mut name = base.clone();
+    let mut suffix = 0;
+    while catalog.sequence_exists(&name) {
+        suffix += 1;
+        name = format!("{}{}", base, suffix);
     }
+    catalog.create_sequence(sequence_from_options(&name, &identity.options, data_type)?)?;
+    Ok(Identity {
+        sequence: name,
+        always: identity.always,
+    })
+}
 
-    fn literal_to_value(&self, lit: &Literal) -> Value {
-        match lit {
-            Literal::Null => Value::Null,
-            Literal::Boolean(b) => Value::Boolean(*b),
-            Literal::Integer(i) => Value::Integer(*i as i32),
-            Literal::Float(f) => Value::Float(*f),
-            Literal::String(s) => Value::String(s.clone()),
-        }
+/// A foreign key on `columns` with an analyzed REFERENCES clause
+fn foreign_key(columns: Vec<String>, references: References) -> ForeignKey {
+    ForeignKey {
+        columns,
+        ref_table: references.table,
+        ref_columns: references.columns,
+        on_delete: references.on_delete,
+        on_update: references.on_update,
     }
+}
 
-    fn evaluate_binary_op(
-        &self,
-        left: &Value,
-        op: &BinaryOperator,
-        right: &Value,
-    ) -> Result<Value> {
-        match op {
-            BinaryOperator::Eq => {
-                let cmp = left.compare(right);
-                Ok(Value::Boolean(cmp == Some(Ordering::Equal)))
-            }
-            BinaryOperator::Neq => {
-                let cmp = left.compare(right);
-                Ok(Value::Boolean(cmp != Some(Ordering::Equal)))
-            }
-            BinaryOperator::Lt => {
-                let cmp = left.compare(right);
-                Ok(Value::Boolean(cmp == Some(Ordering::Less)))
-            }
-            BinaryOperator::Gt => {
-                let cmp = left.compare(right);
-                Ok(Value::Boolean(cmp == Some(Ordering::Greater)))
-            }
-            BinaryOperator::Lte => {
-                let cmp = left.compare(right);
-                Ok(Value::Boolean(matches!(
-                    cmp,
-                    Some(Ordering::Less) | Some(Ordering::Equal)
-                )))
-            }
-            BinaryOperator::Gte => {
-                let cmp = left.compare(right);
-                Ok(Value::Boolean(matches!(
-                    cmp,
-                    Some(Ordering::Greater) | Some(Ordering::Equal)
-                )))
-            }
-            BinaryOperator::And => {
-                let left_bool = left.as_bool().unwrap_or(false);
-                let right_bool = right.as_bool().unwrap_or(false);
-                Ok(Value::Boolean(left_bool && right_bool))
-            }
-            BinaryOperator::Or => {
-                let left_bool = left.as_bool().unwrap_or(false);
-                let right_bool = right.as_bool().unwrap_or(false);
-                Ok(Value::Boolean(left_bool || right_bool))
-            }
-            BinaryOperator::Add => left.add(right).ok_or_else(|| Error::TypeMismatch {
-                from: left.type_name().to_string(),
-                to: right.type_name().to_string(),
-            }),
-            BinaryOperator::Sub => left.sub(right).ok_or_else(|| Error::TypeMismatch {
-                from: left.type_name().to_string(),
-                to: right.type_name().to_string(),
-            }),
-            BinaryOperator::Mul => left.mul(right).ok_or_else(|| Error::TypeMismatch {
-                from: left.type_name().to_string(),
-                to: right.type_name().to_string(),
-            }),
-            BinaryOperator::Div => {
-                if let Some(r) = right.as_f64() {
-                    if r == 0.0 {
-                        return Err(Error::DivisionByZero);
-                    }
-                }
-                left.div(right).ok_or_else(|| Error::TypeMismatch {
-                    from: left.type_name().to_string(),
-                    to: right.type_name().to_string(),
-                })
-            }
-            BinaryOperator::Concat => match (left, right) {
-                (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
-                _ => Ok(Value::String(format!("{}{}", left, right))),
-            },
-            _ => Err(Error::ExecutionError(format!(
-                "Unsupported operator: {:?}",
-                op
-            ))),
-        }
-    }
-
-    fn evaluate_unary_op(&self, op: &UnaryOperator, val: &Value) -> Result<Value> {
-        match op {
-            UnaryOperator::Not => {
-                let bool_val = val.as_bool().unwrap_or(false);
-                Ok(Value::Boolean(!bool_val))
-            }
-            UnaryOperator::Minus => match val {
-                Value::Integer(i) => Ok(Value::Integer(-i)),
-                Value::BigInt(i) => Ok(Value::BigInt(-i)),
-                Value::Float(f) => Ok(Value::Float(-f)),
-                _ => Err(Error::TypeMismatch {
-                    from: val.type_name().to_string(),
-                    to: "numeric".to_string(),
-                }),
-            },
-            UnaryOperator::Plus => Ok(val.clone()),
-        }
+/// Rename a column in a list of constraint columns
+fn rename(columns: &mut [String], old_name: &str, new_name: &str) {
+    for column in columns.iter_mut().filter(|c| *c == old_name) {
+        *column = new_name.to_string();
     }
+}
 
-    fn evaluate_function(
-        &self,
-        name: &str,
-        args: &[Expr],
-        row: &[Value],
-        columns: &[String],
-    ) -> Result<Value> {
-        let name_upper = name.to_uppercase();
-
-        // For now, just handle simple cases
-        match name_upper.as_str() {
-            "COUNT" => {
-                // This is a simplified version; real aggregates need proper handling
-                Ok(Value::Integer(1))
-            }
-            "UPPER" => {
-                if let Some(arg) = args.first() {
-                    let val = self.evaluate_expr(arg, row, columns)?;
-                    if let Value::String(s) = val {
-                        Ok(Value::String(s.to_uppercase()))
-                    } else {
-                        Ok(val)
-                    }
-                } else {
-                    Ok(Value::Null)
-                }
-            }
-            "LOWER" => {
-                if let Some(arg) = args.first() {
-                    let val = self.evaluate_expr(arg, row, columns)?;
-                    if let Value::String(s) = val {
-                        Ok(Value::String(s.to_lowercase()))
-                    } else {
-                        Ok(val)
-                    }
-                } else {
-                    Ok(Value::Null)
-                }
-            }
-            "LENGTH" => {
-                if let Some(arg) = args.first() {
-                    let val = self.evaluate_expr(arg, row, columns)?;
-                    if let Value::String(s) = val {
-                        Ok(Value::Integer(s.len() as i32))
-                    } else {
-                        Ok(Value::Null)
-                    }
-                } else {
-                    Ok(Value::Null)
-                }
-            }
-            _ => Err(Error::ExecutionError(format!("Unknown function: {}", name))),
+/// Whether a stored CHECK expression references a column
+fn check_references(sql: &str, column: &str) -> Result<bool> {
+    let expr = Parser::new(sql)?.parse_expression()?;
+    let mut found = false;
+    binder::visit(&expr, &mut |expr| {
+        if let Expr::Column(col_ref) = expr {
+            found |= col_ref.column == column;
         }
-    }
+    });
+    Ok(found)
+}
 
-    fn expr_to_string(&self, expr: &Expr) -> String {
-        match expr {
-            Expr::Column(col_ref) => col_ref.column.clone(),
-            Expr::Literal(lit) => format!("{:?}", lit),
-            Expr::Function { name, .. } => name.clone(),
-            _ => "expr".to_string(),
+/// Rewrite the column references of a stored CHECK expression
+fn rewrite_check(sql: &str, f: &mut dyn FnMut(&mut ColumnRef)) -> Result<String> {
+    fn rewrite(expr: &mut Expr, f: &mut dyn FnMut(&mut ColumnRef)) {
+        if let Expr::Column(col_ref) = expr {
+            f(col_ref);
         }
+        binder::for_each_child_mut(expr, &mut |child| rewrite(child, f));
+    }
+    let mut expr = Parser::new(sql)?.parse_expression()?;
+    rewrite(&mut expr, f);
+    Ok(expr.to_string())
+}
+
+/// Whether an expression must be computed over its input rows by operators
+/// other than the one evaluating it: subqueries and sequence calls
+fn needs_apply(expr: &Expr) -> bool {
+    contains_subquery(expr) || contains_sequence_call(expr)
+}
+
+/// Take the sequence calls out of an expression, those in arguments first,
+/// leaving references to the columns that will hold their results
+fn take_sequence_calls(expr: &mut Expr, width: usize, taken: &mut Vec<(String, Expr)>) {
+    binder::for_each_child_mut(expr, &mut |child| take_sequence_calls(child, width, taken));
+
+    if matches!(expr, Expr::Function { name, .. } if is_sequence_function(name)) {
+        let name = format!("$sequence{}", width + taken.len());
+        let call = std::mem::replace(expr, Expr::Column(ColumnRef::from(name.clone())));
+        taken.push((name, call));
     }
 }
 
+/// Take the subqueries out of an expression, those in an IN operand first,
+/// leaving references to the columns that will hold their results
+fn take_subqueries(
+    expr: &mut Expr,
+    width: usize,
+    taken: &mut Vec<(String, SubqueryKind, SelectStatement)>,
+) {
+    binder::for_each_child_mut(expr, &mut |child| take_subqueries(child, width, taken));
+
+    let name = format!("$subquery{}", width + taken.len());
+    let placeholder = Expr::Column(ColumnRef::from(name.clone()));
+    let (kind, query) = match std::mem::replace(expr, placeholder) {
+        Expr::Subquery(query) => (SubqueryKind::Scalar, *query),
+        Expr::Exists(query) => (SubqueryKind::Exists, *query),
+        Expr::InSubquery {
+            expr,
+            subquery,
+            negated,
+        } => (
+            SubqueryKind::In {
+                expr: *expr,
+                negated,
+            },
+            *subquery,
+        ),
+        other => {
+            *expr = other;
+            return;
+        }
+    };
+    taken.push((name, kind, query));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    fn create_test_engine() -> ExecutionEngine {
+    fn create_test_engine() -> (ExecutionEngine, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
         let catalog = Arc::new(Catalog::new());
-        ExecutionEngine::new(catalog).unwrap()
+        let engine = ExecutionEngine::open(catalog, dir.path()).unwrap();
+        (engine, dir)
     }
 
     #[test]
     fn test_create_table() {
-        let mut engine = create_test_engine();
+        let (mut engine, _dir) = create_test_engine();
 
         let plan = LogicalPlan::CreateTable {
             table_name: "users".to_string(),
@@ -1377,6 +2581,8 @@ mod tests {
                     default: None,
                     primary_key: true,
                     unique: false,
+                    references: None,
+                    identity: None,
                 },
                 ColumnDef {
                     name: "name".to_string(),
@@ -1385,8 +2591,11 @@ mod tests {
                     default: None,
                     primary_key: false,
                     unique: false,
+                    references: None,
+                    identity: None,
                 },
             ],
+            constraints: Vec::new(),
             if_not_exists: false,
         };
 
@@ -1397,7 +2606,7 @@ mod tests {
 
     #[test]
     fn test_insert_and_select() {
-        let mut engine = create_test_engine();
+        let (mut engine, _dir) = create_test_engine();
 
         // Create table
         let create_plan = LogicalPlan::CreateTable {
@@ -1410,6 +2619,8 @@ mod tests {
                     default: None,
                     primary_key: true,
                     unique: false,
+                    references: None,
+                    identity: None,
                 },
                 ColumnDef {
                     name: "value".to_string(),
@@ -1418,8 +2629,11 @@ mod tests {
                     default: None,
                     primary_key: false,
                     unique: false,
+                    references: None,
+                    identity: None,
                 },
             ],
+            constraints: Vec::new(),
             if_not_exists: false,
         };
         engine.execute(create_plan).unwrap();
@@ -1432,6 +2646,8 @@ mod tests {
                 Expr::Literal(Literal::Integer(1)),
                 Expr::Literal(Literal::String("hello".to_string())),
             ]],
+            query: None,
+            returning: Vec::new(),
         };
         let result = engine.execute(insert_plan).unwrap();
         assert_eq!(result.affected_rows, 1);
@@ -1439,6 +2655,7 @@ mod tests {
         // Select
         let scan_plan = LogicalPlan::Scan {
             table_name: "test".to_string(),
+            alias: None,
             projection: None,
         };
         let result = engine.execute(scan_plan).unwrap();
@@ -1447,8 +2664,9 @@ mod tests {
 
     #[test]
     fn test_join_execution() {
+        let dir = tempfile::tempdir().unwrap();
         let catalog = Arc::new(Catalog::new());
-        let mut engine = ExecutionEngine::new(catalog.clone()).unwrap();
+        let mut engine = ExecutionEngine::open(catalog.clone(), dir.path()).unwrap();
 
         // Create users table
         engine
@@ -1462,6 +2680,8 @@ mod tests {
                         default: None,
                         primary_key: true,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                     ColumnDef {
                         name: "name".to_string(),
@@ -1470,8 +2690,11 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                 ],
+                constraints: Vec::new(),
                 if_not_exists: false,
             })
             .unwrap();
@@ -1488,6 +2711,8 @@ mod tests {
                         default: None,
                         primary_key: true,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                     ColumnDef {
                         name: "uid".to_string(),
@@ -1496,6 +2721,8 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                     ColumnDef {
                         name: "amount".to_string(),
@@ -1504,8 +2731,11 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                 ],
+                constraints: Vec::new(),
                 if_not_exists: false,
             })
             .unwrap();
@@ -1527,6 +2757,8 @@ mod tests {
                         Expr::Literal(Literal::String("Bob".to_string())),
                     ],
                 ],
+                None,
+                Vec::new(),
             )
             .unwrap();
 
@@ -1552,6 +2784,8 @@ mod tests {
                         Expr::Literal(Literal::Integer(700)),
                     ],
                 ],
+                None,
+                Vec::new(),
             )
             .unwrap();
 
@@ -1559,10 +2793,12 @@ mod tests {
         // Note: ColumnRef needs full path if not imported, but super::* should handle it if in ast
         let scan_users = LogicalPlan::Scan {
             table_name: "users".to_string(),
+            alias: None,
             projection: None,
         };
         let scan_orders = LogicalPlan::Scan {
             table_name: "orders".to_string(),
+            alias: None,
             projection: None,
         };
 
@@ -1598,7 +2834,7 @@ mod tests {
 
     #[test]
     fn test_index_execution() {
-        let mut engine = create_test_engine();
+        let (mut engine, _dir) = create_test_engine();
 
         // 1. Create Table
         let plan = LogicalPlan::CreateTable {
@@ -1611,6 +2847,8 @@ mod tests {
                     default: None,
                     primary_key: true,
                     unique: false,
+                    references: None,
+                    identity: None,
                 },
                 ColumnDef {
                     name: "email".to_string(),
@@ -1619,8 +2857,11 @@ mod tests {
                     default: None,
                     primary_key: false,
                     unique: true,
+                    references: None,
+                    identity: None,
                 },
             ],
+            constraints: Vec::new(),
             if_not_exists: false,
         };
         engine.execute(plan).unwrap();
@@ -1650,6 +2891,8 @@ mod tests {
                         Expr::Literal(Literal::String("bob@example.com".to_string())),
                     ],
                 ],
+                None,
+                Vec::new(),
             )
             .unwrap();
 
@@ -1663,6 +2906,7 @@ mod tests {
         // 5. Execute Index Scan with ID logic
         let plan = LogicalPlan::IndexScan {
             table_name: "users".to_string(),
+            alias: None,
             index_name: "email_idx".to_string(),
             columns: vec!["email".to_string()],
             op: BinaryOperator::Eq,
@@ -1679,8 +2923,9 @@ mod tests {
 
     #[test]
     fn test_analyze_execution() {
+        let dir = tempfile::tempdir().unwrap();
         let catalog = Arc::new(Catalog::new());
-        let mut engine = ExecutionEngine::new(catalog.clone()).unwrap();
+        let mut engine = ExecutionEngine::open(catalog.clone(), dir.path()).unwrap();
 
         // Create table and insert data
         engine
@@ -1694,6 +2939,8 @@ mod tests {
                         default: None,
                         primary_key: true,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                     ColumnDef {
                         name: "val".to_string(),
@@ -1702,8 +2949,11 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                 ],
+                constraints: Vec::new(),
                 if_not_exists: false,
             })
             .unwrap();
@@ -1717,6 +2967,8 @@ mod tests {
                         Expr::Literal(Literal::Integer(i as i64)),
                         Expr::Literal(Literal::Integer(i as i64 * 10)),
                     ]],
+                    query: None,
+                    returning: Vec::new(),
                 })
                 .unwrap();
         }
@@ -1736,8 +2988,9 @@ mod tests {
 
     #[test]
     fn test_hash_join_execution() {
+        let dir = tempfile::tempdir().unwrap();
         let catalog = Arc::new(Catalog::new());
-        let mut engine = ExecutionEngine::new(catalog).unwrap();
+        let mut engine = ExecutionEngine::open(catalog, dir.path()).unwrap();
         engine.execute(LogicalPlan::BeginTransaction).unwrap();
 
         // 1. Create tables
@@ -1752,6 +3005,8 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                     ColumnDef {
                         name: "name".to_string(),
@@ -1760,8 +3015,11 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                 ],
+                Vec::new(),
                 false,
             )
             .unwrap();
@@ -1777,6 +3035,8 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                     ColumnDef {
                         name: "user_id".to_string(),
@@ -1785,6 +3045,8 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                     ColumnDef {
                         name: "item".to_string(),
@@ -1793,8 +3055,11 @@ mod tests {
                         default: None,
                         primary_key: false,
                         unique: false,
+                        references: None,
+                        identity: None,
                     },
                 ],
+                Vec::new(),
                 false,
             )
             .unwrap();
@@ -1818,6 +3083,8 @@ mod tests {
                         Expr::Literal(Literal::String("Charlie".to_string())),
                     ],
                 ],
+                None,
+                Vec::new(),
             )
             .unwrap();
 
@@ -1844,16 +3111,20 @@ mod tests {
                         Expr::Literal(Literal::String("Phone".to_string())),
                     ],
                 ],
+                None,
+                Vec::new(),
             )
             .unwrap();
 
         // 3. Execute Hash Join: users.id = orders.user_id
         let left = LogicalPlan::Scan {
             table_name: "users".to_string(),
+            alias: None,
             projection: None,
         };
         let right = LogicalPlan::Scan {
             table_name: "orders".to_string(),
+            alias: None,
             projection: None,
         };
 
@@ -1861,7 +3132,7 @@ mod tests {
             left: Box::new(left),
             right: Box::new(right),
             left_key: Expr::Column(ColumnRef {
-                table: None,
+                table: Some("users".to_string()),
                 column: "id".to_string(),
             }),
             right_key: Expr::Column(ColumnRef {
@@ -1879,15 +3150,13 @@ mod tests {
         let mut bob_count = 0;
 
         for row in result.rows {
-            if let Some(val) = row.get(1) {
-                // name column
-                if let Value::String(s) = val {
-                    if s == "Alice" {
-                        alice_count += 1;
-                    }
-                    if s == "Bob" {
-                        bob_count += 1;
-                    }
+            // name column
+            if let Some(Value::String(s)) = row.get(1) {
+                if s == "Alice" {
+                    alice_count += 1;
+                }
+                if s == "Bob" {
+                    bob_count += 1;
                 }
             }
         }
@@ -1,12 +1,19 @@
 //! Query execution module
 //!
-//! This module contains the query planner and executor.
+//! This module contains the semantic analyzer, query planner, optimizer,
+//! physical operators and executor.
 
+pub mod analyzer;
+pub mod binder;
+pub mod eval;
+#[allow(clippy::module_inception)]
 pub mod executor;
+pub mod operator;
 pub mod planner;
 
 pub mod optimizer;
 
+pub use analyzer::Analyzer;
 pub use executor::{ExecutionEngine, QueryResult};
 pub use optimizer::HeuristicOptimizer;
 pub use planner::{LogicalPlan, Planner};
@@ -0,0 +1,2353 @@
+//! Physical operators for ArcDB
+//!
+//! Queries are executed as a tree of pull-based (Volcano-style) operators.
+//! Each operator is opened, asked for rows one at a time with `next`, and
+//! closed. Scans, filters, projections and limits stream rows through; only
+//! blocking operators such as sort, aggregation and hash join build
+//! materialize their input. Every operator accepts any other as its child.
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use super::binder::{self, BoundColumn};
+use super::eval::{evaluate_binary_op, evaluate_expr, evaluate_in, expr_to_string};
+use super::executor::Sequences;
+use crate::catalog::TableDef;
+use crate::error::{Error, Result};
+use crate::sql::ast::{
+    BinaryOperator, Expr, FrameBound, FrameUnits, JoinType, Literal, OrderByItem, SelectItem,
+    SetOperator, WindowFrame, WindowSpec,
+};
+use crate::storage::btree::IndexKey;
+use crate::storage::{
+    BufferPoolManager, Decimal, HeapFile, HeapIterator, SlotId, Table, Tuple, Value,
+};
+
+/// A physical operator producing a stream of rows
+pub trait Operator {
+    /// Prepare the operator and its children to produce rows
+    fn open(&mut self) -> Result<()>;
+
+    /// Produce the next row, or None when the operator is exhausted
+    fn next(&mut self) -> Result<Option<Tuple>>;
+
+    /// Release resources held by the operator and its children
+    fn close(&mut self) -> Result<()>;
+
+    /// Output columns, with the table each one was read from
+    fn columns(&self) -> &[BoundColumn];
+}
+
+/// A node in an operator tree
+pub type BoxedOperator = Box<dyn Operator>;
+
+/// Run an operator to completion and collect its rows
+pub fn collect(op: &mut dyn Operator) -> Result<Vec<Tuple>> {
+    op.open()?;
+    let mut rows = Vec::new();
+    let result = loop {
+        match op.next() {
+            Ok(Some(row)) => rows.push(row),
+            Ok(None) => break Ok(rows),
+            Err(e) => break Err(e),
+        }
+    };
+    op.close()?;
+    result
+}
+
+// ========== Values ==========
+
+/// Produces a fixed list of rows
+pub struct ValuesOperator {
+    columns: Vec<BoundColumn>,
+    rows: Vec<Tuple>,
+    position: usize,
+}
+
+impl ValuesOperator {
+    /// Create an operator over the given rows
+    pub fn new(columns: Vec<BoundColumn>, rows: Vec<Tuple>) -> Self {
+        Self {
+            columns,
+            rows,
+            position: 0,
+        }
+    }
+
+    /// A single row with no columns (for SELECT without FROM)
+    pub fn single_row() -> Self {
+        Self::new(Vec::new(), vec![Tuple::empty()])
+    }
+}
+
+impl Operator for ValuesOperator {
+    fn open(&mut self) -> Result<()> {
+        self.position = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        let row = self.rows.get(self.position).cloned();
+        self.position += 1;
+        Ok(row)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Sequential Scan ==========
+
+/// Streams every tuple of a table, one page at a time
+pub struct SeqScanOperator {
+    def: Arc<TableDef>,
+    buffer_pool: Arc<Mutex<BufferPoolManager>>,
+    columns: Vec<BoundColumn>,
+    iter: Option<HeapIterator>,
+}
+
+impl SeqScanOperator {
+    /// Create a scan over a table whose columns are qualified by `qualifier`
+    pub fn new(table: &Table, qualifier: &str) -> Self {
+        Self {
+            def: table.shared_definition(),
+            buffer_pool: table.buffer_pool(),
+            columns: binder::qualify(qualifier, &table.schema().column_names()),
+            iter: None,
+        }
+    }
+}
+
+impl Operator for SeqScanOperator {
+    fn open(&mut self) -> Result<()> {
+        self.iter = Some(HeapIterator::new(self.def.id, self.buffer_pool.clone()));
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        Ok(self
+            .iter
+            .as_mut()
+            .and_then(|iter| iter.next())
+            .map(|(_, tuple)| self.def.decode(tuple)))
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.iter = None;
+        Ok(())
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Filter ==========
+
+/// Passes through rows for which the predicate is true
+pub struct FilterOperator {
+    input: BoxedOperator,
+    predicate: Expr,
+}
+
+impl FilterOperator {
+    /// Create a filter over an input
+    pub fn new(input: BoxedOperator, predicate: Expr) -> Result<Self> {
+        binder::bind_expr(&predicate, input.columns())?;
+        Ok(Self { input, predicate })
+    }
+}
+
+impl Operator for FilterOperator {
+    fn open(&mut self) -> Result<()> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while let Some(row) = self.input.next()? {
+            let result = evaluate_expr(&self.predicate, row.values(), self.input.columns())?;
+            if result.as_bool().unwrap_or(false) {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        self.input.columns()
+    }
+}
+
+// ========== Project ==========
+
+/// Computes the SELECT list for each input row
+pub struct ProjectOperator {
+    input: BoxedOperator,
+    items: Vec<ProjectItem>,
+    columns: Vec<BoundColumn>,
+}
+
+/// A bound SELECT list entry
+enum ProjectItem {
+    /// Copy input columns by position (`*` and `t.*`)
+    Columns(Vec<usize>),
+    /// Compute an expression
+    Expr(Expr),
+}
+
+impl ProjectOperator {
+    /// Create a projection over an input
+    pub fn new(input: BoxedOperator, items: Vec<SelectItem>) -> Result<Self> {
+        let width = input.columns().len();
+        Self::over_prefix(input, items, width)
+    }
+
+    /// Keep only the first `width` columns of the input
+    pub fn truncate(input: BoxedOperator, width: usize) -> Result<Self> {
+        Self::over_prefix(input, vec![SelectItem::Wildcard], width)
+    }
+
+    /// Create a projection whose wildcards expand only the first `width`
+    /// input columns, hiding the subquery results appended after them
+    pub fn over_prefix(input: BoxedOperator, items: Vec<SelectItem>, width: usize) -> Result<Self> {
+        let all_columns = input.columns();
+        let input_columns = &all_columns[..width];
+        let mut bound = Vec::with_capacity(items.len());
+        let mut columns = Vec::new();
+
+        for item in items {
+            match item {
+                SelectItem::Wildcard => {
+                    columns.extend(input_columns.iter().cloned());
+                    bound.push(ProjectItem::Columns((0..input_columns.len()).collect()));
+                }
+                SelectItem::QualifiedWildcard(table) => {
+                    let positions = binder::resolve_qualified_wildcard(input_columns, &table)?;
+                    columns.extend(positions.iter().map(|&i| input_columns[i].clone()));
+                    bound.push(ProjectItem::Columns(positions));
+                }
+                SelectItem::Expr { expr, alias } => {
+                    binder::bind_expr(&expr, all_columns)?;
+                    let column = match (alias, &expr) {
+                        (Some(alias), _) => BoundColumn::unqualified(alias),
+                        // A plain column reference keeps its provenance
+                        (None, Expr::Column(col_ref)) => {
+                            all_columns[binder::resolve_column(all_columns, col_ref)?].clone()
+                        }
+                        (None, expr) => BoundColumn::unqualified(expr_to_string(expr)),
+                    };
+                    columns.push(column);
+                    bound.push(ProjectItem::Expr(expr));
+                }
+            }
+        }
+
+        Ok(Self {
+            input,
+            items: bound,
+            columns,
+        })
+    }
+}
+
+impl Operator for ProjectOperator {
+    fn open(&mut self) -> Result<()> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        let row = match self.input.next()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let mut values = Vec::new();
+        for item in &self.items {
+            match item {
+                ProjectItem::Columns(positions) => {
+                    values.extend(positions.iter().map(|&i| row.values()[i].clone()));
+                }
+                ProjectItem::Expr(expr) => {
+                    values.push(evaluate_expr(expr, row.values(), self.input.columns())?);
+                }
+            }
+        }
+        Ok(Some(Tuple::new(values)))
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Alias ==========
+
+/// Passes through the rows of a derived table, qualifying its columns by the alias
+pub struct AliasOperator {
+    input: BoxedOperator,
+    columns: Vec<BoundColumn>,
+}
+
+impl AliasOperator {
+    /// Create an alias over the output of a subquery, renaming its leading
+    /// columns to `names`
+    pub fn new(input: BoxedOperator, alias: &str, names: &[String]) -> Self {
+        let columns = input
+            .columns()
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let name = names.get(idx).unwrap_or(&column.name);
+                BoundColumn::qualified(alias, name.clone())
+            })
+            .collect();
+        Self { input, columns }
+    }
+}
+
+impl Operator for AliasOperator {
+    fn open(&mut self) -> Result<()> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        self.input.next()
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Sort ==========
+
+/// Sorts its input (blocking: materializes all input rows on open)
+pub struct SortOperator {
+    input: BoxedOperator,
+    order_by: Vec<OrderByItem>,
+    sorted: std::vec::IntoIter<Tuple>,
+}
+
+impl SortOperator {
+    /// Create a sort over an input; an integer literal key is the 1-based
+    /// position of an input column
+    pub fn new(input: BoxedOperator, order_by: Vec<OrderByItem>) -> Result<Self> {
+        let width = input.columns().len();
+        for item in &order_by {
+            match Self::position(&item.expr) {
+                Some(n) if n == 0 || n > width => {
+                    return Err(Error::ExecutionError(format!(
+                        "ORDER BY position {} is not in select list",
+                        n
+                    )))
+                }
+                Some(_) => {}
+                None => binder::bind_expr(&item.expr, input.columns())?,
+            }
+        }
+        Ok(Self {
+            input,
+            order_by,
+            sorted: Vec::new().into_iter(),
+        })
+    }
+
+    fn position(expr: &Expr) -> Option<usize> {
+        match expr {
+            Expr::Literal(Literal::Integer(n)) => Some(usize::try_from(*n).unwrap_or(0)),
+            _ => None,
+        }
+    }
+}
+
+impl Operator for SortOperator {
+    fn open(&mut self) -> Result<()> {
+        self.input.open()?;
+
+        // Evaluate sort keys once per row
+        let mut keyed: Vec<(Vec<Value>, Tuple)> = Vec::new();
+        while let Some(row) = self.input.next()? {
+            let mut keys = Vec::with_capacity(self.order_by.len());
+            for item in &self.order_by {
+                keys.push(match Self::position(&item.expr) {
+                    Some(n) => row.values()[n - 1].clone(),
+                    None => evaluate_expr(&item.expr, row.values(), self.input.columns())?,
+                });
+            }
+            keyed.push((keys, row));
+        }
+
+        let order_by = &self.order_by;
+        keyed.sort_by(|(a, _), (b, _)| {
+            for (i, item) in order_by.iter().enumerate() {
+                let cmp = a[i].compare(&b[i]).unwrap_or(Ordering::Equal);
+                if cmp != Ordering::Equal {
+                    return if item.ascending { cmp } else { cmp.reverse() };
+                }
+            }
+            Ordering::Equal
+        });
+
+        self.sorted = keyed
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect::<Vec<_>>()
+            .into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        Ok(self.sorted.next())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.sorted = Vec::new().into_iter();
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        self.input.columns()
+    }
+}
+
+// ========== Limit ==========
+
+/// Skips `offset` rows, then stops pulling after `limit` rows
+pub struct LimitOperator {
+    input: BoxedOperator,
+    limit: Option<usize>,
+    offset: usize,
+    emitted: usize,
+    skipped: bool,
+}
+
+impl LimitOperator {
+    /// Create a limit over an input
+    pub fn new(input: BoxedOperator, limit: Option<usize>, offset: usize) -> Self {
+        Self {
+            input,
+            limit,
+            offset,
+            emitted: 0,
+            skipped: false,
+        }
+    }
+}
+
+impl Operator for LimitOperator {
+    fn open(&mut self) -> Result<()> {
+        self.emitted = 0;
+        self.skipped = false;
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.limit.is_some_and(|limit| self.emitted >= limit) {
+            return Ok(None);
+        }
+
+        if !self.skipped {
+            self.skipped = true;
+            for _ in 0..self.offset {
+                if self.input.next()?.is_none() {
+                    return Ok(None);
+                }
+            }
+        }
+
+        let row = self.input.next()?;
+        if row.is_some() {
+            self.emitted += 1;
+        }
+        Ok(row)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        self.input.columns()
+    }
+}
+
+// ========== Index Scan ==========
+
+/// Fetches the tuples found by an index lookup
+pub struct IndexScanOperator {
+    def: Arc<TableDef>,
+    buffer_pool: Arc<Mutex<BufferPoolManager>>,
+    columns: Vec<BoundColumn>,
+    slots: Vec<SlotId>,
+    /// Exclusive bound re-checked on each tuple: (column index, operator, key)
+    residual: Option<(usize, BinaryOperator, IndexKey)>,
+    position: usize,
+}
+
+impl IndexScanOperator {
+    /// Create a scan over the given slots of a table, qualified by `qualifier`
+    pub fn new(
+        table: &Table,
+        qualifier: &str,
+        slots: Vec<SlotId>,
+        residual: Option<(usize, BinaryOperator, IndexKey)>,
+    ) -> Self {
+        Self {
+            def: table.shared_definition(),
+            buffer_pool: table.buffer_pool(),
+            columns: binder::qualify(qualifier, &table.schema().column_names()),
+            slots,
+            residual,
+            position: 0,
+        }
+    }
+}
+
+impl Operator for IndexScanOperator {
+    fn open(&mut self) -> Result<()> {
+        self.position = 0;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while let Some(&slot_id) = self.slots.get(self.position) {
+            self.position += 1;
+            let tuple = match HeapFile::read(&self.buffer_pool, self.def.id, slot_id) {
+                Some(tuple) => self.def.decode(tuple),
+                None => continue,
+            };
+
+            let matches = match &self.residual {
+                Some((col_idx, op, key)) => tuple.get(*col_idx).is_some_and(|v| {
+                    let value = IndexKey::new(v.clone());
+                    match op {
+                        BinaryOperator::Gt => value > *key,
+                        BinaryOperator::Lt => value < *key,
+                        _ => true,
+                    }
+                }),
+                None => true,
+            };
+            if matches {
+                return Ok(Some(tuple));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Nested Loop Join ==========
+
+/// Joins each left row with every matching right row
+///
+/// The right input is materialized on open; the left input is streamed.
+pub struct NestedLoopJoinOperator {
+    left: BoxedOperator,
+    right: BoxedOperator,
+    join_type: JoinType,
+    condition: Option<Expr>,
+    columns: Vec<BoundColumn>,
+    right_rows: Vec<Tuple>,
+    right_matched: Vec<bool>,
+    current_left: Option<Tuple>,
+    current_matched: bool,
+    right_position: usize,
+    left_done: bool,
+    unmatched_position: usize,
+}
+
+impl NestedLoopJoinOperator {
+    /// Create a join of two inputs
+    pub fn new(
+        left: BoxedOperator,
+        right: BoxedOperator,
+        join_type: JoinType,
+        condition: Option<Expr>,
+    ) -> Result<Self> {
+        let mut columns = left.columns().to_vec();
+        columns.extend(right.columns().iter().cloned());
+        if let Some(cond) = &condition {
+            binder::bind_expr(cond, &columns)?;
+        }
+
+        Ok(Self {
+            left,
+            right,
+            join_type,
+            condition,
+            columns,
+            right_rows: Vec::new(),
+            right_matched: Vec::new(),
+            current_left: None,
+            current_matched: false,
+            right_position: 0,
+            left_done: false,
+            unmatched_position: 0,
+        })
+    }
+
+    fn null_row(width: usize) -> Vec<Value> {
+        vec![Value::Null; width]
+    }
+}
+
+impl Operator for NestedLoopJoinOperator {
+    fn open(&mut self) -> Result<()> {
+        self.right_rows = collect(self.right.as_mut())?;
+        self.right_matched = vec![false; self.right_rows.len()];
+        self.current_left = None;
+        self.current_matched = false;
+        self.right_position = 0;
+        self.left_done = false;
+        self.unmatched_position = 0;
+        self.left.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        let left_width = self.left.columns().len();
+        let right_width = self.right.columns().len();
+
+        while !self.left_done {
+            let left_row = match &self.current_left {
+                Some(row) => row.clone(),
+                None => match self.left.next()? {
+                    Some(row) => {
+                        self.current_left = Some(row.clone());
+                        self.current_matched = false;
+                        self.right_position = 0;
+                        row
+                    }
+                    None => {
+                        self.left_done = true;
+                        break;
+                    }
+                },
+            };
+
+            while self.right_position < self.right_rows.len() {
+                let index = self.right_position;
+                self.right_position += 1;
+
+                let joined = left_row.concat(&self.right_rows[index]);
+                let matches = match &self.condition {
+                    Some(cond) => evaluate_expr(cond, joined.values(), &self.columns)?
+                        .as_bool()
+                        .unwrap_or(false),
+                    None => true,
+                };
+                if matches {
+                    self.current_matched = true;
+                    self.right_matched[index] = true;
+                    return Ok(Some(joined));
+                }
+            }
+
+            // Left row exhausted the right side
+            self.current_left = None;
+            if !self.current_matched && matches!(self.join_type, JoinType::Left | JoinType::Full) {
+                let mut values = left_row.into_values();
+                values.extend(Self::null_row(right_width));
+                return Ok(Some(Tuple::new(values)));
+            }
+        }
+
+        // Emit unmatched right rows for RIGHT and FULL joins
+        if matches!(self.join_type, JoinType::Right | JoinType::Full) {
+            while self.unmatched_position < self.right_rows.len() {
+                let index = self.unmatched_position;
+                self.unmatched_position += 1;
+                if !self.right_matched[index] {
+                    let mut values = Self::null_row(left_width);
+                    values.extend(self.right_rows[index].values().iter().cloned());
+                    return Ok(Some(Tuple::new(values)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.right_rows.clear();
+        self.right_matched.clear();
+        self.left.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Hash Join ==========
+
+/// Inner equi-join: builds a hash table from the left input on open, then
+/// streams the right input and probes it
+pub struct HashJoinOperator {
+    left: BoxedOperator,
+    right: BoxedOperator,
+    left_key: Expr,
+    right_key: Expr,
+    columns: Vec<BoundColumn>,
+    hash_table: HashMap<Value, Vec<Tuple>>,
+    pending: Vec<Tuple>,
+}
+
+impl HashJoinOperator {
+    /// Create a hash join; the keys are swapped if written in the opposite order
+    pub fn new(
+        left: BoxedOperator,
+        right: BoxedOperator,
+        left_key: Expr,
+        right_key: Expr,
+    ) -> Result<Self> {
+        let mut columns = left.columns().to_vec();
+        columns.extend(right.columns().iter().cloned());
+        binder::bind_expr(&left_key, &columns)?;
+        binder::bind_expr(&right_key, &columns)?;
+
+        let binds = |expr: &Expr, op: &BoxedOperator| binder::bind_expr(expr, op.columns()).is_ok();
+        let (left_key, right_key) = if binds(&left_key, &left) && binds(&right_key, &right) {
+            (left_key, right_key)
+        } else if binds(&left_key, &right) && binds(&right_key, &left) {
+            (right_key, left_key)
+        } else {
+            return Err(Error::ExecutionError(format!(
+                "Hash join keys {} and {} must each reference one side of the join",
+                left_key, right_key
+            )));
+        };
+
+        Ok(Self {
+            left,
+            right,
+            left_key,
+            right_key,
+            columns,
+            hash_table: HashMap::new(),
+            pending: Vec::new(),
+        })
+    }
+}
+
+impl Operator for HashJoinOperator {
+    fn open(&mut self) -> Result<()> {
+        // Build phase
+        self.hash_table.clear();
+        self.pending.clear();
+        self.left.open()?;
+        while let Some(row) = self.left.next()? {
+            let key = hash_key(evaluate_expr(
+                &self.left_key,
+                row.values(),
+                self.left.columns(),
+            )?);
+            // NULL never equals anything, so NULL keys cannot match
+            if !key.is_null() {
+                self.hash_table.entry(key).or_default().push(row);
+            }
+        }
+        self.left.close()?;
+
+        self.right.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        loop {
+            if let Some(row) = self.pending.pop() {
+                return Ok(Some(row));
+            }
+
+            // Probe phase
+            let right_row = match self.right.next()? {
+                Some(row) => row,
+                None => return Ok(None),
+            };
+            let key = hash_key(evaluate_expr(
+                &self.right_key,
+                right_row.values(),
+                self.right.columns(),
+            )?);
+            if let Some(matches) = self.hash_table.get(&key) {
+                // Reversed so that pop() yields matches in build order
+                self.pending = matches
+                    .iter()
+                    .rev()
+                    .map(|left_row| left_row.concat(&right_row))
+                    .collect();
+            }
+        }
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.hash_table.clear();
+        self.pending.clear();
+        self.right.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Semi Join ==========
+
+/// Keeps the left rows that have (or, for an anti join, lack) a match in the
+/// right input, which produces exactly the key columns
+///
+/// Built by the planner from `EXISTS`, `NOT EXISTS`, `IN` and `NOT IN`
+/// subqueries in WHERE. A null-aware anti join implements `NOT IN`: once
+/// the subquery returns a NULL, no row is kept, and a NULL probe is only
+/// kept when the subquery is empty.
+pub struct HashSemiJoinOperator {
+    left: BoxedOperator,
+    right: BoxedOperator,
+    left_keys: Vec<Expr>,
+    anti: bool,
+    null_aware: bool,
+    keys: HashSet<Vec<Value>>,
+    right_empty: bool,
+    right_has_null: bool,
+}
+
+impl HashSemiJoinOperator {
+    /// Create a semi join (or anti join) of two inputs
+    pub fn new(
+        left: BoxedOperator,
+        right: BoxedOperator,
+        left_keys: Vec<Expr>,
+        anti: bool,
+        null_aware: bool,
+    ) -> Result<Self> {
+        for key in &left_keys {
+            binder::bind_expr(key, left.columns())?;
+        }
+        if right.columns().len() != left_keys.len() {
+            return Err(Error::ExecutionError(format!(
+                "Semi join has {} keys but the subquery returns {} columns",
+                left_keys.len(),
+                right.columns().len()
+            )));
+        }
+        Ok(Self {
+            left,
+            right,
+            left_keys,
+            anti,
+            null_aware,
+            keys: HashSet::new(),
+            right_empty: true,
+            right_has_null: false,
+        })
+    }
+}
+
+/// Normalize a join key so that equal numbers of different types hash alike
+fn hash_key(value: Value) -> Value {
+    match value {
+        Value::Integer(i) => Value::BigInt(i as i64),
+        Value::Decimal(d) if d.rescale(0) == Some(d) => d.to_i64().map_or(value, Value::BigInt),
+        Value::Float(f) if f.fract() == 0.0 && f.abs() < i64::MAX as f64 => Value::BigInt(f as i64),
+        other => other,
+    }
+}
+
+impl Operator for HashSemiJoinOperator {
+    fn open(&mut self) -> Result<()> {
+        self.keys.clear();
+        self.right_empty = true;
+        self.right_has_null = false;
+        for row in collect(self.right.as_mut())? {
+            self.right_empty = false;
+            let key: Vec<Value> = row.values().iter().cloned().map(hash_key).collect();
+            // NULL never equals anything, so NULL keys cannot match
+            if key.iter().any(Value::is_null) {
+                self.right_has_null = true;
+            } else {
+                self.keys.insert(key);
+            }
+        }
+        self.left.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while let Some(row) = self.left.next()? {
+            let mut key = Vec::with_capacity(self.left_keys.len());
+            for expr in &self.left_keys {
+                key.push(hash_key(evaluate_expr(
+                    expr,
+                    row.values(),
+                    self.left.columns(),
+                )?));
+            }
+            let probe_has_null = key.iter().any(Value::is_null);
+            let found = !probe_has_null && self.keys.contains(&key);
+
+            let keep = if !self.anti {
+                found
+            } else if self.null_aware {
+                // x NOT IN (...) is UNKNOWN rather than TRUE if either side has a NULL
+                self.right_empty || (!found && !probe_has_null && !self.right_has_null)
+            } else {
+                !found
+            };
+            if keep {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.keys.clear();
+        self.left.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        self.left.columns()
+    }
+}
+
+// ========== Set Operations ==========
+
+/// Combines the rows of two inputs with UNION, INTERSECT or EXCEPT.
+///
+/// Rows are compared by hashing, with NULLs equal to each other. UNION
+/// streams both inputs; INTERSECT and EXCEPT materialize the right input
+/// and stream the left.
+pub struct SetOperationOperator {
+    left: BoxedOperator,
+    right: BoxedOperator,
+    op: SetOperator,
+    all: bool,
+    columns: Vec<BoundColumn>,
+    /// Right rows not yet matched by a left row, by key (INTERSECT and EXCEPT)
+    right_counts: HashMap<Vec<Value>, usize>,
+    /// Keys already returned (without ALL)
+    returned: HashSet<Vec<Value>>,
+    left_done: bool,
+}
+
+impl SetOperationOperator {
+    /// Create a set operation; the output columns are named after the left input
+    pub fn new(
+        left: BoxedOperator,
+        right: BoxedOperator,
+        op: SetOperator,
+        all: bool,
+    ) -> Result<Self> {
+        if left.columns().len() != right.columns().len() {
+            return Err(Error::ExecutionError(format!(
+                "each {} query must have the same number of columns",
+                op
+            )));
+        }
+        let columns = left
+            .columns()
+            .iter()
+            .map(|column| BoundColumn::unqualified(column.name.clone()))
+            .collect();
+
+        Ok(Self {
+            left,
+            right,
+            op,
+            all,
+            columns,
+            right_counts: HashMap::new(),
+            returned: HashSet::new(),
+            left_done: false,
+        })
+    }
+
+    fn key(row: &Tuple) -> Vec<Value> {
+        row.values().iter().cloned().map(hash_key).collect()
+    }
+
+    /// Whether a row with this key is returned, given the set so far
+    fn keep(&mut self, key: Vec<Value>, from_left: bool) -> bool {
+        let keep = match (self.op, from_left) {
+            (SetOperator::Union, _) => true,
+            (SetOperator::Intersect | SetOperator::Except, false) => false,
+            (op, true) => {
+                // With ALL, each right row cancels (or matches) one left row
+                let matched = match self.right_counts.get_mut(&key) {
+                    Some(count) if *count > 0 => {
+                        if self.all {
+                            *count -= 1;
+                        }
+                        true
+                    }
+                    _ => false,
+                };
+                matched == (op == SetOperator::Intersect)
+            }
+        };
+        keep && (self.all || self.returned.insert(key))
+    }
+}
+
+impl Operator for SetOperationOperator {
+    fn open(&mut self) -> Result<()> {
+        self.right_counts.clear();
+        self.returned.clear();
+        self.left_done = false;
+        if self.op == SetOperator::Union {
+            self.right.open()?;
+        } else {
+            for row in collect(self.right.as_mut())? {
+                *self.right_counts.entry(Self::key(&row)).or_insert(0) += 1;
+            }
+        }
+        self.left.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while !self.left_done {
+            match self.left.next()? {
+                Some(row) => {
+                    if self.keep(Self::key(&row), true) {
+                        return Ok(Some(row));
+                    }
+                }
+                None => self.left_done = true,
+            }
+        }
+        if self.op != SetOperator::Union {
+            return Ok(None);
+        }
+        while let Some(row) = self.right.next()? {
+            if self.keep(Self::key(&row), false) {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.left.close()?;
+        if self.op == SetOperator::Union {
+            self.right.close()?;
+        }
+        Ok(())
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Distinct ==========
+
+/// Removes duplicate rows, keeping the first occurrence of each
+///
+/// Rows are compared by hashing, with NULLs equal to each other, and the
+/// input order is preserved.
+pub struct DistinctOperator {
+    input: BoxedOperator,
+    seen: HashSet<Vec<Value>>,
+}
+
+impl DistinctOperator {
+    /// Create a duplicate elimination over an input
+    pub fn new(input: BoxedOperator) -> Self {
+        Self {
+            input,
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl Operator for DistinctOperator {
+    fn open(&mut self) -> Result<()> {
+        self.seen.clear();
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        while let Some(row) = self.input.next()? {
+            let key = row.values().iter().cloned().map(hash_key).collect();
+            if self.seen.insert(key) {
+                return Ok(Some(row));
+            }
+        }
+        Ok(None)
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.seen.clear();
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        self.input.columns()
+    }
+}
+
+// ========== Apply ==========
+
+/// Values of the enclosing-query columns read by a correlated subquery
+///
+/// Shared between an [`ApplyOperator`], which sets it before each run of
+/// the subquery, and the [`ParameterOperator`] inside the subquery plan.
+pub type OuterRow = Arc<Mutex<Vec<Value>>>;
+
+/// Produces a single row holding the current values of an [`OuterRow`]
+pub struct ParameterOperator {
+    columns: Vec<BoundColumn>,
+    outer_row: OuterRow,
+    done: bool,
+}
+
+impl ParameterOperator {
+    /// Create an operator exposing an outer row under the given column names
+    pub fn new(names: &[String], outer_row: OuterRow) -> Self {
+        Self {
+            columns: names.iter().map(BoundColumn::unqualified).collect(),
+            outer_row,
+            done: false,
+        }
+    }
+}
+
+impl Operator for ParameterOperator {
+    fn open(&mut self) -> Result<()> {
+        self.done = false;
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        let values = self
+            .outer_row
+            .lock()
+            .map_err(|_| Error::Internal("outer row lock poisoned".to_string()))?
+            .clone();
+        Ok(Some(Tuple::new(values)))
+    }
+
+    fn close(&mut self) -> Result<()> {
+        Ok(())
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+/// How the rows of a subquery become a value
+#[derive(Debug, Clone)]
+pub enum SubqueryKind {
+    /// `(SELECT ...)`: the only value of the only row, or NULL without rows
+    Scalar,
+    /// `EXISTS (SELECT ...)`
+    Exists,
+    /// `expr [NOT] IN (SELECT ...)`
+    In { expr: Expr, negated: bool },
+}
+
+/// Appends the result of a subquery to each input row
+///
+/// An uncorrelated subquery runs once and its rows are cached. A correlated
+/// subquery runs again for every input row, after the outer columns it
+/// reads are copied into its [`OuterRow`].
+pub struct ApplyOperator {
+    input: BoxedOperator,
+    subquery: BoxedOperator,
+    kind: SubqueryKind,
+    /// Positions in the input row of the outer columns the subquery reads
+    outer_positions: Vec<usize>,
+    outer_row: OuterRow,
+    columns: Vec<BoundColumn>,
+    cached: Option<Vec<Tuple>>,
+}
+
+impl ApplyOperator {
+    /// Create an apply whose result column is called `name`
+    pub fn new(
+        input: BoxedOperator,
+        subquery: BoxedOperator,
+        kind: SubqueryKind,
+        outer_positions: Vec<usize>,
+        outer_row: OuterRow,
+        name: String,
+    ) -> Result<Self> {
+        if let SubqueryKind::In { expr, .. } = &kind {
+            binder::bind_expr(expr, input.columns())?;
+        }
+        let mut columns = input.columns().to_vec();
+        columns.push(BoundColumn::unqualified(name));
+        Ok(Self {
+            input,
+            subquery,
+            kind,
+            outer_positions,
+            outer_row,
+            columns,
+            cached: None,
+        })
+    }
+
+    /// Rows of the subquery for an input row; EXISTS stops at the first row
+    fn subquery_rows(&mut self, row: &Tuple) -> Result<Vec<Tuple>> {
+        if let Some(rows) = &self.cached {
+            return Ok(rows.clone());
+        }
+
+        let outer: Vec<Value> = self
+            .outer_positions
+            .iter()
+            .map(|&i| row.values()[i].clone())
+            .collect();
+        *self
+            .outer_row
+            .lock()
+            .map_err(|_| Error::Internal("outer row lock poisoned".to_string()))? = outer;
+
+        let rows = if matches!(self.kind, SubqueryKind::Exists) {
+            self.subquery.open()?;
+            let first = self.subquery.next();
+            self.subquery.close()?;
+            first?.into_iter().collect()
+        } else {
+            collect(self.subquery.as_mut())?
+        };
+
+        if self.outer_positions.is_empty() {
+            self.cached = Some(rows.clone());
+        }
+        Ok(rows)
+    }
+}
+
+impl Operator for ApplyOperator {
+    fn open(&mut self) -> Result<()> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        let row = match self.input.next()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        let rows = self.subquery_rows(&row)?;
+        let first_column = |rows: &[Tuple]| -> Vec<Value> {
+            rows.iter()
+                .map(|r| r.get(0).cloned().unwrap_or(Value::Null))
+                .collect()
+        };
+
+        let result = match &self.kind {
+            SubqueryKind::Scalar => match rows.as_slice() {
+                [] => Value::Null,
+                [only] => only.get(0).cloned().unwrap_or(Value::Null),
+                _ => {
+                    return Err(Error::ExecutionError(
+                        "more than one row returned by a subquery used as an expression"
+                            .to_string(),
+                    ))
+                }
+            },
+            SubqueryKind::Exists => Value::Boolean(!rows.is_empty()),
+            SubqueryKind::In { expr, negated } => {
+                let value = evaluate_expr(expr, row.values(), self.input.columns())?;
+                evaluate_in(&value, &first_column(&rows), *negated)
+            }
+        };
+
+        let mut values = row.values().to_vec();
+        values.push(result);
+        Ok(Some(Tuple::new(values)))
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Sequences ==========
+
+/// Appends the results of NEXTVAL, CURRVAL and SETVAL calls to each input
+/// row, so every row advances the sequences it calls
+pub struct SequenceOperator {
+    input: BoxedOperator,
+    /// Calls in evaluation order; a call's arguments may read the result
+    /// columns of the calls before it
+    calls: Vec<Expr>,
+    sequences: Sequences,
+    columns: Vec<BoundColumn>,
+}
+
+impl SequenceOperator {
+    /// Create an operator appending the result of each of `calls` in a
+    /// column named after it
+    pub fn new(
+        input: BoxedOperator,
+        calls: Vec<(String, Expr)>,
+        sequences: Sequences,
+    ) -> Result<Self> {
+        let mut columns = input.columns().to_vec();
+        let mut exprs = Vec::with_capacity(calls.len());
+        for (name, call) in calls {
+            binder::bind_expr(&call, &columns)?;
+            columns.push(BoundColumn::unqualified(name));
+            exprs.push(call);
+        }
+        Ok(Self {
+            input,
+            calls: exprs,
+            sequences,
+            columns,
+        })
+    }
+}
+
+impl Operator for SequenceOperator {
+    fn open(&mut self) -> Result<()> {
+        self.input.open()
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        let row = match self.input.next()? {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let mut values = row.values().to_vec();
+        for call in &self.calls {
+            let Expr::Function { name, args, .. } = call else {
+                return Err(Error::Internal(format!("{} is not a sequence call", call)));
+            };
+            let args = args
+                .iter()
+                .map(|arg| evaluate_expr(arg, &values, &self.columns[..values.len()]))
+                .collect::<Result<Vec<_>>>()?;
+            values.push(self.sequences.call(name, &args)?);
+        }
+        Ok(Some(Tuple::new(values)))
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.input.close()
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Aggregate ==========
+
+/// Supported aggregate functions
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AggregateKind {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+/// An aggregate call: kind, DISTINCT flag and argument (None for COUNT(*))
+#[derive(Debug, Clone)]
+struct AggregateCall {
+    kind: AggregateKind,
+    distinct: bool,
+    arg: Option<Expr>,
+}
+
+impl AggregateCall {
+    fn from_expr(expr: &Expr) -> Result<Self> {
+        let (name, args, distinct) = match expr {
+            Expr::Function {
+                name,
+                args,
+                distinct,
+                ..
+            } => (name, args, *distinct),
+            _ => {
+                return Err(Error::ExecutionError(format!(
+                    "Not an aggregate function: {}",
+                    expr
+                )))
+            }
+        };
+
+        let kind = match name.to_uppercase().as_str() {
+            "COUNT" => AggregateKind::Count,
+            "SUM" => AggregateKind::Sum,
+            "AVG" => AggregateKind::Avg,
+            "MIN" => AggregateKind::Min,
+            "MAX" => AggregateKind::Max,
+            _ => {
+                return Err(Error::ExecutionError(format!(
+                    "Unknown aggregate: {}",
+                    name
+                )))
+            }
+        };
+
+        let arg = match args.first() {
+            Some(Expr::Column(col_ref)) if col_ref.column == "*" => None,
+            Some(arg) => Some(arg.clone()),
+            None if kind == AggregateKind::Count => None,
+            None => {
+                return Err(Error::ExecutionError(format!(
+                    "{} requires an argument",
+                    name
+                )))
+            }
+        };
+
+        Ok(Self {
+            kind,
+            distinct,
+            arg,
+        })
+    }
+}
+
+/// Running state of one aggregate within one group
+#[derive(Debug, Clone, Default)]
+struct Accumulator {
+    count: i64,
+    int_sum: i64,
+    float_sum: Option<f64>,
+    decimal_sum: Option<Decimal>,
+    extreme: Option<Value>,
+    seen: HashSet<Value>,
+}
+
+impl Accumulator {
+    fn update(&mut self, call: &AggregateCall, value: Option<Value>) -> Result<()> {
+        let value = match value {
+            // COUNT(*) counts every row
+            None => {
+                self.count += 1;
+                return Ok(());
+            }
+            Some(v) if v.is_null() => return Ok(()),
+            Some(v) => v,
+        };
+
+        if call.distinct && !self.seen.insert(value.clone()) {
+            return Ok(());
+        }
+        self.count += 1;
+
+        match call.kind {
+            AggregateKind::Count => {}
+            AggregateKind::Sum | AggregateKind::Avg => match value {
+                Value::Integer(i) => self.add_int(i as i64)?,
+                Value::BigInt(i) => self.add_int(i)?,
+                Value::Float(f) => *self.float_sum.get_or_insert(0.0) += f,
+                Value::Decimal(d) => {
+                    let sum = self.decimal_sum.unwrap_or(Decimal::from_i64(0));
+                    self.decimal_sum = Some(sum.checked_add(&d).ok_or_else(|| {
+                        Error::ExecutionError("Numeric overflow in SUM".to_string())
+                    })?);
+                }
+                other => {
+                    return Err(Error::TypeMismatch {
+                        from: other.type_name().to_string(),
+                        to: "numeric".to_string(),
+                    })
+                }
+            },
+            AggregateKind::Min | AggregateKind::Max => {
+                let replace = match &self.extreme {
+                    None => true,
+                    Some(current) => {
+                        let ord = value.compare(current).unwrap_or(Ordering::Equal);
+                        if call.kind == AggregateKind::Min {
+                            ord == Ordering::Less
+                        } else {
+                            ord == Ordering::Greater
+                        }
+                    }
+                };
+                if replace {
+                    self.extreme = Some(value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn add_int(&mut self, i: i64) -> Result<()> {
+        self.int_sum = self
+            .int_sum
+            .checked_add(i)
+            .ok_or_else(|| Error::ExecutionError("Integer overflow in SUM".to_string()))?;
+        Ok(())
+    }
+
+    /// Exact sum of the integer and decimal inputs, if any input was a decimal
+    fn exact_sum(&self) -> Result<Option<Decimal>> {
+        self.decimal_sum
+            .map(|d| {
+                d.checked_add(&Decimal::from_i64(self.int_sum))
+                    .ok_or_else(|| Error::ExecutionError("Numeric overflow in SUM".to_string()))
+            })
+            .transpose()
+    }
+
+    fn finish(&self, call: &AggregateCall) -> Result<Value> {
+        let float_total = || {
+            self.float_sum.unwrap_or(0.0)
+                + self.int_sum as f64
+                + self.decimal_sum.map_or(0.0, |d| d.to_f64())
+        };
+        let value = match call.kind {
+            AggregateKind::Count => Value::BigInt(self.count),
+            _ if self.count == 0 => Value::Null,
+            AggregateKind::Sum => match (self.float_sum, self.exact_sum()?) {
+                (Some(_), _) => Value::Float(float_total()),
+                (None, Some(d)) => Value::Decimal(d),
+                (None, None) => Value::BigInt(self.int_sum),
+            },
+            AggregateKind::Avg => match (self.float_sum, self.exact_sum()?) {
+                (None, Some(d)) => d
+                    .checked_div(&Decimal::from_i64(self.count))
+                    .map(Value::Decimal)
+                    .ok_or_else(|| Error::ExecutionError("Numeric overflow in AVG".to_string()))?,
+                _ => Value::Float(float_total() / self.count as f64),
+            },
+            AggregateKind::Min | AggregateKind::Max => self.extreme.clone().unwrap_or(Value::Null),
+        };
+        Ok(value)
+    }
+}
+
+/// Groups its input and computes aggregates (blocking)
+///
+/// Output columns are the GROUP BY expressions followed by the aggregates,
+/// named by their SQL text.
+pub struct AggregateOperator {
+    input: BoxedOperator,
+    group_by: Vec<Expr>,
+    calls: Vec<AggregateCall>,
+    columns: Vec<BoundColumn>,
+    results: std::vec::IntoIter<Tuple>,
+}
+
+impl AggregateOperator {
+    /// Create an aggregation over an input
+    pub fn new(input: BoxedOperator, group_by: Vec<Expr>, aggregates: Vec<Expr>) -> Result<Self> {
+        let input_columns = input.columns();
+        let mut columns = Vec::with_capacity(group_by.len() + aggregates.len());
+        for expr in &group_by {
+            binder::bind_expr(expr, input_columns)?;
+            columns.push(match expr {
+                // Grouping by a column keeps its provenance
+                Expr::Column(col_ref) => {
+                    input_columns[binder::resolve_column(input_columns, col_ref)?].clone()
+                }
+                _ => BoundColumn::unqualified(expr.to_string()),
+            });
+        }
+        for expr in &aggregates {
+            binder::bind_expr(expr, input_columns)?;
+            columns.push(BoundColumn::unqualified(expr.to_string()));
+        }
+
+        let calls = aggregates
+            .iter()
+            .map(AggregateCall::from_expr)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            input,
+            group_by,
+            calls,
+            columns,
+            results: Vec::new().into_iter(),
+        })
+    }
+}
+
+impl Operator for AggregateOperator {
+    fn open(&mut self) -> Result<()> {
+        self.input.open()?;
+
+        // Groups in order of first appearance
+        let mut group_index: HashMap<Vec<Value>, usize> = HashMap::new();
+        let mut groups: Vec<(Vec<Value>, Vec<Accumulator>)> = Vec::new();
+
+        while let Some(row) = self.input.next()? {
+            let columns = self.input.columns();
+            let key = self
+                .group_by
+                .iter()
+                .map(|expr| evaluate_expr(expr, row.values(), columns))
+                .collect::<Result<Vec<_>>>()?;
+
+            let index = *group_index.entry(key.clone()).or_insert_with(|| {
+                groups.push((key, vec![Accumulator::default(); self.calls.len()]));
+                groups.len() - 1
+            });
+
+            for (call, acc) in self.calls.iter().zip(groups[index].1.iter_mut()) {
+                let value = match &call.arg {
+                    Some(arg) => Some(evaluate_expr(arg, row.values(), columns)?),
+                    None => None,
+                };
+                acc.update(call, value)?;
+            }
+        }
+        self.input.close()?;
+
+        // Without GROUP BY there is always exactly one group
+        if groups.is_empty() && self.group_by.is_empty() {
+            groups.push((Vec::new(), vec![Accumulator::default(); self.calls.len()]));
+        }
+
+        let rows = groups
+            .into_iter()
+            .map(|(mut key, accs)| {
+                for (call, acc) in self.calls.iter().zip(accs.iter()) {
+                    key.push(acc.finish(call)?);
+                }
+                Ok(Tuple::new(key))
+            })
+            .collect::<Result<Vec<Tuple>>>()?;
+        self.results = rows.into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        Ok(self.results.next())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.results = Vec::new().into_iter();
+        Ok(())
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+// ========== Window ==========
+
+/// What a window function computes for each row
+#[derive(Debug, Clone)]
+enum WindowKind {
+    RowNumber,
+    Rank,
+    DenseRank,
+    /// LAG (backward) or LEAD (forward): a value `offset` rows away
+    Offset {
+        arg: Expr,
+        offset: Option<Expr>,
+        default: Option<Expr>,
+        forward: bool,
+    },
+    /// An aggregate over the rows of the window frame
+    Aggregate(AggregateCall),
+}
+
+/// A window function call with its OVER clause
+#[derive(Debug, Clone)]
+struct WindowCall {
+    kind: WindowKind,
+    spec: WindowSpec,
+}
+
+impl WindowCall {
+    fn from_expr(expr: &Expr) -> Result<Self> {
+        let (name, args, spec) = match expr {
+            Expr::Function {
+                name,
+                args,
+                over: Some(over),
+                ..
+            } => (name.to_uppercase(), args, (**over).clone()),
+            _ => {
+                return Err(Error::ExecutionError(format!(
+                    "Not a window function: {}",
+                    expr
+                )))
+            }
+        };
+
+        let kind = match name.as_str() {
+            "ROW_NUMBER" => WindowKind::RowNumber,
+            "RANK" => WindowKind::Rank,
+            "DENSE_RANK" => WindowKind::DenseRank,
+            "LAG" | "LEAD" => WindowKind::Offset {
+                arg: args.first().cloned().ok_or_else(|| {
+                    Error::ExecutionError(format!("{} requires an argument", name))
+                })?,
+                offset: args.get(1).cloned(),
+                default: args.get(2).cloned(),
+                forward: name == "LEAD",
+            },
+            _ => WindowKind::Aggregate(AggregateCall::from_expr(expr)?),
+        };
+        Ok(Self { kind, spec })
+    }
+
+    /// Frame of an aggregate; without a frame clause it runs from the start
+    /// of the partition to the last peer of the current row, or covers the
+    /// whole partition when there is no ORDER BY
+    fn frame(&self) -> WindowFrame {
+        match &self.spec.frame {
+            Some(frame) => frame.clone(),
+            None if self.spec.order_by.is_empty() => WindowFrame {
+                units: FrameUnits::Rows,
+                start: FrameBound::UnboundedPreceding,
+                end: FrameBound::UnboundedFollowing,
+            },
+            None => WindowFrame {
+                units: FrameUnits::Range,
+                start: FrameBound::UnboundedPreceding,
+                end: FrameBound::CurrentRow,
+            },
+        }
+    }
+}
+
+/// One partition of the input, in window order
+struct Partition<'a> {
+    /// Input row indices
+    rows: &'a [usize],
+    /// ORDER BY keys, parallel to `rows`
+    keys: &'a [Vec<Value>],
+    /// Start and end (exclusive) of the peer group of each row
+    peers: Vec<(usize, usize)>,
+}
+
+impl<'a> Partition<'a> {
+    fn new(rows: &'a [usize], keys: &'a [Vec<Value>]) -> Self {
+        let mut peers = Vec::with_capacity(rows.len());
+        let mut start = 0;
+        for i in 1..=rows.len() {
+            if i == rows.len() || !keys_equal(&keys[i], &keys[start]) {
+                peers.extend(std::iter::repeat_n((start, i), i - start));
+                start = i;
+            }
+        }
+        Self { rows, keys, peers }
+    }
+
+    /// Start and end (exclusive) of the frame of the `i`th row
+    fn frame_bounds(
+        &self,
+        frame: &WindowFrame,
+        ascending: bool,
+        i: usize,
+    ) -> Result<(usize, usize)> {
+        let n = self.rows.len();
+        let start = match (&frame.start, frame.units) {
+            (FrameBound::UnboundedPreceding, _) => 0,
+            (FrameBound::CurrentRow, FrameUnits::Rows) => i,
+            (FrameBound::CurrentRow, FrameUnits::Range) => self.peers[i].0,
+            (FrameBound::Preceding(k), FrameUnits::Rows) => i.saturating_sub(*k as usize),
+            (FrameBound::Following(k), FrameUnits::Rows) => n.min(i.saturating_add(*k as usize)),
+            (bound, _) => self.range_start(bound, ascending, i)?,
+        };
+        let end = match (&frame.end, frame.units) {
+            (FrameBound::UnboundedFollowing, _) => n,
+            (FrameBound::CurrentRow, FrameUnits::Rows) => i + 1,
+            (FrameBound::CurrentRow, FrameUnits::Range) => self.peers[i].1,
+            (FrameBound::Preceding(k), FrameUnits::Rows) => (i + 1).saturating_sub(*k as usize),
+            (FrameBound::Following(k), FrameUnits::Rows) => {
+                n.min(i.saturating_add(*k as usize).saturating_add(1))
+            }
+            (bound, _) => self.range_end(bound, ascending, i)?,
+        };
+        Ok((start, end.max(start)))
+    }
+
+    /// The key value an offset RANGE bound reaches from the `i`th row, or
+    /// None when the row's key is NULL (its frame is then its peers)
+    fn range_limit(&self, bound: &FrameBound, ascending: bool, i: usize) -> Result<Option<Value>> {
+        let key = &self.keys[i][0];
+        if key.is_null() {
+            return Ok(None);
+        }
+        let (offset, backward) = match bound {
+            FrameBound::Preceding(k) => (*k, true),
+            FrameBound::Following(k) => (*k, false),
+            _ => return Ok(Some(key.clone())),
+        };
+        // Preceding rows have smaller keys in ascending order, larger in descending
+        let op = if backward == ascending {
+            BinaryOperator::Sub
+        } else {
+            BinaryOperator::Add
+        };
+        // The offset takes the key's type, as arithmetic does not mix integer widths
+        let out_of_range = || Error::ExecutionError("frame offset out of range".to_string());
+        let offset = i64::try_from(offset).map_err(|_| out_of_range())?;
+        let offset = match key {
+            Value::Integer(_) => Value::Integer(i32::try_from(offset).map_err(|_| out_of_range())?),
+            Value::Float(_) => Value::Float(offset as f64),
+            Value::Decimal(_) => Value::Decimal(Decimal::from_i64(offset)),
+            _ => Value::BigInt(offset),
+        };
+        evaluate_binary_op(key, &op, &offset).map(Some)
+    }
+
+    /// Where the key of row `j` lies relative to `limit` in window order
+    fn position(&self, j: usize, limit: &Value, ascending: bool) -> Ordering {
+        let ord = self.keys[j][0].compare(limit).unwrap_or(Ordering::Equal);
+        if ascending {
+            ord
+        } else {
+            ord.reverse()
+        }
+    }
+
+    fn range_start(&self, bound: &FrameBound, ascending: bool, i: usize) -> Result<usize> {
+        let Some(limit) = self.range_limit(bound, ascending, i)? else {
+            return Ok(self.peers[i].0);
+        };
+        let (lo, hi) = self.non_null_keys();
+        Ok(lo
+            + (lo..hi)
+                .take_while(|&j| self.position(j, &limit, ascending) == Ordering::Less)
+                .count())
+    }
+
+    fn range_end(&self, bound: &FrameBound, ascending: bool, i: usize) -> Result<usize> {
+        let Some(limit) = self.range_limit(bound, ascending, i)? else {
+            return Ok(self.peers[i].1);
+        };
+        let (lo, hi) = self.non_null_keys();
+        Ok(lo
+            + (lo..hi)
+                .take_while(|&j| self.position(j, &limit, ascending) != Ordering::Greater)
+                .count())
+    }
+
+    /// Range of rows whose single ORDER BY key is not NULL; NULLs sort
+    /// first in ascending order and last in descending order
+    fn non_null_keys(&self) -> (usize, usize) {
+        let nulls = self.keys.iter().filter(|k| k[0].is_null()).count();
+        if self.keys.first().is_some_and(|k| k[0].is_null()) {
+            (nulls, self.rows.len())
+        } else {
+            (0, self.rows.len() - nulls)
+        }
+    }
+}
+
+/// Whether two key lists are equal, NULL matching NULL
+fn keys_equal(a: &[Value], b: &[Value]) -> bool {
+    a.iter()
+        .zip(b)
+        .all(|(a, b)| a.compare(b) == Some(Ordering::Equal))
+}
+
+/// Computes window functions over its input (blocking)
+///
+/// Output columns are the input columns followed by one column per window
+/// function, named by its SQL text. Rows are returned in the window order
+/// of the first function.
+pub struct WindowOperator {
+    input: BoxedOperator,
+    calls: Vec<WindowCall>,
+    columns: Vec<BoundColumn>,
+    results: std::vec::IntoIter<Tuple>,
+}
+
+impl WindowOperator {
+    /// Create a window computation over an input
+    pub fn new(input: BoxedOperator, functions: Vec<Expr>) -> Result<Self> {
+        let mut columns = input.columns().to_vec();
+        for expr in &functions {
+            binder::bind_expr(expr, input.columns())?;
+            columns.push(BoundColumn::unqualified(expr.to_string()));
+        }
+        let calls = functions
+            .iter()
+            .map(WindowCall::from_expr)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            input,
+            calls,
+            columns,
+            results: Vec::new().into_iter(),
+        })
+    }
+
+    /// Compute one window function for every row, returning the row
+    /// indices in window order along with the values indexed by row
+    fn evaluate(&self, call: &WindowCall, rows: &[Tuple]) -> Result<(Vec<usize>, Vec<Value>)> {
+        let columns = self.input.columns();
+        let eval_all = |exprs: &mut dyn Iterator<Item = &Expr>, row: &Tuple| {
+            exprs
+                .map(|expr| evaluate_expr(expr, row.values(), columns))
+                .collect::<Result<Vec<_>>>()
+        };
+        let mut partition_keys = Vec::with_capacity(rows.len());
+        let mut order_keys = Vec::with_capacity(rows.len());
+        for row in rows {
+            partition_keys.push(eval_all(&mut call.spec.partition_by.iter(), row)?);
+            order_keys.push(eval_all(
+                &mut call.spec.order_by.iter().map(|i| &i.expr),
+                row,
+            )?);
+        }
+
+        // Sort by partition, then by the window's ORDER BY
+        let order_by = &call.spec.order_by;
+        let mut order: Vec<usize> = (0..rows.len()).collect();
+        order.sort_by(|&a, &b| {
+            for (x, y) in partition_keys[a].iter().zip(&partition_keys[b]) {
+                let cmp = x.compare(y).unwrap_or(Ordering::Equal);
+                if cmp != Ordering::Equal {
+                    return cmp;
+                }
+            }
+            for (i, item) in order_by.iter().enumerate() {
+                let cmp = order_keys[a][i]
+                    .compare(&order_keys[b][i])
+                    .unwrap_or(Ordering::Equal);
+                if cmp != Ordering::Equal {
+                    return if item.ascending { cmp } else { cmp.reverse() };
+                }
+            }
+            Ordering::Equal
+        });
+        let sorted_keys: Vec<Vec<Value>> = order.iter().map(|&r| order_keys[r].clone()).collect();
+
+        let mut values = vec![Value::Null; rows.len()];
+        let mut start = 0;
+        for end in 1..=order.len() {
+            if end < order.len()
+                && keys_equal(&partition_keys[order[end]], &partition_keys[order[start]])
+            {
+                continue;
+            }
+            let partition = Partition::new(&order[start..end], &sorted_keys[start..end]);
+            self.evaluate_partition(call, &partition, rows, &mut values)?;
+            start = end;
+        }
+        Ok((order, values))
+    }
+
+    fn evaluate_partition(
+        &self,
+        call: &WindowCall,
+        partition: &Partition,
+        rows: &[Tuple],
+        values: &mut [Value],
+    ) -> Result<()> {
+        let columns = self.input.columns();
+        let n = partition.rows.len();
+        match &call.kind {
+            WindowKind::RowNumber => {
+                for (i, &row) in partition.rows.iter().enumerate() {
+                    values[row] = Value::BigInt(i as i64 + 1);
+                }
+            }
+            WindowKind::Rank | WindowKind::DenseRank => {
+                let mut dense = 0;
+                for (i, &row) in partition.rows.iter().enumerate() {
+                    let (peer_start, _) = partition.peers[i];
+                    if peer_start == i {
+                        dense += 1;
+                    }
+                    values[row] = match call.kind {
+                        WindowKind::Rank => Value::BigInt(peer_start as i64 + 1),
+                        _ => Value::BigInt(dense),
+                    };
+                }
+            }
+            WindowKind::Offset {
+                arg,
+                offset,
+                default,
+                forward,
+            } => {
+                for (i, &row) in partition.rows.iter().enumerate() {
+                    let current = rows[row].values();
+                    let offset = match offset {
+                        Some(expr) => match evaluate_expr(expr, current, columns)? {
+                            Value::Null => {
+                                values[row] = Value::Null;
+                                continue;
+                            }
+                            Value::Integer(k) => k as i64,
+                            Value::BigInt(k) => k,
+                            other => {
+                                return Err(Error::TypeMismatch {
+                                    from: other.type_name().to_string(),
+                                    to: "INTEGER".to_string(),
+                                })
+                            }
+                        },
+                        None => 1,
+                    };
+                    let target = if *forward {
+                        (i as i64).checked_add(offset)
+                    } else {
+                        (i as i64).checked_sub(offset)
+                    };
+                    values[row] = match target.filter(|&t| t >= 0 && (t as usize) < n) {
+                        Some(t) => {
+                            let other = rows[partition.rows[t as usize]].values();
+                            evaluate_expr(arg, other, columns)?
+                        }
+                        None => match default {
+                            Some(expr) => evaluate_expr(expr, current, columns)?,
+                            None => Value::Null,
+                        },
+                    };
+                }
+            }
+            WindowKind::Aggregate(agg) => {
+                let frame = call.frame();
+                let ascending = call.spec.order_by.first().is_none_or(|item| item.ascending);
+                let arg_value = |row: usize| -> Result<Option<Value>> {
+                    match &agg.arg {
+                        Some(arg) => Ok(Some(evaluate_expr(arg, rows[row].values(), columns)?)),
+                        None => Ok(None),
+                    }
+                };
+
+                // Frames anchored at the partition start only grow, so one
+                // running accumulator serves every row
+                let mut running = Accumulator::default();
+                let mut accumulated = 0;
+                for (i, &row) in partition.rows.iter().enumerate() {
+                    let (frame_start, frame_end) = partition.frame_bounds(&frame, ascending, i)?;
+                    values[row] = if frame.start == FrameBound::UnboundedPreceding {
+                        while accumulated < frame_end {
+                            running.update(agg, arg_value(partition.rows[accumulated])?)?;
+                            accumulated += 1;
+                        }
+                        running.finish(agg)?
+                    } else {
+                        let mut acc = Accumulator::default();
+                        for &r in &partition.rows[frame_start..frame_end] {
+                            acc.update(agg, arg_value(r)?)?;
+                        }
+                        acc.finish(agg)?
+                    };
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Operator for WindowOperator {
+    fn open(&mut self) -> Result<()> {
+        let rows = collect(self.input.as_mut())?;
+
+        let mut order: Vec<usize> = (0..rows.len()).collect();
+        let mut results = Vec::with_capacity(self.calls.len());
+        for (i, call) in self.calls.iter().enumerate() {
+            let (call_order, values) = self.evaluate(call, &rows)?;
+            if i == 0 {
+                order = call_order;
+            }
+            results.push(values);
+        }
+
+        let output = order
+            .into_iter()
+            .map(|r| {
+                let mut values = rows[r].values().to_vec();
+                values.extend(results.iter().map(|result| result[r].clone()));
+                Tuple::new(values)
+            })
+            .collect::<Vec<_>>();
+        self.results = output.into_iter();
+        Ok(())
+    }
+
+    fn next(&mut self) -> Result<Option<Tuple>> {
+        Ok(self.results.next())
+    }
+
+    fn close(&mut self) -> Result<()> {
+        self.results = Vec::new().into_iter();
+        Ok(())
+    }
+
+    fn columns(&self) -> &[BoundColumn] {
+        &self.columns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::ast::{ColumnRef, Literal};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn values(rows: &[i32]) -> BoxedOperator {
+        Box::new(ValuesOperator::new(
+            vec![BoundColumn::unqualified("n")],
+            rows.iter()
+                .map(|&n| Tuple::new(vec![Value::Integer(n)]))
+                .collect(),
+        ))
+    }
+
+    /// Rows of a single column `n` read from `table`
+    fn table(table: &str, rows: &[i32]) -> BoxedOperator {
+        Box::new(ValuesOperator::new(
+            vec![BoundColumn::qualified(table, "n")],
+            rows.iter()
+                .map(|&n| Tuple::new(vec![Value::Integer(n)]))
+                .collect(),
+        ))
+    }
+
+    fn column(name: &str) -> Expr {
+        Expr::Column(ColumnRef::from(name.to_string()))
+    }
+
+    fn qualified(table: &str, name: &str) -> Expr {
+        Expr::Column(ColumnRef {
+            table: Some(table.to_string()),
+            column: name.to_string(),
+        })
+    }
+
+    /// Counts how many rows are pulled from its input
+    struct Counting {
+        input: BoxedOperator,
+        pulled: Rc<Cell<usize>>,
+    }
+
+    impl Operator for Counting {
+        fn open(&mut self) -> Result<()> {
+            self.input.open()
+        }
+
+        fn next(&mut self) -> Result<Option<Tuple>> {
+            self.pulled.set(self.pulled.get() + 1);
+            self.input.next()
+        }
+
+        fn close(&mut self) -> Result<()> {
+            self.input.close()
+        }
+
+        fn columns(&self) -> &[BoundColumn] {
+            self.input.columns()
+        }
+    }
+
+    #[test]
+    fn test_limit_stops_pulling() {
+        let pulled = Rc::new(Cell::new(0));
+        let input = Box::new(Counting {
+            input: values(&(0..1000).collect::<Vec<_>>()),
+            pulled: pulled.clone(),
+        });
+
+        let mut limit = LimitOperator::new(input, Some(10), 5);
+        let rows = collect(&mut limit).unwrap();
+
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows[0].get(0), Some(&Value::Integer(5)));
+        assert_eq!(pulled.get(), 15);
+    }
+
+    #[test]
+    fn test_filter_project_sort() {
+        let filter = FilterOperator::new(
+            values(&[3, 8, 1, 6, 4]),
+            Expr::BinaryOp {
+                left: Box::new(column("n")),
+                op: BinaryOperator::Gt,
+                right: Box::new(Expr::Literal(Literal::Integer(2))),
+            },
+        )
+        .unwrap();
+        let project = ProjectOperator::new(
+            Box::new(filter),
+            vec![SelectItem::Expr {
+                expr: Expr::BinaryOp {
+                    left: Box::new(column("n")),
+                    op: BinaryOperator::Mul,
+                    right: Box::new(Expr::Literal(Literal::Integer(10))),
+                },
+                alias: Some("m".to_string()),
+            }],
+        )
+        .unwrap();
+        let mut sort = SortOperator::new(
+            Box::new(project),
+            vec![OrderByItem {
+                expr: column("m"),
+                ascending: false,
+            }],
+        )
+        .unwrap();
+
+        assert_eq!(sort.columns(), &[BoundColumn::unqualified("m")]);
+        let rows = collect(&mut sort).unwrap();
+        let result: Vec<_> = rows.iter().map(|r| r.get(0).cloned().unwrap()).collect();
+        assert_eq!(
+            result,
+            vec![
+                Value::Integer(80),
+                Value::Integer(60),
+                Value::Integer(40),
+                Value::Integer(30)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_distinct_then_sort_by_position() {
+        let distinct = DistinctOperator::new(values(&[3, 1, 3, 2, 1]));
+        let mut sort = SortOperator::new(
+            Box::new(distinct),
+            vec![OrderByItem {
+                expr: Expr::Literal(Literal::Integer(1)),
+                ascending: true,
+            }],
+        )
+        .unwrap();
+
+        let rows = collect(&mut sort).unwrap();
+        let result: Vec<_> = rows.iter().map(|r| r.get(0).cloned().unwrap()).collect();
+        assert_eq!(
+            result,
+            vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)]
+        );
+
+        let out_of_range = OrderByItem {
+            expr: Expr::Literal(Literal::Integer(2)),
+            ascending: true,
+        };
+        assert!(SortOperator::new(values(&[1]), vec![out_of_range]).is_err());
+    }
+
+    #[test]
+    fn test_reopen_restarts() {
+        let mut limit = LimitOperator::new(values(&[1, 2, 3]), Some(2), 0);
+        assert_eq!(collect(&mut limit).unwrap().len(), 2);
+        assert_eq!(collect(&mut limit).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_full_join_pads_both_sides() {
+        let condition = Expr::BinaryOp {
+            left: Box::new(qualified("a", "n")),
+            op: BinaryOperator::Eq,
+            right: Box::new(qualified("b", "n")),
+        };
+        let mut join = NestedLoopJoinOperator::new(
+            table("a", &[1, 2]),
+            table("b", &[2, 3]),
+            JoinType::Full,
+            Some(condition),
+        )
+        .unwrap();
+        let rows: Vec<Vec<Value>> = collect(&mut join)
+            .unwrap()
+            .into_iter()
+            .map(Tuple::into_values)
+            .collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                vec![Value::Integer(1), Value::Null],
+                vec![Value::Integer(2), Value::Integer(2)],
+                vec![Value::Null, Value::Integer(3)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hash_join_swapped_keys() {
+        // Keys written as right = left still resolve against the right sides
+        let mut join = HashJoinOperator::new(
+            table("a", &[1, 2, 2]),
+            table("b", &[2, 3]),
+            qualified("b", "n"),
+            qualified("a", "n"),
+        )
+        .unwrap();
+        let rows = collect(&mut join).unwrap();
+
+        assert_eq!(
+            join.columns(),
+            &[
+                BoundColumn::qualified("a", "n"),
+                BoundColumn::qualified("b", "n")
+            ]
+        );
+        assert_eq!(rows.len(), 2);
+        assert!(rows
+            .iter()
+            .all(|row| row.values() == [Value::Integer(2), Value::Integer(2)]));
+    }
+
+    #[test]
+    fn test_ambiguous_reference_rejected() {
+        let join =
+            NestedLoopJoinOperator::new(table("a", &[1]), table("b", &[1]), JoinType::Inner, None)
+                .unwrap();
+
+        let result = FilterOperator::new(Box::new(join), column("n"));
+        assert!(matches!(result, Err(Error::AmbiguousColumn(_))));
+    }
+
+    #[test]
+    fn test_qualified_wildcard() {
+        let join = NestedLoopJoinOperator::new(
+            table("a", &[1, 2]),
+            table("b", &[7]),
+            JoinType::Inner,
+            None,
+        )
+        .unwrap();
+        let mut project = ProjectOperator::new(
+            Box::new(join),
+            vec![SelectItem::QualifiedWildcard("b".to_string())],
+        )
+        .unwrap();
+
+        assert_eq!(project.columns(), &[BoundColumn::qualified("b", "n")]);
+        let rows = collect(&mut project).unwrap();
+        assert!(rows.iter().all(|row| row.values() == [Value::Integer(7)]));
+    }
+}
@@ -1,5 +1,5 @@
 use crate::executor::LogicalPlan;
-use crate::sql::ast::{BinaryOperator, Expr};
+use crate::sql::ast::{BinaryOperator, ColumnRef, Expr, Literal};
 use crate::storage::Table;
 use std::collections::HashMap;
 
@@ -24,11 +24,12 @@ impl<'a> HeuristicOptimizer<'a> {
                 // Try to optimize Filter(Scan) into IndexScan
                 if let LogicalPlan::Scan {
                     ref table_name,
+                    ref alias,
                     ref projection,
                 } = optimized_input
                 {
                     if let Some(index_scan) =
-                        self.try_optimize_index_scan(table_name, projection, &predicate)
+                        self.try_optimize_index_scan(table_name, alias, projection, &predicate)
                     {
                         return index_scan;
                     }
@@ -54,6 +55,65 @@ impl<'a> HeuristicOptimizer<'a> {
                 join_type,
                 condition,
             },
+            LogicalPlan::HashJoin {
+                left,
+                right,
+                left_key,
+                right_key,
+            } => LogicalPlan::HashJoin {
+                left: Box::new(self.optimize(*left)),
+                right: Box::new(self.optimize(*right)),
+                left_key,
+                right_key,
+            },
+            LogicalPlan::SemiJoin {
+                left,
+                right,
+                left_keys,
+                anti,
+                null_aware,
+            } => LogicalPlan::SemiJoin {
+                left: Box::new(self.optimize(*left)),
+                right: Box::new(self.optimize(*right)),
+                left_keys,
+                anti,
+                null_aware,
+            },
+            LogicalPlan::Derived {
+                input,
+                alias,
+                columns,
+            } => LogicalPlan::Derived {
+                input: Box::new(self.optimize(*input)),
+                alias,
+                columns,
+            },
+            LogicalPlan::RecursiveCte {
+                anchor,
+                recursive,
+                work_table,
+                all,
+                alias,
+                columns,
+            } => LogicalPlan::RecursiveCte {
+                anchor: Box::new(self.optimize(*anchor)),
+                recursive: Box::new(self.optimize(*recursive)),
+                work_table,
+                all,
+                alias,
+                columns,
+            },
+            LogicalPlan::SetOperation {
+                left,
+                right,
+                op,
+                all,
+            } => LogicalPlan::SetOperation {
+                left: Box::new(self.optimize(*left)),
+                right: Box::new(self.optimize(*right)),
+                op,
+                all,
+            },
             LogicalPlan::Sort { input, order_by } => LogicalPlan::Sort {
                 input: Box::new(self.optimize(*input)),
                 order_by,
@@ -76,6 +136,30 @@ impl<'a> HeuristicOptimizer<'a> {
                 group_by,
                 aggregates,
             },
+            LogicalPlan::Distinct { input } => LogicalPlan::Distinct {
+                input: Box::new(self.optimize(*input)),
+            },
+            LogicalPlan::DropColumns { input, count } => LogicalPlan::DropColumns {
+                input: Box::new(self.optimize(*input)),
+                count,
+            },
+            LogicalPlan::Window { input, functions } => LogicalPlan::Window {
+                input: Box::new(self.optimize(*input)),
+                functions,
+            },
+            LogicalPlan::Insert {
+                table_name,
+                columns,
+                values,
+                query,
+                returning,
+            } => LogicalPlan::Insert {
+                table_name,
+                columns,
+                values,
+                query: query.map(|query| Box::new(self.optimize(*query))),
+                returning,
+            },
             // Other plans are returned as-is
             _ => plan,
         }
@@ -85,10 +169,15 @@ impl<'a> HeuristicOptimizer<'a> {
     fn try_optimize_index_scan(
         &self,
         table_name: &str,
+        alias: &Option<String>,
         _projection: &Option<Vec<String>>,
         predicate: &Expr,
     ) -> Option<LogicalPlan> {
         let table = self.tables.get(table_name)?;
+        let qualifier = alias.as_deref().unwrap_or(table_name);
+        // A column qualified by another table cannot use this table's index
+        let same_table =
+            |col_ref: &ColumnRef| col_ref.table.as_deref().is_none_or(|t| t == qualifier);
 
         // Pattern: column op value
         if let Expr::BinaryOp { left, op, right } = predicate {
@@ -100,11 +189,20 @@ impl<'a> HeuristicOptimizer<'a> {
                     | BinaryOperator::Lt
                     | BinaryOperator::Lte
             ) {
+                // A comparison with NULL never matches, so there is nothing to probe
+                let is_null = |expr: &Expr| matches!(expr, Expr::Literal(Literal::Null));
+                if is_null(left) || is_null(right) {
+                    return None;
+                }
                 // Check if left is column and right is literal (or vice versa)
                 if let (Expr::Column(col_ref), Expr::Literal(lit)) = (&**left, &**right) {
+                    if !same_table(col_ref) {
+                        return None;
+                    }
                     if let Some(index_name) = table.get_index_for_column(&col_ref.column) {
                         return Some(LogicalPlan::IndexScan {
                             table_name: table_name.to_string(),
+                            alias: alias.clone(),
                             index_name,
                             columns: vec![col_ref.column.clone()],
                             op: *op,
@@ -112,6 +210,9 @@ impl<'a> HeuristicOptimizer<'a> {
                         });
                     }
                 } else if let (Expr::Literal(lit), Expr::Column(col_ref)) = (&**left, &**right) {
+                    if !same_table(col_ref) {
+                        return None;
+                    }
                     // For range operators, we might need to flip the operator if literal is on the left
                     // But for Eq it's symmetric. For others, let's keep it simple for now or flip.
                     // For now, only Eq is supported on right-side Column.
@@ -119,6 +220,7 @@ impl<'a> HeuristicOptimizer<'a> {
                         if let Some(index_name) = table.get_index_for_column(&col_ref.column) {
                             return Some(LogicalPlan::IndexScan {
                                 table_name: table_name.to_string(),
+                                alias: alias.clone(),
                                 index_name,
                                 columns: vec![col_ref.column.clone()],
                                 op: *op,
@@ -137,7 +239,6 @@ impl<'a> HeuristicOptimizer<'a> {
 mod tests {
     use super::*;
     use crate::catalog::{Column, DataType, Schema, TableDef};
-    use crate::sql::ast::{ColumnRef, Literal};
     use crate::storage::Table;
     use std::sync::Arc;
 
@@ -159,7 +260,7 @@ mod tests {
 
         // Create an index on 'id'
         table
-            .create_index("id_idx".to_string(), vec!["id".to_string()])
+            .create_index("id_idx".to_string(), vec!["id".to_string()], false)
             .unwrap();
 
         table
@@ -176,6 +277,7 @@ mod tests {
         // Original plan: SELECT * FROM test WHERE id = 1
         let scan = LogicalPlan::Scan {
             table_name: "test".to_string(),
+            alias: None,
             projection: None,
         };
         let filter = LogicalPlan::Filter {
@@ -195,6 +297,7 @@ mod tests {
         // Should be transformed to IndexScan
         if let LogicalPlan::IndexScan {
             table_name,
+            alias: _,
             index_name,
             columns,
             op,
@@ -221,6 +324,7 @@ mod tests {
         // No index on 'name'
         let scan = LogicalPlan::Scan {
             table_name: "test".to_string(),
+            alias: None,
             projection: None,
         };
         let filter = LogicalPlan::Filter {
@@ -255,6 +359,7 @@ mod tests {
         // Original plan: SELECT * FROM test WHERE id > 10
         let scan = LogicalPlan::Scan {
             table_name: "test".to_string(),
+            alias: None,
             projection: None,
         };
         let filter = LogicalPlan::Filter {
@@ -274,6 +379,7 @@ mod tests {
         // Should be transformed to IndexScan
         if let LogicalPlan::IndexScan {
             table_name,
+            alias: _,
             index_name,
             columns,
             op,
@@ -2,7 +2,10 @@
 //!
 //! This module converts parsed SQL AST into executable plans.
 
+use super::binder::{self, BoundColumn};
+use super::eval::expr_to_string;
 use crate::catalog::Catalog;
+use crate::error::Error;
 use crate::sql::ast::*;
 
 /// Logical plan node
@@ -11,11 +14,38 @@ pub enum LogicalPlan {
     /// Scan a table
     Scan {
         table_name: String,
+        /// Alias that qualifies the output columns instead of the table name
+        alias: Option<String>,
         projection: Option<Vec<String>>,
     },
+    /// A derived table: a subquery in FROM whose output columns are
+    /// named `columns` and qualified by `alias`
+    Derived {
+        input: Box<LogicalPlan>,
+        alias: String,
+        columns: Vec<String>,
+    },
+    /// A recursive CTE: `anchor`, then `recursive` run over the rows added
+    /// by the previous iteration (read as `work_table`) until it adds none
+    RecursiveCte {
+        anchor: Box<LogicalPlan>,
+        recursive: Box<LogicalPlan>,
+        work_table: String,
+        /// UNION ALL: keep duplicate rows
+        all: bool,
+        alias: String,
+        columns: Vec<String>,
+    },
+    /// The rows added by the previous iteration of a recursive CTE
+    WorkTable {
+        name: String,
+        alias: String,
+        columns: Vec<String>,
+    },
     /// Index Scan
     IndexScan {
         table_name: String,
+        alias: Option<String>,
         index_name: String,
         columns: Vec<String>, // Index columns
         op: BinaryOperator,
@@ -45,7 +75,35 @@ pub enum LogicalPlan {
         left_key: Expr,
         right_key: Expr,
     },
-    /// Sort rows
+    /// Keep the left rows with (or, if `anti`, without) a match in the
+    /// right input, whose output columns are the keys matched against `left_keys`
+    SemiJoin {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        left_keys: Vec<Expr>,
+        anti: bool,
+        /// NOT IN semantics: a NULL on either side means no match is known
+        null_aware: bool,
+    },
+    /// Combine the rows of two queries with UNION, INTERSECT or EXCEPT
+    SetOperation {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        op: SetOperator,
+        /// Keep duplicate rows
+        all: bool,
+    },
+    /// The current row of the enclosing query, read by a correlated subquery
+    Parameters { columns: Vec<String> },
+    /// Remove duplicate rows
+    Distinct { input: Box<LogicalPlan> },
+    /// Drop the last `count` columns of the input, such as sort keys that
+    /// are not in the select list
+    DropColumns {
+        input: Box<LogicalPlan>,
+        count: usize,
+    },
+    /// Sort rows; an integer literal key is the position of an input column
     Sort {
         input: Box<LogicalPlan>,
         order_by: Vec<OrderByItem>,
@@ -62,27 +120,38 @@ pub enum LogicalPlan {
         group_by: Vec<Expr>,
         aggregates: Vec<Expr>,
     },
-    /// Insert into table
+    /// Compute window functions over the input rows, appending one output
+    /// column per call, named after the call
+    Window {
+        input: Box<LogicalPlan>,
+        functions: Vec<Expr>,
+    },
+    /// Insert into table, the rows of `values` or of the `query` plan
     Insert {
         table_name: String,
         columns: Option<Vec<String>>,
         values: Vec<Vec<Expr>>,
+        query: Option<Box<LogicalPlan>>,
+        returning: Vec<SelectItem>,
     },
     /// Update table
     Update {
         table_name: String,
         assignments: Vec<Assignment>,
         predicate: Option<Expr>,
+        returning: Vec<SelectItem>,
     },
     /// Delete from table
     Delete {
         table_name: String,
         predicate: Option<Expr>,
+        returning: Vec<SelectItem>,
     },
     /// Create table
     CreateTable {
         table_name: String,
         columns: Vec<ColumnDef>,
+        constraints: Vec<TableConstraint>,
         if_not_exists: bool,
     },
     /// Drop table
@@ -101,6 +170,21 @@ pub enum LogicalPlan {
         unique: bool,
         if_not_exists: bool,
     },
+    /// Drop index
+    DropIndex { index_name: String, if_exists: bool },
+    /// Alter table
+    AlterTable {
+        table_name: String,
+        operation: AlterTableOperation,
+    },
+    /// Create sequence
+    CreateSequence {
+        name: String,
+        options: SequenceOptions,
+        if_not_exists: bool,
+    },
+    /// Drop sequence
+    DropSequence { name: String, if_exists: bool },
     /// Analyze table for statistics
     Analyze { table_name: String },
 }
@@ -119,13 +203,30 @@ impl<'a> Planner<'a> {
     /// Plan a statement
     pub fn plan(&self, stmt: Statement) -> LogicalPlan {
         match stmt {
-            Statement::Select(select) => self.plan_select(select),
+            Statement::Select(select) => self.plan_select(select, Vec::new()),
             Statement::Insert(insert) => self.plan_insert(insert),
             Statement::Update(update) => self.plan_update(update),
             Statement::Delete(delete) => self.plan_delete(delete),
             Statement::CreateTable(create) => self.plan_create_table(create),
             Statement::DropTable(drop) => self.plan_drop_table(drop),
             Statement::CreateIndex(create) => self.plan_create_index(create),
+            Statement::DropIndex(drop) => LogicalPlan::DropIndex {
+                index_name: drop.index_name,
+                if_exists: drop.if_exists,
+            },
+            Statement::AlterTable(alter) => LogicalPlan::AlterTable {
+                table_name: alter.table_name,
+                operation: alter.operation,
+            },
+            Statement::CreateSequence(create) => LogicalPlan::CreateSequence {
+                name: create.name,
+                options: create.options,
+                if_not_exists: create.if_not_exists,
+            },
+            Statement::DropSequence(drop) => LogicalPlan::DropSequence {
+                name: drop.name,
+                if_exists: drop.if_exists,
+            },
             Statement::BeginTransaction => LogicalPlan::BeginTransaction,
             Statement::Commit => LogicalPlan::Commit,
             Statement::Rollback => LogicalPlan::Rollback,
@@ -133,22 +234,35 @@ impl<'a> Planner<'a> {
         }
     }
 
-    fn plan_select(&self, select: SelectStatement) -> LogicalPlan {
+    /// Plan a subquery whose references to the enclosing query have been
+    /// renamed to `parameters`, the columns of a [`LogicalPlan::Parameters`] row
+    pub fn plan_subquery(&self, select: SelectStatement, parameters: Vec<String>) -> LogicalPlan {
+        self.plan_select(select, parameters)
+    }
+
+    fn plan_select(&self, mut select: SelectStatement, parameters: Vec<String>) -> LogicalPlan {
+        if let Some(set_op) = select.set_operation.take() {
+            let plan = LogicalPlan::SetOperation {
+                left: Box::new(self.plan_select(set_op.left, parameters.clone())),
+                right: Box::new(self.plan_select(set_op.right, parameters)),
+                op: set_op.op,
+                all: set_op.all,
+            };
+            return self.plan_order_and_limit(plan, select.order_by, select.limit, select.offset);
+        }
+
+        // Columns visible to the WHERE clause, for decorrelating subqueries
+        let mut scope = self.from_columns(&select.from);
+        scope.extend(parameters.iter().map(BoundColumn::unqualified));
+
         // Start with table scan(s)
         let mut plan = if let Some(from) = select.from {
             // Base table scan
-            let mut left = LogicalPlan::Scan {
-                table_name: from.table.name.clone(),
-                projection: None,
-            };
+            let mut left = self.plan_table_ref(from.table);
 
-            // Apply Joins
             // Apply Joins
             for join in from.joins {
-                let right = LogicalPlan::Scan {
-                    table_name: join.table.name.clone(),
-                    projection: None,
-                };
+                let right = self.plan_table_ref(join.table);
 
                 // Check for equality condition to use HashJoin
                 let mut is_hash_join = false;
@@ -161,7 +275,7 @@ impl<'a> Planner<'a> {
                     right: r,
                 }) = &join.condition
                 {
-                    if matches!(op, BinaryOperator::Eq) {
+                    if matches!(op, BinaryOperator::Eq) && join.join_type == JoinType::Inner {
                         // Optimistically assume left expr is for left table and right expr is for right table
                         // In a real planner, we should validate this against schemas
                         is_hash_join = true;
@@ -187,25 +301,66 @@ impl<'a> Planner<'a> {
                 }
             }
             left
+        } else if !parameters.is_empty() {
+            // A correlated subquery without FROM reads only the outer row
+            LogicalPlan::Parameters {
+                columns: parameters.clone(),
+            }
         } else {
             // SELECT without FROM (e.g., SELECT 1 + 1)
             LogicalPlan::Scan {
                 table_name: String::new(),
+                alias: None,
                 projection: None,
             }
         };
 
+        // The outer row is joined to every row of a correlated subquery
+        if !parameters.is_empty() && !matches!(plan, LogicalPlan::Parameters { .. }) {
+            plan = LogicalPlan::Join {
+                left: Box::new(plan),
+                right: Box::new(LogicalPlan::Parameters {
+                    columns: parameters,
+                }),
+                join_type: JoinType::Cross,
+                condition: None,
+            };
+        }
+
+        // EXISTS and IN subqueries in WHERE become semi joins where possible
+        let mut semi_joins = Vec::new();
+        let where_clause = select.where_clause.and_then(|predicate| {
+            let mut rest = Vec::new();
+            for conjunct in split_conjuncts(predicate) {
+                match self.decorrelate(&conjunct, &scope) {
+                    Some(semi_join) => semi_joins.push(semi_join),
+                    None => rest.push(conjunct),
+                }
+            }
+            join_conjuncts(rest)
+        });
+
         // Apply WHERE filter
-        if let Some(predicate) = select.where_clause {
+        if let Some(predicate) = where_clause {
             // Optimization: If simple equality on indexed column, use IndexScan
             let mut optimized = false;
-            if let LogicalPlan::Scan { table_name, .. } = &plan {
+            if let LogicalPlan::Scan {
+                table_name, alias, ..
+            } = &plan
+            {
+                let qualifier = alias.as_ref().unwrap_or(table_name);
                 if !table_name.is_empty() {
                     if let Expr::BinaryOp { left, op, right } = &predicate {
                         if matches!(op, BinaryOperator::Eq) {
                             // Check if left is column and right is value
                             if let Expr::Column(col_ref) = &**left {
-                                if let Expr::Literal(_) = &**right {
+                                // A qualifier for another table is left for the binder to reject
+                                let same_table =
+                                    col_ref.table.as_ref().is_none_or(|t| t == qualifier);
+                                // `col = NULL` is never true, so it must not become an index probe
+                                let is_value =
+                                    matches!(&**right, Expr::Literal(lit) if *lit != Literal::Null);
+                                if is_value && same_table {
                                     let col_name = &col_ref.column;
                                     // Check indexes
                                     let indexes = self.catalog.get_table_indexes(table_name);
@@ -214,9 +369,10 @@ impl<'a> Planner<'a> {
                                             // Found index!
                                             plan = LogicalPlan::IndexScan {
                                                 table_name: table_name.clone(),
+                                                alias: alias.clone(),
                                                 index_name: idx.name.clone(),
                                                 columns: idx.columns.clone(),
-                                                op: op.clone(),
+                                                op: *op,
                                                 value: *right.clone(),
                                             };
                                             optimized = true;
@@ -238,10 +394,69 @@ impl<'a> Planner<'a> {
             }
         }
 
-        // Apply GROUP BY
-        if !select.group_by.is_empty() {
-            // Extract aggregate expressions from select list
-            let aggregates = self.extract_aggregates(&select.columns);
+        for (left_keys, right, anti, null_aware) in semi_joins {
+            plan = LogicalPlan::SemiJoin {
+                left: Box::new(plan),
+                right: Box::new(right),
+                left_keys,
+                anti,
+                null_aware,
+            };
+        }
+
+        // Sort keys that are not output columns are computed as hidden
+        // columns after the select list
+        let mut hidden = Vec::new();
+        let mut order_by = select.order_by;
+        for item in &mut order_by {
+            if !is_output_position(&item.expr) {
+                let name = format!("$order{}", hidden.len());
+                hidden.push(std::mem::replace(
+                    &mut item.expr,
+                    Expr::Column(ColumnRef::from(name)),
+                ));
+            }
+        }
+
+        // Apply GROUP BY (or an implicit single group when aggregates are used)
+        let mut columns = select.columns;
+        let mut aggregates = self.extract_aggregates(&columns);
+        if let Some(having) = &select.having {
+            self.find_aggregates(having, &mut aggregates);
+        }
+        for expr in &hidden {
+            self.find_aggregates(expr, &mut aggregates);
+        }
+
+        if !select.group_by.is_empty() || !aggregates.is_empty() {
+            // The select list and HAVING read group keys and aggregate results
+            // from the output columns of the Aggregate node
+            columns = columns
+                .into_iter()
+                .map(|item| match item {
+                    SelectItem::Expr { expr, alias } => {
+                        let rewritten = self.rewrite_aggregate_refs(&expr, &select.group_by);
+                        let alias = if rewritten != expr {
+                            alias.or_else(|| Some(expr_to_string(&expr)))
+                        } else {
+                            alias
+                        };
+                        SelectItem::Expr {
+                            expr: rewritten,
+                            alias,
+                        }
+                    }
+                    other => other,
+                })
+                .collect();
+            let having = select
+                .having
+                .map(|having| self.rewrite_aggregate_refs(&having, &select.group_by));
+            hidden = hidden
+                .iter()
+                .map(|expr| self.rewrite_aggregate_refs(expr, &select.group_by))
+                .collect();
+
             plan = LogicalPlan::Aggregate {
                 input: Box::new(plan),
                 group_by: select.group_by,
@@ -249,7 +464,7 @@ impl<'a> Planner<'a> {
             };
 
             // Apply HAVING
-            if let Some(having) = select.having {
+            if let Some(having) = having {
                 plan = LogicalPlan::Filter {
                     input: Box::new(plan),
                     predicate: having,
@@ -257,26 +472,79 @@ impl<'a> Planner<'a> {
             }
         }
 
+        // Window functions are computed over the grouped rows, and the
+        // select list reads their results as columns of the Window node
+        let mut functions = Vec::new();
+        for item in &mut columns {
+            if let SelectItem::Expr { expr, alias } = item {
+                let original = expr.clone();
+                extract_window_functions(expr, &mut functions);
+                if *expr != original && alias.is_none() {
+                    *alias = Some(expr_to_string(&original));
+                }
+            }
+        }
+        if !functions.is_empty() {
+            plan = LogicalPlan::Window {
+                input: Box::new(plan),
+                functions,
+            };
+        }
+
         // Apply projection
+        let hidden_count = hidden.len();
+        columns.extend(
+            hidden
+                .into_iter()
+                .enumerate()
+                .map(|(i, expr)| SelectItem::Expr {
+                    expr,
+                    alias: Some(format!("$order{}", i)),
+                }),
+        );
         plan = LogicalPlan::Project {
             input: Box::new(plan),
-            expressions: select.columns,
+            expressions: columns,
         };
 
+        if select.distinct {
+            plan = LogicalPlan::Distinct {
+                input: Box::new(plan),
+            };
+        }
+
+        plan = self.plan_order_and_limit(plan, order_by, select.limit, select.offset);
+        if hidden_count > 0 {
+            plan = LogicalPlan::DropColumns {
+                input: Box::new(plan),
+                count: hidden_count,
+            };
+        }
+        plan
+    }
+
+    /// Sort and limit the output rows of a query
+    fn plan_order_and_limit(
+        &self,
+        mut plan: LogicalPlan,
+        order_by: Vec<OrderByItem>,
+        limit: Option<Expr>,
+        offset: Option<Expr>,
+    ) -> LogicalPlan {
         // Apply ORDER BY
-        if !select.order_by.is_empty() {
+        if !order_by.is_empty() {
             plan = LogicalPlan::Sort {
                 input: Box::new(plan),
-                order_by: select.order_by,
+                order_by,
             };
         }
 
         // Apply LIMIT/OFFSET
-        if select.limit.is_some() || select.offset.is_some() {
+        if limit.is_some() || offset.is_some() {
             plan = LogicalPlan::Limit {
                 input: Box::new(plan),
-                limit: select.limit,
-                offset: select.offset,
+                limit,
+                offset,
             };
         }
 
@@ -288,6 +556,10 @@ impl<'a> Planner<'a> {
             table_name: insert.table_name,
             columns: insert.columns,
             values: insert.values,
+            query: insert
+                .query
+                .map(|query| Box::new(self.plan_select(*query, Vec::new()))),
+            returning: insert.returning,
         }
     }
 
@@ -296,6 +568,7 @@ impl<'a> Planner<'a> {
             table_name: update.table_name,
             assignments: update.assignments,
             predicate: update.where_clause,
+            returning: update.returning,
         }
     }
 
@@ -303,6 +576,7 @@ impl<'a> Planner<'a> {
         LogicalPlan::Delete {
             table_name: delete.table_name,
             predicate: delete.where_clause,
+            returning: delete.returning,
         }
     }
 
@@ -310,6 +584,7 @@ impl<'a> Planner<'a> {
         LogicalPlan::CreateTable {
             table_name: create.table_name,
             columns: create.columns,
+            constraints: create.constraints,
             if_not_exists: create.if_not_exists,
         }
     }
@@ -321,6 +596,38 @@ impl<'a> Planner<'a> {
         }
     }
 
+    /// Scan a table, or plan the query of a derived table
+    fn plan_table_ref(&self, table: TableRef) -> LogicalPlan {
+        let alias = table.alias.clone().unwrap_or_else(|| table.name.clone());
+        if table.is_work_table() {
+            return LogicalPlan::WorkTable {
+                name: table.name,
+                alias,
+                columns: table.columns,
+            };
+        }
+        match (table.subquery, table.recursive) {
+            (Some(anchor), Some(term)) => LogicalPlan::RecursiveCte {
+                anchor: Box::new(self.plan_select(*anchor, Vec::new())),
+                recursive: Box::new(self.plan_select(*term.query, Vec::new())),
+                work_table: TableRef::work_table_name(&table.name),
+                all: term.all,
+                alias,
+                columns: table.columns,
+            },
+            (Some(subquery), None) => LogicalPlan::Derived {
+                input: Box::new(self.plan_select(*subquery, Vec::new())),
+                alias,
+                columns: table.columns,
+            },
+            (None, _) => LogicalPlan::Scan {
+                table_name: table.name,
+                alias: table.alias,
+                projection: None,
+            },
+        }
+    }
+
     fn plan_create_index(&self, create: CreateIndexStatement) -> LogicalPlan {
         LogicalPlan::CreateIndex {
             index_name: create.index_name,
@@ -331,6 +638,207 @@ impl<'a> Planner<'a> {
         }
     }
 
+    // ========== Subqueries ==========
+
+    /// Columns of the tables in a FROM clause
+    pub fn from_columns(&self, from: &Option<FromClause>) -> Vec<BoundColumn> {
+        let tables = from.iter().flat_map(|from| {
+            std::iter::once(&from.table).chain(from.joins.iter().map(|j| &j.table))
+        });
+        let mut columns = Vec::new();
+        for table in tables {
+            let qualifier = table.alias.as_ref().unwrap_or(&table.name);
+            if table.subquery.is_some() || table.is_work_table() {
+                let names: Vec<&str> = table.columns.iter().map(String::as_str).collect();
+                columns.extend(binder::qualify(qualifier, &names));
+            } else if let Ok(def) = self.catalog.get_table(&table.name) {
+                columns.extend(binder::qualify(qualifier, &def.schema().column_names()));
+            }
+        }
+        columns
+    }
+
+    /// Call `f` on every column reference in a query that is bound by an
+    /// enclosing query rather than by the FROM clause of the query itself
+    /// or of a subquery nested in it
+    pub fn for_each_outer_reference(
+        &self,
+        select: &mut SelectStatement,
+        f: &mut dyn FnMut(&mut ColumnRef),
+    ) {
+        if let Some(set_op) = &mut select.set_operation {
+            self.for_each_outer_reference(&mut set_op.left, f);
+            self.for_each_outer_reference(&mut set_op.right, f);
+        }
+        let local = self.from_columns(&select.from);
+        let mut visit = |expr: &mut Expr| self.visit_outer_references(expr, &local, f);
+
+        for item in &mut select.columns {
+            if let SelectItem::Expr { expr, .. } = item {
+                visit(expr);
+            }
+        }
+        if let Some(from) = &mut select.from {
+            from.joins
+                .iter_mut()
+                .filter_map(|join| join.condition.as_mut())
+                .for_each(&mut visit);
+        }
+        select.where_clause.iter_mut().for_each(&mut visit);
+        select.group_by.iter_mut().for_each(&mut visit);
+        select.having.iter_mut().for_each(&mut visit);
+    }
+
+    fn visit_outer_references(
+        &self,
+        expr: &mut Expr,
+        local: &[BoundColumn],
+        f: &mut dyn FnMut(&mut ColumnRef),
+    ) {
+        let is_outer = |col_ref: &ColumnRef| {
+            matches!(
+                binder::resolve_column(local, col_ref),
+                Err(Error::ColumnNotFound(..))
+            )
+        };
+        let mut outer_only = |col_ref: &mut ColumnRef| {
+            if is_outer(col_ref) {
+                f(col_ref)
+            }
+        };
+        match expr {
+            Expr::Column(col_ref) if col_ref.column != "*" => outer_only(col_ref),
+            Expr::Subquery(query) | Expr::Exists(query) => {
+                self.for_each_outer_reference(query, &mut outer_only)
+            }
+            Expr::InSubquery { expr, subquery, .. } => {
+                self.visit_outer_references(expr, local, &mut outer_only);
+                self.for_each_outer_reference(subquery, &mut outer_only);
+            }
+            _ => binder::for_each_child_mut(expr, &mut |child| {
+                self.visit_outer_references(child, local, &mut outer_only)
+            }),
+        }
+    }
+
+    /// Whether an expression reads a column bound outside `select`
+    fn has_outer_references(&self, expr: &Expr, local: &[BoundColumn]) -> bool {
+        let mut found = false;
+        self.visit_outer_references(&mut expr.clone(), local, &mut |_| found = true);
+        found
+    }
+
+    /// Turn a WHERE conjunct `[NOT] EXISTS (...)` or `x [NOT] IN (SELECT ...)`
+    /// into a semi join, returning the left keys, the plan of the right
+    /// keys, and the anti and null-aware flags
+    ///
+    /// The subquery must be a plain select-project-join whose WHERE clause
+    /// relates it to the outer query only through equalities.
+    fn decorrelate(
+        &self,
+        conjunct: &Expr,
+        outer: &[BoundColumn],
+    ) -> Option<(Vec<Expr>, LogicalPlan, bool, bool)> {
+        let (probe, query, anti, null_aware) = match strip_nesting(conjunct) {
+            Expr::Exists(query) => (None, query, false, false),
+            Expr::UnaryOp {
+                op: UnaryOperator::Not,
+                expr,
+            } => match strip_nesting(expr) {
+                Expr::Exists(query) => (None, query, true, false),
+                _ => return None,
+            },
+            Expr::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => (Some(&**expr), subquery, *negated, *negated),
+            _ => return None,
+        };
+
+        let is_plain = query.set_operation.is_none()
+            && query.group_by.is_empty()
+            && query.having.is_none()
+            && query.limit.is_none()
+            && query.offset.is_none()
+            && self.extract_aggregates(&query.columns).is_empty();
+        if !is_plain {
+            return None;
+        }
+
+        let local = self.from_columns(&query.from);
+        let binds_outer =
+            |expr: &Expr| binder::bind_expr(expr, outer).is_ok() && !contains_subquery(expr);
+        let mut left_keys = Vec::new();
+        let mut right_keys = Vec::new();
+        if let Some(probe) = probe {
+            let item = match query.columns.as_slice() {
+                [SelectItem::Expr { expr, .. }] if !self.has_outer_references(expr, &local) => expr,
+                _ => return None,
+            };
+            if !binds_outer(probe) {
+                return None;
+            }
+            left_keys.push(probe.clone());
+            right_keys.push(item.clone());
+        }
+
+        // Split the subquery's WHERE into correlation keys and local filters
+        let mut local_filters = Vec::new();
+        for conjunct in query
+            .where_clause
+            .clone()
+            .map(split_conjuncts)
+            .unwrap_or_default()
+        {
+            if !self.has_outer_references(&conjunct, &local) {
+                local_filters.push(conjunct);
+                continue;
+            }
+            let Expr::BinaryOp {
+                left,
+                op: BinaryOperator::Eq,
+                right,
+            } = &conjunct
+            else {
+                return None;
+            };
+            let is_inner =
+                |expr: &Expr| !self.has_outer_references(expr, &local) && !contains_subquery(expr);
+            let is_outer =
+                |expr: &Expr| binds_outer(expr) && binder::bind_expr(expr, &local).is_err();
+            let (outer_side, inner_side) = if is_outer(left) && is_inner(right) {
+                (left, right)
+            } else if is_outer(right) && is_inner(left) {
+                (right, left)
+            } else {
+                return None;
+            };
+            left_keys.push((**outer_side).clone());
+            right_keys.push((**inner_side).clone());
+        }
+
+        // Uncorrelated EXISTS is cheaper evaluated once; a correlated NOT IN
+        // would need a null-aware anti join per correlation group
+        if left_keys.is_empty() || (null_aware && left_keys.len() > 1) {
+            return None;
+        }
+
+        let right = self.plan_select(
+            SelectStatement {
+                columns: right_keys
+                    .into_iter()
+                    .map(|expr| SelectItem::Expr { expr, alias: None })
+                    .collect(),
+                from: query.from.clone(),
+                where_clause: join_conjuncts(local_filters),
+                ..Default::default()
+            },
+            Vec::new(),
+        );
+        Some((left_keys, right, anti, null_aware))
+    }
+
     fn extract_aggregates(&self, columns: &[SelectItem]) -> Vec<Expr> {
         let mut aggregates = Vec::new();
         for item in columns {
@@ -343,19 +851,177 @@ impl<'a> Planner<'a> {
 
     fn find_aggregates(&self, expr: &Expr, result: &mut Vec<Expr>) {
         match expr {
-            Expr::Function { name, .. } => {
-                let name_upper = name.to_uppercase();
-                if matches!(name_upper.as_str(), "COUNT" | "SUM" | "AVG" | "MIN" | "MAX") {
-                    result.push(expr.clone());
+            Expr::Function { name, over, .. } => {
+                if over.is_none() && is_aggregate_function(name) {
+                    if !result.contains(expr) {
+                        result.push(expr.clone());
+                    }
+                } else {
+                    binder::for_each_child(expr, &mut |child| self.find_aggregates(child, result));
                 }
             }
             Expr::BinaryOp { left, right, .. } => {
                 self.find_aggregates(left, result);
                 self.find_aggregates(right, result);
             }
+            Expr::UnaryOp { expr, .. }
+            | Expr::Nested(expr)
+            | Expr::IsNull(expr)
+            | Expr::IsNotNull(expr)
+            | Expr::Cast { expr, .. } => self.find_aggregates(expr, result),
             _ => {}
         }
     }
+
+    /// Replace aggregate calls and GROUP BY expressions with references to
+    /// the output columns of the Aggregate node
+    fn rewrite_aggregate_refs(&self, expr: &Expr, group_by: &[Expr]) -> Expr {
+        if group_by.contains(expr) && !matches!(expr, Expr::Column(_)) {
+            return Expr::Column(ColumnRef::from(expr.to_string()));
+        }
+
+        let rewrite = |e: &Expr| Box::new(self.rewrite_aggregate_refs(e, group_by));
+        match expr {
+            Expr::Function { name, over, .. } => {
+                if over.is_none() && is_aggregate_function(name) {
+                    Expr::Column(ColumnRef::from(expr.to_string()))
+                } else {
+                    // Arguments, and the keys of a window function
+                    let mut expr = expr.clone();
+                    binder::for_each_child_mut(&mut expr, &mut |child| {
+                        *child = self.rewrite_aggregate_refs(child, group_by)
+                    });
+                    expr
+                }
+            }
+            Expr::BinaryOp { left, op, right } => Expr::BinaryOp {
+                left: rewrite(left),
+                op: *op,
+                right: rewrite(right),
+            },
+            Expr::UnaryOp { op, expr } => Expr::UnaryOp {
+                op: op.clone(),
+                expr: rewrite(expr),
+            },
+            Expr::Nested(expr) => Expr::Nested(rewrite(expr)),
+            Expr::IsNull(expr) => Expr::IsNull(rewrite(expr)),
+            Expr::IsNotNull(expr) => Expr::IsNotNull(rewrite(expr)),
+            Expr::Cast { expr, data_type } => Expr::Cast {
+                expr: rewrite(expr),
+                data_type: data_type.clone(),
+            },
+            _ => expr.clone(),
+        }
+    }
+}
+
+/// Replace window function calls with references to the output columns of
+/// the Window node, collecting the distinct calls into `functions`
+fn extract_window_functions(expr: &mut Expr, functions: &mut Vec<Expr>) {
+    if let Expr::Function { over: Some(_), .. } = expr {
+        if !functions.contains(expr) {
+            functions.push(expr.clone());
+        }
+        *expr = Expr::Column(ColumnRef::from(expr.to_string()));
+        return;
+    }
+    binder::for_each_child_mut(expr, &mut |child| {
+        extract_window_functions(child, functions)
+    });
+}
+
+/// Split a predicate into the conjuncts of its top-level ANDs
+pub fn split_conjuncts(expr: Expr) -> Vec<Expr> {
+    match expr {
+        Expr::BinaryOp {
+            left,
+            op: BinaryOperator::And,
+            right,
+        } => {
+            let mut conjuncts = split_conjuncts(*left);
+            conjuncts.extend(split_conjuncts(*right));
+            conjuncts
+        }
+        Expr::Nested(inner)
+            if matches!(
+                *inner,
+                Expr::BinaryOp {
+                    op: BinaryOperator::And,
+                    ..
+                }
+            ) =>
+        {
+            split_conjuncts(*inner)
+        }
+        other => vec![other],
+    }
+}
+
+/// AND a list of conjuncts back together
+pub fn join_conjuncts(conjuncts: Vec<Expr>) -> Option<Expr> {
+    conjuncts.into_iter().reduce(|left, right| Expr::BinaryOp {
+        left: Box::new(left),
+        op: BinaryOperator::And,
+        right: Box::new(right),
+    })
+}
+
+/// Remove redundant parentheses
+fn strip_nesting(expr: &Expr) -> &Expr {
+    match expr {
+        Expr::Nested(inner) => strip_nesting(inner),
+        other => other,
+    }
+}
+
+/// Whether an expression contains a subquery
+pub fn contains_subquery(expr: &Expr) -> bool {
+    let mut found = false;
+    binder::visit(expr, &mut |e| {
+        found |= matches!(
+            e,
+            Expr::Subquery(_) | Expr::Exists(_) | Expr::InSubquery { .. }
+        );
+    });
+    found
+}
+
+/// Whether an expression calls a function that reads or advances a sequence
+pub fn contains_sequence_call(expr: &Expr) -> bool {
+    let mut found = false;
+    binder::visit(expr, &mut |e| {
+        found |= matches!(e, Expr::Function { name, .. } if is_sequence_function(name));
+    });
+    found
+}
+
+/// Check whether a function name is NEXTVAL, CURRVAL or SETVAL
+pub fn is_sequence_function(name: &str) -> bool {
+    matches!(
+        name.to_uppercase().as_str(),
+        "NEXTVAL" | "CURRVAL" | "SETVAL"
+    )
+}
+
+/// Check whether a function name is an aggregate function
+pub fn is_aggregate_function(name: &str) -> bool {
+    matches!(
+        name.to_uppercase().as_str(),
+        "COUNT" | "SUM" | "AVG" | "MIN" | "MAX"
+    )
+}
+
+/// Whether an analyzed ORDER BY key is the position of an output column
+pub fn is_output_position(expr: &Expr) -> bool {
+    matches!(expr, Expr::Literal(Literal::Integer(_)))
+}
+
+/// Check if a function name is a window-only function
+pub fn is_window_function(name: &str) -> bool {
+    matches!(
+        name.to_uppercase().as_str(),
+        "ROW_NUMBER" | "RANK" | "DENSE_RANK" | "LAG" | "LEAD"
+    )
 }
 
 #[cfg(test)]
@@ -384,4 +1050,46 @@ mod tests {
             _ => panic!("Expected Project"),
         }
     }
+
+    #[test]
+    fn test_decorrelates_exists_and_in() {
+        use crate::catalog::catalog::TableBuilder;
+        use crate::catalog::DataType;
+
+        let catalog = Catalog::new();
+        for name in ["a", "b"] {
+            TableBuilder::new(name)
+                .column("id", DataType::Integer)
+                .column("k", DataType::Integer)
+                .build(&catalog)
+                .unwrap();
+        }
+        let planner = Planner::new(&catalog);
+        let plan = |sql: &str| planner.plan(Parser::new(sql).unwrap().parse().unwrap());
+
+        let semi_join = |plan: LogicalPlan| match plan {
+            LogicalPlan::Project { input, .. } => match *input {
+                LogicalPlan::SemiJoin {
+                    left_keys,
+                    anti,
+                    null_aware,
+                    ..
+                } => Some((left_keys.len(), anti, null_aware)),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        let correlated =
+            plan("SELECT id FROM a WHERE EXISTS (SELECT * FROM b WHERE b.k = a.k AND b.id > 1)");
+        assert_eq!(semi_join(correlated), Some((1, false, false)));
+        let not_in = plan("SELECT id FROM a WHERE k NOT IN (SELECT k FROM b)");
+        assert_eq!(semi_join(not_in), Some((1, true, true)));
+
+        // Aggregates and non-equality correlation stay as subqueries
+        let aggregate = plan("SELECT id FROM a WHERE k IN (SELECT MAX(k) FROM b)");
+        assert_eq!(semi_join(aggregate), None);
+        let inequality = plan("SELECT id FROM a WHERE EXISTS (SELECT * FROM b WHERE b.k > a.k)");
+        assert_eq!(semi_join(inequality), None);
+    }
 }
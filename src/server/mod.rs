@@ -10,7 +10,7 @@ use std::thread;
 
 use crate::catalog::Catalog;
 use crate::error::{Error, Result};
-use crate::executor::{ExecutionEngine, Planner, QueryResult};
+use crate::executor::{Analyzer, ExecutionEngine, Planner, QueryResult};
 use crate::sql::Parser;
 
 /// Default server port
@@ -309,6 +309,12 @@ fn execute_query(
         Err(e) => return format!("Parse error: {}\n", e),
     };
 
+    // Analyze
+    let stmt = match Analyzer::new(catalog).analyze(stmt) {
+        Ok(s) => s,
+        Err(e) => return format!("Error: {}\n", e),
+    };
+
     // Plan
     let planner = Planner::new(catalog);
     let plan = planner.plan(stmt);
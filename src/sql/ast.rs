@@ -2,10 +2,14 @@
 //!
 //! This module defines the AST nodes for SQL statements.
 
-use crate::catalog::DataType;
+use crate::catalog::{DataType, ReferentialAction};
+use crate::storage::datetime::{self, Interval};
+use crate::storage::decimal::Decimal;
+use std::fmt;
 
 /// A SQL statement
 #[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
 pub enum Statement {
     /// SELECT statement
     Select(SelectStatement),
@@ -21,6 +25,14 @@ pub enum Statement {
     DropTable(DropTableStatement),
     /// CREATE INDEX statement
     CreateIndex(CreateIndexStatement),
+    /// DROP INDEX statement
+    DropIndex(DropIndexStatement),
+    /// ALTER TABLE statement
+    AlterTable(AlterTableStatement),
+    /// CREATE SEQUENCE statement
+    CreateSequence(CreateSequenceStatement),
+    /// DROP SEQUENCE statement
+    DropSequence(DropSequenceStatement),
     /// BEGIN TRANSACTION
     BeginTransaction,
     /// COMMIT
@@ -32,8 +44,14 @@ pub enum Statement {
 }
 
 /// SELECT statement
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub struct SelectStatement {
+    /// WITH clause
+    pub with: Option<WithClause>,
+    /// Set operation producing the rows of a compound query, which ORDER BY
+    /// and LIMIT then apply to; the clauses from the select list to HAVING
+    /// are empty
+    pub set_operation: Option<Box<SetOperation>>,
     /// DISTINCT flag
     pub distinct: bool,
     /// Select list (columns or expressions)
@@ -54,22 +72,84 @@ pub struct SelectStatement {
     pub offset: Option<Expr>,
 }
 
-impl Default for SelectStatement {
-    fn default() -> Self {
+impl SelectStatement {
+    /// A compound query combining the rows of two queries
+    pub fn compound(op: SetOperator, all: bool, left: Self, right: Self) -> Self {
         Self {
-            distinct: false,
-            columns: Vec::new(),
-            from: None,
-            where_clause: None,
-            group_by: Vec::new(),
-            having: None,
-            order_by: Vec::new(),
-            limit: None,
-            offset: None,
+            set_operation: Some(Box::new(SetOperation {
+                op,
+                all,
+                left,
+                right,
+            })),
+            ..Default::default()
         }
     }
 }
 
+/// `left UNION|INTERSECT|EXCEPT [ALL] right`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetOperation {
+    /// Set operator
+    pub op: SetOperator,
+    /// ALL keeps duplicate rows
+    pub all: bool,
+    /// Left query, which names the output columns
+    pub left: SelectStatement,
+    /// Right query
+    pub right: SelectStatement,
+}
+
+/// Set operator combining two queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetOperator {
+    Union,
+    Intersect,
+    Except,
+}
+
+impl fmt::Display for SetOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SetOperator::Union => write!(f, "UNION"),
+            SetOperator::Intersect => write!(f, "INTERSECT"),
+            SetOperator::Except => write!(f, "EXCEPT"),
+        }
+    }
+}
+
+/// WITH clause: common table expressions visible to the rest of the query
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithClause {
+    /// WITH RECURSIVE flag
+    pub recursive: bool,
+    /// CTEs, each visible to the ones after it
+    pub ctes: Vec<Cte>,
+}
+
+/// Common table expression: `name [(columns)] AS (query)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cte {
+    /// Name the query is referenced by
+    pub name: String,
+    /// Names for the output columns; empty to keep the query's own
+    pub columns: Vec<String>,
+    /// Query, or the non-recursive part of a recursive CTE
+    pub query: Box<SelectStatement>,
+    /// Recursive term of a WITH RECURSIVE CTE
+    pub recursive: Option<RecursiveTerm>,
+}
+
+/// `UNION [ALL] SELECT ...` after the non-recursive part of a recursive CTE,
+/// evaluated over the rows added by the previous iteration until none are added
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecursiveTerm {
+    /// Query reading the CTE itself
+    pub query: Box<SelectStatement>,
+    /// UNION ALL keeps duplicates; UNION discards rows already produced
+    pub all: bool,
+}
+
 /// A single item in the SELECT list
 #[derive(Debug, Clone, PartialEq)]
 pub enum SelectItem {
@@ -93,10 +173,33 @@ pub struct FromClause {
 /// Table reference
 #[derive(Debug, Clone, PartialEq)]
 pub struct TableRef {
-    /// Table name
+    /// Table name (the alias, for a derived table)
     pub name: String,
     /// Optional alias
     pub alias: Option<String>,
+    /// Query of a derived table: `(SELECT ...) AS alias`
+    pub subquery: Option<Box<SelectStatement>>,
+    /// Output column names of a derived table, filled in by the analyzer
+    pub columns: Vec<String>,
+    /// Recursive term, when the derived table is a recursive CTE whose
+    /// non-recursive part is `subquery`
+    pub recursive: Option<RecursiveTerm>,
+}
+
+impl TableRef {
+    /// Prefix of the name that the recursive term of a CTE reads the
+    /// previous iteration's rows by
+    pub const WORK_TABLE_PREFIX: &'static str = "$";
+
+    /// Name of the rows of the previous iteration of the recursive CTE `cte`
+    pub fn work_table_name(cte: &str) -> String {
+        format!("{}{}", Self::WORK_TABLE_PREFIX, cte)
+    }
+
+    /// Whether this reads the rows of the previous iteration of a recursive CTE
+    pub fn is_work_table(&self) -> bool {
+        self.subquery.is_none() && self.name.starts_with(Self::WORK_TABLE_PREFIX)
+    }
 }
 
 /// JOIN clause
@@ -129,6 +232,102 @@ pub struct OrderByItem {
     pub ascending: bool,
 }
 
+/// Window specification of an OVER clause
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WindowSpec {
+    /// PARTITION BY expressions
+    pub partition_by: Vec<Expr>,
+    /// ORDER BY items within each partition
+    pub order_by: Vec<OrderByItem>,
+    /// Frame clause (ROWS or RANGE)
+    pub frame: Option<WindowFrame>,
+}
+
+/// Window frame clause
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowFrame {
+    /// ROWS or RANGE
+    pub units: FrameUnits,
+    /// Start bound
+    pub start: FrameBound,
+    /// End bound
+    pub end: FrameBound,
+}
+
+/// Units of a window frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FrameUnits {
+    /// Physical rows
+    Rows,
+    /// Peer rows by ORDER BY value
+    Range,
+}
+
+/// Bound of a window frame
+#[derive(Debug, Clone, PartialEq)]
+pub enum FrameBound {
+    /// UNBOUNDED PRECEDING
+    UnboundedPreceding,
+    /// N PRECEDING
+    Preceding(u64),
+    /// CURRENT ROW
+    CurrentRow,
+    /// N FOLLOWING
+    Following(u64),
+    /// UNBOUNDED FOLLOWING
+    UnboundedFollowing,
+}
+
+impl fmt::Display for WindowSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut sep = "";
+        if !self.partition_by.is_empty() {
+            write!(f, "PARTITION BY ")?;
+            write_list(f, &self.partition_by)?;
+            sep = " ";
+        }
+        if !self.order_by.is_empty() {
+            write!(f, "{}ORDER BY ", sep)?;
+            for (i, item) in self.order_by.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", item.expr)?;
+                if !item.ascending {
+                    write!(f, " DESC")?;
+                }
+            }
+            sep = " ";
+        }
+        if let Some(frame) = &self.frame {
+            write!(f, "{}{}", sep, frame)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for WindowFrame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let units = match self.units {
+            FrameUnits::Rows => "ROWS",
+            FrameUnits::Range => "RANGE",
+        };
+        write!(f, "{} BETWEEN {} AND {}", units, self.start, self.end)
+    }
+}
+
+impl fmt::Display for FrameBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameBound::UnboundedPreceding => write!(f, "UNBOUNDED PRECEDING"),
+            FrameBound::Preceding(n) => write!(f, "{} PRECEDING", n),
+            FrameBound::CurrentRow => write!(f, "CURRENT ROW"),
+            FrameBound::Following(n) => write!(f, "{} FOLLOWING", n),
+            FrameBound::UnboundedFollowing => write!(f, "UNBOUNDED FOLLOWING"),
+        }
+    }
+}
+
 /// INSERT statement
 #[derive(Debug, Clone, PartialEq)]
 pub struct InsertStatement {
@@ -138,6 +337,10 @@ pub struct InsertStatement {
     pub columns: Option<Vec<String>>,
     /// Values to insert
     pub values: Vec<Vec<Expr>>,
+    /// Query whose rows are inserted instead of `values`
+    pub query: Option<Box<SelectStatement>>,
+    /// RETURNING list, computed over the inserted rows; empty for none
+    pub returning: Vec<SelectItem>,
 }
 
 /// UPDATE statement
@@ -149,6 +352,8 @@ pub struct UpdateStatement {
     pub assignments: Vec<Assignment>,
     /// WHERE clause
     pub where_clause: Option<Expr>,
+    /// RETURNING list, computed over the updated rows; empty for none
+    pub returning: Vec<SelectItem>,
 }
 
 /// Column assignment (for UPDATE)
@@ -167,6 +372,8 @@ pub struct DeleteStatement {
     pub table_name: String,
     /// WHERE clause
     pub where_clause: Option<Expr>,
+    /// RETURNING list, computed over the deleted rows; empty for none
+    pub returning: Vec<SelectItem>,
 }
 
 /// CREATE TABLE statement
@@ -197,6 +404,49 @@ pub struct ColumnDef {
     pub primary_key: bool,
     /// UNIQUE constraint
     pub unique: bool,
+    /// REFERENCES constraint
+    pub references: Option<References>,
+    /// SERIAL type or GENERATED ... AS IDENTITY clause
+    pub identity: Option<IdentityDef>,
+}
+
+/// `GENERATED {ALWAYS | BY DEFAULT} AS IDENTITY [(options)]`; SERIAL
+/// columns are generated by default
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IdentityDef {
+    /// GENERATED ALWAYS: only DEFAULT may be written to the column
+    pub always: bool,
+    /// Options of the sequence generating the values
+    pub options: SequenceOptions,
+}
+
+/// Options of CREATE SEQUENCE or an identity column; None takes the
+/// default, which depends on the direction of the sequence
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SequenceOptions {
+    /// INCREMENT [BY] n
+    pub increment: Option<i64>,
+    /// MINVALUE n, or None for NO MINVALUE
+    pub min_value: Option<i64>,
+    /// MAXVALUE n, or None for NO MAXVALUE
+    pub max_value: Option<i64>,
+    /// START [WITH] n
+    pub start: Option<i64>,
+    /// CYCLE flag
+    pub cycle: bool,
+}
+
+/// REFERENCES clause of a foreign key
+#[derive(Debug, Clone, PartialEq)]
+pub struct References {
+    /// Referenced table
+    pub table: String,
+    /// Referenced columns, the primary key of the table if empty
+    pub columns: Vec<String>,
+    /// ON DELETE action
+    pub on_delete: ReferentialAction,
+    /// ON UPDATE action
+    pub on_update: ReferentialAction,
 }
 
 /// Table-level constraint
@@ -216,8 +466,7 @@ pub enum TableConstraint {
     ForeignKey {
         name: Option<String>,
         columns: Vec<String>,
-        ref_table: String,
-        ref_columns: Vec<String>,
+        references: References,
     },
     /// CHECK constraint
     Check { name: Option<String>, expr: Expr },
@@ -232,6 +481,60 @@ pub struct DropTableStatement {
     pub if_exists: bool,
 }
 
+/// DROP INDEX statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropIndexStatement {
+    /// Index name
+    pub index_name: String,
+    /// IF EXISTS flag
+    pub if_exists: bool,
+}
+
+/// CREATE SEQUENCE statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateSequenceStatement {
+    /// Sequence name
+    pub name: String,
+    /// Sequence options
+    pub options: SequenceOptions,
+    /// IF NOT EXISTS flag
+    pub if_not_exists: bool,
+}
+
+/// DROP SEQUENCE statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropSequenceStatement {
+    /// Sequence name
+    pub name: String,
+    /// IF EXISTS flag
+    pub if_exists: bool,
+}
+
+/// ALTER TABLE statement
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlterTableStatement {
+    /// Table name
+    pub table_name: String,
+    /// Change to make
+    pub operation: AlterTableOperation,
+}
+
+/// Change made by an ALTER TABLE statement
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum AlterTableOperation {
+    /// ADD [COLUMN] definition
+    AddColumn(ColumnDef),
+    /// DROP [COLUMN] name
+    DropColumn(String),
+    /// RENAME [COLUMN] old TO new
+    RenameColumn { old_name: String, new_name: String },
+    /// RENAME TO new
+    RenameTable(String),
+    /// ALTER [COLUMN] name SET NOT NULL or DROP NOT NULL
+    SetNotNull { column: String, not_null: bool },
+}
+
 /// CREATE INDEX statement
 #[derive(Debug, Clone, PartialEq)]
 pub struct CreateIndexStatement {
@@ -267,6 +570,8 @@ pub enum Expr {
         name: String,
         args: Vec<Expr>,
         distinct: bool,
+        /// OVER clause of a window function call
+        over: Option<Box<WindowSpec>>,
     },
     /// IS NULL
     IsNull(Box<Expr>),
@@ -285,11 +590,13 @@ pub enum Expr {
         list: Vec<Expr>,
         negated: bool,
     },
-    /// LIKE
+    /// LIKE or ILIKE, with an optional ESCAPE character
     Like {
         expr: Box<Expr>,
         pattern: Box<Expr>,
+        escape: Option<Box<Expr>>,
         negated: bool,
+        case_insensitive: bool,
     },
     /// CASE expression
     Case {
@@ -297,12 +604,25 @@ pub enum Expr {
         when_clauses: Vec<(Expr, Expr)>,
         else_clause: Option<Box<Expr>>,
     },
+    /// IN (SELECT ...)
+    InSubquery {
+        expr: Box<Expr>,
+        subquery: Box<SelectStatement>,
+        negated: bool,
+    },
     /// Subquery
     Subquery(Box<SelectStatement>),
     /// EXISTS
     Exists(Box<SelectStatement>),
     /// Nested expression (in parentheses)
     Nested(Box<Expr>),
+    /// CAST(expr AS type), also inserted by the analyzer for implicit conversions
+    Cast {
+        expr: Box<Expr>,
+        data_type: DataType,
+    },
+    /// DEFAULT in VALUES or UPDATE SET, replaced by the column default
+    Default,
 }
 
 /// Column reference
@@ -332,10 +652,20 @@ pub enum Literal {
     Boolean(bool),
     /// Integer
     Integer(i64),
-    /// Float
+    /// Float, for fractional numbers too large to be exact decimals
     Float(f64),
+    /// Exact fractional number, such as `0.1`
+    Decimal(Decimal),
     /// String
     String(String),
+    /// DATE 'YYYY-MM-DD' (days since epoch)
+    Date(i32),
+    /// TIME 'HH:MM:SS' (milliseconds since midnight)
+    Time(i64),
+    /// TIMESTAMP 'YYYY-MM-DD HH:MM:SS' (milliseconds since epoch)
+    Timestamp(i64),
+    /// INTERVAL '...'
+    Interval(Interval),
 }
 
 /// Binary operator
@@ -389,3 +719,182 @@ pub enum UnaryOperator {
     /// + (plus sign)
     Plus,
 }
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Column(col_ref) => write!(f, "{}", col_ref),
+            Expr::Literal(lit) => write!(f, "{}", lit),
+            Expr::BinaryOp { left, op, right } => write!(f, "{} {} {}", left, op, right),
+            Expr::UnaryOp { op, expr } => match op {
+                UnaryOperator::Not => write!(f, "NOT {}", expr),
+                UnaryOperator::Minus => write!(f, "-{}", expr),
+                UnaryOperator::Plus => write!(f, "+{}", expr),
+            },
+            Expr::Function {
+                name,
+                args,
+                distinct,
+                over,
+            } => {
+                // Niladic functions and EXTRACT have their own syntax
+                if args.is_empty() && is_niladic_function(name) {
+                    return write!(f, "{}", name);
+                }
+                if let (true, [Expr::Literal(Literal::String(field)), source]) =
+                    (name.eq_ignore_ascii_case("EXTRACT"), args.as_slice())
+                {
+                    return write!(f, "EXTRACT({} FROM {})", field, source);
+                }
+                write!(f, "{}(", name)?;
+                if *distinct {
+                    write!(f, "DISTINCT ")?;
+                }
+                write_list(f, args)?;
+                write!(f, ")")?;
+                if let Some(over) = over {
+                    write!(f, " OVER ({})", over)?;
+                }
+                Ok(())
+            }
+            Expr::IsNull(expr) => write!(f, "{} IS NULL", expr),
+            Expr::IsNotNull(expr) => write!(f, "{} IS NOT NULL", expr),
+            Expr::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => write!(
+                f,
+                "{} {}BETWEEN {} AND {}",
+                expr,
+                if *negated { "NOT " } else { "" },
+                low,
+                high
+            ),
+            Expr::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                write!(f, "{} {}IN (", expr, if *negated { "NOT " } else { "" })?;
+                write_list(f, list)?;
+                write!(f, ")")
+            }
+            Expr::Like {
+                expr,
+                pattern,
+                escape,
+                negated,
+                case_insensitive,
+            } => {
+                write!(
+                    f,
+                    "{} {}{} {}",
+                    expr,
+                    if *negated { "NOT " } else { "" },
+                    if *case_insensitive { "ILIKE" } else { "LIKE" },
+                    pattern
+                )?;
+                if let Some(escape) = escape {
+                    write!(f, " ESCAPE {}", escape)?;
+                }
+                Ok(())
+            }
+            Expr::Case {
+                operand,
+                when_clauses,
+                else_clause,
+            } => {
+                write!(f, "CASE")?;
+                if let Some(operand) = operand {
+                    write!(f, " {}", operand)?;
+                }
+                for (when, then) in when_clauses {
+                    write!(f, " WHEN {} THEN {}", when, then)?;
+                }
+                if let Some(else_clause) = else_clause {
+                    write!(f, " ELSE {}", else_clause)?;
+                }
+                write!(f, " END")
+            }
+            Expr::InSubquery { expr, negated, .. } => write!(
+                f,
+                "{} {}IN (subquery)",
+                expr,
+                if *negated { "NOT " } else { "" }
+            ),
+            Expr::Subquery(_) => write!(f, "(subquery)"),
+            Expr::Exists(_) => write!(f, "EXISTS (subquery)"),
+            Expr::Nested(expr) => write!(f, "({})", expr),
+            Expr::Cast { expr, data_type } => write!(f, "CAST({} AS {})", expr, data_type),
+            Expr::Default => write!(f, "DEFAULT"),
+        }
+    }
+}
+
+/// Functions written without parentheses, such as CURRENT_DATE
+pub fn is_niladic_function(name: &str) -> bool {
+    ["CURRENT_DATE", "CURRENT_TIME", "CURRENT_TIMESTAMP"]
+        .iter()
+        .any(|f| name.eq_ignore_ascii_case(f))
+}
+
+/// Write a comma-separated list of expressions
+fn write_list(f: &mut fmt::Formatter<'_>, exprs: &[Expr]) -> fmt::Result {
+    for (i, expr) in exprs.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", expr)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for ColumnRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.table {
+            Some(table) => write!(f, "{}.{}", table, self.column),
+            None => write!(f, "{}", self.column),
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Null => write!(f, "NULL"),
+            Literal::Boolean(b) => write!(f, "{}", if *b { "TRUE" } else { "FALSE" }),
+            Literal::Integer(i) => write!(f, "{}", i),
+            Literal::Float(n) => write!(f, "{}", n),
+            Literal::Decimal(d) => write!(f, "{}", d),
+            Literal::String(s) => write!(f, "'{}'", s.replace('\'', "''")),
+            Literal::Date(d) => write!(f, "DATE '{}'", datetime::format_date(*d)),
+            Literal::Time(t) => write!(f, "TIME '{}'", datetime::format_time(*t)),
+            Literal::Timestamp(t) => write!(f, "TIMESTAMP '{}'", datetime::format_timestamp(*t)),
+            Literal::Interval(i) => write!(f, "INTERVAL '{}'", i),
+        }
+    }
+}
+
+impl fmt::Display for BinaryOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            BinaryOperator::Eq => "=",
+            BinaryOperator::Neq => "<>",
+            BinaryOperator::Lt => "<",
+            BinaryOperator::Gt => ">",
+            BinaryOperator::Lte => "<=",
+            BinaryOperator::Gte => ">=",
+            BinaryOperator::And => "AND",
+            BinaryOperator::Or => "OR",
+            BinaryOperator::Add => "+",
+            BinaryOperator::Sub => "-",
+            BinaryOperator::Mul => "*",
+            BinaryOperator::Div => "/",
+            BinaryOperator::Mod => "%",
+            BinaryOperator::Concat => "||",
+        };
+        write!(f, "{}", op)
+    }
+}
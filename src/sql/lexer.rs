@@ -159,8 +159,8 @@ impl Lexer {
             _ => None,
         };
 
-        if token.is_some() {
-            return Ok(token.unwrap());
+        if let Some(token) = token {
+            return Ok(token);
         }
 
         // Numbers
@@ -467,6 +467,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_float_literal() {
         let mut lexer = Lexer::new("SELECT 3.14, 2.5e10");
         let tokens = lexer.tokenize().unwrap();
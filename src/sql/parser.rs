@@ -5,8 +5,10 @@
 use super::ast::*;
 use super::lexer::Lexer;
 use super::token::Token;
-use crate::catalog::DataType;
+use crate::catalog::{DataType, ReferentialAction};
 use crate::error::{Error, Result};
+use crate::storage::datetime::{self, DateTimeField, Interval};
+use crate::storage::decimal::{Decimal, MAX_PRECISION};
 
 /// SQL Parser
 pub struct Parser {
@@ -38,6 +40,18 @@ impl Parser {
         Ok(stmt)
     }
 
+    /// Parse a standalone expression, such as a stored column default
+    pub fn parse_expression(&mut self) -> Result<Expr> {
+        let expr = self.parse_expr()?;
+        if !self.is_at_end() {
+            return Err(Error::UnexpectedToken {
+                expected: "end of expression".to_string(),
+                found: format!("{}", self.current()),
+            });
+        }
+        Ok(expr)
+    }
+
     /// Parse multiple SQL statements
     pub fn parse_all(&mut self) -> Result<Vec<Statement>> {
         let mut statements = Vec::new();
@@ -52,19 +66,22 @@ impl Parser {
     /// Parse a single statement
     fn parse_statement(&mut self) -> Result<Statement> {
         match self.current() {
-            Token::Select => self.parse_select().map(Statement::Select),
+            Token::Select | Token::With | Token::LParen => {
+                self.parse_select().map(Statement::Select)
+            }
             Token::Insert => self.parse_insert().map(Statement::Insert),
             Token::Update => self.parse_update().map(Statement::Update),
             Token::Delete => self.parse_delete().map(Statement::Delete),
             Token::Create => self.parse_create(),
             Token::Drop => self.parse_drop(),
+            Token::Alter => self.parse_alter_table().map(Statement::AlterTable),
             Token::Begin => self.parse_begin(),
             Token::Commit => self.parse_commit(),
             Token::Rollback => self.parse_rollback(),
             Token::Analyze => self.parse_analyze(),
             _ => Err(Error::UnexpectedToken {
                 expected:
-                    "SELECT, INSERT, UPDATE, DELETE, CREATE, DROP, BEGIN, COMMIT, ROLLBACK, or ANALYZE"
+                    "SELECT, INSERT, UPDATE, DELETE, CREATE, DROP, ALTER, BEGIN, COMMIT, ROLLBACK, or ANALYZE"
                         .to_string(),
                 found: format!("{}", self.current()),
             }),
@@ -74,8 +91,115 @@ impl Parser {
     // ========== SELECT Statement ==========
 
     fn parse_select(&mut self) -> Result<SelectStatement> {
-        self.expect(&Token::Select)?;
+        let with = if self.check(&Token::With) {
+            Some(self.parse_with_clause()?)
+        } else {
+            None
+        };
+
+        let mut stmt = self.parse_set_expr()?;
+        if with.is_some() {
+            if stmt.with.is_some() {
+                return Err(Error::ParseError(
+                    "multiple WITH clauses not allowed".to_string(),
+                ));
+            }
+            stmt.with = with;
+        }
+
+        // ORDER BY, LIMIT and OFFSET apply to the result of a compound query;
+        // a parenthesized query may already have its own
+        if self.check(&Token::Order) {
+            self.advance();
+            self.expect(&Token::By)?;
+            if !stmt.order_by.is_empty() {
+                return Err(Error::ParseError(
+                    "multiple ORDER BY clauses not allowed".to_string(),
+                ));
+            }
+            stmt.order_by = self.parse_order_by_list()?;
+        }
+
+        // LIMIT clause
+        if self.check(&Token::Limit) {
+            self.advance();
+            if stmt.limit.is_some() {
+                return Err(Error::ParseError(
+                    "multiple LIMIT clauses not allowed".to_string(),
+                ));
+            }
+            stmt.limit = Some(self.parse_expr()?);
+        }
+
+        // OFFSET clause
+        if self.check(&Token::Offset) {
+            self.advance();
+            if stmt.offset.is_some() {
+                return Err(Error::ParseError(
+                    "multiple OFFSET clauses not allowed".to_string(),
+                ));
+            }
+            stmt.offset = Some(self.parse_expr()?);
+        }
+
+        Ok(stmt)
+    }
+
+    /// Parse queries combined by UNION and EXCEPT, which bind less tightly
+    /// than INTERSECT; all three are left-associative
+    fn parse_set_expr(&mut self) -> Result<SelectStatement> {
+        let mut left = self.parse_intersect_expr()?;
+        loop {
+            let op = match self.current() {
+                Token::Union => SetOperator::Union,
+                Token::Except => SetOperator::Except,
+                _ => return Ok(left),
+            };
+            self.advance();
+            let all = self.parse_set_quantifier();
+            let right = self.parse_intersect_expr()?;
+            left = SelectStatement::compound(op, all, left, right);
+        }
+    }
+
+    fn parse_intersect_expr(&mut self) -> Result<SelectStatement> {
+        let mut left = self.parse_query_term()?;
+        while self.check(&Token::Intersect) {
+            self.advance();
+            let all = self.parse_set_quantifier();
+            let right = self.parse_query_term()?;
+            left = SelectStatement::compound(SetOperator::Intersect, all, left, right);
+        }
+        Ok(left)
+    }
+
+    /// Parse `ALL` or `DISTINCT` after a set operator, returning whether
+    /// duplicates are kept
+    fn parse_set_quantifier(&mut self) -> bool {
+        match self.current() {
+            Token::All => {
+                self.advance();
+                true
+            }
+            Token::Distinct => {
+                self.advance();
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Parse an operand of a set operation: a parenthesized query, or a
+    /// SELECT up to its HAVING clause
+    fn parse_query_term(&mut self) -> Result<SelectStatement> {
+        if self.check(&Token::LParen) {
+            self.advance();
+            let query = self.parse_select()?;
+            self.expect(&Token::RParen)?;
+            return Ok(query);
+        }
 
+        self.expect(&Token::Select)?;
         let mut stmt = SelectStatement::default();
 
         // DISTINCT
@@ -113,26 +237,75 @@ impl Parser {
             stmt.having = Some(self.parse_expr()?);
         }
 
-        // ORDER BY clause
-        if self.check(&Token::Order) {
+        Ok(stmt)
+    }
+
+    fn parse_with_clause(&mut self) -> Result<WithClause> {
+        self.expect(&Token::With)?;
+        let recursive = if self.check(&Token::Recursive) {
             self.advance();
-            self.expect(&Token::By)?;
-            stmt.order_by = self.parse_order_by_list()?;
-        }
+            true
+        } else {
+            false
+        };
 
-        // LIMIT clause
-        if self.check(&Token::Limit) {
+        let mut ctes = Vec::new();
+        loop {
+            ctes.push(self.parse_cte(recursive)?);
+            if !self.check(&Token::Comma) {
+                break;
+            }
             self.advance();
-            stmt.limit = Some(self.parse_expr()?);
         }
 
-        // OFFSET clause
-        if self.check(&Token::Offset) {
+        Ok(WithClause { recursive, ctes })
+    }
+
+    /// Parse `name [(columns)] AS (query)`, where the query of a CTE in
+    /// WITH RECURSIVE may end in `UNION [ALL] <recursive term>`
+    fn parse_cte(&mut self, recursive: bool) -> Result<Cte> {
+        let name = self.expect_identifier()?;
+        let columns = if self.check(&Token::LParen) {
             self.advance();
-            stmt.offset = Some(self.parse_expr()?);
-        }
+            let columns = self.parse_identifier_list()?;
+            self.expect(&Token::RParen)?;
+            columns
+        } else {
+            Vec::new()
+        };
 
-        Ok(stmt)
+        self.expect(&Token::As)?;
+        self.expect(&Token::LParen)?;
+        let mut query = self.parse_select()?;
+
+        // The last UNION of a recursive CTE separates its recursive term
+        let splits = recursive
+            && query.order_by.is_empty()
+            && query.limit.is_none()
+            && query.offset.is_none()
+            && query.with.is_none()
+            && matches!(&query.set_operation, Some(set_op) if set_op.op == SetOperator::Union);
+        let recursive = match query.set_operation.take() {
+            Some(set_op) if splits => {
+                query = set_op.left;
+                Some(RecursiveTerm {
+                    query: Box::new(set_op.right),
+                    all: set_op.all,
+                })
+            }
+            set_operation => {
+                query.set_operation = set_operation;
+                None
+            }
+        };
+        self.expect(&Token::RParen)?;
+
+        Ok(Cte {
+            name,
+            columns,
+            query: Box::new(query),
+            recursive,
+        })
     }
 
     fn parse_select_list(&mut self) -> Result<Vec<SelectItem>> {
@@ -208,8 +381,36 @@ impl Parser {
     }
 
     fn parse_table_ref(&mut self) -> Result<TableRef> {
+        // Derived table: (SELECT ...) [AS] alias
+        if self.check(&Token::LParen) {
+            self.advance();
+            let subquery = self.parse_select()?;
+            self.expect(&Token::RParen)?;
+            let alias = self.parse_table_alias()?.ok_or_else(|| {
+                Error::ParseError("subquery in FROM must have an alias".to_string())
+            })?;
+            return Ok(TableRef {
+                name: alias.clone(),
+                alias: Some(alias),
+                subquery: Some(Box::new(subquery)),
+                columns: Vec::new(),
+                recursive: None,
+            });
+        }
+
         let name = self.expect_identifier()?;
+        let alias = self.parse_table_alias()?;
+        Ok(TableRef {
+            name,
+            alias,
+            subquery: None,
+            columns: Vec::new(),
+            recursive: None,
+        })
+    }
 
+    /// Parse an optional `[AS] alias` after a table reference
+    fn parse_table_alias(&mut self) -> Result<Option<String>> {
         let alias = if self.check(&Token::As) {
             self.advance();
             Some(self.expect_identifier()?)
@@ -231,7 +432,12 @@ impl Parser {
             None
         };
 
-        Ok(TableRef { name, alias })
+        Ok(alias)
+    }
+
+    /// Whether a query (SELECT or WITH ... SELECT) starts here
+    fn at_query(&self) -> bool {
+        matches!(self.current(), Token::Select | Token::With)
     }
 
     fn is_join_keyword(&self) -> bool {
@@ -325,8 +531,25 @@ impl Parser {
 
         let table_name = self.expect_identifier()?;
 
-        // Optional column list
-        let columns = if self.check(&Token::LParen) {
+        // DEFAULT VALUES: one row with every column omitted
+        if self.check(&Token::Default) {
+            self.advance();
+            self.expect(&Token::Values)?;
+            return Ok(InsertStatement {
+                table_name,
+                columns: Some(Vec::new()),
+                values: vec![Vec::new()],
+                query: None,
+                returning: self.parse_returning()?,
+            });
+        }
+
+        // Optional column list, unless the parenthesis opens a query
+        let columns = if self.check(&Token::LParen)
+            && !matches!(
+                self.peek(),
+                Some(Token::Select | Token::With | Token::LParen)
+            ) {
             self.advance();
             let cols = self.parse_identifier_list()?;
             self.expect(&Token::RParen)?;
@@ -335,6 +558,18 @@ impl Parser {
             None
         };
 
+        // INSERT ... SELECT: the rows come from a query
+        if self.at_query() || self.check(&Token::LParen) {
+            let query = self.parse_select()?;
+            return Ok(InsertStatement {
+                table_name,
+                columns,
+                values: Vec::new(),
+                query: Some(Box::new(query)),
+                returning: self.parse_returning()?,
+            });
+        }
+
         self.expect(&Token::Values)?;
 
         // Parse value rows
@@ -355,9 +590,20 @@ impl Parser {
             table_name,
             columns,
             values,
+            query: None,
+            returning: self.parse_returning()?,
         })
     }
 
+    /// Optional `RETURNING <select list>` of INSERT, UPDATE and DELETE
+    fn parse_returning(&mut self) -> Result<Vec<SelectItem>> {
+        if !self.check(&Token::Returning) {
+            return Ok(Vec::new());
+        }
+        self.advance();
+        self.parse_select_list()
+    }
+
     // ========== UPDATE Statement ==========
 
     fn parse_update(&mut self) -> Result<UpdateStatement> {
@@ -391,6 +637,7 @@ impl Parser {
             table_name,
             assignments,
             where_clause,
+            returning: self.parse_returning()?,
         })
     }
 
@@ -412,6 +659,7 @@ impl Parser {
         Ok(DeleteStatement {
             table_name,
             where_clause,
+            returning: self.parse_returning()?,
         })
     }
 
@@ -423,8 +671,11 @@ impl Parser {
         match self.current() {
             Token::Table => self.parse_create_table().map(Statement::CreateTable),
             Token::Index | Token::Unique => self.parse_create_index().map(Statement::CreateIndex),
+            _ if self.check_keyword("SEQUENCE") => {
+                self.parse_create_sequence().map(Statement::CreateSequence)
+            }
             _ => Err(Error::UnexpectedToken {
-                expected: "TABLE or INDEX".to_string(),
+                expected: "TABLE, INDEX or SEQUENCE".to_string(),
                 found: format!("{}", self.current()),
             }),
         }
@@ -459,7 +710,7 @@ impl Parser {
             {
                 constraints.push(self.parse_table_constraint()?);
             } else {
-                columns.push(self.parse_column_def()?);
+                columns.push(self.parse_column_def(&mut constraints)?);
             }
 
             if !self.check(&Token::Comma) {
@@ -514,14 +765,103 @@ impl Parser {
         })
     }
 
-    fn parse_column_def(&mut self) -> Result<ColumnDef> {
+    fn parse_create_sequence(&mut self) -> Result<CreateSequenceStatement> {
+        self.expect_keyword("SEQUENCE")?;
+
+        let if_not_exists = if self.check(&Token::If) {
+            self.advance();
+            self.expect(&Token::Not)?;
+            self.expect(&Token::Exists)?;
+            true
+        } else {
+            false
+        };
+
+        let name = self.expect_identifier()?;
+        let options = self.parse_sequence_options()?;
+
+        Ok(CreateSequenceStatement {
+            name,
+            options,
+            if_not_exists,
+        })
+    }
+
+    /// Parse `[INCREMENT [BY] n] [MINVALUE n | NO MINVALUE] [MAXVALUE n | NO MAXVALUE]
+    /// [START [WITH] n] [CYCLE | NO CYCLE]`, in any order
+    fn parse_sequence_options(&mut self) -> Result<SequenceOptions> {
+        let mut options = SequenceOptions::default();
+        loop {
+            if self.check_keyword("INCREMENT") {
+                self.advance();
+                if self.check(&Token::By) {
+                    self.advance();
+                }
+                options.increment = Some(self.expect_signed_integer()?);
+            } else if self.check_keyword("MINVALUE") {
+                self.advance();
+                options.min_value = Some(self.expect_signed_integer()?);
+            } else if self.check_keyword("MAXVALUE") {
+                self.advance();
+                options.max_value = Some(self.expect_signed_integer()?);
+            } else if self.check_keyword("START") {
+                self.advance();
+                if self.check(&Token::With) {
+                    self.advance();
+                }
+                options.start = Some(self.expect_signed_integer()?);
+            } else if self.check_keyword("CYCLE") {
+                self.advance();
+                options.cycle = true;
+            } else if self.check_keyword("NO") {
+                self.advance();
+                if self.check_keyword("MINVALUE") {
+                    options.min_value = None;
+                } else if self.check_keyword("MAXVALUE") {
+                    options.max_value = None;
+                } else {
+                    self.expect_keyword("CYCLE")?;
+                    options.cycle = false;
+                    continue;
+                }
+                self.advance();
+            } else {
+                return Ok(options);
+            }
+        }
+    }
+
+    /// Parse a column definition; a column CHECK is added to `constraints`
+    /// as the table constraint it is equivalent to
+    fn parse_column_def(&mut self, constraints: &mut Vec<TableConstraint>) -> Result<ColumnDef> {
         let name = self.expect_identifier()?;
-        let data_type = self.parse_data_type()?;
 
         let mut not_null = false;
         let mut default = None;
         let mut primary_key = false;
         let mut unique = false;
+        let mut references = None;
+        let mut identity = None;
+
+        // SERIAL types are integers generated by default
+        let serial_type = match self.current() {
+            Token::Identifier(name) => match name.to_uppercase().as_str() {
+                "SMALLSERIAL" => Some(DataType::SmallInt),
+                "SERIAL" => Some(DataType::Integer),
+                "BIGSERIAL" => Some(DataType::BigInt),
+                _ => None,
+            },
+            _ => None,
+        };
+        let data_type = match serial_type {
+            Some(data_type) => {
+                self.advance();
+                not_null = true;
+                identity = Some(IdentityDef::default());
+                data_type
+            }
+            None => self.parse_data_type()?,
+        };
 
         // Parse column constraints
         loop {
@@ -534,7 +874,7 @@ impl Parser {
                 // NULL is allowed (default)
             } else if self.check(&Token::Default) {
                 self.advance();
-                default = Some(self.parse_primary_expr()?);
+                default = Some(self.parse_additive_expr()?);
             } else if self.check(&Token::Primary) {
                 self.advance();
                 self.expect(&Token::Key)?;
@@ -543,6 +883,13 @@ impl Parser {
             } else if self.check(&Token::Unique) {
                 self.advance();
                 unique = true;
+            } else if self.check(&Token::References) {
+                references = Some(self.parse_references()?);
+            } else if self.check(&Token::Check) {
+                constraints.push(self.parse_table_constraint()?);
+            } else if self.check_keyword("GENERATED") {
+                identity = Some(self.parse_identity()?);
+                not_null = true;
             } else {
                 break;
             }
@@ -555,9 +902,97 @@ impl Parser {
             default,
             primary_key,
             unique,
+            references,
+            identity,
+        })
+    }
+
+    /// Parse `GENERATED {ALWAYS | BY DEFAULT} AS IDENTITY [(options)]`
+    fn parse_identity(&mut self) -> Result<IdentityDef> {
+        self.expect_keyword("GENERATED")?;
+        let always = if self.check_keyword("ALWAYS") {
+            self.advance();
+            true
+        } else {
+            self.expect(&Token::By)?;
+            self.expect(&Token::Default)?;
+            false
+        };
+        self.expect(&Token::As)?;
+        self.expect_keyword("IDENTITY")?;
+
+        let options = if self.check(&Token::LParen) {
+            self.advance();
+            let options = self.parse_sequence_options()?;
+            self.expect(&Token::RParen)?;
+            options
+        } else {
+            SequenceOptions::default()
+        };
+
+        Ok(IdentityDef { always, options })
+    }
+
+    /// Parse `REFERENCES table [(columns)] [ON DELETE action] [ON UPDATE action]`
+    fn parse_references(&mut self) -> Result<References> {
+        self.expect(&Token::References)?;
+        let table = self.expect_identifier()?;
+        let mut columns = Vec::new();
+        if self.check(&Token::LParen) {
+            self.advance();
+            columns = self.parse_identifier_list()?;
+            self.expect(&Token::RParen)?;
+        }
+
+        let mut on_delete = ReferentialAction::NoAction;
+        let mut on_update = ReferentialAction::NoAction;
+        while self.check(&Token::On) {
+            self.advance();
+            if self.check(&Token::Delete) {
+                self.advance();
+                on_delete = self.parse_referential_action()?;
+            } else {
+                self.expect(&Token::Update)?;
+                on_update = self.parse_referential_action()?;
+            }
+        }
+
+        Ok(References {
+            table,
+            columns,
+            on_delete,
+            on_update,
         })
     }
 
+    fn parse_referential_action(&mut self) -> Result<ReferentialAction> {
+        if self.check_keyword("CASCADE") {
+            self.advance();
+            Ok(ReferentialAction::Cascade)
+        } else if self.check_keyword("RESTRICT") {
+            self.advance();
+            Ok(ReferentialAction::Restrict)
+        } else if self.check_keyword("NO") {
+            self.advance();
+            self.expect_keyword("ACTION")?;
+            Ok(ReferentialAction::NoAction)
+        } else if self.check(&Token::Set) {
+            self.advance();
+            if self.check(&Token::Null) {
+                self.advance();
+                Ok(ReferentialAction::SetNull)
+            } else {
+                self.expect(&Token::Default)?;
+                Ok(ReferentialAction::SetDefault)
+            }
+        } else {
+            Err(Error::UnexpectedToken {
+                expected: "CASCADE, RESTRICT, NO ACTION, SET NULL or SET DEFAULT".to_string(),
+                found: format!("{}", self.current()),
+            })
+        }
+    }
+
     fn parse_data_type(&mut self) -> Result<DataType> {
         let dt = match self.current() {
             Token::Int | Token::Integer => {
@@ -584,15 +1019,23 @@ impl Parser {
                 self.advance();
                 if self.check(&Token::LParen) {
                     self.advance();
-                    let precision = self.expect_integer()? as u8;
+                    let precision = self.expect_integer()?;
                     let scale = if self.check(&Token::Comma) {
                         self.advance();
-                        self.expect_integer()? as u8
+                        self.expect_integer()?
                     } else {
                         0
                     };
                     self.expect(&Token::RParen)?;
-                    DataType::Decimal(precision, scale)
+                    if !(1..=MAX_PRECISION as i64).contains(&precision)
+                        || !(0..=precision).contains(&scale)
+                    {
+                        return Err(Error::ParseError(format!(
+                            "invalid DECIMAL({}, {}): precision must be 1 to {} and scale at most the precision",
+                            precision, scale, MAX_PRECISION
+                        )));
+                    }
+                    DataType::Decimal(precision as u8, scale as u8)
                 } else {
                     DataType::Decimal(10, 0)
                 }
@@ -635,6 +1078,10 @@ impl Parser {
                 self.advance();
                 DataType::Timestamp
             }
+            Token::Interval => {
+                self.advance();
+                DataType::Interval
+            }
             _ => {
                 return Err(Error::UnexpectedToken {
                     expected: "data type".to_string(),
@@ -674,16 +1121,11 @@ impl Parser {
             self.expect(&Token::LParen)?;
             let columns = self.parse_identifier_list()?;
             self.expect(&Token::RParen)?;
-            self.expect(&Token::References)?;
-            let ref_table = self.expect_identifier()?;
-            self.expect(&Token::LParen)?;
-            let ref_columns = self.parse_identifier_list()?;
-            self.expect(&Token::RParen)?;
+            let references = self.parse_references()?;
             Ok(TableConstraint::ForeignKey {
                 name,
                 columns,
-                ref_table,
-                ref_columns,
+                references,
             })
         } else if self.check(&Token::Check) {
             self.advance();
@@ -723,18 +1165,115 @@ impl Parser {
                     if_exists,
                 }))
             }
+            Token::Index => {
+                self.advance();
+
+                let if_exists = if self.check(&Token::If) {
+                    self.advance();
+                    self.expect(&Token::Exists)?;
+                    true
+                } else {
+                    false
+                };
+
+                let index_name = self.expect_identifier()?;
+
+                Ok(Statement::DropIndex(DropIndexStatement {
+                    index_name,
+                    if_exists,
+                }))
+            }
+            _ if self.check_keyword("SEQUENCE") => {
+                self.advance();
+
+                let if_exists = if self.check(&Token::If) {
+                    self.advance();
+                    self.expect(&Token::Exists)?;
+                    true
+                } else {
+                    false
+                };
+
+                let name = self.expect_identifier()?;
+
+                Ok(Statement::DropSequence(DropSequenceStatement {
+                    name,
+                    if_exists,
+                }))
+            }
             _ => Err(Error::UnexpectedToken {
-                expected: "TABLE".to_string(),
+                expected: "TABLE, INDEX or SEQUENCE".to_string(),
                 found: format!("{}", self.current()),
             }),
         }
     }
 
-    // ========== Expression Parsing ==========
-
-    fn parse_expr(&mut self) -> Result<Expr> {
-        self.parse_or_expr()
-    }
+    // ========== ALTER Statement ==========
+
+    fn parse_alter_table(&mut self) -> Result<AlterTableStatement> {
+        self.expect(&Token::Alter)?;
+        self.expect(&Token::Table)?;
+        let table_name = self.expect_identifier()?;
+
+        let operation = if self.check_keyword("ADD") {
+            self.advance();
+            self.skip_keyword("COLUMN");
+            let mut checks = Vec::new();
+            let column = self.parse_column_def(&mut checks)?;
+            if !checks.is_empty() {
+                return Err(Error::ParseError(
+                    "CHECK constraints are not supported in ADD COLUMN".to_string(),
+                ));
+            }
+            AlterTableOperation::AddColumn(column)
+        } else if self.check(&Token::Drop) {
+            self.advance();
+            self.skip_keyword("COLUMN");
+            AlterTableOperation::DropColumn(self.expect_identifier()?)
+        } else if self.check_keyword("RENAME") {
+            self.advance();
+            if self.check_keyword("TO") {
+                self.advance();
+                AlterTableOperation::RenameTable(self.expect_identifier()?)
+            } else {
+                self.skip_keyword("COLUMN");
+                let old_name = self.expect_identifier()?;
+                self.expect_keyword("TO")?;
+                let new_name = self.expect_identifier()?;
+                AlterTableOperation::RenameColumn { old_name, new_name }
+            }
+        } else if self.check(&Token::Alter) {
+            self.advance();
+            self.skip_keyword("COLUMN");
+            let column = self.expect_identifier()?;
+            let not_null = if self.check(&Token::Set) {
+                self.advance();
+                true
+            } else {
+                self.expect(&Token::Drop)?;
+                false
+            };
+            self.expect(&Token::Not)?;
+            self.expect(&Token::Null)?;
+            AlterTableOperation::SetNotNull { column, not_null }
+        } else {
+            return Err(Error::UnexpectedToken {
+                expected: "ADD, DROP, RENAME, or ALTER".to_string(),
+                found: format!("{}", self.current()),
+            });
+        };
+
+        Ok(AlterTableStatement {
+            table_name,
+            operation,
+        })
+    }
+
+    // ========== Expression Parsing ==========
+
+    fn parse_expr(&mut self) -> Result<Expr> {
+        self.parse_or_expr()
+    }
 
     fn parse_or_expr(&mut self) -> Result<Expr> {
         let mut left = self.parse_and_expr()?;
@@ -826,48 +1365,20 @@ impl Parser {
                     negated: true,
                 });
             } else if self.check(&Token::In) {
-                self.advance();
-                self.expect(&Token::LParen)?;
-                let list = self.parse_expr_list()?;
-                self.expect(&Token::RParen)?;
-                return Ok(Expr::InList {
-                    expr: Box::new(left),
-                    list,
-                    negated: true,
-                });
-            } else if self.check(&Token::Like) {
-                self.advance();
-                let pattern = self.parse_primary_expr()?;
-                return Ok(Expr::Like {
-                    expr: Box::new(left),
-                    pattern: Box::new(pattern),
-                    negated: true,
-                });
+                return self.parse_in(left, true);
+            } else if self.check(&Token::Like) || self.check(&Token::ILike) {
+                return self.parse_like(left, true);
             }
         }
 
         // IN
         if self.check(&Token::In) {
-            self.advance();
-            self.expect(&Token::LParen)?;
-            let list = self.parse_expr_list()?;
-            self.expect(&Token::RParen)?;
-            return Ok(Expr::InList {
-                expr: Box::new(left),
-                list,
-                negated: false,
-            });
+            return self.parse_in(left, false);
         }
 
-        // LIKE
-        if self.check(&Token::Like) {
-            self.advance();
-            let pattern = self.parse_primary_expr()?;
-            return Ok(Expr::Like {
-                expr: Box::new(left),
-                pattern: Box::new(pattern),
-                negated: false,
-            });
+        // LIKE, ILIKE
+        if self.check(&Token::Like) || self.check(&Token::ILike) {
+            return self.parse_like(left, false);
         }
 
         // Comparison operators
@@ -894,6 +1405,47 @@ impl Parser {
         }
     }
 
+    /// Parse `IN (list)` or `IN (SELECT ...)`, after `left [NOT]`
+    fn parse_in(&mut self, left: Expr, negated: bool) -> Result<Expr> {
+        self.advance();
+        self.expect(&Token::LParen)?;
+        let expr = if self.at_query() {
+            Expr::InSubquery {
+                expr: Box::new(left),
+                subquery: Box::new(self.parse_select()?),
+                negated,
+            }
+        } else {
+            Expr::InList {
+                expr: Box::new(left),
+                list: self.parse_expr_list()?,
+                negated,
+            }
+        };
+        self.expect(&Token::RParen)?;
+        Ok(expr)
+    }
+
+    /// Parse `LIKE pattern [ESCAPE char]` or its ILIKE form, after `left [NOT]`
+    fn parse_like(&mut self, left: Expr, negated: bool) -> Result<Expr> {
+        let case_insensitive = self.check(&Token::ILike);
+        self.advance();
+        let pattern = self.parse_primary_expr()?;
+        let escape = if self.check(&Token::Escape) {
+            self.advance();
+            Some(Box::new(self.parse_primary_expr()?))
+        } else {
+            None
+        };
+        Ok(Expr::Like {
+            expr: Box::new(left),
+            pattern: Box::new(pattern),
+            escape,
+            negated,
+            case_insensitive,
+        })
+    }
+
     fn parse_additive_expr(&mut self) -> Result<Expr> {
         let mut left = self.parse_multiplicative_expr()?;
 
@@ -979,7 +1531,10 @@ impl Parser {
             }
             Token::FloatLiteral(n) => {
                 self.advance();
-                Ok(Expr::Literal(Literal::Float(n)))
+                // Fractional numbers are exact, as in `0.1 + 0.2 = 0.3`
+                Ok(Expr::Literal(
+                    Decimal::from_f64(n).map_or(Literal::Float(n), Literal::Decimal),
+                ))
             }
             Token::StringLiteral(s) => {
                 self.advance();
@@ -997,11 +1552,37 @@ impl Parser {
                 self.advance();
                 Ok(Expr::Literal(Literal::Null))
             }
+            Token::Default => {
+                self.advance();
+                Ok(Expr::Default)
+            }
+
+            // Typed literals: DATE '...', TIME '...', TIMESTAMP '...', INTERVAL '...'
+            Token::Date | Token::Time | Token::Timestamp | Token::Interval => {
+                self.parse_typed_literal()
+            }
+
+            // EXTRACT(field FROM expr)
+            Token::Extract => {
+                self.advance();
+                self.expect(&Token::LParen)?;
+                let field = self.expect_identifier()?;
+                field.parse::<DateTimeField>().map_err(Error::ParseError)?;
+                self.expect(&Token::From)?;
+                let source = self.parse_expr()?;
+                self.expect(&Token::RParen)?;
+                Ok(Expr::Function {
+                    name: "EXTRACT".to_string(),
+                    args: vec![Expr::Literal(Literal::String(field.to_uppercase())), source],
+                    distinct: false,
+                    over: None,
+                })
+            }
 
             // Parenthesized expression or subquery
             Token::LParen => {
                 self.advance();
-                if self.check(&Token::Select) {
+                if self.at_query() {
                     let subquery = self.parse_select()?;
                     self.expect(&Token::RParen)?;
                     Ok(Expr::Subquery(Box::new(subquery)))
@@ -1024,6 +1605,20 @@ impl Parser {
             // CASE
             Token::Case => self.parse_case_expr(),
 
+            // CAST(expr AS type)
+            Token::Cast => {
+                self.advance();
+                self.expect(&Token::LParen)?;
+                let expr = self.parse_expr()?;
+                self.expect(&Token::As)?;
+                let data_type = self.parse_data_type()?;
+                self.expect(&Token::RParen)?;
+                Ok(Expr::Cast {
+                    expr: Box::new(expr),
+                    data_type,
+                })
+            }
+
             // Aggregate functions
             Token::Count | Token::Sum | Token::Avg | Token::Min | Token::Max => {
                 self.parse_function()
@@ -1033,6 +1628,16 @@ impl Parser {
             Token::Identifier(name) => {
                 self.advance();
 
+                // CURRENT_DATE and friends take no parentheses
+                if is_niladic_function(&name) && !self.check(&Token::LParen) {
+                    return Ok(Expr::Function {
+                        name: name.to_uppercase(),
+                        args: vec![],
+                        distinct: false,
+                        over: None,
+                    });
+                }
+
                 // Check for function call
                 if self.check(&Token::LParen) {
                     self.position -= 1; // Go back
@@ -1061,6 +1666,43 @@ impl Parser {
         }
     }
 
+    fn parse_typed_literal(&mut self) -> Result<Expr> {
+        let kind = self.current().clone();
+        self.advance();
+        let text = match self.current().clone() {
+            Token::StringLiteral(s) => {
+                self.advance();
+                s
+            }
+            _ => {
+                return Err(Error::UnexpectedToken {
+                    expected: format!("string literal after {}", kind),
+                    found: format!("{}", self.current()),
+                })
+            }
+        };
+
+        let literal = match kind {
+            Token::Date => datetime::parse_date(&text).map(Literal::Date),
+            Token::Time => datetime::parse_time(&text).map(Literal::Time),
+            Token::Timestamp => datetime::parse_timestamp(&text).map(Literal::Timestamp),
+            _ => {
+                // INTERVAL '3' DAY: the unit may follow the string
+                let text = match self.current().clone() {
+                    Token::Identifier(unit)
+                        if format!("1 {}", unit).parse::<Interval>().is_ok() =>
+                    {
+                        self.advance();
+                        format!("{} {}", text, unit)
+                    }
+                    _ => text,
+                };
+                text.parse().map(Literal::Interval)
+            }
+        };
+        literal.map(Expr::Literal).map_err(Error::ParseError)
+    }
+
     fn parse_function(&mut self) -> Result<Expr> {
         let name = match self.current().clone() {
             Token::Count => {
@@ -1118,13 +1760,98 @@ impl Parser {
 
         self.expect(&Token::RParen)?;
 
+        let over = if self.check_keyword("OVER") {
+            self.advance();
+            Some(Box::new(self.parse_window_spec()?))
+        } else {
+            None
+        };
+
         Ok(Expr::Function {
             name,
             args,
             distinct,
+            over,
         })
     }
 
+    // ========== Window Specifications ==========
+
+    fn parse_window_spec(&mut self) -> Result<WindowSpec> {
+        self.expect(&Token::LParen)?;
+        let mut spec = WindowSpec::default();
+
+        if self.check_keyword("PARTITION") {
+            self.advance();
+            self.expect(&Token::By)?;
+            spec.partition_by = self.parse_expr_list()?;
+        }
+        if self.check(&Token::Order) {
+            self.advance();
+            self.expect(&Token::By)?;
+            spec.order_by = self.parse_order_by_list()?;
+        }
+        let units = if self.check_keyword("ROWS") {
+            Some(FrameUnits::Rows)
+        } else if self.check_keyword("RANGE") {
+            Some(FrameUnits::Range)
+        } else {
+            None
+        };
+        if let Some(units) = units {
+            self.advance();
+            spec.frame = Some(self.parse_window_frame(units)?);
+        }
+
+        self.expect(&Token::RParen)?;
+        Ok(spec)
+    }
+
+    fn parse_window_frame(&mut self, units: FrameUnits) -> Result<WindowFrame> {
+        // A lone start bound implies AND CURRENT ROW
+        if self.check(&Token::Between) {
+            self.advance();
+            let start = self.parse_frame_bound()?;
+            self.expect(&Token::And)?;
+            let end = self.parse_frame_bound()?;
+            Ok(WindowFrame { units, start, end })
+        } else {
+            let start = self.parse_frame_bound()?;
+            Ok(WindowFrame {
+                units,
+                start,
+                end: FrameBound::CurrentRow,
+            })
+        }
+    }
+
+    fn parse_frame_bound(&mut self) -> Result<FrameBound> {
+        if self.check_keyword("UNBOUNDED") {
+            self.advance();
+            if self.check_keyword("PRECEDING") {
+                self.advance();
+                return Ok(FrameBound::UnboundedPreceding);
+            }
+            self.expect_keyword("FOLLOWING")?;
+            return Ok(FrameBound::UnboundedFollowing);
+        }
+        if self.check_keyword("CURRENT") {
+            self.advance();
+            self.expect_keyword("ROW")?;
+            return Ok(FrameBound::CurrentRow);
+        }
+
+        let offset = self.expect_integer()?;
+        let offset = u64::try_from(offset)
+            .map_err(|_| Error::ParseError("frame offset must not be negative".to_string()))?;
+        if self.check_keyword("PRECEDING") {
+            self.advance();
+            return Ok(FrameBound::Preceding(offset));
+        }
+        self.expect_keyword("FOLLOWING")?;
+        Ok(FrameBound::Following(offset))
+    }
+
     fn parse_case_expr(&mut self) -> Result<Expr> {
         self.expect(&Token::Case)?;
 
@@ -1226,6 +1953,30 @@ impl Parser {
         }
     }
 
+    /// Check for a non-reserved keyword, which the lexer produces as an identifier
+    fn check_keyword(&self, keyword: &str) -> bool {
+        matches!(self.current(), Token::Identifier(name) if name.eq_ignore_ascii_case(keyword))
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.check_keyword(keyword) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(Error::UnexpectedToken {
+                expected: keyword.to_string(),
+                found: format!("{}", self.current()),
+            })
+        }
+    }
+
+    /// Skip an optional non-reserved keyword
+    fn skip_keyword(&mut self, keyword: &str) {
+        if self.check_keyword(keyword) {
+            self.advance();
+        }
+    }
+
     fn expect_identifier(&mut self) -> Result<String> {
         match self.current().clone() {
             Token::Identifier(name) => {
@@ -1252,6 +2003,18 @@ impl Parser {
         }
     }
 
+    /// An integer literal with an optional sign
+    fn expect_signed_integer(&mut self) -> Result<i64> {
+        if self.check(&Token::Minus) {
+            self.advance();
+            return Ok(-self.expect_integer()?);
+        }
+        if self.check(&Token::Plus) {
+            self.advance();
+        }
+        self.expect_integer()
+    }
+
     // ========== Transaction Statements ==========
 
     fn parse_begin(&mut self) -> Result<Statement> {
@@ -1345,6 +2108,49 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_foreign_keys() {
+        let mut parser = Parser::new(
+            "CREATE TABLE orders (
+                id INTEGER,
+                customer INTEGER REFERENCES customers ON DELETE CASCADE,
+                CONSTRAINT item_fk FOREIGN KEY (id) REFERENCES items (order_id)
+                    ON UPDATE SET NULL ON DELETE NO ACTION
+            )",
+        )
+        .unwrap();
+
+        let Statement::CreateTable(ct) = parser.parse().unwrap() else {
+            panic!("Expected CREATE TABLE statement");
+        };
+        assert_eq!(
+            ct.columns[1].references,
+            Some(References {
+                table: "customers".to_string(),
+                columns: vec![],
+                on_delete: ReferentialAction::Cascade,
+                on_update: ReferentialAction::NoAction,
+            })
+        );
+        assert_eq!(
+            ct.constraints,
+            vec![TableConstraint::ForeignKey {
+                name: Some("item_fk".to_string()),
+                columns: vec!["id".to_string()],
+                references: References {
+                    table: "items".to_string(),
+                    columns: vec!["order_id".to_string()],
+                    on_delete: ReferentialAction::NoAction,
+                    on_update: ReferentialAction::SetNull,
+                },
+            }]
+        );
+
+        let mut parser =
+            Parser::new("CREATE TABLE t (a INTEGER REFERENCES p ON DELETE DROP)").unwrap();
+        assert!(parser.parse().is_err());
+    }
+
     #[test]
     fn test_parse_insert() {
         let mut parser =
@@ -1360,6 +2166,34 @@ mod tests {
             }
             _ => panic!("Expected INSERT statement"),
         }
+
+        match Parser::new("INSERT INTO users DEFAULT VALUES")
+            .unwrap()
+            .parse()
+            .unwrap()
+        {
+            Statement::Insert(i) => {
+                assert_eq!(i.columns, Some(Vec::new()));
+                assert_eq!(i.values, vec![Vec::new()]);
+            }
+            _ => panic!("Expected INSERT statement"),
+        }
+
+        for sql in [
+            "INSERT INTO archive (id, name) SELECT id, name FROM users WHERE id > 1",
+            "INSERT INTO archive (id, name) (SELECT id, name FROM users)",
+            "INSERT INTO archive WITH u AS (SELECT * FROM users) SELECT * FROM u",
+            "INSERT INTO archive (SELECT * FROM users UNION SELECT * FROM guests)",
+        ] {
+            match Parser::new(sql).unwrap().parse().unwrap() {
+                Statement::Insert(i) => {
+                    assert_eq!(i.table_name, "archive");
+                    assert!(i.values.is_empty());
+                    assert!(i.query.is_some());
+                }
+                _ => panic!("Expected INSERT statement"),
+            }
+        }
     }
 
     #[test]
@@ -1393,6 +2227,50 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_returning() {
+        let returning = |sql: &str| match Parser::new(sql).unwrap().parse().unwrap() {
+            Statement::Insert(i) => i.returning,
+            Statement::Update(u) => u.returning,
+            Statement::Delete(d) => d.returning,
+            _ => panic!("Expected INSERT, UPDATE or DELETE statement"),
+        };
+
+        assert_eq!(
+            returning("INSERT INTO users (name) VALUES ('a') RETURNING *"),
+            vec![SelectItem::Wildcard]
+        );
+        assert_eq!(
+            returning("INSERT INTO users DEFAULT VALUES RETURNING id").len(),
+            1
+        );
+        // RETURNING ends the query of INSERT ... SELECT rather than naming its table
+        assert_eq!(
+            returning("INSERT INTO archive SELECT * FROM users RETURNING id, name AS n").len(),
+            2
+        );
+        assert_eq!(
+            returning("UPDATE users SET age = age + 1 WHERE id = 1 RETURNING age * 2 AS doubled"),
+            vec![SelectItem::Expr {
+                expr: Expr::BinaryOp {
+                    left: Box::new(Expr::Column("age".to_string().into())),
+                    op: BinaryOperator::Mul,
+                    right: Box::new(Expr::Literal(Literal::Integer(2))),
+                },
+                alias: Some("doubled".to_string()),
+            }]
+        );
+        assert_eq!(
+            returning("DELETE FROM users RETURNING users.*"),
+            vec![SelectItem::QualifiedWildcard("users".to_string())]
+        );
+        assert!(returning("DELETE FROM users").is_empty());
+        assert!(Parser::new("DELETE FROM users RETURNING")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
     #[test]
     fn test_parse_join() {
         let mut parser = Parser::new(
@@ -1413,4 +2291,265 @@ mod tests {
             _ => panic!("Expected SELECT statement"),
         }
     }
+
+    #[test]
+    fn test_parse_derived_table() {
+        let mut parser = Parser::new(
+            "SELECT t.n FROM (SELECT id AS n FROM users) AS t JOIN orders o ON t.n = o.id",
+        )
+        .unwrap();
+
+        match parser.parse().unwrap() {
+            Statement::Select(s) => {
+                let from = s.from.unwrap();
+                assert_eq!(from.table.alias, Some("t".to_string()));
+                assert!(from.table.subquery.is_some());
+                assert!(from.joins[0].table.subquery.is_none());
+            }
+            _ => panic!("Expected SELECT statement"),
+        }
+
+        assert!(Parser::new("SELECT * FROM (SELECT 1)")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_with_clause() {
+        let sql = "WITH RECURSIVE a (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM a), \
+                   b AS (SELECT n FROM a) SELECT * FROM b";
+        match Parser::new(sql).unwrap().parse().unwrap() {
+            Statement::Select(s) => {
+                let with = s.with.unwrap();
+                assert!(with.recursive);
+                assert_eq!(with.ctes.len(), 2);
+                assert_eq!(with.ctes[0].columns, vec!["n".to_string()]);
+                assert!(with.ctes[0].recursive.as_ref().is_some_and(|t| t.all));
+                assert!(with.ctes[1].recursive.is_none());
+            }
+            _ => panic!("Expected SELECT statement"),
+        }
+
+        // Without RECURSIVE a UNION stays part of the CTE's query
+        match Parser::new("WITH a AS (SELECT 1 UNION SELECT 2) SELECT * FROM a")
+            .unwrap()
+            .parse()
+            .unwrap()
+        {
+            Statement::Select(s) => {
+                let cte = &s.with.unwrap().ctes[0];
+                assert!(cte.recursive.is_none());
+                assert!(cte.query.set_operation.is_some());
+            }
+            _ => panic!("Expected SELECT statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_set_operations() {
+        let sql = "SELECT a FROM t UNION ALL SELECT b FROM u INTERSECT SELECT c FROM v \
+                   EXCEPT (SELECT d FROM w ORDER BY d LIMIT 1) ORDER BY a LIMIT 5";
+        let s = match Parser::new(sql).unwrap().parse().unwrap() {
+            Statement::Select(s) => s,
+            _ => panic!("Expected SELECT statement"),
+        };
+        assert_eq!(s.order_by.len(), 1);
+        assert!(s.limit.is_some());
+
+        // (t UNION ALL (u INTERSECT v)) EXCEPT (w)
+        let except = s.set_operation.unwrap();
+        assert_eq!(except.op, SetOperator::Except);
+        assert!(!except.all);
+        assert!(except.right.limit.is_some());
+        let union = except.left.set_operation.unwrap();
+        assert_eq!(union.op, SetOperator::Union);
+        assert!(union.all);
+        assert!(union.left.set_operation.is_none());
+        assert_eq!(
+            union.right.set_operation.map(|s| s.op),
+            Some(SetOperator::Intersect)
+        );
+
+        assert!(Parser::new("(SELECT 1 LIMIT 1) LIMIT 2")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_window_functions() {
+        let sql = "SELECT RANK() OVER (PARTITION BY a, b ORDER BY c DESC), \
+                   SUM(x) OVER (ORDER BY c ROWS 2 PRECEDING), \
+                   AVG(x) over (range between unbounded preceding and 1 following), \
+                   COUNT(*) OVER () FROM t";
+        let s = match Parser::new(sql).unwrap().parse().unwrap() {
+            Statement::Select(s) => s,
+            _ => panic!("Expected SELECT statement"),
+        };
+        let shown: Vec<String> = s
+            .columns
+            .iter()
+            .map(|item| match item {
+                SelectItem::Expr { expr, .. } => expr.to_string(),
+                _ => panic!("Expected expression"),
+            })
+            .collect();
+        assert_eq!(
+            shown,
+            vec![
+                "RANK() OVER (PARTITION BY a, b ORDER BY c DESC)",
+                "SUM(x) OVER (ORDER BY c ROWS BETWEEN 2 PRECEDING AND CURRENT ROW)",
+                "AVG(x) OVER (RANGE BETWEEN UNBOUNDED PRECEDING AND 1 FOLLOWING)",
+                "COUNT(*) OVER ()",
+            ]
+        );
+
+        for sql in [
+            "SELECT SUM(x) OVER (ROWS UNBOUNDED) FROM t",
+            "SELECT SUM(x) OVER (ROWS BETWEEN CURRENT AND 1 FOLLOWING) FROM t",
+            "SELECT SUM(x) OVER ORDER BY c FROM t",
+        ] {
+            assert!(Parser::new(sql).unwrap().parse().is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_parse_alter_table() {
+        let operation = |sql: &str| match Parser::new(sql).unwrap().parse().unwrap() {
+            Statement::AlterTable(alter) => {
+                assert_eq!(alter.table_name, "users");
+                alter.operation
+            }
+            _ => panic!("Expected ALTER TABLE statement"),
+        };
+
+        match operation("ALTER TABLE users ADD COLUMN age INT NOT NULL DEFAULT 0") {
+            AlterTableOperation::AddColumn(def) => {
+                assert_eq!(def.name, "age");
+                assert!(def.not_null);
+                assert!(def.default.is_some());
+            }
+            other => panic!("Expected ADD COLUMN, got {:?}", other),
+        }
+        assert_eq!(
+            operation("ALTER TABLE users DROP age"),
+            AlterTableOperation::DropColumn("age".to_string())
+        );
+        assert_eq!(
+            operation("alter table users rename column name to full_name"),
+            AlterTableOperation::RenameColumn {
+                old_name: "name".to_string(),
+                new_name: "full_name".to_string(),
+            }
+        );
+        assert_eq!(
+            operation("ALTER TABLE users RENAME TO people"),
+            AlterTableOperation::RenameTable("people".to_string())
+        );
+        assert_eq!(
+            operation("ALTER TABLE users ALTER COLUMN name DROP NOT NULL"),
+            AlterTableOperation::SetNotNull {
+                column: "name".to_string(),
+                not_null: false,
+            }
+        );
+
+        for sql in [
+            "ALTER TABLE users",
+            "ALTER TABLE users RENAME a b",
+            "ALTER TABLE users ALTER name SET NULL",
+        ] {
+            assert!(Parser::new(sql).unwrap().parse().is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn test_parse_sequences() {
+        match Parser::new(
+            "CREATE SEQUENCE s INCREMENT BY -2 MINVALUE -10 NO MAXVALUE START 0 CYCLE",
+        )
+        .unwrap()
+        .parse()
+        .unwrap()
+        {
+            Statement::CreateSequence(create) => {
+                assert_eq!(create.name, "s");
+                assert_eq!(
+                    create.options,
+                    SequenceOptions {
+                        increment: Some(-2),
+                        min_value: Some(-10),
+                        max_value: None,
+                        start: Some(0),
+                        cycle: true,
+                    }
+                );
+            }
+            _ => panic!("Expected CREATE SEQUENCE statement"),
+        }
+
+        let sql = "CREATE TABLE t (a SERIAL, b BIGINT GENERATED ALWAYS AS IDENTITY (START WITH 5))";
+        match Parser::new(sql).unwrap().parse().unwrap() {
+            Statement::CreateTable(create) => {
+                assert_eq!(create.columns[0].data_type, DataType::Integer);
+                assert_eq!(create.columns[0].identity, Some(IdentityDef::default()));
+                let identity = create.columns[1].identity.as_ref().unwrap();
+                assert!(identity.always);
+                assert_eq!(identity.options.start, Some(5));
+                assert!(create.columns[1].not_null);
+            }
+            _ => panic!("Expected CREATE TABLE statement"),
+        }
+
+        assert_eq!(
+            Parser::new("DROP SEQUENCE IF EXISTS s")
+                .unwrap()
+                .parse()
+                .unwrap(),
+            Statement::DropSequence(DropSequenceStatement {
+                name: "s".to_string(),
+                if_exists: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_datetime_literals() {
+        let mut parser = Parser::new(
+            "SELECT DATE '2024-03-01' + INTERVAL '2' DAY, EXTRACT(year FROM ts), CURRENT_DATE FROM t",
+        )
+        .unwrap();
+
+        match parser.parse().unwrap() {
+            Statement::Select(s) => {
+                let shown: Vec<String> = s
+                    .columns
+                    .iter()
+                    .map(|c| match c {
+                        SelectItem::Expr { expr, .. } => expr.to_string(),
+                        _ => panic!("Expected expression"),
+                    })
+                    .collect();
+                assert_eq!(
+                    shown,
+                    [
+                        "DATE '2024-03-01' + INTERVAL '2 days'",
+                        "EXTRACT(YEAR FROM ts)",
+                        "CURRENT_DATE"
+                    ]
+                );
+            }
+            _ => panic!("Expected SELECT statement"),
+        }
+
+        assert!(Parser::new("SELECT DATE '2024-02-30'")
+            .unwrap()
+            .parse()
+            .is_err());
+        assert!(Parser::new("SELECT EXTRACT(fortnight FROM ts) FROM t")
+            .unwrap()
+            .parse()
+            .is_err());
+    }
 }
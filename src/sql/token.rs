@@ -28,6 +28,9 @@ pub enum Token {
     Set,
     From,
     Where,
+    With,
+    Recursive,
+    Returning,
 
     // Clauses
     And,
@@ -55,6 +58,11 @@ pub enum Token {
     Limit,
     Offset,
 
+    // Set Operations
+    Union,
+    Intersect,
+    Except,
+
     // Constraints
     Primary,
     Foreign,
@@ -82,6 +90,7 @@ pub enum Token {
     Date,
     Time,
     Timestamp,
+    Interval,
 
     // Boolean Literals
     True,
@@ -101,6 +110,8 @@ pub enum Token {
     In,
     Between,
     Like,
+    ILike,
+    Escape,
     Is,
     Case,
     When,
@@ -109,6 +120,8 @@ pub enum Token {
     End,
     If,
     Analyze,
+    Cast,
+    Extract,
     Begin,
     Commit,
     Rollback,
@@ -191,6 +204,9 @@ impl Token {
                 | Token::Set
                 | Token::From
                 | Token::Where
+                | Token::With
+                | Token::Recursive
+                | Token::Returning
                 | Token::And
                 | Token::Or
                 | Token::Not
@@ -213,6 +229,9 @@ impl Token {
                 | Token::Having
                 | Token::Limit
                 | Token::Offset
+                | Token::Union
+                | Token::Intersect
+                | Token::Except
                 | Token::Primary
                 | Token::Foreign
                 | Token::Key
@@ -250,6 +269,9 @@ impl Token {
             "SET" => Some(Token::Set),
             "FROM" => Some(Token::From),
             "WHERE" => Some(Token::Where),
+            "WITH" => Some(Token::With),
+            "RECURSIVE" => Some(Token::Recursive),
+            "RETURNING" => Some(Token::Returning),
 
             // Clauses
             "AND" => Some(Token::And),
@@ -277,6 +299,11 @@ impl Token {
             "LIMIT" => Some(Token::Limit),
             "OFFSET" => Some(Token::Offset),
 
+            // Set Operations
+            "UNION" => Some(Token::Union),
+            "INTERSECT" => Some(Token::Intersect),
+            "EXCEPT" => Some(Token::Except),
+
             // Constraints
             "PRIMARY" => Some(Token::Primary),
             "FOREIGN" => Some(Token::Foreign),
@@ -304,6 +331,7 @@ impl Token {
             "DATE" => Some(Token::Date),
             "TIME" => Some(Token::Time),
             "TIMESTAMP" => Some(Token::Timestamp),
+            "INTERVAL" => Some(Token::Interval),
 
             // Boolean Literals
             "TRUE" => Some(Token::True),
@@ -323,6 +351,8 @@ impl Token {
             "IN" => Some(Token::In),
             "BETWEEN" => Some(Token::Between),
             "LIKE" => Some(Token::Like),
+            "ILIKE" => Some(Token::ILike),
+            "ESCAPE" => Some(Token::Escape),
             "IS" => Some(Token::Is),
             "CASE" => Some(Token::Case),
             "WHEN" => Some(Token::When),
@@ -331,6 +361,8 @@ impl Token {
             "END" => Some(Token::End),
             "IF" => Some(Token::If),
             "ANALYZE" => Some(Token::Analyze),
+            "CAST" => Some(Token::Cast),
+            "EXTRACT" => Some(Token::Extract),
             "BEGIN" => Some(Token::Begin),
             "COMMIT" => Some(Token::Commit),
             "ROLLBACK" => Some(Token::Rollback),
@@ -361,6 +393,9 @@ impl fmt::Display for Token {
             Token::Set => write!(f, "SET"),
             Token::From => write!(f, "FROM"),
             Token::Where => write!(f, "WHERE"),
+            Token::With => write!(f, "WITH"),
+            Token::Recursive => write!(f, "RECURSIVE"),
+            Token::Returning => write!(f, "RETURNING"),
             Token::And => write!(f, "AND"),
             Token::Or => write!(f, "OR"),
             Token::Not => write!(f, "NOT"),
@@ -383,6 +418,9 @@ impl fmt::Display for Token {
             Token::Having => write!(f, "HAVING"),
             Token::Limit => write!(f, "LIMIT"),
             Token::Offset => write!(f, "OFFSET"),
+            Token::Union => write!(f, "UNION"),
+            Token::Intersect => write!(f, "INTERSECT"),
+            Token::Except => write!(f, "EXCEPT"),
             Token::Primary => write!(f, "PRIMARY"),
             Token::Foreign => write!(f, "FOREIGN"),
             Token::Key => write!(f, "KEY"),
@@ -407,6 +445,7 @@ impl fmt::Display for Token {
             Token::Date => write!(f, "DATE"),
             Token::Time => write!(f, "TIME"),
             Token::Timestamp => write!(f, "TIMESTAMP"),
+            Token::Interval => write!(f, "INTERVAL"),
             Token::True => write!(f, "TRUE"),
             Token::False => write!(f, "FALSE"),
             Token::Count => write!(f, "COUNT"),
@@ -420,6 +459,8 @@ impl fmt::Display for Token {
             Token::In => write!(f, "IN"),
             Token::Between => write!(f, "BETWEEN"),
             Token::Like => write!(f, "LIKE"),
+            Token::ILike => write!(f, "ILIKE"),
+            Token::Escape => write!(f, "ESCAPE"),
             Token::Is => write!(f, "IS"),
             Token::Case => write!(f, "CASE"),
             Token::When => write!(f, "WHEN"),
@@ -428,6 +469,8 @@ impl fmt::Display for Token {
             Token::End => write!(f, "END"),
             Token::If => write!(f, "IF"),
             Token::Analyze => write!(f, "ANALYZE"),
+            Token::Cast => write!(f, "CAST"),
+            Token::Extract => write!(f, "EXTRACT"),
             Token::Begin => write!(f, "BEGIN"),
             Token::Commit => write!(f, "COMMIT"),
             Token::Rollback => write!(f, "ROLLBACK"),
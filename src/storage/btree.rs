@@ -10,6 +10,7 @@ use std::fmt::Debug;
 use serde::{Deserialize, Serialize};
 
 use super::buffer_pool::BufferPoolManager;
+use super::format::{self, FORMAT_VERSION, JSON_MAGIC};
 use super::heap::SlotId;
 use super::tuple::Value;
 use crate::error::{Error, Result};
@@ -47,7 +48,7 @@ impl IndexKey {
 
 impl PartialOrd for IndexKey {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.compare(other))
+        Some(self.cmp(other))
     }
 }
 
@@ -65,7 +66,7 @@ enum BPlusNode {
     /// Internal node with keys and child pointers
     Internal {
         keys: Vec<IndexKey>,
-        children: Vec<Box<BPlusNode>>,
+        children: Vec<BPlusNode>,
     },
     /// Leaf node with keys and record pointers
     Leaf {
@@ -89,6 +90,14 @@ pub struct BPlusTree {
     _buffer_pool: Option<Arc<Mutex<BufferPoolManager>>>,
 }
 
+/// On-disk envelope for an index file
+#[derive(Serialize)]
+struct IndexFile<'a> {
+    magic: String,
+    format_version: u32,
+    tree: &'a BPlusTree,
+}
+
 impl BPlusTree {
     /// Create a new empty B+ tree
     pub fn new(name: impl Into<String>, buffer_pool: Arc<Mutex<BufferPoolManager>>) -> Self {
@@ -100,22 +109,47 @@ impl BPlusTree {
         }
     }
 
-    /// Save the tree to disk
+    /// Save the tree to disk, wrapped in a format header
     pub fn save_to_disk(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
         let file = std::fs::File::create(path).map_err(|e| Error::Internal(e.to_string()))?;
-        serde_json::to_writer(file, self).map_err(|e| Error::Internal(e.to_string()))?;
+        let index_file = IndexFile {
+            magic: JSON_MAGIC.to_string(),
+            format_version: FORMAT_VERSION,
+            tree: self,
+        };
+        serde_json::to_writer(file, &index_file).map_err(|e| Error::Internal(e.to_string()))?;
         Ok(())
     }
 
     /// Load the tree from disk
+    ///
+    /// Index files written before format headers existed are rewritten with one.
     pub fn load_from_disk(
         path: impl AsRef<std::path::Path>,
         buffer_pool: Arc<Mutex<BufferPoolManager>>,
     ) -> Result<Self> {
+        let path = path.as_ref();
         let file = std::fs::File::open(path).map_err(|e| Error::Internal(e.to_string()))?;
-        let mut tree: BPlusTree =
+        let mut json: serde_json::Value =
             serde_json::from_reader(file).map_err(|e| Error::Internal(e.to_string()))?;
+
+        let legacy = json.get("magic").is_none();
+        if !legacy {
+            if json["magic"] != JSON_MAGIC {
+                return Err(Error::InvalidFileHeader(path.display().to_string()));
+            }
+            let version = json["format_version"].as_u64().unwrap_or(0) as u32;
+            format::check_version(version)?;
+            json = json["tree"].take();
+        }
+
+        let mut tree: BPlusTree =
+            serde_json::from_value(json).map_err(|e| Error::Internal(e.to_string()))?;
         tree._buffer_pool = Some(buffer_pool);
+
+        if legacy {
+            tree.save_to_disk(path)?;
+        }
         Ok(tree)
     }
 
@@ -136,7 +170,7 @@ impl BPlusTree {
             // Root split, create new root
             self.root = Some(Box::new(BPlusNode::Internal {
                 keys: vec![mid_key],
-                children: vec![root, new_node],
+                children: vec![*root, *new_node],
             }));
         } else {
             self.root = Some(root);
@@ -181,7 +215,7 @@ impl BPlusTree {
                     self.insert_recursive(&mut children[pos], key, value)
                 {
                     keys.insert(pos, mid_key);
-                    children.insert(pos + 1, new_node);
+                    children.insert(pos + 1, *new_node);
 
                     if keys.len() > ORDER {
                         let mid = keys.len() / 2;
@@ -206,9 +240,9 @@ impl BPlusTree {
 
     /// Search for a key in the tree
     pub fn search(&self, key: &IndexKey) -> Option<SlotId> {
-        let mut curr = self.root.as_ref()?;
+        let mut curr: &BPlusNode = self.root.as_ref()?;
         loop {
-            match curr.as_ref() {
+            match curr {
                 BPlusNode::Leaf { keys, values, .. } => {
                     if let Ok(pos) = keys.binary_search(key) {
                         return Some(values[pos]);
@@ -290,8 +324,8 @@ impl BPlusTree {
         match node {
             BPlusNode::Leaf { keys, values, .. } => {
                 for (i, key) in keys.iter().enumerate() {
-                    let too_small = start.map_or(false, |s| key < s);
-                    let too_large = end.map_or(false, |e| key > e);
+                    let too_small = start.is_some_and(|s| key < s);
+                    let too_large = end.is_some_and(|e| key > e);
                     if !too_small && !too_large {
                         result.push((key.clone(), values[i]));
                     }
@@ -304,8 +338,8 @@ impl BPlusTree {
                 let end_pos =
                     end.map_or(keys.len(), |e| keys.binary_search(e).unwrap_or_else(|e| e));
 
-                for i in start_pos..=end_pos {
-                    self.range_scan_recursive(&children[i], start, end, result);
+                for child in &children[start_pos..=end_pos] {
+                    self.range_scan_recursive(child, start, end, result);
                 }
             }
         }
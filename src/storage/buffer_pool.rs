@@ -7,7 +7,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use super::disk::DiskManager;
-use super::page::{Page, PageId, PAGE_SIZE};
+use super::page::{Page, PageId};
 use crate::error::{Error, Result};
 
 /// A global page identifier (table_id, page_id)
@@ -41,9 +41,10 @@ impl BufferPoolManager {
         let mut frames = Vec::with_capacity(pool_size);
         let mut frame_ids = Vec::with_capacity(pool_size);
         let mut free_list = Vec::with_capacity(pool_size);
+        let page_size = disk_manager.page_size();
 
         for i in 0..pool_size {
-            frames.push(Page::new(0)); // Dummy page_id, will be updated
+            frames.push(Page::with_size(0, page_size)); // Dummy page_id, will be updated
             frame_ids.push(None);
             free_list.push(i);
         }
@@ -59,6 +60,11 @@ impl BufferPoolManager {
         }
     }
 
+    /// Get the page size of the underlying database
+    pub fn page_size(&self) -> usize {
+        self.disk_manager.page_size()
+    }
+
     pub fn fetch_page(&mut self, global_id: GlobalPageId) -> Result<usize> {
         if let Some(&index) = self.page_table.get(&global_id) {
             self.pin_page(index);
@@ -73,7 +79,7 @@ impl BufferPoolManager {
         }
 
         // Read page from disk
-        let mut data = vec![0u8; PAGE_SIZE];
+        let mut data = vec![0u8; self.page_size()];
         self.disk_manager
             .read_page(global_id.table_id, global_id.page_id, &mut data)?;
 
@@ -96,7 +102,7 @@ impl BufferPoolManager {
             self.page_table.remove(&old_id);
         }
 
-        self.frames[index] = Page::new(page_id);
+        self.frames[index] = Page::with_size(page_id, self.page_size());
         self.frames[index].set_dirty(true);
         self.frame_ids[index] = Some(global_id);
         self.page_table.insert(global_id, index);
@@ -141,7 +147,7 @@ impl BufferPoolManager {
                 self.disk_manager.write_page(
                     global_id.table_id,
                     global_id.page_id,
-                    &self.frames[index].to_bytes(),
+                    self.frames[index].to_bytes(),
                 )?;
                 self.frames[index].set_dirty(false);
             }
@@ -0,0 +1,654 @@
+//! Calendar arithmetic for DATE, TIME, TIMESTAMP and INTERVAL values
+//!
+//! Dates are days since 1970-01-01, times are milliseconds since midnight and
+//! timestamps are milliseconds since the epoch, all in UTC on the proleptic
+//! Gregorian calendar. This module parses and formats them in ISO 8601 form
+//! and implements EXTRACT, DATE_TRUNC and interval arithmetic.
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Milliseconds in a second
+pub const MS_PER_SECOND: i64 = 1_000;
+/// Milliseconds in a minute
+pub const MS_PER_MINUTE: i64 = 60 * MS_PER_SECOND;
+/// Milliseconds in an hour
+pub const MS_PER_HOUR: i64 = 60 * MS_PER_MINUTE;
+/// Milliseconds in a day
+pub const MS_PER_DAY: i64 = 24 * MS_PER_HOUR;
+
+/// Days assumed per month when intervals are compared
+const DAYS_PER_MONTH: i64 = 30;
+
+// ========== Calendar ==========
+
+/// Days since the epoch of a civil date
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = (month as i64 + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Civil date (year, month, day) of a day number
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Whether a year is a leap year
+pub fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Number of days in a month
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Add months to a day number, clamping to the last day of the target month
+pub fn add_months(days: i64, months: i64) -> i64 {
+    let (year, month, day) = civil_from_days(days);
+    let total = year * 12 + (month as i64 - 1) + months;
+    let (year, month) = (total.div_euclid(12), total.rem_euclid(12) as u32 + 1);
+    days_from_civil(year, month, day.min(days_in_month(year, month)))
+}
+
+/// Today's date in UTC
+pub fn current_date() -> i32 {
+    (current_timestamp().div_euclid(MS_PER_DAY)) as i32
+}
+
+/// The current time of day in UTC
+pub fn current_time() -> i64 {
+    current_timestamp().rem_euclid(MS_PER_DAY)
+}
+
+/// The current timestamp in UTC
+pub fn current_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+// ========== Parsing and Formatting ==========
+
+fn parse_number<T: FromStr>(s: &str, what: &str, input: &str) -> Result<T, String> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("invalid {} '{}'", what, input));
+    }
+    s.parse()
+        .map_err(|_| format!("invalid {} '{}'", what, input))
+}
+
+/// Parse `YYYY-MM-DD` into days since the epoch
+pub fn parse_date(s: &str) -> Result<i32, String> {
+    let invalid = || format!("invalid date '{}'", s);
+    let mut parts = s.trim().splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    let year: i64 = parse_number(year, "date", s)?;
+    let month: u32 = parse_number(month, "date", s)?;
+    let day: u32 = parse_number(day, "date", s)?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+    i32::try_from(days_from_civil(year, month, day)).map_err(|_| invalid())
+}
+
+/// Parse `HH:MM[:SS[.fff]]` into milliseconds since midnight
+pub fn parse_time(s: &str) -> Result<i64, String> {
+    let invalid = || format!("invalid time '{}'", s);
+    let trimmed = s.trim();
+    let (clock, fraction) = trimmed.split_once('.').unwrap_or((trimmed, ""));
+    let parts: Vec<&str> = clock.split(':').collect();
+    if !(2..=3).contains(&parts.len()) || (parts.len() == 2 && !fraction.is_empty()) {
+        return Err(invalid());
+    }
+    let hour: i64 = parse_number(parts[0], "time", s)?;
+    let minute: i64 = parse_number(parts[1], "time", s)?;
+    let second: i64 = match parts.get(2) {
+        Some(p) => parse_number(p, "time", s)?,
+        None => 0,
+    };
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(invalid());
+    }
+    Ok(hour * MS_PER_HOUR
+        + minute * MS_PER_MINUTE
+        + second * MS_PER_SECOND
+        + parse_millis(fraction, s)?)
+}
+
+/// Milliseconds of a fractional-second string, ignoring digits past the third
+fn parse_millis(fraction: &str, input: &str) -> Result<i64, String> {
+    if fraction.is_empty() {
+        return Ok(0);
+    }
+    let digits: String = fraction.chars().chain("000".chars()).take(3).collect();
+    parse_number::<i64>(fraction, "time", input)?;
+    parse_number(&digits, "time", input)
+}
+
+/// Parse `YYYY-MM-DD[ HH:MM[:SS[.fff]]]` (or with a `T` separator) into milliseconds since the epoch
+pub fn parse_timestamp(s: &str) -> Result<i64, String> {
+    let trimmed = s.trim().trim_end_matches('Z');
+    let (date, time) = match trimmed.find([' ', 'T']) {
+        Some(pos) => (&trimmed[..pos], Some(&trimmed[pos + 1..])),
+        None => (trimmed, None),
+    };
+    let days = parse_date(date).map_err(|_| format!("invalid timestamp '{}'", s))? as i64;
+    let millis = match time {
+        Some(time) => parse_time(time).map_err(|_| format!("invalid timestamp '{}'", s))?,
+        None => 0,
+    };
+    Ok(days * MS_PER_DAY + millis)
+}
+
+/// Format days since the epoch as `YYYY-MM-DD`
+pub fn format_date(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Format milliseconds since midnight as `HH:MM:SS[.fff]`
+pub fn format_time(millis: i64) -> String {
+    let hours = millis / MS_PER_HOUR;
+    let minutes = millis % MS_PER_HOUR / MS_PER_MINUTE;
+    let seconds = millis % MS_PER_MINUTE / MS_PER_SECOND;
+    let fraction = millis % MS_PER_SECOND;
+    if fraction == 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+    } else {
+        format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, seconds, fraction)
+    }
+}
+
+/// Format milliseconds since the epoch as `YYYY-MM-DD HH:MM:SS[.fff]`
+pub fn format_timestamp(millis: i64) -> String {
+    let days = millis.div_euclid(MS_PER_DAY);
+    format!(
+        "{} {}",
+        format_date(days as i32),
+        format_time(millis.rem_euclid(MS_PER_DAY))
+    )
+}
+
+// ========== Intervals ==========
+
+/// A span of time; months are kept apart from days because a month is 28
+/// to 31 days long depending on where the interval is applied
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Interval {
+    /// Whole months (a year is 12 months)
+    pub months: i32,
+    /// Whole days
+    pub days: i32,
+    /// Time part in milliseconds
+    pub millis: i64,
+}
+
+impl Interval {
+    /// Create an interval
+    pub fn new(months: i32, days: i32, millis: i64) -> Self {
+        Self {
+            months,
+            days,
+            millis,
+        }
+    }
+
+    /// Negate every component
+    pub fn neg(&self) -> Self {
+        Self::new(-self.months, -self.days, -self.millis)
+    }
+
+    /// Add component-wise
+    pub fn checked_add(&self, other: &Interval) -> Option<Self> {
+        Some(Self::new(
+            self.months.checked_add(other.months)?,
+            self.days.checked_add(other.days)?,
+            self.millis.checked_add(other.millis)?,
+        ))
+    }
+
+    /// Length in milliseconds, counting a month as 30 days
+    pub fn approximate_millis(&self) -> i128 {
+        (self.months as i128 * DAYS_PER_MONTH as i128 + self.days as i128) * MS_PER_DAY as i128
+            + self.millis as i128
+    }
+
+    /// Shift a timestamp by this interval: months first, then days, then time
+    pub fn add_to_timestamp(&self, timestamp: i64) -> Option<i64> {
+        let days = timestamp.div_euclid(MS_PER_DAY);
+        let time = timestamp.rem_euclid(MS_PER_DAY);
+        let days = add_months(days, self.months as i64).checked_add(self.days as i64)?;
+        days.checked_mul(MS_PER_DAY)?
+            .checked_add(time)?
+            .checked_add(self.millis)
+    }
+}
+
+impl PartialEq for Interval {
+    fn eq(&self, other: &Self) -> bool {
+        self.approximate_millis() == other.approximate_millis()
+    }
+}
+
+impl Eq for Interval {}
+
+impl Ord for Interval {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.approximate_millis().cmp(&other.approximate_millis())
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Interval {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.approximate_millis().hash(state);
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+        let plural = |n: i64, unit: &str| {
+            if n.abs() == 1 {
+                format!("{} {}", n, unit)
+            } else {
+                format!("{} {}s", n, unit)
+            }
+        };
+        let (years, months) = (self.months / 12, self.months % 12);
+        if years != 0 {
+            parts.push(plural(years as i64, "year"));
+        }
+        if months != 0 {
+            parts.push(plural(months as i64, "mon"));
+        }
+        if self.days != 0 {
+            parts.push(plural(self.days as i64, "day"));
+        }
+        if self.millis != 0 || parts.is_empty() {
+            let sign = if self.millis < 0 { "-" } else { "" };
+            parts.push(format!("{}{}", sign, format_time(self.millis.abs())));
+        }
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+impl FromStr for Interval {
+    type Err = String;
+
+    /// Parse `N unit [N unit ...] [[-]HH:MM[:SS[.fff]]]`, e.g. `1 year 2 months` or `3 days 04:00`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid interval '{}'", s);
+        let mut interval = Interval::default();
+        let mut words = s.split_whitespace().peekable();
+        if words.peek().is_none() {
+            return Err(invalid());
+        }
+
+        while let Some(word) = words.next() {
+            if word.contains(':') {
+                let (negative, clock) = match word.strip_prefix('-') {
+                    Some(rest) => (true, rest),
+                    None => (false, word.strip_prefix('+').unwrap_or(word)),
+                };
+                // Intervals may exceed 24 hours, so parse the clock by hand
+                let (clock, fraction) = clock.split_once('.').unwrap_or((clock, ""));
+                let parts: Vec<&str> = clock.split(':').collect();
+                if !(2..=3).contains(&parts.len()) {
+                    return Err(invalid());
+                }
+                let mut millis = 0i64;
+                for (part, unit) in parts
+                    .iter()
+                    .zip([MS_PER_HOUR, MS_PER_MINUTE, MS_PER_SECOND])
+                {
+                    let n: i64 = parse_number(part, "interval", s)?;
+                    millis = millis
+                        .checked_add(n.checked_mul(unit).ok_or_else(invalid)?)
+                        .ok_or_else(invalid)?;
+                }
+                millis += parse_millis(fraction, s).map_err(|_| invalid())?;
+                interval.millis += if negative { -millis } else { millis };
+                continue;
+            }
+
+            let amount: f64 = word.parse().map_err(|_| invalid())?;
+            let unit = words.next().ok_or_else(invalid)?.to_lowercase();
+            let whole = |n: f64| -> Result<i32, String> {
+                if n.fract() != 0.0 || n.abs() > i32::MAX as f64 {
+                    return Err(invalid());
+                }
+                Ok(n as i32)
+            };
+            let millis = |n: f64, per: i64| (n * per as f64).round() as i64;
+            match unit.trim_end_matches('s') {
+                "year" | "yr" => interval.months += whole(amount * 12.0)?,
+                "month" | "mon" => interval.months += whole(amount)?,
+                "week" | "w" => {
+                    interval.days += whole((amount * 7.0).trunc())?;
+                    interval.millis += millis((amount * 7.0).fract(), MS_PER_DAY);
+                }
+                "day" | "d" => {
+                    interval.days += whole(amount.trunc())?;
+                    interval.millis += millis(amount.fract(), MS_PER_DAY);
+                }
+                "hour" | "hr" | "h" => interval.millis += millis(amount, MS_PER_HOUR),
+                "minute" | "min" | "m" => interval.millis += millis(amount, MS_PER_MINUTE),
+                "second" | "sec" => interval.millis += millis(amount, MS_PER_SECOND),
+                "millisecond" | "msec" => interval.millis += millis(amount, 1),
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(interval)
+    }
+}
+
+// ========== Fields ==========
+
+/// A date or time field named by EXTRACT and DATE_TRUNC
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateTimeField {
+    Millennium,
+    Century,
+    Decade,
+    Year,
+    Quarter,
+    Month,
+    /// ISO 8601 week number
+    Week,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Millisecond,
+    /// Day of the week, Sunday is 0
+    DayOfWeek,
+    /// Day of the year, starting at 1
+    DayOfYear,
+    /// Seconds since the epoch
+    Epoch,
+}
+
+impl FromStr for DateTimeField {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let field = match s.trim().to_uppercase().as_str() {
+            "MILLENNIUM" | "MILLENNIUMS" => DateTimeField::Millennium,
+            "CENTURY" | "CENTURIES" => DateTimeField::Century,
+            "DECADE" | "DECADES" => DateTimeField::Decade,
+            "YEAR" | "YEARS" => DateTimeField::Year,
+            "QUARTER" => DateTimeField::Quarter,
+            "MONTH" | "MONTHS" => DateTimeField::Month,
+            "WEEK" | "WEEKS" => DateTimeField::Week,
+            "DAY" | "DAYS" => DateTimeField::Day,
+            "HOUR" | "HOURS" => DateTimeField::Hour,
+            "MINUTE" | "MINUTES" => DateTimeField::Minute,
+            "SECOND" | "SECONDS" => DateTimeField::Second,
+            "MILLISECOND" | "MILLISECONDS" => DateTimeField::Millisecond,
+            "DOW" => DateTimeField::DayOfWeek,
+            "DOY" => DateTimeField::DayOfYear,
+            "EPOCH" => DateTimeField::Epoch,
+            _ => return Err(format!("unknown date/time field '{}'", s)),
+        };
+        Ok(field)
+    }
+}
+
+impl fmt::Display for DateTimeField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DateTimeField::Millennium => "MILLENNIUM",
+            DateTimeField::Century => "CENTURY",
+            DateTimeField::Decade => "DECADE",
+            DateTimeField::Year => "YEAR",
+            DateTimeField::Quarter => "QUARTER",
+            DateTimeField::Month => "MONTH",
+            DateTimeField::Week => "WEEK",
+            DateTimeField::Day => "DAY",
+            DateTimeField::Hour => "HOUR",
+            DateTimeField::Minute => "MINUTE",
+            DateTimeField::Second => "SECOND",
+            DateTimeField::Millisecond => "MILLISECOND",
+            DateTimeField::DayOfWeek => "DOW",
+            DateTimeField::DayOfYear => "DOY",
+            DateTimeField::Epoch => "EPOCH",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl DateTimeField {
+    /// Whether the field is part of the time of day
+    pub fn is_time_field(&self) -> bool {
+        matches!(
+            self,
+            DateTimeField::Hour
+                | DateTimeField::Minute
+                | DateTimeField::Second
+                | DateTimeField::Millisecond
+                | DateTimeField::Epoch
+        )
+    }
+
+    /// Whether EXTRACT returns the field with a fractional part (in milliseconds)
+    pub fn is_fractional(&self) -> bool {
+        matches!(self, DateTimeField::Second | DateTimeField::Epoch)
+    }
+}
+
+/// ISO 8601 week number of a day
+fn iso_week(days: i64) -> i64 {
+    let (year, _, _) = civil_from_days(days);
+    // Monday is 1, Sunday is 7
+    let weekday = (days + 3).rem_euclid(7) + 1;
+    let ordinal = days - days_from_civil(year, 1, 1) + 1;
+    let week = (ordinal - weekday + 10) / 7;
+    let weeks_in = |y: i64| {
+        let p =
+            |y: i64| (y + y.div_euclid(4) - y.div_euclid(100) + y.div_euclid(400)).rem_euclid(7);
+        if p(y) == 4 || p(y - 1) == 3 {
+            53
+        } else {
+            52
+        }
+    };
+    if week < 1 {
+        weeks_in(year - 1)
+    } else if week > weeks_in(year) {
+        1
+    } else {
+        week
+    }
+}
+
+/// Value of a field of a timestamp; SECOND and EPOCH are in milliseconds
+pub fn extract_from_timestamp(field: DateTimeField, timestamp: i64) -> i64 {
+    let days = timestamp.div_euclid(MS_PER_DAY);
+    let time = timestamp.rem_euclid(MS_PER_DAY);
+    let (year, month, day) = civil_from_days(days);
+    match field {
+        DateTimeField::Millennium => (year - 1).div_euclid(1000) + 1,
+        DateTimeField::Century => (year - 1).div_euclid(100) + 1,
+        DateTimeField::Decade => year.div_euclid(10),
+        DateTimeField::Year => year,
+        DateTimeField::Quarter => (month as i64 - 1) / 3 + 1,
+        DateTimeField::Month => month as i64,
+        DateTimeField::Week => iso_week(days),
+        DateTimeField::Day => day as i64,
+        DateTimeField::DayOfWeek => (days + 4).rem_euclid(7),
+        DateTimeField::DayOfYear => days - days_from_civil(year, 1, 1) + 1,
+        DateTimeField::Epoch => timestamp,
+        _ => extract_from_time(field, time).unwrap_or(0),
+    }
+}
+
+/// Value of a time-of-day field; SECOND and EPOCH are in milliseconds
+pub fn extract_from_time(field: DateTimeField, time: i64) -> Option<i64> {
+    match field {
+        DateTimeField::Hour => Some(time / MS_PER_HOUR),
+        DateTimeField::Minute => Some(time % MS_PER_HOUR / MS_PER_MINUTE),
+        DateTimeField::Second => Some(time % MS_PER_MINUTE),
+        DateTimeField::Millisecond => Some(time % MS_PER_MINUTE),
+        DateTimeField::Epoch => Some(time),
+        _ => None,
+    }
+}
+
+/// Value of a field of an interval; SECOND and EPOCH are in milliseconds
+pub fn extract_from_interval(field: DateTimeField, interval: &Interval) -> Option<i64> {
+    let years = interval.months as i64 / 12;
+    match field {
+        DateTimeField::Millennium => Some(years / 1000),
+        DateTimeField::Century => Some(years / 100),
+        DateTimeField::Decade => Some(years / 10),
+        DateTimeField::Year => Some(years),
+        DateTimeField::Quarter => Some((interval.months as i64 % 12) / 3 + 1),
+        DateTimeField::Month => Some(interval.months as i64 % 12),
+        DateTimeField::Day => Some(interval.days as i64),
+        DateTimeField::Hour => Some(interval.millis / MS_PER_HOUR),
+        DateTimeField::Minute => Some(interval.millis % MS_PER_HOUR / MS_PER_MINUTE),
+        DateTimeField::Second | DateTimeField::Millisecond => Some(interval.millis % MS_PER_MINUTE),
+        DateTimeField::Epoch => i64::try_from(interval.approximate_millis()).ok(),
+        DateTimeField::Week | DateTimeField::DayOfWeek | DateTimeField::DayOfYear => None,
+    }
+}
+
+/// Truncate a timestamp to the start of the given field, or `None` if the
+/// field does not name a unit of time
+pub fn truncate_timestamp(field: DateTimeField, timestamp: i64) -> Option<i64> {
+    let days = timestamp.div_euclid(MS_PER_DAY);
+    let (year, month, _) = civil_from_days(days);
+    let start_of_year = |year: i64| days_from_civil(year, 1, 1) * MS_PER_DAY;
+    let truncate = |unit: i64| timestamp - timestamp.rem_euclid(unit);
+    let truncated = match field {
+        DateTimeField::Millennium => start_of_year((year - 1).div_euclid(1000) * 1000 + 1),
+        DateTimeField::Century => start_of_year((year - 1).div_euclid(100) * 100 + 1),
+        DateTimeField::Decade => start_of_year(year.div_euclid(10) * 10),
+        DateTimeField::Year => start_of_year(year),
+        DateTimeField::Quarter => days_from_civil(year, (month - 1) / 3 * 3 + 1, 1) * MS_PER_DAY,
+        DateTimeField::Month => days_from_civil(year, month, 1) * MS_PER_DAY,
+        // Weeks start on Monday
+        DateTimeField::Week => (days - (days + 3).rem_euclid(7)) * MS_PER_DAY,
+        DateTimeField::Day => truncate(MS_PER_DAY),
+        DateTimeField::Hour => truncate(MS_PER_HOUR),
+        DateTimeField::Minute => truncate(MS_PER_MINUTE),
+        DateTimeField::Second => truncate(MS_PER_SECOND),
+        DateTimeField::Millisecond => timestamp,
+        DateTimeField::DayOfWeek | DateTimeField::DayOfYear | DateTimeField::Epoch => return None,
+    };
+    Some(truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_round_trip() {
+        for days in [-719_468, -1, 0, 11_016, 19_358, 2_932_896] {
+            let (y, m, d) = civil_from_days(days);
+            assert_eq!(days_from_civil(y, m, d), days);
+        }
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+    }
+
+    #[test]
+    fn test_parse_and_format() {
+        let date = parse_date("2024-02-29").unwrap();
+        assert_eq!(format_date(date), "2024-02-29");
+        assert!(parse_date("2023-02-29").is_err());
+        assert!(parse_date("2024-13-01").is_err());
+
+        assert_eq!(
+            format_time(parse_time("23:59:58.5").unwrap()),
+            "23:59:58.500"
+        );
+        assert!(parse_time("24:00:00").is_err());
+
+        let ts = parse_timestamp("1969-12-31T23:00:00Z").unwrap();
+        assert_eq!(ts, -MS_PER_HOUR);
+        assert_eq!(format_timestamp(ts), "1969-12-31 23:00:00");
+        assert_eq!(
+            format_timestamp(parse_timestamp("2024-01-05").unwrap()),
+            "2024-01-05 00:00:00"
+        );
+    }
+
+    #[test]
+    fn test_interval_parse_and_arithmetic() {
+        let interval: Interval = "1 year 2 months 3 days 04:05:06".parse().unwrap();
+        assert_eq!(interval.months, 14);
+        assert_eq!(interval.days, 3);
+        assert_eq!(interval.to_string(), "1 year 2 mons 3 days 04:05:06");
+        assert_eq!(interval.to_string().parse::<Interval>().unwrap(), interval);
+        assert_eq!(
+            "1.5 hours".parse::<Interval>().unwrap().millis,
+            90 * MS_PER_MINUTE
+        );
+        assert!("3 fortnights".parse::<Interval>().is_err());
+
+        // Month arithmetic clamps to the end of the month
+        let jan31 = parse_timestamp("2024-01-31 10:00:00").unwrap();
+        let month = Interval::new(1, 0, 0);
+        assert_eq!(
+            format_timestamp(month.add_to_timestamp(jan31).unwrap()),
+            "2024-02-29 10:00:00"
+        );
+        assert_eq!(Interval::new(1, 0, 0), Interval::new(0, 30, 0));
+    }
+
+    #[test]
+    fn test_extract_and_truncate() {
+        let ts = parse_timestamp("2024-08-15 13:45:30.250").unwrap();
+        assert_eq!(extract_from_timestamp(DateTimeField::Year, ts), 2024);
+        assert_eq!(extract_from_timestamp(DateTimeField::Quarter, ts), 3);
+        assert_eq!(extract_from_timestamp(DateTimeField::Hour, ts), 13);
+        assert_eq!(extract_from_timestamp(DateTimeField::Second, ts), 30_250);
+        // 2024-08-15 is a Thursday in ISO week 33
+        assert_eq!(extract_from_timestamp(DateTimeField::DayOfWeek, ts), 4);
+        assert_eq!(extract_from_timestamp(DateTimeField::Week, ts), 33);
+        let jan1 = parse_timestamp("2021-01-01").unwrap();
+        assert_eq!(extract_from_timestamp(DateTimeField::Week, jan1), 53);
+
+        let trunc = |field| format_timestamp(truncate_timestamp(field, ts).unwrap());
+        assert_eq!(trunc(DateTimeField::Month), "2024-08-01 00:00:00");
+        assert_eq!(trunc(DateTimeField::Quarter), "2024-07-01 00:00:00");
+        assert_eq!(trunc(DateTimeField::Week), "2024-08-12 00:00:00");
+        assert_eq!(trunc(DateTimeField::Hour), "2024-08-15 13:00:00");
+        assert!(truncate_timestamp(DateTimeField::Epoch, ts).is_none());
+    }
+}
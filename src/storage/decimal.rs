@@ -0,0 +1,384 @@
+//! Fixed-point decimal numbers for ArcDB
+//!
+//! A [`Decimal`] is an integer mantissa scaled by a power of ten, so values
+//! such as `0.10` are stored and added exactly. Arithmetic follows the usual
+//! SQL rules for the scale of NUMERIC results and fails instead of losing
+//! digits when a result exceeds [`MAX_PRECISION`].
+
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+/// Maximum number of significant digits in a decimal
+pub const MAX_PRECISION: u32 = 38;
+
+/// Minimum scale of a division result
+pub const MIN_DIVISION_SCALE: u32 = 6;
+
+/// A fixed-point decimal: `mantissa * 10^-scale`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Create a decimal, or `None` if it has more than [`MAX_PRECISION`] digits
+    pub fn new(mantissa: i128, scale: u32) -> Option<Self> {
+        let decimal = Self { mantissa, scale };
+        (scale <= MAX_PRECISION && decimal.digits() <= MAX_PRECISION).then_some(decimal)
+    }
+
+    /// Create a decimal with scale 0 from an integer
+    pub fn from_i64(value: i64) -> Self {
+        Self {
+            mantissa: value as i128,
+            scale: 0,
+        }
+    }
+
+    /// Convert a float through its shortest decimal representation
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        value.to_string().parse().ok()
+    }
+
+    /// Unscaled integer value
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    /// Number of digits after the decimal point
+    pub fn scale(&self) -> u32 {
+        self.scale
+    }
+
+    /// Total number of significant digits, counting the fractional digits
+    pub fn precision(&self) -> u32 {
+        self.digits().max(self.scale)
+    }
+
+    /// Number of digits before the decimal point
+    pub fn integer_digits(&self) -> u32 {
+        self.digits().saturating_sub(self.scale)
+    }
+
+    /// Whether the value is zero
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    /// Change the scale, rounding half away from zero when digits are dropped
+    pub fn rescale(&self, scale: u32) -> Option<Self> {
+        if scale > MAX_PRECISION {
+            return None;
+        }
+        let mantissa = if scale >= self.scale {
+            self.mantissa.checked_mul(pow10(scale - self.scale)?)?
+        } else {
+            div_round(self.mantissa, pow10(self.scale - scale)?)
+        };
+        Self::new(mantissa, scale)
+    }
+
+    /// Round to `scale` digits and check that the value fits DECIMAL(precision, scale)
+    pub fn fit(&self, precision: u32, scale: u32) -> Option<Self> {
+        let rounded = self.rescale(scale)?;
+        (rounded.integer_digits() <= precision.saturating_sub(scale)).then_some(rounded)
+    }
+
+    /// Round to an integer
+    pub fn to_i64(&self) -> Option<i64> {
+        i64::try_from(self.rescale(0)?.mantissa).ok()
+    }
+
+    /// Convert to the nearest float
+    pub fn to_f64(&self) -> f64 {
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Negate the value
+    pub fn neg(&self) -> Self {
+        Self {
+            mantissa: -self.mantissa,
+            scale: self.scale,
+        }
+    }
+
+    /// Add; the result scale is the larger of both scales
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(scale)?, other.rescale(scale)?);
+        Self::new(a.mantissa.checked_add(b.mantissa)?, scale)
+    }
+
+    /// Subtract; the result scale is the larger of both scales
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&other.neg())
+    }
+
+    /// Multiply; the result scale is the sum of both scales
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        let scale = self.scale + other.scale;
+        if scale > MAX_PRECISION {
+            // Keep as many fractional digits as fit
+            let excess = scale - MAX_PRECISION;
+            return Self::new(div_round(mantissa, pow10(excess)?), MAX_PRECISION);
+        }
+        Self::new(mantissa, scale)
+    }
+
+    /// Divide, or `None` when dividing by zero or on overflow
+    ///
+    /// The result scale is `max(6, s1 + p2 + 1)`, capped so that the
+    /// quotient still fits in [`MAX_PRECISION`] digits.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        let wanted = MIN_DIVISION_SCALE
+            .max(self.scale + other.precision() + 1)
+            .min(MAX_PRECISION);
+        // Quotient digits before the point, at most
+        let integer_digits = (self.integer_digits() + other.scale + 1).min(MAX_PRECISION);
+        let scale = wanted.min(MAX_PRECISION - integer_digits);
+
+        // (m1 / 10^s1) / (m2 / 10^s2) = m1 * 10^(scale - s1 + s2) / m2 / 10^scale
+        let shift = scale as i64 - self.scale as i64 + other.scale as i64;
+        let (numerator, denominator) = if shift >= 0 {
+            (
+                self.mantissa.checked_mul(pow10(shift as u32)?)?,
+                other.mantissa,
+            )
+        } else {
+            (
+                self.mantissa,
+                other.mantissa.checked_mul(pow10((-shift) as u32)?)?,
+            )
+        };
+        Self::new(div_round(numerator, denominator), scale)
+    }
+
+    /// Remainder with the sign of the dividend, or `None` when dividing by zero
+    pub fn checked_rem(&self, other: &Self) -> Option<Self> {
+        if other.is_zero() {
+            return None;
+        }
+        let scale = self.scale.max(other.scale);
+        let (a, b) = (self.rescale(scale)?, other.rescale(scale)?);
+        Self::new(a.mantissa.checked_rem(b.mantissa).unwrap_or(0), scale)
+    }
+
+    /// Remove trailing fractional zeros, so equal values compare field-wise
+    fn normalized(&self) -> Self {
+        let mut decimal = *self;
+        while decimal.scale > 0 && decimal.mantissa % 10 == 0 {
+            decimal.mantissa /= 10;
+            decimal.scale -= 1;
+        }
+        decimal
+    }
+
+    fn digits(&self) -> u32 {
+        let mut n = self.mantissa.unsigned_abs();
+        let mut digits = 1;
+        while n >= 10 {
+            n /= 10;
+            digits += 1;
+        }
+        digits
+    }
+}
+
+/// 10^exp, or `None` if it does not fit in an i128
+fn pow10(exp: u32) -> Option<i128> {
+    10i128.checked_pow(exp)
+}
+
+/// Integer division rounding half away from zero
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder.unsigned_abs() * 2 >= denominator.unsigned_abs() {
+        if (numerator < 0) == (denominator < 0) {
+            quotient + 1
+        } else {
+            quotient - 1
+        }
+    } else {
+        quotient
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.normalized(), other.normalized());
+        let scale = a.scale.max(b.scale);
+        match (a.rescale(scale), b.rescale(scale)) {
+            (Some(a), Some(b)) => a.mantissa.cmp(&b.mantissa),
+            // Aligning would overflow: compare the integer parts first
+            _ => a
+                .rescale(0)
+                .map(|a| a.mantissa)
+                .cmp(&b.rescale(0).map(|b| b.mantissa))
+                .then_with(|| a.to_f64().total_cmp(&b.to_f64())),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Hash for Decimal {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let normalized = self.normalized();
+        normalized.mantissa.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+        let digits = format!("{:0>width$}", digits, width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, integer, fraction)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid decimal '{}'", s);
+        let trimmed = s.trim();
+        let (negative, unsigned) = match trimmed.as_bytes().first() {
+            Some(b'-') => (true, &trimmed[1..]),
+            Some(b'+') => (false, &trimmed[1..]),
+            _ => (false, trimmed),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let all_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integer.is_empty() && fraction.is_empty()
+            || !all_digits(integer)
+            || !all_digits(fraction)
+        {
+            return Err(invalid());
+        }
+
+        let mut mantissa: i128 = 0;
+        for b in integer.bytes().chain(fraction.bytes()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add((b - b'0') as i128))
+                .ok_or_else(invalid)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+        let decimal = Decimal {
+            mantissa,
+            scale: fraction.len() as u32,
+        };
+        // Drop fractional digits beyond what can be stored
+        if decimal.scale > MAX_PRECISION {
+            return decimal.rescale(MAX_PRECISION).ok_or_else(invalid);
+        }
+        Decimal::new(decimal.mantissa, decimal.scale).ok_or_else(invalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(dec("12.50").to_string(), "12.50");
+        assert_eq!(dec("-0.05").to_string(), "-0.05");
+        assert_eq!(dec("7").to_string(), "7");
+        assert_eq!(Decimal::from_f64(0.1).unwrap().to_string(), "0.1");
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert!("".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        // 0.1 + 0.2 is exactly 0.3, unlike f64
+        assert_eq!(dec("0.1").checked_add(&dec("0.2")).unwrap(), dec("0.3"));
+        assert_eq!(
+            dec("1.10").checked_sub(&dec("0.015")).unwrap().to_string(),
+            "1.085"
+        );
+        assert_eq!(
+            dec("1.5").checked_mul(&dec("2.25")).unwrap().to_string(),
+            "3.375"
+        );
+        assert_eq!(
+            dec("10").checked_div(&dec("3")).unwrap().to_string(),
+            "3.333333"
+        );
+        assert_eq!(
+            dec("2").checked_div(&dec("3")).unwrap().to_string(),
+            "0.666667"
+        );
+        assert!(dec("1").checked_div(&dec("0")).is_none());
+    }
+
+    #[test]
+    fn test_rescale_rounds_half_away_from_zero() {
+        assert_eq!(dec("2.345").rescale(2).unwrap().to_string(), "2.35");
+        assert_eq!(dec("-2.345").rescale(2).unwrap().to_string(), "-2.35");
+        assert_eq!(dec("2.5").to_i64(), Some(3));
+        assert_eq!(dec("1.2").rescale(3).unwrap().to_string(), "1.200");
+        assert_eq!(dec("999.995").fit(5, 2), None);
+        assert_eq!(dec("0.5").fit(1, 0).unwrap().to_string(), "1");
+    }
+
+    #[test]
+    fn test_compare_and_hash_ignore_trailing_zeros() {
+        use std::collections::HashSet;
+
+        assert_eq!(dec("1.50"), dec("1.5"));
+        assert!(dec("-1") < dec("0.001"));
+        assert!(dec("99999999999999999999999999999999999999") > dec("0.5"));
+
+        let set: HashSet<Decimal> = [dec("1.50"), dec("1.5"), dec("1.500")].into();
+        assert_eq!(set.len(), 1);
+    }
+
+    #[test]
+    fn test_overflow_is_detected() {
+        let max = dec("99999999999999999999999999999999999999");
+        assert!(max.checked_add(&dec("1")).is_none());
+        assert!(max.checked_mul(&dec("10")).is_none());
+        assert!("999999999999999999999999999999999999999"
+            .parse::<Decimal>()
+            .is_err());
+    }
+}
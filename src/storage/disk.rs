@@ -1,9 +1,11 @@
 //! Disk manager for ArcDB
 //!
 //! This module handles direct file I/O for multiple tables.
+//! Every data file starts with a format header, followed by its pages.
 
 use crate::error::Result;
-use crate::storage::page::{PageId, PAGE_SIZE};
+use crate::storage::format::{self, Superblock, FILE_HEADER_SIZE};
+use crate::storage::page::PageId;
 use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
 use std::io::{Read, Seek, SeekFrom, Write};
@@ -19,6 +21,8 @@ pub struct DiskManager {
     open_files: Mutex<HashMap<u32, File>>,
     /// Directory where data files are stored
     data_dir: PathBuf,
+    /// Superblock of the database
+    superblock: Superblock,
 }
 
 impl DiskManager {
@@ -27,9 +31,43 @@ impl DiskManager {
             table_files: Mutex::new(HashMap::new()),
             open_files: Mutex::new(HashMap::new()),
             data_dir,
+            superblock: Superblock::default(),
         }
     }
 
+    /// Open a data directory, checking its superblock and upgrading older files
+    pub fn open(data_dir: PathBuf) -> Result<Self> {
+        Self::open_with(data_dir, None)
+    }
+
+    /// Open a data directory, creating new databases with the given page size
+    pub fn open_with_page_size(data_dir: PathBuf, page_size: usize) -> Result<Self> {
+        Self::open_with(data_dir, Some(page_size))
+    }
+
+    fn open_with(data_dir: PathBuf, page_size: Option<usize>) -> Result<Self> {
+        let superblock = format::open_data_dir(&data_dir, page_size)?;
+        Ok(Self {
+            superblock,
+            ..Self::new(data_dir)
+        })
+    }
+
+    /// Get the page size of the database
+    pub fn page_size(&self) -> usize {
+        self.superblock.page_size as usize
+    }
+
+    /// Get the superblock of the database
+    pub fn superblock(&self) -> &Superblock {
+        &self.superblock
+    }
+
+    /// Get the directory the database files live in
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
     pub fn register_table(&self, table_id: u32, path: impl AsRef<Path>) {
         let mut table_files = self.table_files.lock().unwrap();
         table_files.insert(table_id, path.as_ref().to_path_buf());
@@ -38,7 +76,7 @@ impl DiskManager {
     pub fn read_page(&self, table_id: u32, page_id: PageId, data: &mut [u8]) -> Result<()> {
         let mut open_files = self.open_files.lock().unwrap();
         let file = self.get_file_mut(&mut open_files, table_id)?;
-        file.seek(SeekFrom::Start((page_id as u64) * (PAGE_SIZE as u64)))?;
+        file.seek(SeekFrom::Start(self.page_offset(page_id)))?;
         file.read_exact(data)?;
         Ok(())
     }
@@ -46,7 +84,7 @@ impl DiskManager {
     pub fn write_page(&self, table_id: u32, page_id: PageId, data: &[u8]) -> Result<()> {
         let mut open_files = self.open_files.lock().unwrap();
         let file = self.get_file_mut(&mut open_files, table_id)?;
-        file.seek(SeekFrom::Start((page_id as u64) * (PAGE_SIZE as u64)))?;
+        file.seek(SeekFrom::Start(self.page_offset(page_id)))?;
         file.write_all(data)?;
         file.flush()?;
         Ok(())
@@ -57,11 +95,11 @@ impl DiskManager {
         let mut open_files = self.open_files.lock().unwrap();
         let file = self.get_file_mut(&mut open_files, table_id)?;
         let file_len = file.metadata()?.len();
-        let page_id = (file_len / PAGE_SIZE as u64) as PageId;
+        let page_id = self.pages_in(file_len) as PageId;
 
         // Extend file by one page
         file.seek(SeekFrom::End(0))?;
-        let zero_page = vec![0u8; PAGE_SIZE];
+        let zero_page = vec![0u8; self.page_size()];
         file.write_all(&zero_page)?;
         file.flush()?;
 
@@ -72,7 +110,17 @@ impl DiskManager {
         let mut open_files = self.open_files.lock().unwrap();
         let file = self.get_file_mut(&mut open_files, table_id)?;
         let file_len = file.metadata()?.len();
-        Ok(file_len / PAGE_SIZE as u64)
+        Ok(self.pages_in(file_len))
+    }
+
+    /// Byte offset of a page within its data file
+    fn page_offset(&self, page_id: PageId) -> u64 {
+        FILE_HEADER_SIZE as u64 + (page_id as u64) * (self.page_size() as u64)
+    }
+
+    /// Number of whole pages in a data file of the given length
+    fn pages_in(&self, file_len: u64) -> u64 {
+        file_len.saturating_sub(FILE_HEADER_SIZE as u64) / self.page_size() as u64
     }
 
     fn get_file_mut<'a>(
@@ -80,19 +128,22 @@ impl DiskManager {
         open_files: &'a mut HashMap<u32, File>,
         table_id: u32,
     ) -> Result<&'a mut File> {
-        if !open_files.contains_key(&table_id) {
+        if let std::collections::hash_map::Entry::Vacant(e) = open_files.entry(table_id) {
             let table_files = self.table_files.lock().unwrap();
             let path = table_files
                 .get(&table_id)
                 .cloned()
                 .unwrap_or_else(|| self.data_dir.join(format!("table_{}.data", table_id)));
 
+            format::prepare_data_file(&path, table_id, self.page_size())?;
+
             let file = OpenOptions::new()
                 .read(true)
                 .write(true)
                 .create(true)
+                .truncate(false)
                 .open(path)?;
-            open_files.insert(table_id, file);
+            e.insert(file);
         }
         Ok(open_files.get_mut(&table_id).unwrap())
     }
@@ -0,0 +1,375 @@
+//! On-disk format headers for ArcDB
+//!
+//! Every data directory carries a superblock (`arcdb.super`) recording the
+//! format version, page size and creation info of the database, and every
+//! table data file starts with a small header carrying the same version and
+//! page size. Files written before headers existed are treated as format
+//! version 0 and are rewritten in place when the database is opened.
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::page::{self, PAGE_SIZE};
+use crate::error::{Error, Result};
+
+/// Current on-disk format version
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Magic bytes at the start of the superblock
+pub const SUPERBLOCK_MAGIC: [u8; 8] = *b"ARCDBSB\0";
+
+/// Magic bytes at the start of every table data file
+pub const DATA_FILE_MAGIC: [u8; 8] = *b"ARCDBTB\0";
+
+/// Magic string stored in the JSON catalog and index files
+pub const JSON_MAGIC: &str = "ArcDB";
+
+/// Superblock file name inside the data directory
+pub const SUPERBLOCK_FILE: &str = "arcdb.super";
+
+/// Size of the superblock and of the header in front of the first page of a data file
+pub const FILE_HEADER_SIZE: usize = 64;
+
+/// Maximum length of the `created_by` string
+const CREATED_BY_LEN: usize = 32;
+
+/// Database superblock
+#[derive(Debug, Clone, PartialEq)]
+pub struct Superblock {
+    /// Format version the database was last written with
+    pub format_version: u32,
+    /// Page size in bytes
+    pub page_size: u32,
+    /// Creation time (seconds since the Unix epoch)
+    pub created_at: u64,
+    /// ArcDB version that created the database
+    pub created_by: String,
+}
+
+impl Superblock {
+    /// Create a superblock for a new database
+    pub fn new(page_size: usize) -> Self {
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+
+        Self {
+            format_version: FORMAT_VERSION,
+            page_size: page_size as u32,
+            created_at,
+            created_by: format!("arcdb {}", env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    /// Serialize the superblock to its fixed-size binary layout
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; FILE_HEADER_SIZE];
+        bytes[0..8].copy_from_slice(&SUPERBLOCK_MAGIC);
+        bytes[8..12].copy_from_slice(&self.format_version.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.page_size.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.created_at.to_le_bytes());
+
+        let created_by = self.created_by.as_bytes();
+        let len = created_by.len().min(CREATED_BY_LEN);
+        bytes[24..24 + len].copy_from_slice(&created_by[..len]);
+        bytes
+    }
+
+    /// Deserialize a superblock, checking its magic number
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() < FILE_HEADER_SIZE || bytes[0..8] != SUPERBLOCK_MAGIC {
+            return Err(Error::InvalidFileHeader(SUPERBLOCK_FILE.to_string()));
+        }
+
+        let created_by = &bytes[24..24 + CREATED_BY_LEN];
+        let end = created_by
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(CREATED_BY_LEN);
+
+        Ok(Self {
+            format_version: read_u32(bytes, 8),
+            page_size: read_u32(bytes, 12),
+            created_at: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            created_by: String::from_utf8_lossy(&created_by[..end]).into_owned(),
+        })
+    }
+
+    /// Check that this build can read a database with this superblock
+    pub fn validate(&self) -> Result<()> {
+        check_version(self.format_version)?;
+        page::validate_page_size(self.page_size as usize)
+    }
+
+    /// Load the superblock from a data directory, if there is one
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(SUPERBLOCK_FILE);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = fs::read(path)?;
+        Self::from_bytes(&bytes).map(Some)
+    }
+
+    /// Write the superblock to a data directory
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        write_atomically(&dir.join(SUPERBLOCK_FILE), &self.to_bytes())
+    }
+}
+
+impl Default for Superblock {
+    fn default() -> Self {
+        Self::new(PAGE_SIZE)
+    }
+}
+
+/// Header at the start of a table data file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DataFileHeader {
+    /// Format version the file was written with
+    pub format_version: u32,
+    /// Page size in bytes
+    pub page_size: u32,
+    /// Table this file belongs to
+    pub table_id: u32,
+}
+
+impl DataFileHeader {
+    /// Create a header for a new data file
+    pub fn new(table_id: u32, page_size: usize) -> Self {
+        Self {
+            format_version: FORMAT_VERSION,
+            page_size: page_size as u32,
+            table_id,
+        }
+    }
+
+    /// Serialize the header to its fixed-size binary layout
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![0u8; FILE_HEADER_SIZE];
+        bytes[0..8].copy_from_slice(&DATA_FILE_MAGIC);
+        bytes[8..12].copy_from_slice(&self.format_version.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.page_size.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.table_id.to_le_bytes());
+        bytes
+    }
+
+    /// Deserialize a header, returning None if the magic number is missing
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < FILE_HEADER_SIZE || bytes[0..8] != DATA_FILE_MAGIC {
+            return None;
+        }
+        Some(Self {
+            format_version: read_u32(bytes, 8),
+            page_size: read_u32(bytes, 12),
+            table_id: read_u32(bytes, 16),
+        })
+    }
+}
+
+/// Open a data directory, creating or upgrading its superblock and data files
+///
+/// `page_size` is only used when creating a new database; an existing database
+/// keeps the page size recorded in its superblock and rejects a different one.
+pub fn open_data_dir(dir: &Path, page_size: Option<usize>) -> Result<Superblock> {
+    if let Some(page_size) = page_size {
+        page::validate_page_size(page_size)?;
+    }
+    if !dir.exists() {
+        fs::create_dir_all(dir)?;
+    }
+
+    match Superblock::load(dir)? {
+        Some(mut superblock) => {
+            superblock.validate()?;
+            if let Some(page_size) = page_size {
+                check_page_size(superblock.page_size, page_size, SUPERBLOCK_FILE)?;
+            }
+            if superblock.format_version < FORMAT_VERSION {
+                upgrade_data_dir(dir, superblock.page_size as usize)?;
+                superblock.format_version = FORMAT_VERSION;
+                superblock.save(dir)?;
+            }
+            Ok(superblock)
+        }
+        None => {
+            // Either a brand new database or one written before superblocks existed
+            let page_size = page_size.unwrap_or(PAGE_SIZE);
+            upgrade_data_dir(dir, page_size)?;
+            let superblock = Superblock::new(page_size);
+            superblock.save(dir)?;
+            Ok(superblock)
+        }
+    }
+}
+
+/// Bring every table data file in a directory up to the current format
+pub fn upgrade_data_dir(dir: &Path, page_size: usize) -> Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if let Some(table_id) = data_file_table_id(&path) {
+            prepare_data_file(&path, table_id, page_size)?;
+        }
+    }
+    Ok(())
+}
+
+/// Make sure a table data file exists with a current header
+///
+/// Missing or empty files get a fresh header, files from an older format are
+/// rewritten in place, and files from a newer format or with a different page
+/// size are rejected.
+pub fn prepare_data_file(path: &Path, table_id: u32, page_size: usize) -> Result<()> {
+    // Only the header is read, unless the file has to be rewritten
+    let mut head = Vec::with_capacity(FILE_HEADER_SIZE);
+    let len = if path.exists() {
+        let file = fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        file.take(FILE_HEADER_SIZE as u64).read_to_end(&mut head)?;
+        len
+    } else {
+        0
+    };
+
+    if len == 0 {
+        return write_atomically(path, &DataFileHeader::new(table_id, page_size).to_bytes());
+    }
+
+    match DataFileHeader::from_bytes(&head) {
+        Some(header) => {
+            check_version(header.format_version)?;
+            check_page_size(header.page_size, page_size, &path.display().to_string())?;
+            if header.format_version < FORMAT_VERSION {
+                let bytes = fs::read(path)?;
+                let mut upgraded = DataFileHeader {
+                    format_version: FORMAT_VERSION,
+                    ..header
+                }
+                .to_bytes();
+                upgraded.extend_from_slice(&bytes[FILE_HEADER_SIZE..]);
+                write_atomically(path, &upgraded)?;
+            }
+            Ok(())
+        }
+        None => {
+            // Version 0: raw pages with no header, always 4KB
+            if len % PAGE_SIZE as u64 != 0 {
+                return Err(Error::InvalidFileHeader(path.display().to_string()));
+            }
+            check_page_size(PAGE_SIZE as u32, page_size, &path.display().to_string())?;
+            let mut upgraded = DataFileHeader::new(table_id, page_size).to_bytes();
+            upgraded.extend_from_slice(&fs::read(path)?);
+            write_atomically(path, &upgraded)
+        }
+    }
+}
+
+/// Check that this build can read files of the given format version
+pub fn check_version(version: u32) -> Result<()> {
+    if version > FORMAT_VERSION {
+        return Err(Error::UnsupportedFormatVersion {
+            found: version,
+            supported: FORMAT_VERSION,
+        });
+    }
+    Ok(())
+}
+
+fn check_page_size(found: u32, expected: usize, file: &str) -> Result<()> {
+    if found as usize != expected {
+        return Err(Error::StorageError(format!(
+            "'{}' uses page size {}, expected {}",
+            file, found, expected
+        )));
+    }
+    Ok(())
+}
+
+/// Extract the table ID from a `table_N.data` file name
+fn data_file_table_id(path: &Path) -> Option<u32> {
+    let name = path.file_name()?.to_str()?;
+    name.strip_prefix("table_")?
+        .strip_suffix(".data")?
+        .parse()
+        .ok()
+}
+
+/// Replace a file by writing a sibling temp file and renaming it over the original
+fn write_atomically(path: &Path, bytes: &[u8]) -> Result<()> {
+    let mut tmp = PathBuf::from(path);
+    tmp.set_extension("tmp");
+    fs::write(&tmp, bytes)?;
+    fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_superblock_roundtrip() {
+        let superblock = Superblock::new(16384);
+        let decoded = Superblock::from_bytes(&superblock.to_bytes()).unwrap();
+        assert_eq!(decoded, superblock);
+        assert!(decoded.validate().is_ok());
+    }
+
+    #[test]
+    fn test_open_upgrades_legacy_data_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("table_3.data");
+        let mut legacy = vec![0u8; PAGE_SIZE * 2];
+        legacy[PAGE_SIZE] = 0xAB;
+        fs::write(&path, &legacy).unwrap();
+
+        let superblock = open_data_dir(dir.path(), None).unwrap();
+        assert_eq!(superblock.format_version, FORMAT_VERSION);
+        assert!(dir.path().join(SUPERBLOCK_FILE).exists());
+
+        let bytes = fs::read(&path).unwrap();
+        let header = DataFileHeader::from_bytes(&bytes).unwrap();
+        assert_eq!(header, DataFileHeader::new(3, PAGE_SIZE));
+        assert_eq!(&bytes[FILE_HEADER_SIZE..], &legacy[..]);
+
+        // Opening again leaves the file untouched
+        open_data_dir(dir.path(), None).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), bytes);
+    }
+
+    #[test]
+    fn test_newer_format_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let superblock = Superblock {
+            format_version: FORMAT_VERSION + 1,
+            ..Superblock::default()
+        };
+        superblock.save(dir.path()).unwrap();
+
+        let result = open_data_dir(dir.path(), None);
+        assert!(matches!(
+            result,
+            Err(Error::UnsupportedFormatVersion { .. })
+        ));
+    }
+
+    #[test]
+    fn test_page_size_recorded_and_checked() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(open_data_dir(dir.path(), Some(65536)).is_err());
+
+        let superblock = open_data_dir(dir.path(), Some(16384)).unwrap();
+        assert_eq!(superblock.page_size, 16384);
+
+        // Reopening uses the recorded size and rejects a different one
+        assert_eq!(open_data_dir(dir.path(), None).unwrap().page_size, 16384);
+        assert!(open_data_dir(dir.path(), Some(32768)).is_err());
+    }
+}
@@ -120,6 +120,42 @@ impl HeapFile {
         Ok(SlotId::new(new_global_id.page_id, sn))
     }
 
+    /// Insert many tuples, filling pages in order under one buffer pool lock
+    pub fn insert_many(&mut self, tuples: Vec<Tuple>) -> Result<Vec<SlotId>> {
+        let mut slot_ids = Vec::with_capacity(tuples.len());
+        let mut global_id = GlobalPageId {
+            table_id: self.table_id,
+            page_id: self.last_page_id,
+        };
+
+        let mut bpm = self.buffer_pool.lock().unwrap();
+        let mut frame_index = bpm.fetch_page(global_id)?;
+        let mut dirty = false;
+
+        for tuple in tuples {
+            let bytes = tuple.to_bytes();
+            let slot_num = match bpm.get_page_mut(frame_index).insert_tuple(&bytes) {
+                Some(sn) => sn,
+                None => {
+                    // Current page full, unpin and allocate new page
+                    bpm.unpin_page(global_id, dirty)?;
+                    (global_id, frame_index) = bpm.new_page(self.table_id)?;
+                    self.last_page_id = global_id.page_id;
+                    bpm.get_page_mut(frame_index)
+                        .insert_tuple(&bytes)
+                        .ok_or_else(|| {
+                            Error::StorageError("Failed to insert into new page".to_string())
+                        })?
+                }
+            };
+            dirty = true;
+            slot_ids.push(SlotId::new(global_id.page_id, slot_num));
+        }
+
+        bpm.unpin_page(global_id, dirty)?;
+        Ok(slot_ids)
+    }
+
     /// Delete a tuple by slot ID
     pub fn delete(&mut self, slot_id: SlotId) -> Result<()> {
         let global_id = GlobalPageId {
@@ -173,11 +209,20 @@ impl HeapFile {
 
     /// Get a tuple by slot ID
     pub fn get(&mut self, slot_id: SlotId) -> Option<Tuple> {
+        Self::read(&self.buffer_pool, self.table_id, slot_id)
+    }
+
+    /// Read a tuple by slot ID without holding a heap file
+    pub fn read(
+        buffer_pool: &Mutex<BufferPoolManager>,
+        table_id: u32,
+        slot_id: SlotId,
+    ) -> Option<Tuple> {
         let global_id = GlobalPageId {
-            table_id: self.table_id,
+            table_id,
             page_id: slot_id.page_id,
         };
-        let mut bpm = self.buffer_pool.lock().unwrap();
+        let mut bpm = buffer_pool.lock().unwrap();
         let frame_index = bpm.fetch_page(global_id).ok()?;
 
         let tuple = {
@@ -192,34 +237,12 @@ impl HeapFile {
 
     /// Scan all tuples
     pub fn scan(&mut self) -> Vec<(SlotId, Tuple)> {
-        let mut result = Vec::new();
-        let page_count = {
-            let bpm = self.buffer_pool.lock().unwrap();
-            bpm.disk_manager()
-                .get_page_count(self.table_id)
-                .unwrap_or(0) as PageId
-        };
+        self.iter().collect()
+    }
 
-        for pid in 0..page_count {
-            let global_id = GlobalPageId {
-                table_id: self.table_id,
-                page_id: pid,
-            };
-            let mut bpm = self.buffer_pool.lock().unwrap();
-            if let Ok(index) = bpm.fetch_page(global_id) {
-                let page = bpm.get_page(index);
-                let count = page.tuple_count();
-                for sn in 0..count as u16 {
-                    if let Some(bytes) = page.get_tuple(sn) {
-                        if let Ok(tuple) = Tuple::from_bytes(bytes) {
-                            result.push((SlotId::new(pid, sn), tuple));
-                        }
-                    }
-                }
-                bpm.unpin_page(global_id, false).ok();
-            }
-        }
-        result
+    /// Iterate over all tuples, reading one page at a time
+    pub fn iter(&self) -> HeapIterator {
+        HeapIterator::new(self.table_id, self.buffer_pool.clone())
     }
 
     /// Flush to disk
@@ -273,6 +296,78 @@ impl HeapFile {
     }
 }
 
+/// Streaming iterator over the tuples of a heap file
+///
+/// Only the tuples of the current page are held in memory.
+#[derive(Debug)]
+pub struct HeapIterator {
+    table_id: u32,
+    buffer_pool: Arc<Mutex<BufferPoolManager>>,
+    page_count: PageId,
+    next_page: PageId,
+    buffered: std::vec::IntoIter<(SlotId, Tuple)>,
+}
+
+impl HeapIterator {
+    /// Start iterating over the heap file of a table
+    pub fn new(table_id: u32, buffer_pool: Arc<Mutex<BufferPoolManager>>) -> Self {
+        let page_count = {
+            let bpm = buffer_pool.lock().unwrap();
+            bpm.disk_manager().get_page_count(table_id).unwrap_or(0) as PageId
+        };
+
+        Self {
+            table_id,
+            buffer_pool,
+            page_count,
+            next_page: 0,
+            buffered: Vec::new().into_iter(),
+        }
+    }
+
+    /// Load the tuples of the next page into the buffer
+    fn load_next_page(&mut self) {
+        let pid = self.next_page;
+        self.next_page += 1;
+
+        let global_id = GlobalPageId {
+            table_id: self.table_id,
+            page_id: pid,
+        };
+        let mut tuples = Vec::new();
+        let mut bpm = self.buffer_pool.lock().unwrap();
+        if let Ok(index) = bpm.fetch_page(global_id) {
+            let page = bpm.get_page(index);
+            let count = page.tuple_count();
+            for sn in 0..count as u16 {
+                if let Some(bytes) = page.get_tuple(sn) {
+                    if let Ok(tuple) = Tuple::from_bytes(bytes) {
+                        tuples.push((SlotId::new(pid, sn), tuple));
+                    }
+                }
+            }
+            bpm.unpin_page(global_id, false).ok();
+        }
+        self.buffered = tuples.into_iter();
+    }
+}
+
+impl Iterator for HeapIterator {
+    type Item = (SlotId, Tuple);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(item) = self.buffered.next() {
+                return Some(item);
+            }
+            if self.next_page >= self.page_count {
+                return None;
+            }
+            self.load_next_page();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -343,4 +438,27 @@ mod tests {
         let tuples = heap.scan();
         assert_eq!(tuples.len(), 5);
     }
+
+    #[test]
+    fn test_heap_file_large_pages() {
+        let dir = tempfile::tempdir().unwrap();
+        let disk = DiskManager::open_with_page_size(dir.path().to_path_buf(), 16384).unwrap();
+        let bpm = Arc::new(Mutex::new(BufferPoolManager::new(10, Arc::new(disk))));
+        let mut heap = HeapFile::new(1, bpm);
+
+        // Too large for a default 4KB page
+        let big = "x".repeat(10000);
+        let slot_id = heap
+            .insert(Tuple::new(vec![Value::String(big.clone())]))
+            .unwrap();
+        heap.flush().unwrap();
+
+        // Reopening picks the page size up from the superblock
+        let disk = DiskManager::open(dir.path().to_path_buf()).unwrap();
+        assert_eq!(disk.page_size(), 16384);
+        let bpm = Arc::new(Mutex::new(BufferPoolManager::new(10, Arc::new(disk))));
+        let mut reopened = HeapFile::open(1, bpm).unwrap();
+        let tuple = reopened.get(slot_id).unwrap();
+        assert_eq!(tuple.get(0), Some(&Value::String(big)));
+    }
 }
@@ -1,6 +1,7 @@
 //! Storage engine module
 //!
 //! This module contains the storage engine components:
+//! - On-disk format headers
 //! - Page management
 //! - Buffer pool
 //! - Heap file storage
@@ -8,7 +9,10 @@
 
 pub mod btree;
 pub mod buffer_pool;
+pub mod datetime;
+pub mod decimal;
 pub mod disk;
+pub mod format;
 pub mod heap;
 pub mod page;
 pub mod table;
@@ -17,8 +21,11 @@ pub mod wal;
 
 pub use btree::{BPlusTree, IndexKey};
 pub use buffer_pool::{BufferPoolManager, GlobalPageId};
+pub use datetime::Interval;
+pub use decimal::Decimal;
 pub use disk::DiskManager;
-pub use heap::{HeapFile, SlotId};
+pub use format::{Superblock, FORMAT_VERSION};
+pub use heap::{HeapFile, HeapIterator, SlotId};
 pub use page::Page;
 pub use table::Table;
 pub use tuple::{Tuple, Value};
@@ -2,10 +2,19 @@
 //!
 //! This module defines the page structure used for disk-based storage.
 //! Each page is a fixed-size block (4KB by default) that can store tuples.
+//! The page size is chosen per database and recorded in its superblock.
 
-/// Page size in bytes (4KB)
+use crate::error::{Error, Result};
+
+/// Default page size in bytes (4KB)
 pub const PAGE_SIZE: usize = 4096;
 
+/// Smallest supported page size
+pub const MIN_PAGE_SIZE: usize = 4096;
+
+/// Largest supported page size (slot offsets are stored as u16)
+pub const MAX_PAGE_SIZE: usize = 32768;
+
 /// Page header size
 pub const PAGE_HEADER_SIZE: usize = 24;
 
@@ -15,6 +24,17 @@ pub type PageId = u32;
 /// Special page ID for invalid/unallocated pages
 pub const INVALID_PAGE_ID: PageId = u32::MAX;
 
+/// Check that a page size is a power of two between MIN_PAGE_SIZE and MAX_PAGE_SIZE
+pub fn validate_page_size(page_size: usize) -> Result<()> {
+    if !page_size.is_power_of_two() || !(MIN_PAGE_SIZE..=MAX_PAGE_SIZE).contains(&page_size) {
+        return Err(Error::StorageError(format!(
+            "invalid page size {}: must be a power of two between {} and {}",
+            page_size, MIN_PAGE_SIZE, MAX_PAGE_SIZE
+        )));
+    }
+    Ok(())
+}
+
 /// Page header structure
 #[derive(Debug, Clone, Copy)]
 pub struct PageHeader {
@@ -33,12 +53,12 @@ pub struct PageHeader {
 }
 
 impl PageHeader {
-    /// Create a new page header
-    pub fn new(page_id: PageId) -> Self {
+    /// Create a new page header for a page of the given size
+    pub fn new(page_id: PageId, page_size: usize) -> Self {
         Self {
             page_id,
             tuple_count: 0,
-            free_space_offset: PAGE_SIZE as u16,
+            free_space_offset: page_size as u16,
             page_type: 0,
             lsn: 0,
             reserved: [0; 5],
@@ -65,11 +85,17 @@ pub struct Page {
 }
 
 impl Page {
-    /// Create a new empty page
+    /// Create a new empty page of the default size
     pub fn new(page_id: PageId) -> Self {
+        Self::with_size(page_id, PAGE_SIZE)
+    }
+
+    /// Create a new empty page of the given size
+    pub fn with_size(page_id: PageId, page_size: usize) -> Self {
+        debug_assert!(page_size <= MAX_PAGE_SIZE);
         let mut page = Self {
-            header: PageHeader::new(page_id),
-            data: vec![0u8; PAGE_SIZE],
+            header: PageHeader::new(page_id, page_size),
+            data: vec![0u8; page_size],
             dirty: false,
             pin_count: 0,
         };
@@ -96,11 +122,9 @@ impl Page {
         self.write_header();
     }
 
-    /// Create a page from raw bytes
+    /// Create a page from raw bytes; the page size is the length of `bytes`
     pub fn from_bytes(page_id: PageId, bytes: &[u8]) -> Self {
-        let mut data = vec![0u8; PAGE_SIZE];
-        let len = bytes.len().min(PAGE_SIZE);
-        data[..len].copy_from_slice(&bytes[..len]);
+        let data = bytes.to_vec();
 
         // Parse header from bytes
         let tuple_count = u16::from_le_bytes([data[4], data[5]]);
@@ -132,6 +156,11 @@ impl Page {
         self.header.page_id
     }
 
+    /// Get page size in bytes
+    pub fn page_size(&self) -> usize {
+        self.data.len()
+    }
+
     /// Get page LSN
     pub fn lsn(&self) -> u64 {
         self.header.lsn
@@ -305,126 +334,6 @@ impl Page {
     }
 }
 
-use std::fs::{File, OpenOptions};
-use std::io::{Read, Seek, SeekFrom, Write};
-use std::path::Path;
-
-/// In-memory page storage with disk persistence
-#[derive(Debug)]
-pub struct PageStorage {
-    /// Pages stored in memory (cache)
-    pages: std::collections::HashMap<PageId, Page>,
-    /// Next page ID to allocate
-    next_page_id: PageId,
-    /// Disk file for persistence
-    file: Option<File>,
-}
-
-impl PageStorage {
-    /// Create a new in-memory page storage
-    pub fn new() -> Self {
-        Self {
-            pages: std::collections::HashMap::new(),
-            next_page_id: 0,
-            file: None,
-        }
-    }
-
-    /// Open or create a disk-backed page storage
-    pub fn open(path: impl AsRef<Path>) -> std::io::Result<Self> {
-        let file = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(path)?;
-
-        let metadata = file.metadata()?;
-        let next_page_id = (metadata.len() / PAGE_SIZE as u64) as PageId;
-
-        Ok(Self {
-            pages: std::collections::HashMap::new(),
-            next_page_id,
-            file: Some(file),
-        })
-    }
-
-    /// Allocate a new page
-    pub fn allocate_page(&mut self) -> PageId {
-        let page_id = self.next_page_id;
-        self.next_page_id += 1;
-
-        let mut page = Page::new(page_id);
-        page.mark_dirty();
-
-        if let Some(ref mut file) = self.file {
-            let offset = page_id as u64 * PAGE_SIZE as u64;
-            file.seek(SeekFrom::Start(offset)).unwrap();
-            file.write_all(page.to_bytes()).unwrap();
-            page.clear_dirty();
-        }
-
-        self.pages.insert(page_id, page);
-        page_id
-    }
-
-    /// Get a page by ID
-    pub fn get_page(&mut self, page_id: PageId) -> Option<&Page> {
-        if !self.pages.contains_key(&page_id) {
-            if let Some(ref mut file) = self.file {
-                let offset = page_id as u64 * PAGE_SIZE as u64;
-                if offset < file.metadata().unwrap().len() {
-                    let mut bytes = vec![0u8; PAGE_SIZE];
-                    file.seek(SeekFrom::Start(offset)).unwrap();
-                    file.read_exact(&mut bytes).unwrap();
-                    let page = Page::from_bytes(page_id, &bytes);
-                    self.pages.insert(page_id, page);
-                }
-            }
-        }
-        self.pages.get(&page_id)
-    }
-
-    /// Get a mutable page by ID
-    pub fn get_page_mut(&mut self, page_id: PageId) -> Option<&mut Page> {
-        if !self.pages.contains_key(&page_id) {
-            self.get_page(page_id);
-        }
-        self.pages.get_mut(&page_id)
-    }
-
-    /// Flush all dirty pages to disk
-    pub fn flush(&mut self) -> std::io::Result<()> {
-        if let Some(ref mut file) = self.file {
-            for (&page_id, page) in self.pages.iter_mut() {
-                if page.is_dirty() {
-                    let offset = page_id as u64 * PAGE_SIZE as u64;
-                    file.seek(SeekFrom::Start(offset))?;
-                    file.write_all(page.to_bytes())?;
-                    page.clear_dirty();
-                }
-            }
-            file.flush()?;
-        }
-        Ok(())
-    }
-
-    /// Free a page (mark for deletion - simplified)
-    pub fn free_page(&mut self, page_id: PageId) -> bool {
-        self.pages.remove(&page_id).is_some()
-    }
-
-    /// Get number of pages
-    pub fn page_count(&self) -> usize {
-        self.next_page_id as usize
-    }
-}
-
-impl Default for PageStorage {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,17 +359,26 @@ mod tests {
     }
 
     #[test]
-    fn test_page_storage() {
-        let mut storage = PageStorage::new();
+    fn test_large_page() {
+        let mut page = Page::with_size(0, MAX_PAGE_SIZE);
+        assert_eq!(page.page_size(), MAX_PAGE_SIZE);
 
-        let page_id = storage.allocate_page();
-        assert_eq!(page_id, 0);
+        let tuple = vec![7u8; 20000];
+        let slot = page.insert_tuple(&tuple).unwrap();
+        assert_eq!(page.get_tuple(slot), Some(&tuple[..]));
 
-        let page = storage.get_page(page_id);
-        assert!(page.is_some());
-        assert_eq!(page.unwrap().page_id(), 0);
+        let reloaded = Page::from_bytes(0, page.to_bytes());
+        assert_eq!(reloaded.page_size(), MAX_PAGE_SIZE);
+        assert_eq!(reloaded.get_tuple(slot), Some(&tuple[..]));
+    }
 
-        assert!(storage.free_page(page_id));
-        assert!(storage.get_page(page_id).is_none());
+    #[test]
+    fn test_validate_page_size() {
+        assert!(validate_page_size(4096).is_ok());
+        assert!(validate_page_size(16384).is_ok());
+        assert!(validate_page_size(32768).is_ok());
+        assert!(validate_page_size(65536).is_err());
+        assert!(validate_page_size(6000).is_err());
+        assert!(validate_page_size(1024).is_err());
     }
 }
@@ -3,11 +3,14 @@
 //! This module combines schema and heap file to provide table operations.
 
 use super::btree::{BPlusTree, IndexKey};
-use super::heap::{HeapFile, SlotId};
-use super::tuple::Tuple;
-use crate::catalog::{Schema, TableDef};
+use super::datetime::MS_PER_DAY;
+use super::decimal::Decimal;
+use super::heap::{HeapFile, HeapIterator, SlotId};
+use super::tuple::{Tuple, Value};
+use crate::catalog::{Column, DataType, IndexDef, Schema, TableDef};
 use crate::error::{Error, Result};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 
 use super::buffer_pool::BufferPoolManager;
@@ -19,12 +22,48 @@ pub struct Table {
     def: Arc<TableDef>,
     /// Heap file storage
     heap: HeapFile,
-    /// Indexes: Map from index name to (column indices, B+ Tree)
-    indexes: HashMap<String, (Vec<usize>, BPlusTree)>,
+    /// Indexes by name
+    indexes: HashMap<String, TableIndex>,
     /// Buffer pool
     buffer_pool: Arc<Mutex<BufferPoolManager>>,
 }
 
+/// An index of a table
+#[derive(Debug)]
+struct TableIndex {
+    /// Positions of the indexed columns
+    columns: Vec<usize>,
+    /// Whether rows must have distinct keys; keys containing NULL never conflict
+    unique: bool,
+    tree: BPlusTree,
+}
+
+impl TableIndex {
+    /// The key of a row in this index
+    fn key(&self, tuple: &Tuple) -> IndexKey {
+        IndexKey::composite(
+            self.columns
+                .iter()
+                .filter_map(|&col_idx| tuple.get(col_idx).cloned())
+                .collect(),
+        )
+    }
+
+    /// Another row with the same key as `tuple`, when the index is unique
+    fn conflict(&self, tuple: &Tuple, slot_id: Option<SlotId>) -> Option<SlotId> {
+        if !self.unique {
+            return None;
+        }
+        let key = self.key(tuple);
+        if key.0.iter().any(Value::is_null) {
+            return None;
+        }
+        self.tree
+            .search(&key)
+            .filter(|&existing| Some(existing) != slot_id)
+    }
+}
+
 impl Table {
     /// Create a new table
     pub fn new(def: Arc<TableDef>, buffer_pool: Arc<Mutex<BufferPoolManager>>) -> Self {
@@ -68,39 +107,62 @@ impl Table {
         &self.def
     }
 
-    /// Create an index on the table
-    pub fn create_index(&mut self, name: String, columns: Vec<String>) -> Result<()> {
-        let schema = self.def.schema();
-        let mut column_indices = Vec::new();
-
-        for col_name in &columns {
-            match schema.get_column_index(col_name) {
-                Some(idx) => column_indices.push(idx),
-                None => {
-                    return Err(Error::ColumnNotFound(
-                        col_name.clone(),
-                        self.name().to_string(),
-                    ))
-                }
+    /// Get a shared handle to the table definition
+    pub fn shared_definition(&self) -> Arc<TableDef> {
+        self.def.clone()
+    }
+
+    /// Switch to an altered definition of the table
+    ///
+    /// Indexes not in `indexes` are dropped and the column positions of the
+    /// others are looked up again.
+    pub fn alter(&mut self, def: Arc<TableDef>, indexes: &[Arc<IndexDef>]) -> Result<()> {
+        self.def = def;
+        self.indexes
+            .retain(|name, _| indexes.iter().any(|index| index.name == *name));
+        for index in indexes {
+            let columns = self.column_indices(&index.columns)?;
+            if let Some(table_index) = self.indexes.get_mut(&index.name) {
+                table_index.columns = columns;
             }
         }
+        Ok(())
+    }
+
+    /// Positions of the named columns
+    fn column_indices(&self, columns: &[String]) -> Result<Vec<usize>> {
+        columns
+            .iter()
+            .map(|name| {
+                self.get_column_index(name)
+                    .ok_or_else(|| Error::ColumnNotFound(name.clone(), self.name().to_string()))
+            })
+            .collect()
+    }
 
-        let mut tree = BPlusTree::new(name.clone(), self.buffer_pool.clone());
+    /// Create an index on the table
+    ///
+    /// A unique index fails with `ConstraintViolation` if existing rows share a key.
+    pub fn create_index(&mut self, name: String, columns: Vec<String>, unique: bool) -> Result<()> {
+        let mut index = TableIndex {
+            columns: self.column_indices(&columns)?,
+            unique,
+            tree: BPlusTree::new(name.clone(), self.buffer_pool.clone()),
+        };
 
         // Populate index with existing data
-        for (slot_id, tuple) in self.heap.scan() {
-            let mut key_values = Vec::new();
-            for &col_idx in &column_indices {
-                if let Some(val) = tuple.get(col_idx) {
-                    key_values.push(val.clone());
-                }
+        for (slot_id, tuple) in self.scan() {
+            if index.conflict(&tuple, Some(slot_id)).is_some() {
+                return Err(Error::ConstraintViolation(name));
             }
-            let key = IndexKey::composite(key_values);
-            tree.insert(key, slot_id)?;
+            index.tree.insert(index.key(&tuple), slot_id)?;
         }
 
-        self.indexes.insert(name.clone(), (column_indices, tree));
-        self.save_index(&name)?;
+        self.indexes.insert(name.clone(), index);
+        if let Err(e) = self.save_index(&name) {
+            self.indexes.remove(&name);
+            return Err(e);
+        }
         Ok(())
     }
 
@@ -114,50 +176,59 @@ impl Table {
 
     /// Save a specific index to disk
     fn save_index(&self, name: &str) -> Result<()> {
-        if let Some((_, tree)) = self.indexes.get(name) {
-            let path = format!("table_{}_{}.index", self.def.id, name);
-            tree.save_to_disk(path)?;
+        if let Some(index) = self.indexes.get(name) {
+            index.tree.save_to_disk(self.index_path(name))?;
         }
         Ok(())
     }
 
     /// Load a specific index from disk or rebuild it
-    pub fn load_index(&mut self, index_def: Arc<crate::catalog::IndexDef>) -> Result<()> {
+    pub fn load_index(&mut self, index_def: Arc<IndexDef>) -> Result<()> {
         let name = &index_def.name;
-        let schema = self.def.schema();
-        let mut column_indices = Vec::new();
-
-        for col_name in &index_def.columns {
-            match schema.get_column_index(col_name) {
-                Some(idx) => column_indices.push(idx),
-                None => {
-                    return Err(Error::ColumnNotFound(
-                        col_name.clone(),
-                        self.name().to_string(),
-                    ))
-                }
-            }
-        }
-
-        let path = format!("table_{}_{}.index", self.def.id, name);
-        if std::path::Path::new(&path).exists() {
-            let tree = BPlusTree::load_from_disk(path, self.buffer_pool.clone())?;
-            self.indexes.insert(name.clone(), (column_indices, tree));
+        let path = self.index_path(name);
+        if path.exists() {
+            let index = TableIndex {
+                columns: self.column_indices(&index_def.columns)?,
+                unique: index_def.unique,
+                tree: BPlusTree::load_from_disk(path, self.buffer_pool.clone())?,
+            };
+            self.indexes.insert(name.clone(), index);
         } else {
             // Index file doesn't exist, rebuild it
-            self.create_index(name.clone(), index_def.columns.clone())?;
+            self.create_index(name.clone(), index_def.columns.clone(), index_def.unique)?;
         }
         Ok(())
     }
 
+    /// Drop an index and delete its file
+    pub fn drop_index(&mut self, name: &str) -> Result<()> {
+        if !self.indexes.contains_key(name) {
+            return Err(Error::IndexNotFound(name.to_string()));
+        }
+        let path = self.index_path(name);
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        self.indexes.remove(name);
+        Ok(())
+    }
+
+    /// File an index is saved to
+    fn index_path(&self, name: &str) -> PathBuf {
+        let disk_manager = self.buffer_pool.lock().unwrap().disk_manager();
+        disk_manager
+            .data_dir()
+            .join(format!("table_{}_{}.index", self.def.id, name))
+    }
+
     /// Get index by name
     pub fn get_index(&self, name: &str) -> Option<&BPlusTree> {
-        self.indexes.get(name).map(|(_, tree)| tree)
+        self.indexes.get(name).map(|index| &index.tree)
     }
 
     /// Get column indices for an index
     pub fn get_index_columns(&self, name: &str) -> Option<&[usize]> {
-        self.indexes.get(name).map(|(cols, _)| cols.as_slice())
+        self.indexes.get(name).map(|index| index.columns.as_slice())
     }
 
     /// Get index name for a specific column
@@ -165,8 +236,8 @@ impl Table {
         let schema = self.def.schema();
         let col_idx = schema.get_column_index(column_name)?;
 
-        for (index_name, (cols, _)) in &self.indexes {
-            if cols.len() == 1 && cols[0] == col_idx {
+        for (index_name, index) in &self.indexes {
+            if index.columns == [col_idx] {
                 return Some(index_name.clone());
             }
         }
@@ -175,12 +246,11 @@ impl Table {
 
     /// Get a tuple from the table by slot ID
     pub fn get_tuple(&mut self, slot_id: SlotId) -> Option<Tuple> {
-        self.heap.get(slot_id)
+        self.get(slot_id)
     }
 
-    /// Insert a tuple into the table
-    pub fn insert(&mut self, tuple: Tuple) -> Result<SlotId> {
-        // Validate tuple matches schema
+    /// Check a tuple against the schema, converting each value to its column type
+    pub fn conform(&self, tuple: Tuple) -> Result<Tuple> {
         let schema = self.def.schema();
         if tuple.len() != schema.column_count() {
             return Err(Error::ExecutionError(format!(
@@ -190,31 +260,41 @@ impl Table {
             )));
         }
 
-        // Check NOT NULL constraints
-        for (i, col) in schema.columns().iter().enumerate() {
-            if !col.nullable {
-                if let Some(value) = tuple.get(i) {
-                    if value.is_null() {
-                        return Err(Error::NullNotAllowed(col.name.clone()));
-                    }
-                }
+        let mut values = Vec::with_capacity(tuple.len());
+        for (value, col) in tuple.into_values().into_iter().zip(schema.columns()) {
+            // Check NOT NULL constraints
+            if value.is_null() && !col.nullable {
+                return Err(Error::NullNotAllowed(col.name.clone()));
             }
+            values.push(coerce_value(value, col)?);
         }
+        Ok(Tuple::new(values))
+    }
+
+    /// Fail with `ConstraintViolation` if a unique index already holds the
+    /// key of `tuple` for a row other than `slot_id`
+    fn check_unique(&self, tuple: &Tuple, slot_id: Option<SlotId>) -> Result<()> {
+        for (name, index) in &self.indexes {
+            if index.conflict(tuple, slot_id).is_some() {
+                return Err(Error::ConstraintViolation(name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Insert a tuple into the table
+    pub fn insert(&mut self, tuple: Tuple) -> Result<SlotId> {
+        let tuple = self.conform(tuple)?;
+        self.check_unique(&tuple, None)?;
 
         // Insert into heap
-        let slot_id = self.heap.insert(tuple.clone())?;
+        let slot_id = self.heap.insert(self.def.encode(tuple.clone()))?;
 
         // Update indexes
-        for (col_indices, tree) in self.indexes.values_mut() {
-            let mut key_values = Vec::new();
-            for col_idx in col_indices.iter() {
-                if let Some(val) = tuple.get(*col_idx) {
-                    key_values.push(val.clone());
-                }
-            }
-            let key = IndexKey::composite(key_values);
-            if let Err(e) = tree.insert(key, slot_id) {
-                // Rollback heap insert if index fails (e.g. unique constraint)
+        for index in self.indexes.values_mut() {
+            let key = index.key(&tuple);
+            if let Err(e) = index.tree.insert(key, slot_id) {
+                // Rollback heap insert if index fails
                 // Note: naive rollback, physical delete
                 self.heap.delete(slot_id).ok();
                 return Err(e);
@@ -224,25 +304,84 @@ impl Table {
         Ok(slot_id)
     }
 
+    /// Insert many tuples into the table, storing them under one buffer
+    /// pool lock
+    ///
+    /// Every tuple is checked, against the unique indexes and the other
+    /// tuples, before any is stored.
+    pub fn insert_many(&mut self, tuples: Vec<Tuple>) -> Result<Vec<SlotId>> {
+        let tuples = tuples
+            .into_iter()
+            .map(|tuple| self.conform(tuple))
+            .collect::<Result<Vec<_>>>()?;
+        for (name, index) in &self.indexes {
+            let mut keys = BTreeSet::new();
+            for tuple in &tuples {
+                if index.conflict(tuple, None).is_some() {
+                    return Err(Error::ConstraintViolation(name.clone()));
+                }
+                let key = index.key(tuple);
+                if index.unique && !key.0.iter().any(Value::is_null) && !keys.insert(key) {
+                    return Err(Error::ConstraintViolation(name.clone()));
+                }
+            }
+        }
+
+        // Insert into heap
+        let encoded = tuples
+            .iter()
+            .map(|tuple| self.def.encode(tuple.clone()))
+            .collect();
+        let slot_ids = self.heap.insert_many(encoded)?;
+
+        // Update indexes, remembering the keys added to each
+        let mut added: Vec<(String, Vec<IndexKey>)> = Vec::new();
+        let mut failure = None;
+        'indexes: for (name, index) in self.indexes.iter_mut() {
+            let mut keys = Vec::with_capacity(tuples.len());
+            for (tuple, &slot_id) in tuples.iter().zip(&slot_ids) {
+                let key = index.key(tuple);
+                if let Err(e) = index.tree.insert(key.clone(), slot_id) {
+                    added.push((name.clone(), keys));
+                    failure = Some(e);
+                    break 'indexes;
+                }
+                keys.push(key);
+            }
+            added.push((name.clone(), keys));
+        }
+
+        if let Some(e) = failure {
+            // Rollback the index entries added so far, then the heap inserts
+            for (name, keys) in &added {
+                if let Some(index) = self.indexes.get_mut(name) {
+                    for key in keys {
+                        index.tree.delete(key).ok();
+                    }
+                }
+            }
+            for &slot_id in &slot_ids {
+                self.heap.delete(slot_id).ok();
+            }
+            return Err(e);
+        }
+
+        Ok(slot_ids)
+    }
+
     /// Delete a tuple from the table
     pub fn delete(&mut self, slot_id: SlotId) -> Result<()> {
-        let tuple = match self.heap.get(slot_id) {
-            Some(t) => t.clone(),
+        let tuple = match self.get(slot_id) {
+            Some(t) => t,
             None => return Err(Error::ExecutionError("Tuple not found".to_string())),
         };
 
         self.heap.delete(slot_id)?;
 
         // Remove from indexes
-        for (col_indices, tree) in self.indexes.values_mut() {
-            let mut key_values = Vec::new();
-            for col_idx in col_indices.iter() {
-                if let Some(val) = tuple.get(*col_idx) {
-                    key_values.push(val.clone());
-                }
-            }
-            let key = IndexKey::composite(key_values);
-            tree.delete(&key)?;
+        for index in self.indexes.values_mut() {
+            let key = index.key(&tuple);
+            index.tree.delete(&key)?;
         }
 
         Ok(())
@@ -250,48 +389,25 @@ impl Table {
 
     /// Update a tuple in the table
     pub fn update(&mut self, slot_id: SlotId, tuple: Tuple) -> Result<()> {
-        // Validate tuple matches schema
-        let schema = self.def.schema();
-        if tuple.len() != schema.column_count() {
-            return Err(Error::ExecutionError(format!(
-                "Expected {} columns, got {}",
-                schema.column_count(),
-                tuple.len()
-            )));
-        }
+        let tuple = self.conform(tuple)?;
+        self.check_unique(&tuple, Some(slot_id))?;
 
         // Retrieve old tuple for index cleanup
-        let old_tuple = match self.heap.get(slot_id) {
-            Some(t) => t.clone(),
+        let old_tuple = match self.get(slot_id) {
+            Some(t) => t,
             None => return Err(Error::ExecutionError("Tuple not found".to_string())),
         };
 
         // Update heap
-        self.heap.update(slot_id, tuple.clone())?;
+        self.heap.update(slot_id, self.def.encode(tuple.clone()))?;
 
         // Update indexes
-        for (col_indices, tree) in self.indexes.values_mut() {
-            // Construct old key
-            let mut old_key_values = Vec::new();
-            for col_idx in col_indices.iter() {
-                if let Some(val) = old_tuple.get(*col_idx) {
-                    old_key_values.push(val.clone());
-                }
-            }
-            let old_key = IndexKey::composite(old_key_values);
-
-            // Construct new key
-            let mut new_key_values = Vec::new();
-            for col_idx in col_indices.iter() {
-                if let Some(val) = tuple.get(*col_idx) {
-                    new_key_values.push(val.clone());
-                }
-            }
-            let new_key = IndexKey::composite(new_key_values);
-
+        for index in self.indexes.values_mut() {
+            let old_key = index.key(&old_tuple);
+            let new_key = index.key(&tuple);
             if old_key != new_key {
-                tree.delete(&old_key)?;
-                tree.insert(new_key, slot_id)?;
+                index.tree.delete(&old_key)?;
+                index.tree.insert(new_key, slot_id)?;
             }
         }
 
@@ -300,12 +416,66 @@ impl Table {
 
     /// Get a tuple by slot ID
     pub fn get(&mut self, slot_id: SlotId) -> Option<Tuple> {
-        self.heap.get(slot_id)
+        self.heap.get(slot_id).map(|tuple| self.def.decode(tuple))
     }
 
     /// Scan all tuples
     pub fn scan(&mut self) -> Vec<(SlotId, Tuple)> {
-        self.heap.scan()
+        self.heap
+            .scan()
+            .into_iter()
+            .map(|(slot_id, tuple)| (slot_id, self.def.decode(tuple)))
+            .collect()
+    }
+
+    /// Rows whose values in `columns` equal `key`
+    pub fn find_rows(&mut self, columns: &[usize], key: &[Value]) -> Vec<(SlotId, Tuple)> {
+        self.scan()
+            .into_iter()
+            .filter(|(_, tuple)| {
+                columns
+                    .iter()
+                    .zip(key)
+                    .all(|(&col_idx, value)| tuple.get(col_idx) == Some(value))
+            })
+            .collect()
+    }
+
+    /// Whether a row has `key` in `columns`, searched through a unique index
+    /// on those columns when there is one
+    pub fn contains_key(&mut self, columns: &[usize], key: &[Value]) -> bool {
+        let index = self.indexes.values().find(|index| {
+            index.unique
+                && index.columns.len() == columns.len()
+                && index
+                    .columns
+                    .iter()
+                    .all(|col_idx| columns.contains(col_idx))
+        });
+        match index {
+            Some(index) => {
+                let values = index
+                    .columns
+                    .iter()
+                    .filter_map(|col_idx| columns.iter().position(|c| c == col_idx))
+                    .map(|i| key[i].clone())
+                    .collect();
+                index.tree.search(&IndexKey::composite(values)).is_some()
+            }
+            None => !self.find_rows(columns, key).is_empty(),
+        }
+    }
+
+    /// Iterate over all stored tuples without materializing the table
+    ///
+    /// Tuples are returned as stored; `TableDef::decode` reads them as rows.
+    pub fn iter(&self) -> HeapIterator {
+        self.heap.iter()
+    }
+
+    /// Get the buffer pool backing this table
+    pub fn buffer_pool(&self) -> Arc<Mutex<BufferPoolManager>> {
+        self.buffer_pool.clone()
     }
 
     /// Get tuple count
@@ -333,7 +503,7 @@ impl Table {
     /// Clear the table
     pub fn clear(&mut self) {
         self.heap.clear();
-        for (_, _tree) in self.indexes.values_mut() {
+        for _index in self.indexes.values_mut() {
             // Ideally clear tree, but for now re-create or unsupported
             // Just clearing heap leaves indexes dangling if not cleared.
             // BPlusTree doesn't expose clean clear from this interface easily without reconstructing
@@ -351,11 +521,135 @@ impl Table {
     }
 }
 
+/// Convert a value to the type of the column it is written to
+///
+/// Numbers are range-checked and rounded to the column's precision, strings
+/// are checked against the declared length and CHAR values are padded.
+pub fn coerce_value(value: Value, column: &Column) -> Result<Value> {
+    let too_large = || Error::ValueTooLarge(column.name.clone());
+    let mismatch = |value: &Value| Error::TypeMismatch {
+        from: value.type_name().to_string(),
+        to: column.data_type.to_string(),
+    };
+
+    if value.is_null() {
+        return Ok(Value::Null);
+    }
+
+    match &column.data_type {
+        DataType::SmallInt | DataType::Integer | DataType::BigInt => {
+            let wide: i64 = match &value {
+                Value::Integer(i) => *i as i64,
+                Value::BigInt(i) => *i,
+                Value::Decimal(d) => d.to_i64().ok_or_else(too_large)?,
+                Value::Float(f) => {
+                    let rounded = f.round();
+                    if !rounded.is_finite()
+                        || rounded < i64::MIN as f64
+                        || rounded >= i64::MAX as f64
+                    {
+                        return Err(too_large());
+                    }
+                    rounded as i64
+                }
+                _ => return Err(mismatch(&value)),
+            };
+            match column.data_type {
+                DataType::SmallInt => i16::try_from(wide)
+                    .map(|v| Value::Integer(v as i32))
+                    .map_err(|_| too_large()),
+                DataType::Integer => i32::try_from(wide)
+                    .map(Value::Integer)
+                    .map_err(|_| too_large()),
+                _ => Ok(Value::BigInt(wide)),
+            }
+        }
+        DataType::Float | DataType::Double => value
+            .as_f64()
+            .filter(|_| !matches!(value, Value::Boolean(_)))
+            .map(Value::Float)
+            .ok_or_else(|| mismatch(&value)),
+        DataType::Decimal(precision, scale) => {
+            let decimal = match &value {
+                Value::Float(f) => Decimal::from_f64(*f).ok_or_else(too_large)?,
+                other => other.as_decimal().ok_or_else(|| mismatch(&value))?,
+            };
+            decimal
+                .fit(*precision as u32, *scale as u32)
+                .map(Value::Decimal)
+                .ok_or_else(too_large)
+        }
+        DataType::Char(n) | DataType::Varchar(n) => {
+            let s = match value {
+                Value::String(s) => s,
+                other => return Err(mismatch(&other)),
+            };
+            // Trailing spaces beyond the limit are dropped, anything else is an error
+            let len = s.chars().count();
+            let s = if len > *n {
+                let trimmed = s.trim_end_matches(' ');
+                if trimmed.chars().count() > *n {
+                    return Err(too_large());
+                }
+                trimmed
+                    .chars()
+                    .chain(std::iter::repeat(' '))
+                    .take(*n)
+                    .collect()
+            } else {
+                s
+            };
+            match column.data_type {
+                DataType::Char(_) => Ok(Value::String(pad_char(s, *n))),
+                _ => Ok(Value::String(s)),
+            }
+        }
+        DataType::Text => match value {
+            Value::String(s) => Ok(Value::String(s)),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Boolean => match value {
+            Value::Boolean(b) => Ok(Value::Boolean(b)),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Date => match value {
+            Value::Date(d) => Ok(Value::Date(d)),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Time => match value {
+            Value::Time(t) => Ok(Value::Time(t)),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Timestamp => match value {
+            Value::Timestamp(t) => Ok(Value::Timestamp(t)),
+            Value::Date(d) => Ok(Value::Timestamp(d as i64 * MS_PER_DAY)),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Interval => match value {
+            Value::Interval(i) => Ok(Value::Interval(i)),
+            other => Err(mismatch(&other)),
+        },
+        DataType::Blob => match value {
+            Value::Bytes(b) => Ok(Value::Bytes(b)),
+            other => Err(mismatch(&other)),
+        },
+    }
+}
+
+/// Pad a string with spaces to `n` characters
+pub fn pad_char(s: String, n: usize) -> String {
+    let len = s.chars().count();
+    if len >= n {
+        return s;
+    }
+    let mut padded = s;
+    padded.extend(std::iter::repeat_n(' ', n - len));
+    padded
+}
+
 #[cfg(test)]
 mod tests {
-    use super::super::tuple::Value;
     use super::*;
-    use crate::catalog::{Column, DataType};
 
     fn create_test_table() -> Table {
         let mut schema = Schema::new();
@@ -441,7 +735,7 @@ mod tests {
         let mut table = create_test_table();
         // Create index on name (column index 1)
         table
-            .create_index("name_idx".to_string(), vec!["name".to_string()])
+            .create_index("name_idx".to_string(), vec!["name".to_string()], false)
             .unwrap();
 
         let tuple = Tuple::new(vec![
@@ -485,4 +779,44 @@ mod tests {
             None
         );
     }
+
+    #[test]
+    fn test_coerce_value() {
+        let col = |data_type| Column::new("c", data_type, 0);
+
+        assert_eq!(
+            coerce_value(Value::Integer(7), &col(DataType::BigInt)).unwrap(),
+            Value::BigInt(7)
+        );
+        assert!(matches!(
+            coerce_value(Value::Integer(40_000), &col(DataType::SmallInt)),
+            Err(Error::ValueTooLarge(_))
+        ));
+        assert!(matches!(
+            coerce_value(Value::BigInt(1 << 40), &col(DataType::Integer)),
+            Err(Error::ValueTooLarge(_))
+        ));
+        assert!(matches!(
+            coerce_value(Value::String("toolong".into()), &col(DataType::Varchar(3))),
+            Err(Error::ValueTooLarge(_))
+        ));
+        assert_eq!(
+            coerce_value(Value::String("ab".into()), &col(DataType::Char(4))).unwrap(),
+            Value::String("ab  ".into())
+        );
+        assert_eq!(
+            coerce_value(Value::Float(12.345), &col(DataType::Decimal(5, 2)))
+                .unwrap()
+                .to_string(),
+            "12.35"
+        );
+        assert!(matches!(
+            coerce_value(Value::Float(1234.5), &col(DataType::Decimal(5, 2))),
+            Err(Error::ValueTooLarge(_))
+        ));
+        assert!(matches!(
+            coerce_value(Value::String("1".into()), &col(DataType::Integer)),
+            Err(Error::TypeMismatch { .. })
+        ));
+    }
 }
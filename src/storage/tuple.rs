@@ -4,6 +4,9 @@
 
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
+
+use super::datetime::{self, Interval, MS_PER_DAY};
+use super::decimal::Decimal;
 use std::fmt;
 
 /// A value in the database
@@ -19,12 +22,18 @@ pub enum Value {
     BigInt(i64),
     /// Float value (64-bit)
     Float(f64),
+    /// Fixed-point decimal value
+    Decimal(Decimal),
     /// String value
     String(String),
     /// Date value (days since epoch as i32)
     Date(i32),
+    /// Time of day (milliseconds since midnight as i64)
+    Time(i64),
     /// Timestamp value (milliseconds since epoch as i64)
     Timestamp(i64),
+    /// Interval between two points in time
+    Interval(Interval),
     /// Binary data
     Bytes(Vec<u8>),
 }
@@ -38,9 +47,12 @@ impl PartialEq for Value {
             (Value::Integer(a), Value::Integer(b)) => a == b,
             (Value::BigInt(a), Value::BigInt(b)) => a == b,
             (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Decimal(a), Value::Decimal(b)) => a == b,
             (Value::String(a), Value::String(b)) => a == b,
             (Value::Date(a), Value::Date(b)) => a == b,
+            (Value::Time(a), Value::Time(b)) => a == b,
             (Value::Timestamp(a), Value::Timestamp(b)) => a == b,
+            (Value::Interval(a), Value::Interval(b)) => a == b,
             (Value::Bytes(a), Value::Bytes(b)) => a == b,
             _ => false,
         }
@@ -58,9 +70,12 @@ impl std::hash::Hash for Value {
             Value::Integer(v) => v.hash(state),
             Value::BigInt(v) => v.hash(state),
             Value::Float(v) => v.to_bits().hash(state),
+            Value::Decimal(v) => v.hash(state),
             Value::String(v) => v.hash(state),
             Value::Date(v) => v.hash(state),
+            Value::Time(v) => v.hash(state),
             Value::Timestamp(v) => v.hash(state),
+            Value::Interval(v) => v.hash(state),
             Value::Bytes(v) => v.hash(state),
         }
     }
@@ -88,6 +103,7 @@ impl Value {
             Value::Integer(i) => Some(*i),
             Value::BigInt(i) => (*i).try_into().ok(),
             Value::Float(f) => Some(*f as i32),
+            Value::Decimal(d) => d.to_i64().and_then(|i| i.try_into().ok()),
             _ => None,
         }
     }
@@ -98,6 +114,7 @@ impl Value {
             Value::Integer(i) => Some(*i as i64),
             Value::BigInt(i) => Some(*i),
             Value::Float(f) => Some(*f as i64),
+            Value::Decimal(d) => d.to_i64(),
             _ => None,
         }
     }
@@ -108,6 +125,17 @@ impl Value {
             Value::Integer(i) => Some(*i as f64),
             Value::BigInt(i) => Some(*i as f64),
             Value::Float(f) => Some(*f),
+            Value::Decimal(d) => Some(d.to_f64()),
+            _ => None,
+        }
+    }
+
+    /// Try to convert to an exact decimal (integers and decimals only)
+    pub fn as_decimal(&self) -> Option<Decimal> {
+        match self {
+            Value::Integer(i) => Some(Decimal::from_i64(*i as i64)),
+            Value::BigInt(i) => Some(Decimal::from_i64(*i)),
+            Value::Decimal(d) => Some(*d),
             _ => None,
         }
     }
@@ -128,9 +156,12 @@ impl Value {
             Value::Integer(_) => "INTEGER",
             Value::BigInt(_) => "BIGINT",
             Value::Float(_) => "FLOAT",
+            Value::Decimal(_) => "DECIMAL",
             Value::String(_) => "STRING",
             Value::Date(_) => "DATE",
+            Value::Time(_) => "TIME",
             Value::Timestamp(_) => "TIMESTAMP",
+            Value::Interval(_) => "INTERVAL",
             Value::Bytes(_) => "BYTES",
         }
     }
@@ -155,10 +186,22 @@ impl Value {
             (Value::BigInt(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
             (Value::Float(a), Value::BigInt(b)) => a.partial_cmp(&(*b as f64)),
 
+            (Value::Decimal(a), Value::Decimal(b)) => Some(a.cmp(b)),
+            (Value::Decimal(_), Value::Float(_)) | (Value::Float(_), Value::Decimal(_)) => {
+                self.as_f64()?.partial_cmp(&other.as_f64()?)
+            }
+            (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+                Some(self.as_decimal()?.cmp(&other.as_decimal()?))
+            }
+
             (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
 
             (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+            (Value::Time(a), Value::Time(b)) => Some(a.cmp(b)),
             (Value::Timestamp(a), Value::Timestamp(b)) => Some(a.cmp(b)),
+            (Value::Date(a), Value::Timestamp(b)) => Some((*a as i64 * MS_PER_DAY).cmp(b)),
+            (Value::Timestamp(a), Value::Date(b)) => Some(a.cmp(&(*b as i64 * MS_PER_DAY))),
+            (Value::Interval(a), Value::Interval(b)) => Some(a.cmp(b)),
 
             (Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
 
@@ -210,6 +253,24 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Remainder of a division, with the sign of the dividend
+    pub fn rem(&self, other: &Value) -> Option<Value> {
+        match (self, other) {
+            (Value::Integer(a), Value::Integer(b)) if *b != 0 => {
+                Some(Value::Integer(a.wrapping_rem(*b)))
+            }
+            (Value::Integer(_) | Value::BigInt(_), Value::Integer(_) | Value::BigInt(_)) => {
+                let (a, b) = (self.as_i64()?, other.as_i64()?);
+                (b != 0).then(|| Value::BigInt(a.wrapping_rem(b)))
+            }
+            (Value::Float(_), _) | (_, Value::Float(_)) => {
+                let (a, b) = (self.as_f64()?, other.as_f64()?);
+                (b != 0.0).then(|| Value::Float(a % b))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -220,9 +281,12 @@ impl fmt::Display for Value {
             Value::Integer(i) => write!(f, "{}", i),
             Value::BigInt(i) => write!(f, "{}", i),
             Value::Float(n) => write!(f, "{}", n),
+            Value::Decimal(d) => write!(f, "{}", d),
             Value::String(s) => write!(f, "{}", s),
-            Value::Date(d) => write!(f, "DATE({})", d),
-            Value::Timestamp(t) => write!(f, "TIMESTAMP({})", t),
+            Value::Date(d) => write!(f, "{}", datetime::format_date(*d)),
+            Value::Time(t) => write!(f, "{}", datetime::format_time(*t)),
+            Value::Timestamp(t) => write!(f, "{}", datetime::format_timestamp(*t)),
+            Value::Interval(i) => write!(f, "{}", i),
             Value::Bytes(b) => write!(f, "BYTES[{}]", b.len()),
         }
     }
@@ -393,6 +457,21 @@ impl Tuple {
                     bytes.extend_from_slice(&(b.len() as u32).to_le_bytes());
                     bytes.extend_from_slice(b);
                 }
+                Value::Decimal(d) => {
+                    bytes.push(9);
+                    bytes.extend_from_slice(&d.mantissa().to_le_bytes());
+                    bytes.push(d.scale() as u8);
+                }
+                Value::Time(t) => {
+                    bytes.push(10);
+                    bytes.extend_from_slice(&t.to_le_bytes());
+                }
+                Value::Interval(i) => {
+                    bytes.push(11);
+                    bytes.extend_from_slice(&i.months.to_le_bytes());
+                    bytes.extend_from_slice(&i.days.to_le_bytes());
+                    bytes.extend_from_slice(&i.millis.to_le_bytes());
+                }
             }
         }
         bytes
@@ -505,6 +584,44 @@ impl Tuple {
                     values.push(Value::Bytes(bytes[offset..offset + b_len].to_vec()));
                     offset += b_len;
                 }
+                9 => {
+                    if offset + 17 > bytes.len() {
+                        return Err("Buffer overflow while reading decimal".to_string());
+                    }
+                    let mut mantissa = [0u8; 16];
+                    mantissa.copy_from_slice(&bytes[offset..offset + 16]);
+                    let scale = bytes[offset + 16] as u32;
+                    let decimal = Decimal::new(i128::from_le_bytes(mantissa), scale)
+                        .ok_or_else(|| "Invalid decimal value".to_string())?;
+                    values.push(Value::Decimal(decimal));
+                    offset += 17;
+                }
+                10 => {
+                    if offset + 8 > bytes.len() {
+                        return Err("Buffer overflow while reading time".to_string());
+                    }
+                    let mut time = [0u8; 8];
+                    time.copy_from_slice(&bytes[offset..offset + 8]);
+                    values.push(Value::Time(i64::from_le_bytes(time)));
+                    offset += 8;
+                }
+                11 => {
+                    if offset + 16 > bytes.len() {
+                        return Err("Buffer overflow while reading interval".to_string());
+                    }
+                    let mut months = [0u8; 4];
+                    let mut days = [0u8; 4];
+                    let mut millis = [0u8; 8];
+                    months.copy_from_slice(&bytes[offset..offset + 4]);
+                    days.copy_from_slice(&bytes[offset + 4..offset + 8]);
+                    millis.copy_from_slice(&bytes[offset + 8..offset + 16]);
+                    values.push(Value::Interval(Interval::new(
+                        i32::from_le_bytes(months),
+                        i32::from_le_bytes(days),
+                        i64::from_le_bytes(millis),
+                    )));
+                    offset += 16;
+                }
                 _ => return Err(format!("Unknown type tag: {}", type_tag)),
             }
         }
@@ -584,4 +701,45 @@ mod tests {
         assert_eq!(projected.len(), 2);
         assert_eq!(projected.get(1), Some(&Value::Boolean(true)));
     }
+
+    #[test]
+    fn test_decimal_round_trip_and_compare() {
+        let price = Value::Decimal("-1234.5600".parse().unwrap());
+        let tuple = Tuple::new(vec![Value::Integer(1), price.clone(), Value::Null]);
+
+        let decoded = Tuple::from_bytes(&tuple.to_bytes()).unwrap();
+        assert_eq!(decoded, tuple);
+        assert_eq!(decoded.get(1).unwrap().to_string(), "-1234.5600");
+
+        let one = Value::Decimal("1.00".parse().unwrap());
+        assert_eq!(one.compare(&Value::Integer(1)), Some(Ordering::Equal));
+        assert_eq!(one.compare(&Value::Float(0.5)), Some(Ordering::Greater));
+    }
+
+    #[test]
+    fn test_temporal_round_trip_and_display() {
+        let tuple = Tuple::new(vec![
+            Value::Date(19_723),
+            Value::Time(45_296_789),
+            Value::Timestamp(1_704_067_200_000),
+            Value::Interval(Interval::new(14, 3, 3_600_000)),
+        ]);
+
+        let decoded = Tuple::from_bytes(&tuple.to_bytes()).unwrap();
+        assert_eq!(decoded, tuple);
+        let shown: Vec<String> = decoded.values().iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "2024-01-01",
+                "12:34:56.789",
+                "2024-01-01 00:00:00",
+                "1 year 2 mons 3 days 01:00:00"
+            ]
+        );
+        assert_eq!(
+            Value::Date(19_723).compare(&Value::Timestamp(1_704_067_200_001)),
+            Some(Ordering::Less)
+        );
+    }
 }
@@ -22,12 +22,17 @@ pub enum LogRecordType {
     Rollback,
     /// Insert Tuple
     Insert,
+    /// Insert of many tuples at once, held in `rows`
+    BulkInsert,
     /// Update Tuple
     Update,
     /// Delete Tuple
     Delete,
     /// Aborted Transaction
     Abort,
+    /// Sequence values reserved, outside of any transaction; the table name
+    /// is the sequence and the after image holds the last value reserved
+    Sequence,
 }
 
 /// A single log record
@@ -47,6 +52,9 @@ pub struct LogRecord {
     pub before_image: Option<Tuple>,
     /// After Image (for Redo/Recovery)
     pub after_image: Option<Tuple>,
+    /// Location and image of each row of a bulk insert
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rows: Vec<(SlotId, Tuple)>,
 }
 
 impl LogRecord {
@@ -67,8 +75,31 @@ impl LogRecord {
             slot_id,
             before_image,
             after_image,
+            rows: Vec::new(),
         }
     }
+
+    /// The records of single rows this record stands for: an Insert with
+    /// the same LSN for each row of a bulk insert, otherwise itself
+    pub fn split(self) -> Vec<LogRecord> {
+        if self.record_type != LogRecordType::BulkInsert {
+            return vec![self];
+        }
+        self.rows
+            .into_iter()
+            .map(|(slot_id, tuple)| {
+                LogRecord::new(
+                    self.lsn,
+                    self.trans_id,
+                    LogRecordType::Insert,
+                    self.table_name.clone(),
+                    Some(slot_id),
+                    None,
+                    Some(tuple),
+                )
+            })
+            .collect()
+    }
 }
 
 /// Manages Write-Ahead Logs
@@ -84,6 +115,12 @@ pub struct LogManager {
     next_lsn: Arc<Mutex<u64>>,
 }
 
+impl Default for LogManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LogManager {
     pub fn new() -> Self {
         Self {
@@ -125,6 +162,32 @@ impl LogManager {
         Ok(lsn)
     }
 
+    /// Append one record for the insert of many rows
+    pub fn append_bulk_insert(
+        &self,
+        trans_id: u64,
+        table_name: String,
+        rows: Vec<(SlotId, Tuple)>,
+    ) -> Result<u64> {
+        let mut lsn_guard = self.next_lsn.lock().unwrap();
+        let lsn = *lsn_guard;
+        *lsn_guard += 1;
+
+        let mut record = LogRecord::new(
+            lsn,
+            trans_id,
+            LogRecordType::BulkInsert,
+            Some(table_name),
+            None,
+            None,
+            None,
+        );
+        record.rows = rows;
+
+        self.buffer.lock().unwrap().push(record);
+        Ok(lsn)
+    }
+
     /// Flush logs to disk
     pub fn flush(&self) -> Result<()> {
         let mut buffer = self.buffer.lock().unwrap();
@@ -137,9 +200,9 @@ impl LogManager {
             for record in buffer.iter() {
                 serde_json::to_writer(&mut *file, record)
                     .map_err(|e| Error::Internal(e.to_string()))?;
-                writeln!(file).map_err(|e| Error::IoError(e))?;
+                writeln!(file).map_err(Error::IoError)?;
             }
-            file.flush().map_err(|e| Error::IoError(e))?;
+            file.flush().map_err(Error::IoError)?;
             buffer.clear();
         }
         Ok(())
@@ -151,20 +214,21 @@ impl LogManager {
             .create(true)
             .append(true)
             .open(path)
-            .map_err(|e| Error::IoError(e))?;
+            .map_err(Error::IoError)?;
         self.log_file = Some(Arc::new(Mutex::new(file)));
         Ok(())
     }
 
     /// Read all logs from disk (for Recovery)
-    pub fn read_from_log(&self, path: &str) -> Result<Vec<LogRecord>> {
+    pub fn read_from_log(&self, path: impl AsRef<std::path::Path>) -> Result<Vec<LogRecord>> {
         use std::io::BufRead;
-        let file = File::open(path).map_err(|_| Error::FileNotFound(path.to_string()))?;
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|_| Error::FileNotFound(path.display().to_string()))?;
         let reader = std::io::BufReader::new(file);
         let mut records = Vec::new();
 
         for line in reader.lines() {
-            let line = line.map_err(|e| Error::IoError(e))?;
+            let line = line.map_err(Error::IoError)?;
             if line.trim().is_empty() {
                 continue;
             }
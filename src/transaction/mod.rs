@@ -1,3 +1,4 @@
+#[allow(clippy::module_inception)]
 pub mod transaction;
 
 pub use transaction::{LockMode, TransactionManager, TransactionState};
@@ -42,10 +42,19 @@ pub enum LockMode {
     Exclusive,
 }
 
+/// Lock table: Table Name -> (Exclusive Lock Holder, Shared Lock Holders)
+type LockTable = HashMap<String, (Option<u64>, Vec<u64>)>;
+
 /// Lock Manager
 pub struct LockManager {
     /// Locks: Table Name -> (Exclusive Lock Holder, Shared Lock Holders)
-    locks: RwLock<HashMap<String, (Option<u64>, Vec<u64>)>>,
+    locks: RwLock<LockTable>,
+}
+
+impl Default for LockManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LockManager {
@@ -0,0 +1,27 @@
+//! Helpers shared by the SQL integration tests
+
+use arcdb::catalog::Catalog;
+use arcdb::executor::{Analyzer, ExecutionEngine, Planner, QueryResult};
+use arcdb::sql::Parser;
+use std::sync::Arc;
+use tempfile::TempDir;
+
+/// Parse, analyze, plan and execute a single SQL statement
+pub fn run(
+    engine: &mut ExecutionEngine,
+    catalog: &Catalog,
+    sql: &str,
+) -> arcdb::Result<QueryResult> {
+    let stmt = Parser::new(sql)?.parse()?;
+    let stmt = Analyzer::new(catalog).analyze(stmt)?;
+    let plan = Planner::new(catalog).plan(stmt);
+    engine.execute(plan)
+}
+
+/// An engine over a fresh database, removed when the directory is dropped
+pub fn setup() -> (ExecutionEngine, Arc<Catalog>, TempDir) {
+    let dir = tempfile::tempdir().unwrap();
+    let catalog = Arc::new(Catalog::new());
+    let engine = ExecutionEngine::open(catalog.clone(), dir.path()).unwrap();
+    (engine, catalog, dir)
+}
@@ -0,0 +1,166 @@
+//! Conformance tests for SQL three-valued logic
+//!
+//! Every comparison with NULL is UNKNOWN, which WHERE treats as not true,
+//! while GROUP BY and DISTINCT still place NULLs in a single group.
+
+mod common;
+
+use arcdb::catalog::Catalog;
+use arcdb::executor::ExecutionEngine;
+use arcdb::storage::Value;
+use common::{run, setup};
+
+/// Evaluate a scalar expression with `SELECT <expr>`
+fn eval(engine: &mut ExecutionEngine, catalog: &Catalog, expr: &str) -> Value {
+    let result = run(engine, catalog, &format!("SELECT {}", expr)).unwrap();
+    result.rows[0].get(0).cloned().unwrap()
+}
+
+/// A table t(id, x) where x is NULL for id 3
+fn setup_nullable(engine: &mut ExecutionEngine, catalog: &Catalog) {
+    run(engine, catalog, "CREATE TABLE t (id INTEGER, x INTEGER)").unwrap();
+    for sql in [
+        "INSERT INTO t VALUES (1, 1)",
+        "INSERT INTO t VALUES (2, 2)",
+        "INSERT INTO t VALUES (3, NULL)",
+    ] {
+        run(engine, catalog, sql).unwrap();
+    }
+}
+
+/// Ids of the rows of t matching a predicate
+fn matching_ids(engine: &mut ExecutionEngine, catalog: &Catalog, predicate: &str) -> Vec<i32> {
+    let sql = format!("SELECT id FROM t WHERE {} ORDER BY id", predicate);
+    run(engine, catalog, &sql)
+        .unwrap()
+        .rows
+        .iter()
+        .map(|row| match row.get(0) {
+            Some(Value::Integer(id)) => *id,
+            other => panic!("unexpected id {:?}", other),
+        })
+        .collect()
+}
+
+const T: Value = Value::Boolean(true);
+const F: Value = Value::Boolean(false);
+const U: Value = Value::Null;
+
+#[test]
+fn test_comparisons_with_null_are_unknown() {
+    let (mut engine, catalog, _dir) = setup();
+    let cases = [
+        ("NULL = NULL", U),
+        ("NULL <> NULL", U),
+        ("1 = NULL", U),
+        ("NULL < 1", U),
+        ("'a' >= NULL", U),
+        ("1 = 1", T),
+        ("1 <> 1", F),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval(&mut engine, &catalog, expr), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_kleene_and_or_not() {
+    let (mut engine, catalog, _dir) = setup();
+    let cases = [
+        ("TRUE AND NULL", U),
+        ("FALSE AND NULL", F),
+        ("NULL AND FALSE", F),
+        ("NULL AND NULL", U),
+        ("TRUE OR NULL", T),
+        ("NULL OR TRUE", T),
+        ("FALSE OR NULL", U),
+        ("NULL OR NULL", U),
+        ("NOT NULL", U),
+        ("NOT (1 = NULL)", U),
+        ("NOT FALSE", T),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval(&mut engine, &catalog, expr), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_in_between_and_case_with_null() {
+    let (mut engine, catalog, _dir) = setup();
+    let cases = [
+        ("1 IN (1, NULL)", T),
+        ("2 IN (1, NULL)", U),
+        ("2 NOT IN (1, NULL)", U),
+        ("2 NOT IN (1, 3)", T),
+        ("NULL IN (1, 2)", U),
+        ("NULL BETWEEN 1 AND 2", U),
+        ("5 BETWEEN NULL AND 2", F),
+        ("1 BETWEEN NULL AND 2", U),
+        ("5 NOT BETWEEN 6 AND NULL", T),
+        ("CASE WHEN NULL THEN 1 ELSE 2 END", Value::Integer(2)),
+        ("CASE WHEN NULL = NULL THEN 1 END", U),
+        ("CASE NULL WHEN NULL THEN 1 ELSE 2 END", Value::Integer(2)),
+        (
+            "CASE 2 WHEN 1 THEN 'a' WHEN 2 THEN 'b' END",
+            Value::String("b".into()),
+        ),
+    ];
+    for (expr, expected) in cases {
+        assert_eq!(eval(&mut engine, &catalog, expr), expected, "{}", expr);
+    }
+}
+
+#[test]
+fn test_arithmetic_with_null_is_null() {
+    let (mut engine, catalog, _dir) = setup();
+    for expr in ["1 + NULL", "NULL * 2", "-(NULL + 1)", "'a' || NULL"] {
+        assert_eq!(eval(&mut engine, &catalog, expr), U, "{}", expr);
+    }
+}
+
+#[test]
+fn test_where_filters_unknown_rows() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_nullable(&mut engine, &catalog);
+
+    let cases: [(&str, &[i32]); 9] = [
+        ("x = NULL", &[]),
+        ("x <> 1", &[2]),
+        ("NOT (x = 1)", &[2]),
+        ("x = 1 OR x IS NULL", &[1, 3]),
+        ("x IN (1, NULL)", &[1]),
+        ("x NOT IN (1, NULL)", &[]),
+        ("x NOT BETWEEN 2 AND 5", &[1]),
+        ("NOT (x > 1 AND id > 0)", &[1]),
+        ("x IS NULL", &[3]),
+    ];
+    for (predicate, expected) in cases {
+        assert_eq!(
+            matching_ids(&mut engine, &catalog, predicate),
+            expected,
+            "{}",
+            predicate
+        );
+    }
+}
+
+#[test]
+fn test_nulls_group_together() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_nullable(&mut engine, &catalog);
+    run(&mut engine, &catalog, "INSERT INTO t VALUES (4, NULL)").unwrap();
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT x, COUNT(*) FROM t GROUP BY x",
+    )
+    .unwrap();
+    assert_eq!(result.rows.len(), 3);
+    let null_group = result
+        .rows
+        .iter()
+        .find(|row| row.get(0) == Some(&Value::Null))
+        .unwrap();
+    assert_eq!(null_group.get(1), Some(&Value::BigInt(2)));
+}
@@ -0,0 +1,1942 @@
+mod common;
+
+use arcdb::catalog::Catalog;
+use arcdb::executor::{ExecutionEngine, QueryResult};
+use arcdb::storage::Value;
+use common::{run, setup};
+
+/// First column of every row
+fn first_column(result: &QueryResult) -> Vec<Value> {
+    result
+        .rows
+        .iter()
+        .map(|row| row.get(0).cloned().unwrap())
+        .collect()
+}
+
+#[test]
+fn test_order_by_with_limit() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE nums (id INTEGER, label VARCHAR(10))",
+    )
+    .unwrap();
+    for i in 0..50 {
+        let sql = format!("INSERT INTO nums VALUES ({}, 'n{}')", i, i);
+        run(&mut engine, &catalog, &sql).unwrap();
+    }
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT id FROM nums WHERE id > 10 ORDER BY id DESC LIMIT 3 OFFSET 1",
+    )
+    .unwrap();
+
+    assert_eq!(result.columns, vec!["id".to_string()]);
+    assert_eq!(
+        first_column(&result),
+        vec![Value::Integer(48), Value::Integer(47), Value::Integer(46)]
+    );
+}
+
+#[test]
+fn test_limit_without_order() {
+    let (mut engine, catalog, _dir) = setup();
+    run(&mut engine, &catalog, "CREATE TABLE items (id INTEGER)").unwrap();
+    for i in 0..20 {
+        let sql = format!("INSERT INTO items VALUES ({})", i);
+        run(&mut engine, &catalog, &sql).unwrap();
+    }
+
+    let result = run(&mut engine, &catalog, "SELECT * FROM items LIMIT 5").unwrap();
+    assert_eq!(result.rows.len(), 5);
+}
+
+#[test]
+fn test_select_without_from() {
+    let (mut engine, catalog, _dir) = setup();
+    let result = run(&mut engine, &catalog, "SELECT 1 + 2 AS three").unwrap();
+
+    assert_eq!(result.columns, vec!["three".to_string()]);
+    assert_eq!(first_column(&result), vec![Value::Integer(3)]);
+}
+
+/// Two small tables for join tests: emp(emp_id, ename, dept) and dept(dept_no, dname)
+fn setup_join_tables(engine: &mut ExecutionEngine, catalog: &Catalog) {
+    for sql in [
+        "CREATE TABLE emp (emp_id INTEGER, ename VARCHAR(20), dept INTEGER)",
+        "CREATE TABLE dept (dept_no INTEGER, dname VARCHAR(20))",
+        "INSERT INTO emp VALUES (1, 'ann', 10)",
+        "INSERT INTO emp VALUES (2, 'bob', 20)",
+        "INSERT INTO emp VALUES (3, 'cat', 10)",
+        "INSERT INTO emp VALUES (4, 'dan', 30)",
+        "INSERT INTO dept VALUES (10, 'eng')",
+        "INSERT INTO dept VALUES (20, 'ops')",
+        "INSERT INTO dept VALUES (40, 'hr')",
+    ] {
+        run(engine, catalog, sql).unwrap();
+    }
+}
+
+fn text(s: &str) -> Value {
+    Value::String(s.to_string())
+}
+
+#[test]
+fn test_filter_over_hash_join() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename FROM emp JOIN dept ON dept = dept_no WHERE dname = 'eng' ORDER BY ename",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("ann"), text("cat")]);
+}
+
+#[test]
+fn test_sort_and_limit_over_join() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename, dname FROM emp JOIN dept ON dept_no = dept ORDER BY ename DESC LIMIT 2",
+    )
+    .unwrap();
+    assert_eq!(
+        result.columns,
+        vec!["ename".to_string(), "dname".to_string()]
+    );
+    assert_eq!(first_column(&result), vec![text("cat"), text("bob")]);
+    assert_eq!(result.rows[0].get(1), Some(&text("eng")));
+}
+
+#[test]
+fn test_hash_join_on_mixed_numeric_types() {
+    let (mut engine, catalog, _dir) = setup();
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+    query("CREATE TABLE small (id INTEGER, tag VARCHAR(5))").unwrap();
+    query("CREATE TABLE big (id BIGINT)").unwrap();
+    query("CREATE TABLE exact (id DECIMAL(10, 2))").unwrap();
+    query("INSERT INTO small VALUES (1, 'a'), (2, 'b'), (3, 'c')").unwrap();
+    query("INSERT INTO big VALUES (2), (3), (4)").unwrap();
+    query("INSERT INTO exact VALUES (1.00), (3.00), (3.50)").unwrap();
+
+    let tags = |result: QueryResult| first_column(&result);
+    let inner = query("SELECT tag FROM small JOIN big ON small.id = big.id ORDER BY tag").unwrap();
+    let left = query(
+        "SELECT tag FROM small LEFT JOIN big ON small.id = big.id \
+           WHERE big.id IS NOT NULL ORDER BY tag",
+    )
+    .unwrap();
+    assert_eq!(tags(inner), vec![text("b"), text("c")]);
+    assert_eq!(tags(left), vec![text("b"), text("c")]);
+
+    let result =
+        query("SELECT tag FROM exact JOIN small ON exact.id = small.id ORDER BY tag").unwrap();
+    assert_eq!(tags(result), vec![text("a"), text("c")]);
+}
+
+#[test]
+fn test_left_join_pads_with_nulls() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename, dname FROM emp LEFT JOIN dept ON dept = dept_no ORDER BY ename",
+    )
+    .unwrap();
+    assert_eq!(result.rows.len(), 4);
+    assert_eq!(result.rows[3].get(0), Some(&text("dan")));
+    assert_eq!(result.rows[3].get(1), Some(&Value::Null));
+}
+
+#[test]
+fn test_index_scan_under_project() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE INDEX idx_emp_id ON emp (emp_id)",
+    )
+    .unwrap();
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename, emp_id * 10 AS scaled FROM emp WHERE emp_id = 3",
+    )
+    .unwrap();
+    assert_eq!(
+        result.columns,
+        vec!["ename".to_string(), "scaled".to_string()]
+    );
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0].get(0), Some(&text("cat")));
+    assert_eq!(result.rows[0].get(1), Some(&Value::Integer(30)));
+}
+
+#[test]
+fn test_group_by_over_join_with_having() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT dname, COUNT(*) FROM emp JOIN dept ON dept = dept_no \
+         GROUP BY dname HAVING COUNT(*) > 1",
+    )
+    .unwrap();
+    assert_eq!(
+        result.columns,
+        vec!["dname".to_string(), "COUNT(*)".to_string()]
+    );
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0].get(0), Some(&text("eng")));
+    assert_eq!(result.rows[0].get(1), Some(&Value::BigInt(2)));
+}
+
+#[test]
+fn test_aggregates_without_group_by() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT COUNT(*), SUM(emp_id), MAX(ename) FROM emp WHERE dept = 10",
+    )
+    .unwrap();
+    assert_eq!(
+        result.rows[0].values(),
+        &[Value::BigInt(2), Value::BigInt(4), text("cat")]
+    );
+
+    // An empty input still yields one row
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT COUNT(*), MIN(emp_id) FROM emp WHERE dept = 99",
+    )
+    .unwrap();
+    assert_eq!(result.rows[0].values(), &[Value::BigInt(0), Value::Null]);
+}
+
+#[test]
+fn test_qualified_columns_and_aliases() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    // Self-join: both sides have the same column names
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT a.ename, b.ename FROM emp a JOIN emp b ON a.dept = b.dept \
+         WHERE a.emp_id < b.emp_id",
+    )
+    .unwrap();
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0].values(), &[text("ann"), text("cat")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT d.*, e.ename FROM emp e JOIN dept AS d ON e.dept = d.dept_no \
+         WHERE e.emp_id = 2",
+    )
+    .unwrap();
+    assert_eq!(
+        result.columns,
+        vec![
+            "dept_no".to_string(),
+            "dname".to_string(),
+            "ename".to_string()
+        ]
+    );
+    assert_eq!(
+        result.rows[0].values(),
+        &[Value::Integer(20), text("ops"), text("bob")]
+    );
+}
+
+#[test]
+fn test_ambiguous_column_is_an_error() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename FROM emp a JOIN emp b ON a.emp_id = b.emp_id",
+    );
+    assert!(matches!(result, Err(arcdb::Error::AmbiguousColumn(_))));
+
+    // Once aliased, the table name no longer qualifies its columns
+    let result = run(&mut engine, &catalog, "SELECT emp.ename FROM emp e");
+    assert!(matches!(result, Err(arcdb::Error::ColumnNotFound(_, _))));
+}
+
+#[test]
+fn test_analyzer_rejects_and_coerces() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE typed (id INTEGER, total BIGINT, ratio DOUBLE)",
+    )
+    .unwrap();
+
+    // Values are converted to the column type on write
+    run(&mut engine, &catalog, "INSERT INTO typed VALUES (1, 2, 3)").unwrap();
+    let result = run(&mut engine, &catalog, "SELECT total, ratio FROM typed").unwrap();
+    assert_eq!(
+        result.rows[0].values(),
+        &[Value::BigInt(2), Value::Float(3.0)]
+    );
+
+    // Ill-typed statements fail before execution
+    let result = run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO typed VALUES ('one', 2, 3)",
+    );
+    assert!(matches!(result, Err(arcdb::Error::TypeMismatch { .. })));
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT id FROM typed WHERE id = 'one'",
+    );
+    assert!(matches!(
+        result,
+        Err(arcdb::Error::OperatorTypeMismatch { .. })
+    ));
+    let result = run(&mut engine, &catalog, "SELECT id FROM typed WHERE total");
+    assert!(matches!(result, Err(arcdb::Error::TypeMismatch { .. })));
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT CAST(ratio AS INTEGER) AS r, CAST(id AS TEXT) FROM typed",
+    )
+    .unwrap();
+    assert_eq!(
+        result.rows[0].values(),
+        &[Value::Integer(3), Value::String("1".to_string())]
+    );
+}
+
+#[test]
+fn test_column_types_enforced_on_write() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE sized (code CHAR(4), label VARCHAR(5), small SMALLINT, big BIGINT)",
+    )
+    .unwrap();
+
+    run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO sized VALUES ('ab', 'five', 7, 3000000000)",
+    )
+    .unwrap();
+    let result = run(&mut engine, &catalog, "SELECT code, big FROM sized").unwrap();
+    assert_eq!(
+        result.rows[0].values(),
+        &[text("ab  "), Value::BigInt(3_000_000_000)]
+    );
+
+    // CHAR comparisons see the padded value
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT label FROM sized WHERE code = 'ab'",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("five")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO sized VALUES ('ab', 'toolong', 1, 1)",
+    );
+    assert!(matches!(result, Err(arcdb::Error::ValueTooLarge(c)) if c == "label"));
+    let result = run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO sized VALUES ('ab', 'x', 40000, 1)",
+    );
+    assert!(matches!(result, Err(arcdb::Error::ValueTooLarge(c)) if c == "small"));
+    let result = run(&mut engine, &catalog, "UPDATE sized SET small = 99999");
+    assert!(matches!(result, Err(arcdb::Error::ValueTooLarge(c)) if c == "small"));
+}
+
+#[test]
+fn test_decimal_arithmetic_is_exact() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE ledger (id INTEGER, amount DECIMAL(10, 2))",
+    )
+    .unwrap();
+    for (id, amount) in [(1, "0.10"), (2, "0.20"), (3, "19.995")] {
+        run(
+            &mut engine,
+            &catalog,
+            &format!("INSERT INTO ledger VALUES ({}, {})", id, amount),
+        )
+        .unwrap();
+    }
+
+    let dec = |s: &str| Value::Decimal(s.parse().unwrap());
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT amount FROM ledger ORDER BY amount",
+    )
+    .unwrap();
+    // Values are rounded to the column scale on write
+    assert_eq!(
+        first_column(&result),
+        vec![dec("0.10"), dec("0.20"), dec("20.00")]
+    );
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT SUM(amount), amount * 3, amount / 3 FROM ledger WHERE id = 1 GROUP BY amount",
+    )
+    .unwrap();
+    assert_eq!(result.rows[0].values()[0].to_string(), "0.10");
+    assert_eq!(result.rows[0].values()[1].to_string(), "0.30");
+    assert_eq!(result.rows[0].values()[2].to_string(), "0.033333");
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT id FROM ledger WHERE amount + amount = 0.4",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(2)]);
+
+    // Fractional literals are exact when used with a DECIMAL
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT amount * 1.08, amount + 0.1, 0.1 + amount FROM ledger WHERE id = 3",
+    )
+    .unwrap();
+    assert_eq!(
+        result.rows[0].values(),
+        &[dec("21.6000"), dec("20.10"), dec("20.10")]
+    );
+    assert_eq!(result.rows[0].values()[0].to_string(), "21.6000");
+
+    // Fractional literals are exact on their own too
+    let result = run(&mut engine, &catalog, "SELECT 0.1 + 0.2 = 0.3, 0.1 + 0.2").unwrap();
+    assert_eq!(result.rows[0].values(), &[Value::Boolean(true), dec("0.3")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO ledger VALUES (4, 123456789.00)",
+    );
+    assert!(matches!(result, Err(arcdb::Error::ValueTooLarge(c)) if c == "amount"));
+}
+
+#[test]
+fn test_dates_times_and_intervals() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE events (id INTEGER, day DATE, at TIMESTAMP, starts TIME)",
+    )
+    .unwrap();
+    run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO events VALUES (1, DATE '2024-01-31', TIMESTAMP '2024-01-31 22:30:00', TIME '09:15:00')",
+    )
+    .unwrap();
+    // Plain strings are read as literals of the column type
+    run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO events VALUES (2, '2024-03-15', '2024-03-15 08:00:00', '17:45:30.5')",
+    )
+    .unwrap();
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT day, at, starts FROM events WHERE day = '2024-03-15'",
+    )
+    .unwrap();
+    let shown: Vec<String> = result.rows[0]
+        .values()
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    assert_eq!(shown, ["2024-03-15", "2024-03-15 08:00:00", "17:45:30.500"]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT day + INTERVAL '1 month', at + INTERVAL '2 hours', day - DATE '2024-01-01', \
+         EXTRACT(MONTH FROM day), EXTRACT(HOUR FROM starts), DATE_TRUNC('month', at) \
+         FROM events WHERE id = 1",
+    )
+    .unwrap();
+    let shown: Vec<String> = result.rows[0]
+        .values()
+        .iter()
+        .map(|v| v.to_string())
+        .collect();
+    assert_eq!(
+        shown,
+        [
+            "2024-02-29 00:00:00",
+            "2024-02-01 00:30:00",
+            "30",
+            "1",
+            "9",
+            "2024-01-01 00:00:00"
+        ]
+    );
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT id FROM events WHERE at - INTERVAL '1 day' > TIMESTAMP '2024-02-01 00:00:00'",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(2)]);
+
+    // Temporal literals are named by their SQL form
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT DATE '2024-01-15', TIME '08:30:00', TIMESTAMP '2024-01-15 08:30:00', \
+         INTERVAL '2 hours'",
+    )
+    .unwrap();
+    assert_eq!(
+        result.columns,
+        [
+            "DATE '2024-01-15'",
+            "TIME '08:30:00'",
+            "TIMESTAMP '2024-01-15 08:30:00'",
+            "INTERVAL '02:00:00'"
+        ]
+    );
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT id FROM events WHERE day <= CURRENT_DATE AND at < CURRENT_TIMESTAMP",
+    )
+    .unwrap();
+    assert_eq!(result.rows.len(), 2);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO events VALUES (3, '2024-02-30', NULL, NULL)",
+    );
+    assert!(matches!(result, Err(arcdb::Error::SemanticError(_))));
+}
+
+#[test]
+fn test_like_in_between_case_and_modulo() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE products (id INTEGER, name VARCHAR(20), price INTEGER)",
+    )
+    .unwrap();
+    for sql in [
+        "INSERT INTO products VALUES (1, 'Apple', 120)",
+        "INSERT INTO products VALUES (2, 'apricot', 45)",
+        "INSERT INTO products VALUES (3, 'banana_split', 300)",
+        "INSERT INTO products VALUES (4, 'Cherry 100%', 75)",
+        "INSERT INTO products VALUES (5, NULL, NULL)",
+    ] {
+        run(&mut engine, &catalog, sql).unwrap();
+    }
+
+    let ids = |engine: &mut ExecutionEngine, predicate: &str| {
+        let sql = format!("SELECT id FROM products WHERE {} ORDER BY id", predicate);
+        first_column(&run(engine, &catalog, &sql).unwrap())
+    };
+    let ints = |ids: &[i32]| ids.iter().map(|&i| Value::Integer(i)).collect::<Vec<_>>();
+
+    assert_eq!(ids(&mut engine, "name LIKE 'ap%'"), ints(&[2]));
+    assert_eq!(ids(&mut engine, "name ILIKE 'ap%'"), ints(&[1, 2]));
+    assert_eq!(ids(&mut engine, "name NOT LIKE '%a%'"), ints(&[1, 4]));
+    assert_eq!(ids(&mut engine, "name LIKE '_pple'"), ints(&[1]));
+    assert_eq!(ids(&mut engine, "name LIKE '%\\_%'"), ints(&[3]));
+    assert_eq!(ids(&mut engine, "name LIKE '%!%' ESCAPE '!'"), ints(&[4]));
+    assert_eq!(ids(&mut engine, "price BETWEEN 50 AND 150"), ints(&[1, 4]));
+    assert_eq!(ids(&mut engine, "price NOT IN (45, 300)"), ints(&[1, 4]));
+    assert_eq!(ids(&mut engine, "price % 100 = 20"), ints(&[1]));
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT id, CASE WHEN price < 100 THEN 'cheap' WHEN price < 200 THEN 'fair' \
+         ELSE 'dear' END, price % 7 FROM products ORDER BY id",
+    )
+    .unwrap();
+    let shown: Vec<String> = result
+        .rows
+        .iter()
+        .flat_map(|row| {
+            row.values()
+                .iter()
+                .map(|v| v.to_string())
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    assert_eq!(
+        shown,
+        [
+            "1", "fair", "1", "2", "cheap", "3", "3", "dear", "6", "4", "cheap", "5", "5", "dear",
+            "NULL"
+        ]
+    );
+
+    assert!(matches!(
+        run(&mut engine, &catalog, "SELECT 1 % 0"),
+        Err(arcdb::Error::DivisionByZero)
+    ));
+    assert!(run(&mut engine, &catalog, "SELECT 'a' LIKE 'a!' ESCAPE '!'").is_err());
+}
+
+#[test]
+fn test_uncorrelated_subqueries() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename FROM emp WHERE emp_id = (SELECT MAX(emp_id) FROM emp)",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("dan")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename FROM emp WHERE dept IN (SELECT dept_no FROM dept) ORDER BY ename",
+    )
+    .unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![text("ann"), text("bob"), text("cat")]
+    );
+
+    let not_in = "SELECT ename FROM emp WHERE dept NOT IN (SELECT dept_no FROM dept)";
+    let result = run(&mut engine, &catalog, not_in).unwrap();
+    assert_eq!(first_column(&result), vec![text("dan")]);
+
+    // A NULL in the subquery makes NOT IN unknown for every row
+    run(
+        &mut engine,
+        &catalog,
+        "INSERT INTO dept VALUES (NULL, 'tmp')",
+    )
+    .unwrap();
+    let result = run(&mut engine, &catalog, not_in).unwrap();
+    assert!(result.rows.is_empty());
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT (SELECT COUNT(*) FROM dept), EXISTS (SELECT * FROM dept WHERE dept_no > 100)",
+    )
+    .unwrap();
+    assert_eq!(result.columns, ["expr", "expr"]);
+    assert_eq!(
+        result.rows[0].values(),
+        [Value::BigInt(4), Value::Boolean(false)]
+    );
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename FROM emp WHERE dept = (SELECT dept_no FROM dept)",
+    );
+    assert!(matches!(result, Err(arcdb::Error::ExecutionError(_))));
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename FROM emp WHERE dept IN (SELECT dept_no, dname FROM dept)",
+    );
+    assert!(matches!(result, Err(arcdb::Error::SemanticError(_))));
+}
+
+#[test]
+fn test_correlated_subqueries() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT dname FROM dept d WHERE EXISTS \
+         (SELECT * FROM emp e WHERE e.dept = d.dept_no) ORDER BY dname",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("eng"), text("ops")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT dname FROM dept WHERE NOT EXISTS \
+         (SELECT * FROM emp WHERE emp.dept = dept.dept_no)",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("hr")]);
+
+    // Under OR the subquery cannot become a semi join and runs per row
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT dname FROM dept WHERE dept_no = 40 OR EXISTS \
+         (SELECT * FROM emp WHERE emp.dept = dept_no AND ename = 'bob') ORDER BY dname",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("hr"), text("ops")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT dname, (SELECT COUNT(*) FROM emp WHERE emp.dept = dept.dept_no) AS staff \
+         FROM dept ORDER BY dname",
+    )
+    .unwrap();
+    assert_eq!(result.columns, ["dname", "staff"]);
+    let staff: Vec<Value> = result
+        .rows
+        .iter()
+        .map(|row| row.get(1).cloned().unwrap())
+        .collect();
+    assert_eq!(
+        staff,
+        vec![Value::BigInt(2), Value::BigInt(0), Value::BigInt(1)]
+    );
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT ename FROM emp e WHERE emp_id > \
+         (SELECT MIN(emp_id) FROM emp x WHERE x.dept = e.dept)",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("cat")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "DELETE FROM emp WHERE dept NOT IN (SELECT dept_no FROM dept)",
+    )
+    .unwrap();
+    assert_eq!(result.affected_rows, 1);
+    let result = run(
+        &mut engine,
+        &catalog,
+        "UPDATE emp SET dept = (SELECT MAX(dept_no) FROM dept) WHERE ename = 'ann'",
+    )
+    .unwrap();
+    assert_eq!(result.affected_rows, 1);
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT dept FROM emp WHERE ename = 'ann'",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(40)]);
+}
+
+#[test]
+fn test_derived_tables() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT t.dept, t.staff FROM \
+         (SELECT dept, COUNT(*) AS staff FROM emp GROUP BY dept) AS t \
+         WHERE t.staff > 1",
+    )
+    .unwrap();
+    assert_eq!(result.columns, ["dept", "staff"]);
+    assert_eq!(result.rows.len(), 1);
+    assert_eq!(result.rows[0].get(0), Some(&Value::Integer(10)));
+    assert_eq!(result.rows[0].get(1), Some(&Value::BigInt(2)));
+
+    // Derived tables join like base tables, under their alias
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT d.dname, e.name FROM dept d \
+         JOIN (SELECT ename AS name, dept FROM emp WHERE emp_id > 1) e ON d.dept_no = e.dept \
+         ORDER BY e.name",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("ops"), text("eng")]);
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "SELECT * FROM (SELECT * FROM (SELECT ename FROM emp) inner_t) outer_t \
+         WHERE ename IN (SELECT ename FROM emp WHERE dept = 30)",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![text("dan")]);
+
+    // Only the alias names the derived table, and its columns are its output
+    for sql in [
+        "SELECT emp.ename FROM (SELECT ename FROM emp) t",
+        "SELECT t.emp_id FROM (SELECT ename FROM emp) t",
+        "SELECT * FROM (SELECT ename FROM emp)",
+        "SELECT * FROM dept d CROSS JOIN (SELECT * FROM emp WHERE dept = d.dept_no) t",
+    ] {
+        assert!(run(&mut engine, &catalog, sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_common_table_expressions() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+
+    // A CTE can be referenced more than once and by the CTEs after it
+    let result = run(
+        &mut engine,
+        &catalog,
+        "WITH big AS (SELECT dept_no, dname FROM dept WHERE dept_no > 10), \
+              named (n) AS (SELECT dname FROM big) \
+         SELECT a.n FROM named a JOIN big b ON a.n = b.dname \
+         WHERE b.dept_no IN (SELECT dept_no FROM big) ORDER BY a.n",
+    )
+    .unwrap();
+    assert_eq!(result.columns, ["n"]);
+    assert_eq!(first_column(&result), vec![text("hr"), text("ops")]);
+
+    // A CTE shadows a table of the same name
+    let result = run(
+        &mut engine,
+        &catalog,
+        "WITH emp AS (SELECT dname AS ename FROM dept) SELECT COUNT(*) FROM emp",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(3)]);
+
+    for sql in [
+        "WITH t (a, b) AS (SELECT dname FROM dept) SELECT * FROM t",
+        "WITH t AS (SELECT dname FROM dept) SELECT dept_no FROM t",
+        "WITH t AS (SELECT * FROM t) SELECT * FROM t",
+    ] {
+        assert!(run(&mut engine, &catalog, sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_recursive_ctes() {
+    let (mut engine, catalog, _dir) = setup();
+    for sql in [
+        "CREATE TABLE staff (id INTEGER, name VARCHAR(10), manager INTEGER)",
+        "INSERT INTO staff VALUES (1, 'ceo', NULL)",
+        "INSERT INTO staff VALUES (2, 'cto', 1)",
+        "INSERT INTO staff VALUES (3, 'dev', 2)",
+        "INSERT INTO staff VALUES (4, 'intern', 3)",
+        "INSERT INTO staff VALUES (5, 'cfo', 1)",
+        "CREATE TABLE edges (src INTEGER, dst INTEGER)",
+        "INSERT INTO edges VALUES (1, 2)",
+        "INSERT INTO edges VALUES (2, 3)",
+        "INSERT INTO edges VALUES (3, 1)",
+    ] {
+        run(&mut engine, &catalog, sql).unwrap();
+    }
+
+    // Everyone under the CTO, with their depth in the org chart
+    let result = run(
+        &mut engine,
+        &catalog,
+        "WITH RECURSIVE reports (id, name, level) AS ( \
+             SELECT id, name, 0 FROM staff WHERE name = 'cto' \
+             UNION ALL \
+             SELECT s.id, s.name, r.level + 1 FROM staff s JOIN reports r ON s.manager = r.id) \
+         SELECT name, level FROM reports ORDER BY level",
+    )
+    .unwrap();
+    assert_eq!(result.columns, ["name", "level"]);
+    assert_eq!(
+        first_column(&result),
+        vec![text("cto"), text("dev"), text("intern")]
+    );
+    assert_eq!(result.rows[2].get(1), Some(&Value::Integer(2)));
+
+    // UNION discards rows already produced, so a cycle terminates
+    let result = run(
+        &mut engine,
+        &catalog,
+        "WITH RECURSIVE reach (node) AS ( \
+             SELECT 1 UNION SELECT e.dst FROM edges e JOIN reach r ON e.src = r.node) \
+         SELECT node FROM reach ORDER BY node",
+    )
+    .unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)]
+    );
+
+    let result = run(
+        &mut engine,
+        &catalog,
+        "WITH RECURSIVE nums (n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM nums WHERE n < 100) \
+         SELECT COUNT(*), SUM(n) FROM nums",
+    )
+    .unwrap();
+    assert_eq!(result.rows[0].get(0), Some(&Value::BigInt(100)));
+
+    // ... while UNION ALL follows it until the depth limit
+    let err = run(
+        &mut engine,
+        &catalog,
+        "WITH RECURSIVE walk (node) AS ( \
+             SELECT 1 UNION ALL SELECT e.dst FROM edges e JOIN walk w ON e.src = w.node) \
+         SELECT COUNT(*) FROM walk",
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("recursion depth"), "{}", err);
+
+    // A UNION that does not read the CTE is evaluated once
+    let result = run(
+        &mut engine,
+        &catalog,
+        "WITH RECURSIVE r (n) AS (SELECT 1 UNION ALL SELECT id FROM staff) SELECT COUNT(*) FROM r",
+    )
+    .unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(6)]);
+
+    for sql in [
+        "WITH RECURSIVE r (n) AS (SELECT 1 UNION ALL SELECT n + 1, n FROM r) SELECT * FROM r",
+        "WITH RECURSIVE r (n) AS (SELECT 1 UNION ALL \
+         SELECT a.n FROM r a JOIN r b ON a.n = b.n) SELECT * FROM r",
+        "WITH RECURSIVE r (n) AS (SELECT 1 UNION ALL \
+         SELECT id FROM staff WHERE id IN (SELECT n FROM r)) SELECT * FROM r",
+    ] {
+        assert!(run(&mut engine, &catalog, sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_set_operations() {
+    let (mut engine, catalog, _dir) = setup();
+    for sql in [
+        "CREATE TABLE a (x INTEGER)",
+        "CREATE TABLE b (y BIGINT)",
+        "INSERT INTO a VALUES (1)",
+        "INSERT INTO a VALUES (2)",
+        "INSERT INTO a VALUES (2)",
+        "INSERT INTO a VALUES (3)",
+        "INSERT INTO a VALUES (NULL)",
+        "INSERT INTO b VALUES (2)",
+        "INSERT INTO b VALUES (4)",
+        "INSERT INTO b VALUES (NULL)",
+    ] {
+        run(&mut engine, &catalog, sql).unwrap();
+    }
+    let mut query = |sql: &str| first_column(&run(&mut engine, &catalog, sql).unwrap());
+    let ints = |values: &[i64]| values.iter().map(|&v| Value::BigInt(v)).collect::<Vec<_>>();
+
+    // INTEGER and BIGINT values compare equal; NULLs are not distinct
+    assert_eq!(
+        query("SELECT x FROM a UNION SELECT y FROM b ORDER BY x").len(),
+        5
+    );
+    assert_eq!(query("SELECT x FROM a UNION ALL SELECT y FROM b").len(), 8);
+    assert_eq!(
+        query("SELECT CAST(x AS BIGINT) FROM a INTERSECT SELECT y FROM b").len(),
+        2
+    );
+    assert_eq!(
+        query("SELECT CAST(x AS BIGINT) AS v FROM a WHERE x IS NOT NULL EXCEPT SELECT y FROM b ORDER BY v"),
+        ints(&[1, 3])
+    );
+    assert_eq!(
+        query("SELECT CAST(x AS BIGINT) AS v FROM a EXCEPT ALL SELECT y FROM b ORDER BY v LIMIT 3"),
+        ints(&[1, 2, 3])
+    );
+    assert_eq!(
+        query(
+            "SELECT CAST(x AS BIGINT) AS v FROM a INTERSECT ALL \
+               (SELECT y FROM b UNION ALL SELECT 2) ORDER BY v"
+        ),
+        vec![Value::Null, Value::BigInt(2), Value::BigInt(2)]
+    );
+
+    // INTERSECT binds more tightly than UNION, and parentheses override it
+    assert_eq!(
+        query("SELECT 1 AS n UNION SELECT 2 INTERSECT SELECT 3 ORDER BY n"),
+        vec![Value::Integer(1)]
+    );
+    assert_eq!(
+        query("(SELECT 1 AS n UNION SELECT 2) INTERSECT SELECT 2"),
+        vec![Value::Integer(2)]
+    );
+    assert_eq!(
+        query("SELECT x FROM a WHERE x > 0 EXCEPT SELECT 1 EXCEPT SELECT 3 ORDER BY x LIMIT 1"),
+        vec![Value::Integer(2)]
+    );
+
+    // Set operations nest in subqueries, derived tables and CTEs
+    assert_eq!(
+        query("SELECT COUNT(*) FROM (SELECT x FROM a UNION SELECT y FROM b) u"),
+        vec![Value::BigInt(5)]
+    );
+    assert_eq!(
+        query(
+            "WITH u AS (SELECT x FROM a INTERSECT SELECT y FROM b) \
+               SELECT x FROM a WHERE x IN (SELECT x FROM u)"
+        ),
+        vec![Value::Integer(2), Value::Integer(2)]
+    );
+
+    // A correlated subquery may combine queries that both read the outer row
+    assert_eq!(
+        query(
+            "SELECT x FROM a WHERE EXISTS \
+               (SELECT y FROM b WHERE y = a.x UNION SELECT 1 WHERE a.x = 3) ORDER BY x"
+        ),
+        vec![Value::Integer(2), Value::Integer(2), Value::Integer(3)]
+    );
+
+    for sql in [
+        "SELECT x FROM a UNION SELECT y, y FROM b",
+        "SELECT x FROM a UNION SELECT 'text'",
+        "SELECT x FROM a UNION SELECT y FROM b ORDER BY y",
+    ] {
+        assert!(run(&mut engine, &catalog, sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_window_functions() {
+    let (mut engine, catalog, _dir) = setup();
+    for sql in [
+        "CREATE TABLE sales (region VARCHAR(10), day INTEGER, amount INTEGER)",
+        "INSERT INTO sales VALUES ('east', 1, 10)",
+        "INSERT INTO sales VALUES ('east', 2, 20)",
+        "INSERT INTO sales VALUES ('east', 2, 30)",
+        "INSERT INTO sales VALUES ('east', 4, 40)",
+        "INSERT INTO sales VALUES ('west', 1, 5)",
+        "INSERT INTO sales VALUES ('west', 3, 15)",
+    ] {
+        run(&mut engine, &catalog, sql).unwrap();
+    }
+    let mut rows = |sql: &str| {
+        run(&mut engine, &catalog, sql)
+            .unwrap()
+            .rows
+            .iter()
+            .map(|row| row.values().to_vec())
+            .collect::<Vec<_>>()
+    };
+    let ints = |values: &[i64]| values.iter().map(|&v| Value::BigInt(v)).collect::<Vec<_>>();
+    let column =
+        |rows: &[Vec<Value>], i: usize| rows.iter().map(|row| row[i].clone()).collect::<Vec<_>>();
+
+    // Ranking within each partition, with ties sharing a rank
+    let result = rows(
+        "SELECT region, ROW_NUMBER() OVER (PARTITION BY region ORDER BY day), \
+           RANK() OVER (PARTITION BY region ORDER BY day), \
+           DENSE_RANK() OVER (PARTITION BY region ORDER BY day) FROM sales",
+    );
+    assert_eq!(column(&result, 1), ints(&[1, 2, 3, 4, 1, 2]));
+    assert_eq!(column(&result, 2), ints(&[1, 2, 2, 4, 1, 2]));
+    assert_eq!(column(&result, 3), ints(&[1, 2, 2, 3, 1, 2]));
+
+    // LAG and LEAD stay within the partition, with an optional default
+    let result = rows(
+        "SELECT LAG(amount) OVER (PARTITION BY region ORDER BY amount), \
+           LEAD(amount, 2, 0) OVER (PARTITION BY region ORDER BY amount) FROM sales",
+    );
+    assert_eq!(
+        column(&result, 0),
+        vec![
+            Value::Null,
+            Value::Integer(10),
+            Value::Integer(20),
+            Value::Integer(30),
+            Value::Null,
+            Value::Integer(5),
+        ]
+    );
+    assert_eq!(
+        column(&result, 1),
+        [30, 40, 0, 0, 0, 0].map(Value::Integer).to_vec()
+    );
+
+    // The default frame of an ordered window includes the current row's peers
+    let result =
+        rows("SELECT SUM(amount) OVER (PARTITION BY region ORDER BY day) AS total FROM sales");
+    assert_eq!(column(&result, 0), ints(&[10, 60, 60, 100, 5, 20]));
+    let result = rows("SELECT SUM(amount) OVER (PARTITION BY region) FROM sales");
+    assert_eq!(column(&result, 0), ints(&[100, 100, 100, 100, 20, 20]));
+
+    // Explicit ROWS and RANGE frames
+    let result = rows(
+        "SELECT SUM(amount) OVER (ORDER BY amount ROWS BETWEEN 1 PRECEDING AND CURRENT ROW) \
+           FROM sales",
+    );
+    assert_eq!(column(&result, 0), ints(&[5, 15, 25, 35, 50, 70]));
+    let result = rows(
+        "SELECT day, SUM(amount) OVER (PARTITION BY region ORDER BY day \
+           RANGE BETWEEN 1 PRECEDING AND CURRENT ROW) FROM sales",
+    );
+    assert_eq!(column(&result, 1), ints(&[10, 60, 60, 40, 5, 15]));
+    let result = rows(
+        "SELECT AVG(amount) OVER (ORDER BY amount DESC \
+           ROWS BETWEEN UNBOUNDED PRECEDING AND UNBOUNDED FOLLOWING) FROM sales",
+    );
+    assert_eq!(column(&result, 0), vec![Value::Float(20.0); 6]);
+
+    // Windows over grouped rows, and ORDER BY on a window result
+    let result = rows(
+        "SELECT region, SUM(amount), RANK() OVER (ORDER BY SUM(amount) DESC) FROM sales \
+           GROUP BY region ORDER BY RANK() OVER (ORDER BY SUM(amount) DESC)",
+    );
+    assert_eq!(
+        result,
+        vec![
+            vec![text("east"), Value::BigInt(100), Value::BigInt(1)],
+            vec![text("west"), Value::BigInt(20), Value::BigInt(2)],
+        ]
+    );
+
+    for sql in [
+        "SELECT ROW_NUMBER() FROM sales",
+        "SELECT UPPER(region) OVER () FROM sales",
+        "SELECT region FROM sales WHERE RANK() OVER (ORDER BY day) = 1",
+        "SELECT COUNT(DISTINCT day) OVER () FROM sales",
+        "SELECT SUM(amount) OVER (ORDER BY day ROWS BETWEEN CURRENT ROW AND 1 PRECEDING) FROM sales",
+        "SELECT SUM(amount) OVER (ORDER BY region RANGE 1 PRECEDING) FROM sales",
+    ] {
+        assert!(run(&mut engine, &catalog, sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_distinct_and_order_by_resolution() {
+    let (mut engine, catalog, _dir) = setup();
+    setup_join_tables(&mut engine, &catalog);
+    for sql in [
+        "CREATE TABLE orders (customer VARCHAR(10), qty INTEGER, price INTEGER)",
+        "INSERT INTO orders VALUES ('ann', 1, 10)",
+        "INSERT INTO orders VALUES ('bob', 5, 2)",
+        "INSERT INTO orders VALUES ('ann', 2, 10)",
+        "INSERT INTO orders VALUES ('cy', 3, 3)",
+        "INSERT INTO orders VALUES ('bob', NULL, 2)",
+    ] {
+        run(&mut engine, &catalog, sql).unwrap();
+    }
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql).unwrap();
+
+    // DISTINCT keeps one of each row, NULLs included
+    let result = query("SELECT DISTINCT customer FROM orders ORDER BY customer");
+    assert_eq!(
+        first_column(&result),
+        vec![text("ann"), text("bob"), text("cy")]
+    );
+    assert_eq!(
+        query("SELECT DISTINCT price, qty IS NULL FROM orders")
+            .rows
+            .len(),
+        4
+    );
+
+    // ORDER BY a column that is not selected; the result keeps only the select list
+    let result = query("SELECT customer FROM orders WHERE qty IS NOT NULL ORDER BY qty DESC");
+    assert_eq!(result.columns, vec!["customer".to_string()]);
+    assert_eq!(
+        first_column(&result),
+        vec![text("bob"), text("cy"), text("ann"), text("ann")]
+    );
+    let result = query("SELECT customer FROM orders ORDER BY price * qty DESC, customer LIMIT 3");
+    assert_eq!(
+        first_column(&result),
+        vec![text("ann"), text("ann"), text("bob")]
+    );
+
+    // Output aliases and positions
+    let result = query(
+        "SELECT customer, SUM(qty * price) AS total FROM orders \
+           GROUP BY customer ORDER BY total DESC",
+    );
+    assert_eq!(
+        result.columns,
+        vec!["customer".to_string(), "total".to_string()]
+    );
+    assert_eq!(
+        first_column(&result),
+        vec![text("ann"), text("bob"), text("cy")]
+    );
+    let result = query("SELECT customer, price FROM orders ORDER BY 2, 1 DESC LIMIT 3");
+    assert_eq!(
+        first_column(&result),
+        vec![text("bob"), text("bob"), text("cy")]
+    );
+    let result = query("SELECT * FROM orders WHERE qty > 1 ORDER BY 2");
+    assert_eq!(
+        first_column(&result),
+        vec![text("ann"), text("cy"), text("bob")]
+    );
+
+    // Grouped queries may sort by aggregates and keys outside the select list
+    let result =
+        query("SELECT customer FROM orders GROUP BY customer ORDER BY COUNT(*) DESC, MAX(price)");
+    assert_eq!(
+        first_column(&result),
+        vec![text("bob"), text("ann"), text("cy")]
+    );
+
+    // An output name wins over an input column of the same name
+    let result = query("SELECT -emp_id AS emp_id FROM emp ORDER BY emp_id LIMIT 1");
+    assert_eq!(first_column(&result), vec![Value::Integer(-4)]);
+
+    // Set operations sort by position or name
+    let result = query("SELECT qty FROM orders UNION SELECT price FROM orders ORDER BY 1 DESC");
+    assert_eq!(result.rows[0].get(0), Some(&Value::Integer(10)));
+
+    for sql in [
+        "SELECT DISTINCT customer FROM orders ORDER BY qty",
+        "SELECT customer FROM orders ORDER BY 3",
+        "SELECT customer FROM orders ORDER BY 0",
+        "SELECT customer FROM orders GROUP BY customer ORDER BY qty",
+        "SELECT qty FROM orders UNION SELECT price FROM orders ORDER BY customer",
+        "SELECT qty FROM orders UNION SELECT price FROM orders ORDER BY COUNT(*)",
+    ] {
+        assert!(run(&mut engine, &catalog, sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_alter_table() {
+    let (mut engine, catalog, _dir) = setup();
+    for sql in [
+        "CREATE TABLE items (id INTEGER PRIMARY KEY, name VARCHAR(10), price INTEGER)",
+        "CREATE INDEX idx_items_price ON items (price)",
+        "INSERT INTO items VALUES (1, 'pen', 3)",
+        "INSERT INTO items VALUES (2, NULL, 5)",
+    ] {
+        run(&mut engine, &catalog, sql).unwrap();
+    }
+    let mut alter = |sql: &str| run(&mut engine, &catalog, sql);
+
+    // Existing rows read the default of an added column
+    alter("ALTER TABLE items ADD COLUMN stock INTEGER NOT NULL DEFAULT 10").unwrap();
+    alter("INSERT INTO items VALUES (3, 'ink', 7, 0)").unwrap();
+    let result = alter("SELECT stock FROM items ORDER BY id").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![Value::Integer(10), Value::Integer(10), Value::Integer(0)]
+    );
+    assert!(alter("ALTER TABLE items ADD weight INTEGER NOT NULL").is_err());
+
+    // Dropping a column drops its index and leaves the other columns readable
+    alter("ALTER TABLE items DROP COLUMN price").unwrap();
+    assert!(catalog.get_index("idx_items_price").is_err());
+    let result = alter("SELECT * FROM items WHERE id = 2").unwrap();
+    assert_eq!(
+        result.columns,
+        vec!["id".to_string(), "name".to_string(), "stock".to_string()]
+    );
+    assert_eq!(
+        result.rows[0].values(),
+        &[Value::Integer(2), Value::Null, Value::Integer(10)]
+    );
+    alter("UPDATE items SET stock = stock + 1 WHERE id = 1").unwrap();
+
+    // Renames keep the data
+    alter("ALTER TABLE items RENAME COLUMN name TO label").unwrap();
+    let result = alter("ALTER TABLE items RENAME TO products").unwrap();
+    assert_eq!(result.message.unwrap(), "Table 'products' altered");
+    assert!(alter("SELECT name FROM products").is_err());
+    assert!(alter("SELECT * FROM items").is_err());
+    let result = alter("SELECT label, stock FROM products WHERE id = 1").unwrap();
+    assert_eq!(result.rows[0].values(), &[text("pen"), Value::Integer(11)]);
+
+    // SET NOT NULL checks the rows already stored
+    assert!(alter("ALTER TABLE products ALTER COLUMN label SET NOT NULL").is_err());
+    alter("DELETE FROM products WHERE label IS NULL").unwrap();
+    alter("ALTER TABLE products ALTER COLUMN label SET NOT NULL").unwrap();
+    assert!(alter("INSERT INTO products VALUES (4, NULL, 1)").is_err());
+    alter("ALTER TABLE products ALTER label DROP NOT NULL").unwrap();
+    alter("INSERT INTO products VALUES (4, NULL, 1)").unwrap();
+
+    for sql in [
+        "ALTER TABLE products ADD COLUMN id INTEGER",
+        "ALTER TABLE products DROP COLUMN missing",
+        "ALTER TABLE products RENAME COLUMN label TO stock",
+        "ALTER TABLE products ALTER COLUMN id DROP NOT NULL",
+        "ALTER TABLE products ADD COLUMN extra INTEGER DEFAULT 'x'",
+    ] {
+        assert!(alter(sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_create_and_drop_index() {
+    let (mut engine, catalog, dir) = setup();
+    for sql in [
+        "CREATE TABLE gadgets (id INTEGER, kind VARCHAR(10))",
+        "CREATE TABLE widgets (id INTEGER)",
+        "INSERT INTO gadgets VALUES (1, 'a')",
+        "INSERT INTO gadgets VALUES (2, 'b')",
+        "CREATE INDEX idx_gadgets_kind ON gadgets (kind)",
+    ] {
+        run(&mut engine, &catalog, sql).unwrap();
+    }
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+    let gadgets = catalog.get_table("gadgets").unwrap().id;
+    let widgets = catalog.get_table("widgets").unwrap().id;
+    let index_file = |table: u32| {
+        dir.path()
+            .join(format!("table_{}_idx_gadgets_kind.index", table))
+    };
+    assert!(index_file(gadgets).exists());
+
+    // A duplicate name fails before any storage is built
+    assert!(query("CREATE INDEX idx_gadgets_kind ON widgets (id)").is_err());
+    assert!(!index_file(widgets).exists());
+    query("CREATE INDEX IF NOT EXISTS idx_gadgets_kind ON widgets (id)").unwrap();
+    assert_eq!(
+        catalog.get_index("idx_gadgets_kind").unwrap().table_name,
+        "gadgets"
+    );
+
+    query("DROP INDEX idx_gadgets_kind").unwrap();
+    assert!(catalog.get_index("idx_gadgets_kind").is_err());
+    assert!(!index_file(gadgets).exists());
+    let result = query("SELECT id FROM gadgets WHERE kind = 'b'").unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(2)]);
+
+    assert!(query("DROP INDEX idx_gadgets_kind").is_err());
+    query("DROP INDEX IF EXISTS idx_gadgets_kind").unwrap();
+
+    // The name is free again
+    query("CREATE INDEX idx_gadgets_kind ON widgets (id)").unwrap();
+    assert!(index_file(widgets).exists());
+    query("DROP INDEX idx_gadgets_kind").unwrap();
+}
+
+#[test]
+fn test_column_defaults() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE events (id INTEGER DEFAULT -1, kind VARCHAR(10) NOT NULL DEFAULT 'note', \
+           score DECIMAL(5, 2) DEFAULT 1 + 1, at TIMESTAMP DEFAULT CURRENT_TIMESTAMP, memo TEXT)",
+    )
+    .unwrap();
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+
+    // Defaults are stored in the catalog as expressions
+    assert_eq!(
+        catalog
+            .get_table("events")
+            .unwrap()
+            .get_column("score")
+            .unwrap()
+            .default,
+        Some("1 + 1".to_string())
+    );
+
+    query("INSERT INTO events (id) VALUES (1)").unwrap();
+    query("INSERT INTO events VALUES (2, DEFAULT, 3.5, DEFAULT, 'x')").unwrap();
+    query("INSERT INTO events DEFAULT VALUES").unwrap();
+    query("INSERT INTO events (memo, id) VALUES ('a', 4), ('b', 5)").unwrap();
+
+    let result = query("SELECT id, kind, score, memo FROM events ORDER BY id").unwrap();
+    let rows: Vec<Vec<Value>> = result.rows.iter().map(|r| r.values().to_vec()).collect();
+    let two = Value::Decimal("2.00".parse().unwrap());
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Integer(-1), text("note"), two.clone(), Value::Null],
+            vec![Value::Integer(1), text("note"), two.clone(), Value::Null],
+            vec![
+                Value::Integer(2),
+                text("note"),
+                Value::Decimal("3.50".parse().unwrap()),
+                text("x")
+            ],
+            vec![Value::Integer(4), text("note"), two.clone(), text("a")],
+            vec![Value::Integer(5), text("note"), two, text("b")],
+        ]
+    );
+
+    // CURRENT_TIMESTAMP is evaluated when each row is inserted
+    let result = query("SELECT COUNT(*) FROM events WHERE at IS NULL").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(0)]);
+
+    // DEFAULT in UPDATE SET
+    query("UPDATE events SET kind = 'alert' WHERE id = 1").unwrap();
+    query("UPDATE events SET kind = DEFAULT, memo = DEFAULT WHERE id = 1").unwrap();
+    let result = query("SELECT kind, memo FROM events WHERE id = 1").unwrap();
+    assert_eq!(result.rows[0].values(), &[text("note"), Value::Null]);
+
+    for sql in [
+        "INSERT INTO events VALUES (DEFAULT + 1, 'a', 1, NULL, NULL)",
+        "SELECT DEFAULT FROM events",
+        "INSERT INTO events (kind) VALUES (NULL)",
+        "CREATE TABLE bad (id INTEGER DEFAULT 'x')",
+        "CREATE TABLE bad (id INTEGER DEFAULT id)",
+    ] {
+        assert!(query(sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_table_constraints() {
+    let (mut engine, catalog, _dir) = setup();
+    run(
+        &mut engine,
+        &catalog,
+        "CREATE TABLE items (shop INTEGER, sku INTEGER, code VARCHAR(10) UNIQUE, price INTEGER, \
+           PRIMARY KEY (shop, sku), CONSTRAINT positive_price CHECK (price > 0))",
+    )
+    .unwrap();
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+
+    let def = catalog.get_table("items").unwrap();
+    let names: Vec<&str> = def.constraints.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["items_code_key", "items_pkey", "positive_price"]
+    );
+
+    query("INSERT INTO items VALUES (1, 1, 'a', 10), (1, 2, NULL, 5), (2, 1, NULL, NULL)").unwrap();
+
+    let violation = |result: Result<QueryResult, arcdb::Error>, name: &str| {
+        assert!(
+            matches!(&result, Err(arcdb::Error::ConstraintViolation(n)) if n == name),
+            "{:?}",
+            result.map(|r| r.rows.len())
+        );
+    };
+    // Composite primary key
+    violation(
+        query("INSERT INTO items VALUES (1, 2, 'b', 1)"),
+        "items_pkey",
+    );
+    // NULL keys never conflict, duplicates do
+    query("INSERT INTO items VALUES (3, 1, NULL, 1)").unwrap();
+    violation(
+        query("INSERT INTO items VALUES (3, 2, 'a', 1)"),
+        "items_code_key",
+    );
+    // CHECK on INSERT and UPDATE
+    violation(
+        query("INSERT INTO items VALUES (4, 1, 'x', 0)"),
+        "positive_price",
+    );
+    violation(
+        query("UPDATE items SET price = price - 10 WHERE shop = 1"),
+        "positive_price",
+    );
+    // A row failing after another passed leaves both unchanged
+    violation(
+        query("UPDATE items SET price = price - 6 WHERE shop = 1"),
+        "positive_price",
+    );
+    violation(
+        query("UPDATE items SET sku = 1 WHERE code = 'a' OR sku = 2"),
+        "items_pkey",
+    );
+
+    let result = query("SELECT COUNT(*) FROM items").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(4)]);
+    let result = query("SELECT price FROM items WHERE shop = 1 ORDER BY sku").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![Value::Integer(10), Value::Integer(5)]
+    );
+
+    // Constraint indexes cannot be dropped directly
+    assert!(query("DROP INDEX items_pkey").is_err());
+    // A unique index cannot be built over duplicates
+    query("INSERT INTO items VALUES (5, 1, 'z', 10)").unwrap();
+    violation(
+        query("CREATE UNIQUE INDEX price_idx ON items (price)"),
+        "price_idx",
+    );
+
+    // Constraints follow renames and go away with their columns
+    query("ALTER TABLE items RENAME COLUMN price TO cost").unwrap();
+    violation(
+        query("INSERT INTO items VALUES (6, 1, NULL, -1)"),
+        "positive_price",
+    );
+    query("ALTER TABLE items DROP COLUMN cost").unwrap();
+    query("INSERT INTO items VALUES (6, 1, NULL)").unwrap();
+    let def = catalog.get_table("items").unwrap();
+    let names: Vec<&str> = def.constraints.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["items_code_key", "items_pkey"]);
+
+    // A column CHECK is the same constraint as a table CHECK
+    query("CREATE TABLE tags (id INTEGER, price INT CHECK (price > 0) NOT NULL)").unwrap();
+    let def = catalog.get_table("tags").unwrap();
+    let names: Vec<&str> = def.constraints.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["tags_check"]);
+    query("INSERT INTO tags VALUES (1, 3)").unwrap();
+    violation(query("INSERT INTO tags VALUES (2, 0)"), "tags_check");
+    assert!(query("ALTER TABLE tags ADD COLUMN n INT CHECK (n > 0)").is_err());
+
+    for sql in [
+        "CREATE TABLE bad (a INTEGER PRIMARY KEY, b INTEGER, PRIMARY KEY (b))",
+        "CREATE TABLE bad (a INTEGER, CONSTRAINT c CHECK (a > 0), CONSTRAINT c CHECK (a < 9))",
+        "CREATE TABLE bad (a INTEGER, CHECK (a + 1))",
+        "CREATE TABLE bad (a INTEGER, CHECK (b > 0))",
+        "CREATE TABLE bad (a INTEGER CHECK (a + 1))",
+    ] {
+        assert!(query(sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_foreign_keys() {
+    let (mut engine, catalog, _dir) = setup();
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+    for sql in [
+        "CREATE TABLE customers (id INTEGER PRIMARY KEY, name TEXT)",
+        "CREATE TABLE orders (id INTEGER PRIMARY KEY, \
+           customer INTEGER REFERENCES customers ON DELETE CASCADE ON UPDATE CASCADE)",
+        "CREATE TABLE notes (id INTEGER, order_id INTEGER, \
+           FOREIGN KEY (order_id) REFERENCES orders (id) ON DELETE SET NULL)",
+        "CREATE TABLE invoices (id INTEGER, customer INTEGER DEFAULT 0 \
+           REFERENCES customers ON DELETE SET DEFAULT ON UPDATE RESTRICT)",
+        "INSERT INTO customers VALUES (0, 'nobody'), (1, 'a'), (2, 'b')",
+        "INSERT INTO orders VALUES (10, 1), (11, 1), (20, 2), (30, NULL)",
+        "INSERT INTO notes VALUES (1, 10), (2, 20)",
+        "INSERT INTO invoices VALUES (1, 2)",
+    ] {
+        query(sql).unwrap();
+    }
+
+    let violation = |result: Result<QueryResult, arcdb::Error>, name: &str| {
+        assert!(
+            matches!(&result, Err(arcdb::Error::ForeignKeyViolation(n)) if n == name),
+            "{}: {:?}",
+            name,
+            result.map(|r| r.rows.len())
+        );
+    };
+    // Referencing rows must match a referenced key
+    violation(
+        query("INSERT INTO orders VALUES (40, 9)"),
+        "orders_customer_fkey",
+    );
+    violation(
+        query("UPDATE orders SET customer = 9 WHERE id = 10"),
+        "orders_customer_fkey",
+    );
+    // RESTRICT
+    violation(
+        query("UPDATE customers SET id = 5 WHERE id = 2"),
+        "invoices_customer_fkey",
+    );
+
+    // ON UPDATE CASCADE
+    query("UPDATE customers SET id = 3 WHERE id = 1").unwrap();
+    let result = query("SELECT customer FROM orders ORDER BY id").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![
+            Value::Integer(3),
+            Value::Integer(3),
+            Value::Integer(2),
+            Value::Null
+        ]
+    );
+
+    // ON DELETE CASCADE, then SET NULL and SET DEFAULT
+    query("DELETE FROM customers WHERE id = 2").unwrap();
+    let result = query("SELECT id FROM orders ORDER BY id").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![Value::Integer(10), Value::Integer(11), Value::Integer(30)]
+    );
+    let result = query("SELECT order_id FROM notes ORDER BY id").unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(10), Value::Null]);
+    let result = query("SELECT customer FROM invoices").unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(0)]);
+    // The default must itself be referenced
+    violation(
+        query("DELETE FROM customers WHERE id = 0"),
+        "invoices_customer_fkey",
+    );
+
+    // An action failing anywhere in a cascade leaves every table unchanged,
+    // as does a later row of the same statement failing
+    for sql in [
+        "CREATE TABLE g (id INTEGER PRIMARY KEY)",
+        "CREATE TABLE p (id INTEGER PRIMARY KEY, gid INTEGER REFERENCES g ON DELETE CASCADE)",
+        "CREATE TABLE c (id INTEGER, pid INTEGER REFERENCES p ON DELETE RESTRICT)",
+        "CREATE TABLE d (id INTEGER, gid INTEGER DEFAULT 99 REFERENCES g ON DELETE SET DEFAULT)",
+        "INSERT INTO g VALUES (1), (2), (3)",
+        "INSERT INTO p VALUES (1, 2)",
+        "INSERT INTO c VALUES (1, 1)",
+        "INSERT INTO d VALUES (1, 3)",
+    ] {
+        query(sql).unwrap();
+    }
+    violation(query("DELETE FROM g WHERE id = 2"), "c_pid_fkey");
+    violation(query("DELETE FROM g WHERE id = 3"), "d_gid_fkey");
+    violation(query("DELETE FROM g"), "c_pid_fkey");
+    let result = query("SELECT id FROM g ORDER BY id").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![Value::Integer(1), Value::Integer(2), Value::Integer(3)]
+    );
+    let result = query("SELECT gid FROM p").unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(2)]);
+    let result = query("SELECT gid FROM d").unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(3)]);
+    query("CREATE TABLE e (gid INTEGER NOT NULL REFERENCES g ON DELETE SET NULL)").unwrap();
+    query("INSERT INTO e VALUES (1)").unwrap();
+    assert!(query("DELETE FROM g WHERE id = 1").is_err());
+    let result = query("SELECT COUNT(*) FROM g").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(3)]);
+
+    // A table may reference itself
+    query("CREATE TABLE staff (id INTEGER PRIMARY KEY, boss INTEGER REFERENCES staff ON DELETE CASCADE)")
+        .unwrap();
+    query("INSERT INTO staff VALUES (1, 1), (2, 1), (3, 2)").unwrap();
+    let result = query("DELETE FROM staff WHERE id = 2").unwrap();
+    assert_eq!(result.affected_rows, 1);
+    let result = query("SELECT id FROM staff").unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(1)]);
+
+    // Referenced tables and keys follow renames and cannot be dropped
+    assert!(query("DROP TABLE customers").is_err());
+    assert!(query("ALTER TABLE customers DROP COLUMN id").is_err());
+    query("ALTER TABLE customers RENAME TO clients").unwrap();
+    query("ALTER TABLE clients RENAME COLUMN id TO cid").unwrap();
+    violation(
+        query("INSERT INTO orders VALUES (40, 9)"),
+        "orders_customer_fkey",
+    );
+    query("INSERT INTO orders VALUES (40, 3)").unwrap();
+    let def = catalog.get_table("orders").unwrap();
+    let (_, fk) = def.foreign_keys().next().unwrap();
+    assert_eq!(
+        (fk.ref_table.as_str(), fk.ref_columns.as_slice()),
+        ("clients", &["cid".to_string()][..])
+    );
+
+    for sql in [
+        "CREATE TABLE bad (a TEXT REFERENCES clients)",
+        "CREATE TABLE bad (a TEXT REFERENCES clients (name))",
+        "CREATE TABLE bad (a INTEGER REFERENCES missing)",
+        "CREATE TABLE bad (a INTEGER, b INTEGER, FOREIGN KEY (a, b) REFERENCES clients)",
+        "CREATE TABLE bad (a INTEGER REFERENCES notes)",
+    ] {
+        assert!(query(sql).is_err(), "{}", sql);
+    }
+}
+
+#[test]
+fn test_sequences_and_identity_columns() {
+    let (mut engine, catalog, _dir) = setup();
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+
+    query("CREATE SEQUENCE ticket_seq START WITH 10 INCREMENT BY 5").unwrap();
+    let result = query("SELECT nextval('ticket_seq'), nextval('ticket_seq')").unwrap();
+    assert_eq!(
+        result.rows[0].values(),
+        &[Value::BigInt(10), Value::BigInt(15)]
+    );
+    let result = query("SELECT currval('ticket_seq')").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(15)]);
+    query("SELECT setval('ticket_seq', 100)").unwrap();
+    let result = query("SELECT nextval('ticket_seq')").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(105)]);
+
+    // SERIAL and BY DEFAULT identity columns take explicit values too
+    query("CREATE TABLE orders (id SERIAL PRIMARY KEY, item VARCHAR(10))").unwrap();
+    query("INSERT INTO orders (item) VALUES ('a'), ('b')").unwrap();
+    query("INSERT INTO orders VALUES (DEFAULT, 'c')").unwrap();
+    query("INSERT INTO orders VALUES (50, 'd')").unwrap();
+    query("INSERT INTO orders (item) VALUES ('e')").unwrap();
+    let result = query("SELECT id FROM orders ORDER BY id").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![
+            Value::Integer(1),
+            Value::Integer(2),
+            Value::Integer(3),
+            Value::Integer(4),
+            Value::Integer(50)
+        ]
+    );
+    assert!(catalog.sequence_exists("orders_id_seq"));
+
+    // nextval advances once per row
+    query("UPDATE orders SET id = nextval('ticket_seq') WHERE id > 3").unwrap();
+    let result = query("SELECT id FROM orders WHERE id > 3 ORDER BY id").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![Value::Integer(110), Value::Integer(115)]
+    );
+
+    // GENERATED ALWAYS accepts only DEFAULT
+    query(
+        "CREATE TABLE audit (id BIGINT GENERATED ALWAYS AS IDENTITY (START WITH 3 INCREMENT BY -1 \
+           MINVALUE 1 MAXVALUE 3), note TEXT)",
+    )
+    .unwrap();
+    query("INSERT INTO audit (note) VALUES ('x'), ('y'), ('z')").unwrap();
+    let result = query("SELECT id FROM audit").unwrap();
+    assert_eq!(
+        first_column(&result),
+        vec![Value::BigInt(3), Value::BigInt(2), Value::BigInt(1)]
+    );
+    let err = query("INSERT INTO audit (note) VALUES ('w')").unwrap_err();
+    assert!(err.to_string().contains("minimum"), "{}", err);
+
+    // Dropping the table drops the sequences it owns
+    query("DROP TABLE audit").unwrap();
+    assert!(!catalog.sequence_exists("audit_id_seq"));
+    query("DROP SEQUENCE ticket_seq").unwrap();
+    query("DROP SEQUENCE IF EXISTS ticket_seq").unwrap();
+    query("CREATE SEQUENCE IF NOT EXISTS orders_id_seq").unwrap();
+    query("CREATE SEQUENCE unused_seq").unwrap();
+
+    for sql in [
+        "INSERT INTO audit VALUES (1, 'x')",
+        "CREATE TABLE bad (id TEXT GENERATED ALWAYS AS IDENTITY)",
+        "CREATE TABLE bad (id SERIAL DEFAULT 1)",
+        "CREATE SEQUENCE orders_id_seq",
+        "CREATE SEQUENCE bad INCREMENT BY 0",
+        "CREATE SEQUENCE bad START WITH 0",
+        "DROP SEQUENCE orders_id_seq",
+        "SELECT nextval('missing')",
+        "SELECT currval('unused_seq')",
+        "SELECT id FROM orders ORDER BY nextval('orders_id_seq')",
+    ] {
+        assert!(query(sql).is_err(), "{}", sql);
+    }
+
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+    query("CREATE TABLE locked (id INTEGER GENERATED ALWAYS AS IDENTITY, n INTEGER)").unwrap();
+    query("INSERT INTO locked (n) VALUES (1)").unwrap();
+    assert!(query("UPDATE locked SET id = 5").is_err());
+    query("UPDATE locked SET id = DEFAULT").unwrap();
+    let result = query("SELECT id FROM locked").unwrap();
+    assert_eq!(first_column(&result), vec![Value::Integer(2)]);
+}
+
+#[test]
+fn test_sequences_survive_reload() {
+    let (mut engine, catalog, dir) = setup();
+    run(&mut engine, &catalog, "CREATE SEQUENCE reload_seq").unwrap();
+    run(&mut engine, &catalog, "SELECT nextval('reload_seq')").unwrap();
+
+    // nextval saved the reservation to the catalog in the data directory,
+    // so values reserved before a reload are never handed out again
+    let reloaded = Catalog::load_from_disk(dir.path().join(arcdb::catalog::CATALOG_FILE)).unwrap();
+    let (value, _) = reloaded.next_sequence_value("reload_seq").unwrap();
+    assert_eq!(value, 1 + arcdb::catalog::SEQUENCE_RESERVE as i64);
+}
+
+#[test]
+fn test_insert_select() {
+    let (mut engine, catalog, _dir) = setup();
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+    query("CREATE TABLE src (id INTEGER, name VARCHAR(10))").unwrap();
+    query(
+        "CREATE TABLE dst (id SERIAL, src_id INTEGER NOT NULL UNIQUE, name VARCHAR(20), \
+           note VARCHAR(10) DEFAULT 'copied')",
+    )
+    .unwrap();
+
+    // More rows than one bulk insert batch
+    let rows: Vec<String> = (0..2000).map(|i| format!("({}, 'n{}')", i, i)).collect();
+    let sql = format!("INSERT INTO src VALUES {}", rows.join(", "));
+    assert_eq!(query(&sql).unwrap().affected_rows, 2000);
+
+    // Omitted columns take their defaults, per row
+    let result =
+        query("INSERT INTO dst (src_id, name) SELECT id, name FROM src WHERE id < 1500").unwrap();
+    assert_eq!(result.affected_rows, 1500);
+    let result = query(
+        "SELECT COUNT(*), MIN(id), MAX(id) FROM dst WHERE note = 'copied' AND name = 'n' || src_id",
+    )
+    .unwrap();
+    assert_eq!(
+        result.rows[0].values(),
+        &[Value::BigInt(1500), Value::Integer(1), Value::Integer(1500)]
+    );
+
+    // The query is read in full before any row is inserted
+    let result = query("INSERT INTO src (SELECT id + 2000, name FROM src)").unwrap();
+    assert_eq!(result.affected_rows, 2000);
+    let result = query("SELECT COUNT(*) FROM src").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(4000)]);
+
+    // A batch is checked as a whole, including against its own rows
+    let result = query("INSERT INTO dst (src_id) SELECT 5000 FROM src WHERE id < 2");
+    assert!(
+        matches!(&result, Err(arcdb::Error::ConstraintViolation(n)) if n == "dst_src_id_key"),
+        "{:?}",
+        result.map(|r| r.affected_rows)
+    );
+    let result = query("SELECT COUNT(*) FROM dst").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(1500)]);
+
+    // A row failing after the first 1024 leaves nothing behind
+    query("CREATE TABLE capped (id INTEGER UNIQUE, n INTEGER, CHECK (n < 1500))").unwrap();
+    let result = query("INSERT INTO capped SELECT id, id FROM src WHERE id < 1998");
+    assert!(
+        matches!(&result, Err(arcdb::Error::ConstraintViolation(_))),
+        "{:?}",
+        result.map(|r| r.affected_rows)
+    );
+    let result = query("INSERT INTO capped SELECT id % 1100, 0 FROM src WHERE id < 1200");
+    assert!(
+        matches!(&result, Err(arcdb::Error::ConstraintViolation(n)) if n == "capped_id_key"),
+        "{:?}",
+        result.map(|r| r.affected_rows)
+    );
+    let result = query("SELECT COUNT(*) FROM capped").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(0)]);
+    query("INSERT INTO capped SELECT id, 0 FROM src WHERE id < 1200").unwrap();
+    let result = query("SELECT COUNT(*) FROM capped WHERE id = 1100").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(1)]);
+
+    // Inside a transaction, with a query over several tables
+    query("BEGIN").unwrap();
+    let result = query(
+        "INSERT INTO dst (src_id, name, note) \
+           WITH late AS (SELECT * FROM src WHERE id >= 3990) \
+           SELECT late.id, late.name, 'late' FROM late LEFT JOIN dst ON dst.src_id = late.id",
+    )
+    .unwrap();
+    assert_eq!(result.affected_rows, 10);
+    query("COMMIT").unwrap();
+    let result = query("SELECT COUNT(*) FROM dst WHERE note = 'late'").unwrap();
+    assert_eq!(first_column(&result), vec![Value::BigInt(10)]);
+
+    // The query must fit the target columns
+    assert!(query("INSERT INTO dst (src_id) SELECT id, name FROM src").is_err());
+    assert!(query("INSERT INTO dst (src_id, name) SELECT name, id FROM src").is_err());
+    assert!(query("INSERT INTO dst SELECT id FROM src").is_err());
+}
+
+#[test]
+fn test_returning() {
+    let (mut engine, catalog, _dir) = setup();
+    let mut query = |sql: &str| run(&mut engine, &catalog, sql);
+    query(
+        "CREATE TABLE accounts (id SERIAL PRIMARY KEY, owner VARCHAR(10) NOT NULL, \
+           balance INTEGER DEFAULT 0)",
+    )
+    .unwrap();
+    let row = |values: &[Value]| values.to_vec();
+    let rows = |result: &QueryResult| -> Vec<Vec<Value>> {
+        result.rows.iter().map(|r| r.values().to_vec()).collect()
+    };
+
+    // Generated values of the inserted rows
+    let result =
+        query("INSERT INTO accounts (owner) VALUES ('ann'), ('bob') RETURNING id, balance")
+            .unwrap();
+    assert_eq!(result.affected_rows, 2);
+    assert_eq!(result.columns, vec!["id", "balance"]);
+    assert_eq!(
+        rows(&result),
+        vec![
+            row(&[Value::Integer(1), Value::Integer(0)]),
+            row(&[Value::Integer(2), Value::Integer(0)]),
+        ]
+    );
+
+    // The final state of updated rows, with expressions and aliases
+    let result = query(
+        "UPDATE accounts SET balance = balance + 10 WHERE owner = 'bob' \
+           RETURNING *, balance * 2 AS doubled",
+    )
+    .unwrap();
+    assert_eq!(result.affected_rows, 1);
+    assert_eq!(result.columns, vec!["id", "owner", "balance", "doubled"]);
+    assert_eq!(
+        rows(&result),
+        vec![row(&[
+            Value::Integer(2),
+            Value::String("bob".to_string()),
+            Value::Integer(10),
+            Value::Integer(20),
+        ])]
+    );
+
+    // INSERT ... SELECT, with a subquery seeing the table after the change
+    let result = query(
+        "INSERT INTO accounts (owner, balance) SELECT owner || '2', balance FROM accounts \
+           RETURNING accounts.id, (SELECT COUNT(*) FROM accounts) AS total",
+    )
+    .unwrap();
+    assert_eq!(result.columns, vec!["id", "total"]);
+    assert_eq!(
+        rows(&result),
+        vec![
+            row(&[Value::Integer(3), Value::BigInt(4)]),
+            row(&[Value::Integer(4), Value::BigInt(4)]),
+        ]
+    );
+
+    // Deleted rows, and no rows but still the columns
+    let result = query("DELETE FROM accounts WHERE balance > 0 RETURNING owner").unwrap();
+    assert_eq!(result.affected_rows, 2);
+    assert_eq!(
+        first_column(&result),
+        vec![
+            Value::String("bob".to_string()),
+            Value::String("bob2".to_string())
+        ]
+    );
+    let result = query("DELETE FROM accounts WHERE id > 100 RETURNING id").unwrap();
+    assert_eq!(result.affected_rows, 0);
+    assert_eq!(result.columns, vec!["id"]);
+    assert!(result.rows.is_empty());
+
+    // Without RETURNING only the count is reported
+    let result = query("UPDATE accounts SET balance = 1").unwrap();
+    assert_eq!(result.affected_rows, 2);
+    assert!(result.columns.is_empty() && result.rows.is_empty());
+
+    assert!(query("UPDATE accounts SET balance = 1 RETURNING missing").is_err());
+    assert!(query("DELETE FROM accounts RETURNING COUNT(*)").is_err());
+    assert!(query("INSERT INTO accounts (owner) VALUES ('x') RETURNING other.*").is_err());
+}
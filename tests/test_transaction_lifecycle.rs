@@ -4,8 +4,9 @@ use std::sync::Arc;
 
 #[test]
 fn test_transaction_lifecycle() {
+    let dir = tempfile::tempdir().unwrap();
     let catalog = Arc::new(Catalog::new());
-    let mut engine = ExecutionEngine::new(catalog).unwrap();
+    let mut engine = ExecutionEngine::open(catalog, dir.path()).unwrap();
 
     // BEGIN
     let result = engine.execute(LogicalPlan::BeginTransaction).unwrap();